@@ -0,0 +1,171 @@
+//! Named port aliases from the `[aliases]` table in config.toml.
+//!
+//! A port argument is resolved in this order:
+//! 1. `host:port` and URLs on this machine give their port (`localhost:3000`)
+//! 2. anything starting with a digit is a port or range (`3000`, `3000-3010`)
+//! 3. otherwise it is looked up as an alias (`dev = "3000-3001,5432,6379"`)
+//! 4. `ephemeral` is the OS ephemeral port range, unless an alias took the name
+//! 5. service names (`http`, `postgres`) would come last; they are not resolved
+//!    today, so an alias can never be shadowed by one.
+
+use crate::address;
+use crate::portinfo;
+use crate::process::PortSpec;
+use std::collections::BTreeMap;
+
+/// Keyword for the OS ephemeral port range.
+pub const EPHEMERAL: &str = "ephemeral";
+
+/// Resolve a single command-line port argument into one or more port specs.
+pub fn resolve(arg: &str, aliases: &BTreeMap<String, String>) -> Result<Vec<PortSpec>, String> {
+    if let Some(spec) = address::parse(arg) {
+        return spec.map(|spec| vec![spec]);
+    }
+    if arg.starts_with(|c: char| c.is_ascii_digit()) {
+        return PortSpec::parse(arg)
+            .map(|spec| vec![spec])
+            .map_err(|e| format!("invalid port: '{arg}': {e}"));
+    }
+
+    if let Some(value) = aliases.get(arg) {
+        return parse_alias_value(value)
+            .map_err(|bad| format!("alias '{arg}' contains an invalid port spec: '{bad}'"));
+    }
+
+    if arg == EPHEMERAL {
+        let ((start, end), _) = portinfo::ephemeral_or_default();
+        return Ok(vec![PortSpec::range(start, end)]);
+    }
+
+    let mut message = format!("invalid port: '{arg}'");
+    let close = suggestions(arg, aliases);
+    if !close.is_empty() {
+        message.push_str(&format!(" (did you mean {}?)", close.join(" or ")));
+    }
+    Err(message)
+}
+
+/// Parse an alias value like `"3000-3001,5432 6379"`. On failure returns the offending spec.
+pub fn parse_alias_value(value: &str) -> Result<Vec<PortSpec>, String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| PortSpec::parse(s).map_err(|_| s.to_string()))
+        .collect()
+}
+
+/// Alias names within a small edit distance of `arg`, closest first.
+fn suggestions(arg: &str, aliases: &BTreeMap<String, String>) -> Vec<String> {
+    let max_distance = (arg.chars().count() / 3).max(2);
+    let mut close: Vec<(usize, &String)> = aliases
+        .keys()
+        .map(|name| (edit_distance(arg, name), name))
+        .filter(|(d, _)| *d <= max_distance)
+        .collect();
+    close.sort();
+    close
+        .into_iter()
+        .map(|(_, name)| format!("'{name}'"))
+        .collect()
+}
+
+/// Levenshtein distance, used for "did you mean" suggestions.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push((prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1));
+        }
+        prev = current;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("dev".to_string(), "3000-3001,5432,6379".to_string()),
+            ("web".to_string(), "8080".to_string()),
+        ])
+    }
+
+    #[test]
+    fn numbers_are_never_aliases() {
+        let mut aliases = aliases();
+        aliases.insert("3000".into(), "9999".into());
+        assert_eq!(
+            resolve("3000", &aliases).unwrap(),
+            vec![PortSpec::Single(3000)]
+        );
+    }
+
+    #[test]
+    fn alias_expands_to_all_specs() {
+        assert_eq!(
+            resolve("dev", &aliases()).unwrap(),
+            vec![
+                PortSpec::range(3000, 3001),
+                PortSpec::Single(5432),
+                PortSpec::Single(6379)
+            ]
+        );
+    }
+
+    #[test]
+    fn stepped_ranges_in_comma_lists() {
+        assert_eq!(
+            parse_alias_value("3000-3010:2, 4000,5000-5002").unwrap(),
+            vec![
+                PortSpec::parse("3000-3010:2").unwrap(),
+                PortSpec::Single(4000),
+                PortSpec::range(5000, 5002)
+            ]
+        );
+        assert_eq!(
+            parse_alias_value("3000,3000-3010:0").unwrap_err(),
+            "3000-3010:0"
+        );
+        assert_eq!(
+            resolve("3000-3004:2", &aliases()).unwrap()[0]
+                .expand()
+                .collect::<Vec<_>>(),
+            vec![3000, 3002, 3004]
+        );
+    }
+
+    #[test]
+    fn unknown_alias_suggests_close_names() {
+        let err = resolve("dve", &aliases()).unwrap_err();
+        assert!(err.starts_with("invalid port: 'dve'"), "{err}");
+        assert!(err.contains("did you mean 'dev'"), "{err}");
+        assert!(!resolve("database", &aliases())
+            .unwrap_err()
+            .contains("did you mean"));
+    }
+
+    #[test]
+    fn ephemeral_keyword_is_the_os_range_unless_aliased() {
+        let ((start, end), _) = portinfo::ephemeral_or_default();
+        assert_eq!(
+            resolve("ephemeral", &aliases()).unwrap(),
+            vec![PortSpec::range(start, end)]
+        );
+        let aliases = BTreeMap::from([("ephemeral".to_string(), "9000".to_string())]);
+        assert_eq!(
+            resolve("ephemeral", &aliases).unwrap(),
+            vec![PortSpec::Single(9000)]
+        );
+    }
+
+    #[test]
+    fn bad_alias_value_names_the_spec() {
+        let aliases = BTreeMap::from([("x".to_string(), "80,http".to_string())]);
+        assert!(resolve("x", &aliases).unwrap_err().contains("'http'"));
+    }
+}
@@ -0,0 +1,177 @@
+//! `--output PATH`: whatever a command prints to stdout goes to PATH instead,
+//! while notes, warnings and progress stay on stderr. Stdout is redirected at
+//! the file descriptor, so every printer is covered without threading a
+//! writer through them. By default the output is written under a temporary
+//! name next to PATH and renamed over it once the command succeeds, so a
+//! reader never sees half a document; `--append` writes to PATH as it goes,
+//! for NDJSON streams from `watch`.
+
+use crate::errors::{KillportError, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static ACTIVE: Mutex<Option<Capture>> = Mutex::new(None);
+
+#[cfg_attr(not(unix), allow(dead_code))]
+struct Capture {
+    path: PathBuf,
+    /// Where output goes until it's renamed to `path`; `None` when appending
+    temp: Option<PathBuf>,
+    /// The real stdout, put back by `finish`
+    #[cfg(unix)]
+    stdout: std::os::fd::OwnedFd,
+}
+
+fn failed(path: &Path, source: std::io::Error) -> KillportError {
+    KillportError::Output {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// `.<name>.<pid>.tmp` in the same directory, so the final rename stays on
+/// one filesystem.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map_or_else(|| "output".into(), |name| name.to_string_lossy());
+    path.with_file_name(format!(".{name}.{}.tmp", std::process::id()))
+}
+
+/// Send stdout to `path` until `finish`.
+#[cfg(unix)]
+pub fn start(path: &Path, append: bool) -> Result<()> {
+    use std::fs::{File, OpenOptions};
+    use std::io::Write;
+    use std::os::fd::{AsFd, AsRawFd};
+
+    let temp = (!append).then(|| temp_path(path));
+    let file = match &temp {
+        Some(temp) => File::create(temp),
+        None => OpenOptions::new().create(true).append(true).open(path),
+    }
+    .map_err(|e| failed(path, e))?;
+
+    let stdout = std::io::stdout();
+    stdout.lock().flush()?;
+    let saved = stdout.as_fd().try_clone_to_owned()?;
+    nix::unistd::dup2(file.as_raw_fd(), stdout.as_raw_fd()).map_err(std::io::Error::from)?;
+    quiet_print_failures();
+    tracing::debug!(path = %path.display(), append, "writing stdout to file");
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Capture {
+        path: path.to_path_buf(),
+        temp,
+        stdout: saved,
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn start(_path: &Path, _append: bool) -> Result<()> {
+    Err(KillportError::Usage(
+        "--output is only supported on Unix; redirect stdout instead".into(),
+    ))
+}
+
+/// What `println!` panics with when stdout can't be written.
+const PRINT_FAILED: &str = "failed printing to stdout: ";
+
+/// The reason a `println!` panic gives for a failed write, if `payload` is one.
+fn print_failure(payload: &(dyn std::any::Any + Send)) -> Option<&str> {
+    let message = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())?;
+    message.strip_prefix(PRINT_FAILED)
+}
+
+/// Keep the panic hook from reporting failed writes to the captured file;
+/// `guard` turns them into an error instead.
+#[cfg(unix)]
+fn quiet_print_failures() {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let capturing = ACTIVE.try_lock().map_or(true, |active| active.is_some());
+        if !(capturing && print_failure(info.payload()).is_some()) {
+            hook(info);
+        }
+    }));
+}
+
+/// Run `f`, turning a failed write to the `--output` file (a full disk, say)
+/// into an error that names it. The printers use `println!`, which panics
+/// when stdout can't be written. Other panics carry on unwinding.
+pub fn guard<T>(f: impl FnOnce() -> T) -> Result<T> {
+    let Some(path) = active_path() else {
+        return Ok(f());
+    };
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).map_err(|payload| {
+        match print_failure(payload.as_ref()) {
+            Some(reason) => failed(&path, std::io::Error::other(reason.to_string())),
+            None => std::panic::resume_unwind(payload),
+        }
+    })
+}
+
+fn active_path() -> Option<PathBuf> {
+    let active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    active.as_ref().map(|capture| capture.path.clone())
+}
+
+/// Put stdout back on the terminal. With `keep`, the output replaces the
+/// file; otherwise the file is left as it was (appended output stays).
+/// Does nothing when stdout isn't being captured.
+pub fn finish(keep: bool) -> Result<()> {
+    let Some(capture) = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return Ok(());
+    };
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::fd::AsRawFd;
+
+        let stdout = std::io::stdout();
+        let flushed = stdout.lock().flush();
+        nix::unistd::dup2(capture.stdout.as_raw_fd(), stdout.as_raw_fd())
+            .map_err(std::io::Error::from)?;
+        flushed.map_err(|e| failed(&capture.path, e))?;
+    }
+    if let Some(temp) = capture.temp {
+        if keep {
+            std::fs::rename(&temp, &capture.path).map_err(|e| {
+                let _ = std::fs::remove_file(&temp);
+                failed(&capture.path, e)
+            })?;
+        } else {
+            let _ = std::fs::remove_file(&temp);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn print_failures_are_told_from_other_panics() {
+        let payload: Box<dyn std::any::Any + Send> =
+            Box::new(format!("{PRINT_FAILED}No space left on device (os error 28)"));
+        assert_eq!(
+            print_failure(payload.as_ref()),
+            Some("No space left on device (os error 28)")
+        );
+        let payload: Box<dyn std::any::Any + Send> = Box::new("index out of bounds");
+        assert_eq!(print_failure(payload.as_ref()), None);
+    }
+
+    #[test]
+    fn temp_file_sits_next_to_the_target() {
+        let temp = temp_path(Path::new("out/ports.json"));
+        assert_eq!(temp.parent(), Some(Path::new("out")));
+        assert_eq!(
+            temp.file_name().unwrap().to_string_lossy(),
+            format!(".ports.json.{}.tmp", std::process::id())
+        );
+    }
+}
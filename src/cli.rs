@@ -1,5 +1,7 @@
-use clap::{Args, Parser, Subcommand, ValueEnum};
-use clap_complete::Shell;
+use crate::format;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// A fast, cross-platform port management tool.
 /// Kill, list, and watch processes on network ports.
@@ -9,53 +11,118 @@ use clap_complete::Shell;
     version,
     about,
     long_about = None,
-    arg_required_else_help = true,
     after_help = "\x1b[1mExamples:\x1b[0m
   portzap 3000              Kill process on port 3000
   portzap 3000 8080 9090    Kill processes on multiple ports
   portzap 3000-3010         Kill processes on port range
+  portzap 3000-3010:2       Kill processes on every other port (3000, 3002, ...)
   portzap -i 3000           Interactive mode: choose which to kill
   portzap --dry-run 3000    Show what would be killed
+  portzap -f 3000           Kill with SIGKILL right away, no graceful period
   portzap list              List all listening ports
   portzap list 3000         Show what's on port 3000
   portzap watch 3000        Watch and auto-kill anything on port 3000
   portzap free 3000          Find the next available port starting from 3000
   portzap wait 3000           Wait until port 3000 becomes free
+  portzap check 5432          Exit 0 if something listens on 5432, 1 if not
   portzap completions bash    Generate shell completions for bash
-  portzap gui               Open interactive TUI dashboard"
+  portzap config set theme light  Change a setting in config.toml
+  portzap gui               Open interactive TUI dashboard
+
+With no arguments, portzap prints this help, or runs `default_command` from
+config.toml (gui or list)."
 )]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
 
     /// Ports to kill processes on (default action).
-    /// Supports single ports (3000) and ranges (3000-3010).
+    /// Supports single ports (3000), ranges (3000-3010), stepped ranges (3000-3010:2) and `-` to read them from stdin.
     #[arg(value_name = "PORTS", num_args = 1..)]
     pub ports: Vec<String>,
 
-    /// Signal to send
-    #[arg(short, long, value_enum, default_value_t = Signal::Term)]
-    pub signal: Signal,
+    /// Signal to send [default: term]
+    #[arg(short, long, value_enum, env = "PORTZAP_SIGNAL")]
+    pub signal: Option<Signal>,
 
-    /// Disable graceful shutdown (skip SIGTERM, send signal immediately)
-    #[arg(long)]
+    /// Disable graceful shutdown (skip SIGTERM, send signal immediately).
+    /// Can also be set with PORTZAP_NO_GRACEFUL=1
+    #[arg(long, overrides_with = "graceful")]
     pub no_graceful: bool,
 
-    /// Timeout in seconds for graceful shutdown before escalating to SIGKILL
-    #[arg(short, long, default_value_t = 5)]
-    pub timeout: u64,
+    /// Use graceful shutdown even if the config sets default_no_graceful
+    #[arg(long, overrides_with = "no_graceful")]
+    pub graceful: bool,
+
+    /// Graceful shutdown time before escalating to SIGKILL, in seconds or
+    /// like 500ms or 1m; 0 waits for the process to exit, never sending SIGKILL [default: 5]
+    #[arg(short, long, env = "PORTZAP_TIMEOUT", value_name = "DURATION", value_parser = format::parse_seconds)]
+    pub timeout: Option<Duration>,
 
     /// Show what would be killed without actually killing
     #[arg(long)]
     pub dry_run: bool,
 
+    /// Kill now: SIGKILL with no graceful period, like `--no-graceful --signal kill`
+    #[arg(short, long, conflicts_with = "graceful")]
+    pub force: bool,
+
+    /// Signal processes even if a service manager owns the socket (systemd, launchd)
+    #[arg(long)]
+    pub ignore_manager: bool,
+
+    /// Signal each process's whole process group, e.g. npm and its children (Unix)
+    #[arg(long)]
+    pub group: bool,
+
     /// Interactive mode: select which processes to kill
     #[arg(short, long)]
     pub interactive: bool,
 
-    /// Output format
-    #[arg(long, value_enum, default_value_t = Format::Table, global = true)]
-    pub format: Format,
+    /// What -i does without a terminal (cron, CI): kill all matches, none,
+    /// or fail [default: fail]
+    #[arg(long, value_enum, value_name = "ACTION", requires = "interactive")]
+    pub non_interactive_fallback: Option<Fallback>,
+
+    /// Don't print scan warnings or the summary after killing several ports or processes
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Also target ports below 1024, which usually belong to system services
+    #[arg(long)]
+    pub allow_privileged: bool,
+
+    /// Don't ask before killing processes owned by root or other users
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Under WSL, also kill Windows-side listeners (with taskkill.exe)
+    #[arg(long)]
+    pub include_windows: bool,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, global = true, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
+
+    /// Write stdout (the JSON, CSV or plain output) to this file instead;
+    /// messages stay on the terminal
+    #[arg(long, value_name = "PATH", global = true)]
+    pub output: Option<PathBuf>,
+
+    /// With --output, add to the file instead of replacing it, e.g. for
+    /// NDJSON from watch
+    #[arg(long, global = true, requires = "output")]
+    pub append: bool,
+
+    /// Log what portzap is doing to stderr; repeat (-vv) for per-process
+    /// details. RUST_LOG overrides this
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Print help when run with no arguments, whatever `default_command` in
+    /// config.toml says
+    #[arg(long)]
+    pub no_gui: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -75,80 +142,477 @@ pub enum Commands {
     /// Wait until a port becomes free or occupied
     Wait(WaitArgs),
 
+    /// Test whether ports are in use, silently, for scripts
+    Check(CheckArgs),
+
+    /// Explain a port: service name, range, and who is using it
+    Info(InfoArgs),
+
+    /// Summarize listening sockets by protocol, user and process
+    Stats(StatsArgs),
+
+    /// Show the heaviest listening processes by memory, CPU or port count
+    Top(TopArgs),
+
+    /// Show kills recorded in the history log (enable with `history = true`)
+    History(HistoryArgs),
+
     /// Generate shell completions
     Completions(CompletionsArgs),
 
+    /// View or change settings in config.toml
+    Config(ConfigArgs),
+
+    /// Show version and build information (commit, build date, target)
+    Version,
+
+    /// Check permissions and platform support, with hints for fixing problems
+    Doctor,
+
     /// Open interactive TUI to browse and kill processes on ports
-    Gui,
+    Gui(GuiArgs),
 }
 
 #[derive(Args, Debug)]
 pub struct KillArgs {
-    /// Ports to kill processes on. Supports ranges like 3000-3010.
-    #[arg(value_name = "PORTS", num_args = 1.., required = true)]
+    /// Ports to kill processes on. Supports ranges like 3000-3010, stepped like 3000-3010:2.
+    #[arg(
+        value_name = "PORTS",
+        num_args = 1..,
+        required_unless_present_any = ["from_file", "pid", "all"]
+    )]
     pub ports: Vec<String>,
 
-    /// Signal to send
-    #[arg(short, long, value_enum, default_value_t = Signal::Term)]
-    pub signal: Signal,
+    /// Kill this process. Any ports given must include one it listens on
+    #[arg(long, conflicts_with = "interactive")]
+    pub pid: Option<u32>,
 
-    /// Disable graceful shutdown
+    /// Kill everything listening that you own, after showing the list and
+    /// asking (skips protected_ports/protected_names)
+    #[arg(
+        long,
+        conflicts_with_all = ["ports", "from_file", "pid", "interactive", "require_name"]
+    )]
+    pub all: bool,
+
+    /// With --all, target this user's processes instead of yours (repeatable)
+    #[arg(long, value_name = "NAME", requires = "all")]
+    pub user: Vec<String>,
+
+    /// With --all, target every user's processes
+    #[arg(long, requires = "all", conflicts_with = "user")]
+    pub include_others: bool,
+
+    /// With --all, spare processes whose name, command or working directory
+    /// contains PATTERN (/regex/ too); repeatable
+    #[arg(long, value_name = "PATTERN", requires = "all")]
+    pub except_name: Vec<String>,
+
+    /// With --all, only sockets of this protocol
+    #[arg(long, value_enum, requires = "all")]
+    pub protocol: Option<Protocol>,
+
+    /// Also target ports below 1024, which usually belong to system services
     #[arg(long)]
+    pub allow_privileged: bool,
+
+    /// Don't ask before killing processes owned by root or other users
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Under WSL, also kill Windows-side listeners (with taskkill.exe)
+    #[arg(long)]
+    pub include_windows: bool,
+
+    /// Abort a port, killing nothing, unless every process on it has a name,
+    /// command or working directory containing PATTERN (/regex/ too); repeat
+    /// to allow several
+    #[arg(long, value_name = "PATTERN")]
+    pub require_name: Vec<String>,
+
+    /// Read more port specs from a file (whitespace-separated, `#` comments)
+    #[arg(long, value_name = "PATH")]
+    pub from_file: Option<PathBuf>,
+
+    /// Signal to send [default: term]
+    #[arg(short, long, value_enum, env = "PORTZAP_SIGNAL")]
+    pub signal: Option<Signal>,
+
+    /// Disable graceful shutdown
+    #[arg(long, overrides_with = "graceful")]
     pub no_graceful: bool,
 
-    /// Timeout in seconds for graceful shutdown before escalating to SIGKILL
-    #[arg(short, long, default_value_t = 5)]
-    pub timeout: u64,
+    /// Use graceful shutdown even if the config sets default_no_graceful
+    #[arg(long, overrides_with = "no_graceful")]
+    pub graceful: bool,
+
+    /// Graceful shutdown time before escalating to SIGKILL, in seconds or
+    /// like 500ms or 1m; 0 waits for the process to exit, never sending SIGKILL [default: 5]
+    #[arg(short, long, env = "PORTZAP_TIMEOUT", value_name = "DURATION", value_parser = format::parse_seconds)]
+    pub timeout: Option<Duration>,
 
     /// Show what would be killed without actually killing
     #[arg(long)]
     pub dry_run: bool,
 
+    /// Kill now: SIGKILL with no graceful period, like `--no-graceful --signal kill`
+    #[arg(short, long, conflicts_with = "graceful")]
+    pub force: bool,
+
+    /// Signal processes even if a service manager owns the socket (systemd, launchd)
+    #[arg(long)]
+    pub ignore_manager: bool,
+
+    /// Signal each process's whole process group, e.g. npm and its children (Unix)
+    #[arg(long)]
+    pub group: bool,
+
     /// Interactive mode: select which processes to kill
     #[arg(short, long)]
     pub interactive: bool,
 
-    /// Output format
-    #[arg(long, value_enum, default_value_t = Format::Table)]
-    pub format: Format,
+    /// What -i does without a terminal (cron, CI): kill all matches, none,
+    /// or fail [default: fail]
+    #[arg(long, value_enum, value_name = "ACTION", requires = "interactive")]
+    pub non_interactive_fallback: Option<Fallback>,
+
+    /// Don't print scan warnings or the summary after killing several ports or processes
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Default)]
 pub struct ListArgs {
     /// Ports to inspect. If omitted, lists all listening ports.
     #[arg(value_name = "PORTS")]
     pub ports: Vec<String>,
 
-    /// Output format
-    #[arg(long, value_enum, default_value_t = Format::Table)]
-    pub format: Format,
+    /// Read more port specs from a file (whitespace-separated, `#` comments)
+    #[arg(long, value_name = "PATH")]
+    pub from_file: Option<PathBuf>,
+
+    /// Columns for table and plain output, comma-separated (e.g. port,name,parent)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FIELDS")]
+    pub fields: Vec<Field>,
+
+    /// Order of the listed processes [default: port]
+    #[arg(long, value_enum)]
+    pub sort: Option<SortBy>,
+
+    /// Show one row per process with all of its ports
+    #[arg(long, value_enum, value_name = "BY")]
+    pub group_by: Option<GroupBy>,
+
+    /// Refresh every N seconds until interrupted, marking new (+) and gone (-) rows
+    #[arg(
+        long,
+        value_name = "SECS",
+        num_args = 0..=1,
+        default_missing_value = "2",
+        conflicts_with = "group_by"
+    )]
+    pub watch: Option<u64>,
+
+    /// Only show processes whose name, command or working directory contains
+    /// PATTERN (/regex/ too); repeat to match any of several
+    #[arg(long, value_name = "PATTERN")]
+    pub name: Vec<String>,
+
+    /// Print only the number of matching rows ({"count": N} in JSON)
+    #[arg(long, conflicts_with_all = ["watch", "group_by", "fields"])]
+    pub count: bool,
+
+    /// Exit with 1 if anything matches, including a given port whose owner can't be seen
+    #[arg(long, conflicts_with_all = ["watch", "fail_if_none"])]
+    pub fail_if_any: bool,
+
+    /// Exit with 3 if nothing matches
+    #[arg(long, conflicts_with = "watch")]
+    pub fail_if_none: bool,
+
+    /// Exit with 2 if any given port is in use, or with no ports, if
+    /// anything is listening
+    #[arg(
+        long,
+        conflicts_with_all = ["watch", "count", "fail_if_any", "fail_if_none", "expect_used"]
+    )]
+    pub expect_free: bool,
+
+    /// Exit with 2 if any given port has nothing listening
+    #[arg(
+        long,
+        conflicts_with_all = ["watch", "count", "fail_if_any", "fail_if_none"]
+    )]
+    pub expect_used: bool,
+
+    /// Don't warn about sockets or processes the scan couldn't read
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    #[command(flatten)]
+    pub remote: RemoteArgs,
+
+    /// Under WSL, also show listeners on the Windows side
+    #[arg(long, conflicts_with = "host")]
+    pub include_windows: bool,
+
+    /// Show portzap's own sockets, which are hidden by default
+    #[arg(long)]
+    pub include_self: bool,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Pid,
+    Name,
+    Port,
+    Protocol,
+    Command,
+    User,
+    Container,
+    /// Parent process ID
+    Ppid,
+    /// Parent process name and PID
+    Parent,
+    /// How long the process has been running
+    Age,
+    /// Working directory
+    Cwd,
+    /// The machine, for `--host`
+    Host,
+    /// `windows` for Windows-side listeners, with `--include-windows`
+    Origin,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Port,
+    Pid,
+    Name,
+    Protocol,
+    /// Newest processes first
+    Age,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// One row per PID
+    Process,
+}
+
+#[derive(Args, Debug)]
+pub struct GuiArgs {
+    /// Colour theme for this session, instead of the one in config.toml
+    #[arg(long, value_enum, value_name = "NAME")]
+    pub theme: Option<ThemeName>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ThemeName {
+    Auto,
+    Dark,
+    Light,
+    Solarized,
+    Dracula,
+    Gruvbox,
+    Nord,
+}
+
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// Port to describe
+    #[arg(value_name = "PORT")]
+    pub port: String,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Only show kills newer than this: 30m, 12h, 7d, 2024-05-01 or an RFC 3339 timestamp
+    #[arg(long, value_name = "WHEN")]
+    pub since: Option<String>,
+
+    /// Only show kills on this port or range
+    #[arg(long, value_name = "PORT")]
+    pub port: Option<String>,
+
+    /// Delete the history log
+    #[arg(long, conflicts_with_all = ["since", "port"])]
+    pub clear: bool,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// Refresh every N seconds until interrupted, like watch(1)
+    #[arg(long, value_name = "SECS", num_args = 0..=1, default_missing_value = "2")]
+    pub watch: Option<u64>,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
+}
+
+#[derive(Args, Debug)]
+pub struct TopArgs {
+    /// How many processes to show
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub limit: usize,
+
+    /// What to rank by; falls back to ports where usage can't be read
+    #[arg(long, value_enum, default_value_t = TopBy::Mem)]
+    pub by: TopBy,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopBy {
+    /// Resident memory
+    Mem,
+    /// CPU usage, measured over a quarter of a second
+    Cpu,
+    /// Number of ports held
+    Ports,
 }
 
 #[derive(Args, Debug)]
 pub struct WatchArgs {
     /// Ports to watch. Supports ranges like 3000-3010.
-    #[arg(value_name = "PORTS", num_args = 1.., required = true)]
+    #[arg(
+        value_name = "PORTS",
+        num_args = 1..,
+        required_unless_present_any = ["from_file", "rules", "rules_file", "status", "stop"]
+    )]
     pub ports: Vec<String>,
 
-    /// Signal to send to new processes
-    #[arg(short, long, value_enum, default_value_t = Signal::Term)]
-    pub signal: Signal,
+    /// Read more port specs from a file (whitespace-separated, `#` comments)
+    #[arg(long, value_name = "PATH")]
+    pub from_file: Option<PathBuf>,
+
+    /// Watch PORTS and handle them with ACTION instead of the flags below:
+    /// a signal (term, kill, int, hup), graceful or graceful=DURATION,
+    /// e.g. 80:kill (repeatable)
+    #[arg(long = "rule", value_name = "PORTS:ACTION")]
+    pub rules: Vec<String>,
+
+    /// Read more rules from a TOML file's [rules] table, e.g. "80" = "kill"
+    #[arg(long, value_name = "PATH")]
+    pub rules_file: Option<PathBuf>,
+
+    /// Signal to send to new processes [default: term]
+    #[arg(short, long, value_enum, env = "PORTZAP_SIGNAL")]
+    pub signal: Option<Signal>,
 
     /// Disable graceful shutdown
-    #[arg(long)]
+    #[arg(long, overrides_with = "graceful")]
     pub no_graceful: bool,
 
-    /// Graceful timeout in seconds
-    #[arg(short, long, default_value_t = 5)]
-    pub timeout: u64,
+    /// Use graceful shutdown even if the config sets default_no_graceful
+    #[arg(long, overrides_with = "no_graceful")]
+    pub graceful: bool,
+
+    /// Graceful timeout in seconds or like 500ms or 1m; 0 waits for exit and
+    /// never sends SIGKILL [default: 5]
+    #[arg(short, long, env = "PORTZAP_TIMEOUT", value_name = "DURATION", value_parser = format::parse_seconds)]
+    pub timeout: Option<Duration>,
+
+    /// Poll interval in milliseconds [default: 1000]
+    #[arg(long)]
+    pub poll: Option<u64>,
+
+    /// Never kill processes with this name (case-insensitive, repeatable)
+    #[arg(long = "allow-name", value_name = "NAME")]
+    pub allow_names: Vec<String>,
+
+    /// Never kill the PID in this file, re-read every poll (repeatable)
+    #[arg(long = "ignore-pidfile", value_name = "PATH")]
+    pub ignore_pidfiles: Vec<PathBuf>,
 
-    /// Poll interval in milliseconds
-    #[arg(long, default_value_t = 1000)]
-    pub poll: u64,
+    /// Report what would be killed, once per process, without sending signals
+    #[arg(long)]
+    pub dry_run: bool,
 
-    /// Output format
-    #[arg(long, value_enum, default_value_t = Format::Table)]
-    pub format: Format,
+    /// Also append every event as a line of JSON to this file
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Start a new --log-file when it would grow past this many bytes
+    #[arg(long, value_name = "BYTES", requires = "log_file")]
+    pub log_rotate_size: Option<u64>,
+
+    /// Rotated log files to keep
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 3,
+        requires = "log_rotate_size"
+    )]
+    pub log_keep: usize,
+
+    /// fsync the --log-file after every event
+    #[arg(long, requires = "log_file")]
+    pub log_fsync: bool,
+
+    /// Keep watching in the background after the terminal closes (Unix)
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// List the watchers running in the background
+    #[arg(
+        long,
+        conflicts_with_all = ["ports", "from_file", "rules", "rules_file", "daemon", "stop"]
+    )]
+    pub status: bool,
+
+    /// Stop the background watchers of PORT, or all of them
+    #[arg(
+        long,
+        value_name = "PORT",
+        num_args = 0..=1,
+        conflicts_with_all = ["ports", "from_file", "rules", "rules_file", "daemon"]
+    )]
+    pub stop: Option<Option<u16>>,
+
+    /// Print a service definition that runs this watch, instead of watching
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KIND",
+        conflicts_with_all = ["daemon", "status", "stop"]
+    )]
+    pub emit_service: Option<InitSystem>,
+
+    /// Write the --emit-service definition to the user service directory
+    #[arg(long, requires = "emit_service")]
+    pub install: bool,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum Fallback {
+    All,
+    None,
+    Fail,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
@@ -159,49 +623,148 @@ pub enum Signal {
     Hup,
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// Service managers `watch --emit-service` can write for.
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum InitSystem {
+    /// A systemd user unit (Linux)
+    Systemd,
+    /// A launchd agent (macOS)
+    Launchd,
+}
+
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Format {
     Table,
     Json,
     Plain,
+    /// Unique PIDs, one per line (list and kill only)
+    Pids,
+    /// Unique ports, one per line (list and kill only)
+    Ports,
 }
 
 #[derive(Args, Debug)]
 pub struct FreeArgs {
-    /// Starting port to search from
-    #[arg(value_name = "PORT")]
-    pub port: u16,
+    /// Starting port to search from. A range (or `ephemeral`, the OS
+    /// ephemeral range) also sets the upper bound
+    #[arg(value_name = "PORT", required_unless_present = "start")]
+    pub port: Option<String>,
+
+    /// Same as PORT
+    #[arg(long, value_name = "PORT", conflicts_with = "port")]
+    pub start: Option<String>,
+
+    /// Upper bound for search (inclusive) [default: 65535]
+    #[arg(long)]
+    pub max: Option<u16>,
+
+    /// Don't show a progress bar while searching a wide range
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Keep the port bound for this long (e.g. 30s) after printing it, so
+    /// nothing else takes it before your program starts; exiting releases it
+    #[arg(long, value_name = "DURATION", value_parser = format::parse_seconds)]
+    pub hold: Option<Duration>,
+
+    /// With --hold, release the port as soon as stdin closes
+    #[arg(long, requires = "hold")]
+    pub hold_until_stdin_close: bool,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = "\x1b[1mExit status:\x1b[0m
+  0  every port is in use (with --free: free; with --any: at least one is)
+  1  otherwise
+  2  invalid arguments or port specs
+  5  the socket tables couldn't be read")]
+pub struct CheckArgs {
+    /// Ports to check. Supports ranges like 3000-3010 and aliases.
+    #[arg(value_name = "PORTS", num_args = 1.., required = true)]
+    pub ports: Vec<String>,
 
-    /// Upper bound for search (inclusive)
-    #[arg(long, default_value_t = 65535)]
-    pub max: u16,
+    /// Check that the ports are free instead of in use
+    #[arg(long)]
+    pub free: bool,
+
+    /// Pass when any one port qualifies instead of all of them
+    #[arg(long)]
+    pub any: bool,
 
-    /// Output format
-    #[arg(long, value_enum, default_value_t = Format::Table)]
-    pub format: Format,
+    /// Output format: json prints whether each port qualified; others print nothing
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
 }
 
 #[derive(Args, Debug)]
 pub struct WaitArgs {
-    /// Port to wait on
+    /// Port to wait on (or an alias for a single port)
     #[arg(value_name = "PORT")]
-    pub port: u16,
+    pub port: String,
+
+    /// Wait until the port reaches this state [default: down, or up with --http]
+    #[arg(long, value_enum)]
+    pub until: Option<WaitUntil>,
+
+    /// With --until restart, don't count a port that is already free as down;
+    /// wait for something to listen and go away first
+    #[arg(long)]
+    pub require_down: bool,
+
+    /// Count the port as up only once GET http://127.0.0.1:PORT/PATH passes
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = "/",
+        conflicts_with = "host"
+    )]
+    pub http: Option<String>,
+
+    /// Statuses that pass --http, e.g. 200,204 or 2xx or 200-299 [default: 2xx,3xx]
+    #[arg(long, value_name = "CODES", requires = "http")]
+    pub expect_status: Option<String>,
+
+    /// Timeout in seconds or like 90s or 5m (0 = infinite) [default: 30]
+    #[arg(long, value_name = "DURATION", value_parser = format::parse_seconds)]
+    pub timeout: Option<Duration>,
+
+    /// Poll interval in milliseconds [default: 250]
+    #[arg(long)]
+    pub poll: Option<u64>,
 
-    /// Wait until the port reaches this state
-    #[arg(long, value_enum, default_value_t = WaitUntil::Down)]
-    pub until: WaitUntil,
+    /// Output format [default: table]
+    #[arg(long, value_enum, env = "PORTZAP_FORMAT")]
+    pub format: Option<Format>,
 
-    /// Timeout in seconds (0 = infinite)
-    #[arg(long, default_value_t = 30)]
-    pub timeout: u64,
+    #[command(flatten)]
+    pub remote: RemoteArgs,
 
-    /// Poll interval in milliseconds
-    #[arg(long, default_value_t = 250)]
-    pub poll: u64,
+    /// Under WSL, also watch listeners on the Windows side
+    #[arg(long, conflicts_with = "host")]
+    pub include_windows: bool,
+}
 
-    /// Output format
-    #[arg(long, value_enum, default_value_t = Format::Table)]
-    pub format: Format,
+/// `--host` for commands that can scan another machine.
+#[derive(Args, Debug, Default)]
+pub struct RemoteArgs {
+    /// Scan another machine over SSH, e.g. dev1 or alice@dev1
+    #[arg(long, value_name = "SSH_TARGET")]
+    pub host: Option<String>,
+
+    /// Pass an option to ssh for --host, e.g. ProxyJump=bastion (repeatable)
+    #[arg(long = "ssh-option", value_name = "OPTION", requires = "host")]
+    pub ssh_options: Vec<String>,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
@@ -210,11 +773,55 @@ pub enum WaitUntil {
     Down,
     /// Wait until port is occupied (a process is listening)
     Up,
+    /// Wait until port goes free and is then occupied again
+    Restart,
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the effective config, including defaults
+    List,
+
+    /// Print the value of a single key
+    Get {
+        /// Config key, e.g. theme or default_signal
+        key: String,
+    },
+
+    /// Validate a value and store it in the config file
+    Set {
+        /// Config key, e.g. theme or default_signal
+        key: String,
+        /// New value, e.g. light, 10, true or [3000, 8080]
+        value: String,
+    },
+
+    /// Print the config file location
+    Path,
+
+    /// Open the config file in $EDITOR
+    Edit,
 }
 
 #[derive(Args, Debug)]
 pub struct CompletionsArgs {
     /// Shell to generate completions for
-    #[arg(value_name = "SHELL")]
-    pub shell: Shell,
+    #[arg(value_name = "SHELL", value_enum)]
+    pub shell: CompletionShell,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionShell {
+    Bash,
+    Elvish,
+    Fish,
+    Powershell,
+    Zsh,
+    Nushell,
 }
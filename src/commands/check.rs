@@ -0,0 +1,105 @@
+use crate::errors::Result;
+use crate::output::{self, OutputFormat, Reporter};
+use crate::process::{self, PortSpec};
+use crate::scanner::{create_scanner, PortScanner, ScanWarning};
+use std::collections::HashSet;
+
+pub struct CheckOptions {
+    pub ports: Vec<PortSpec>,
+    /// Pass when the ports are free rather than occupied
+    pub free: bool,
+    /// Pass when any one port qualifies rather than all of them
+    pub any: bool,
+    pub format: OutputFormat,
+}
+
+pub fn execute(opts: CheckOptions) -> Result<bool> {
+    let mut reporter = output::reporter(opts.format);
+    execute_with(create_scanner().as_ref(), reporter.as_mut(), opts)
+}
+
+/// `execute` against a given scanner and reporter, so tests can supply their own.
+/// Only JSON output prints anything: whether each port qualified.
+pub fn execute_with(
+    scanner: &dyn PortScanner,
+    reporter: &mut dyn Reporter,
+    opts: CheckOptions,
+) -> Result<bool> {
+    let scan = scanner.scan_ports(&opts.ports)?;
+    // A socket whose owner we can't see still makes the port busy
+    let unknown = scan.warnings.iter().filter_map(|w| match w {
+        ScanWarning::OwnerUnknown { port } => Some(*port),
+        _ => None,
+    });
+    let occupied: HashSet<u16> = scan
+        .processes
+        .iter()
+        .map(|p| p.port)
+        .chain(unknown)
+        .collect();
+
+    let mut results = serde_json::Map::new();
+    for port in process::unique_ports(&opts.ports) {
+        let qualifies = occupied.contains(&port) != opts.free;
+        results.insert(port.to_string(), qualifies.into());
+    }
+
+    let mut outcomes = results.values().map(|v| v.as_bool() == Some(true));
+    let passed = if opts.any {
+        outcomes.any(|ok| ok)
+    } else {
+        outcomes.all(|ok| ok)
+    };
+    reporter.result_json(&results.into());
+    Ok(passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::VecReporter;
+    use crate::scanner::mock::{process, MockScanner};
+
+    fn check(free: bool, any: bool) -> (bool, Vec<String>) {
+        let scanner = MockScanner::new(vec![process(1, "postgres", 5432)]);
+        let mut reporter = VecReporter::default();
+        let opts = CheckOptions {
+            ports: vec![PortSpec::Single(5432), PortSpec::range(3000, 3001)],
+            free,
+            any,
+            format: OutputFormat::Json,
+        };
+        let passed = execute_with(&scanner, &mut reporter, opts).unwrap();
+        (passed, reporter.lines)
+    }
+
+    #[test]
+    fn all_ports_must_qualify_unless_any() {
+        let (passed, lines) = check(false, false);
+        assert!(!passed);
+        assert_eq!(lines, [r#"json: {"5432":true,"3000":false,"3001":false}"#]);
+        assert!(check(false, true).0);
+
+        let (passed, lines) = check(true, false);
+        assert!(!passed);
+        assert_eq!(lines, [r#"json: {"5432":false,"3000":true,"3001":true}"#]);
+        assert!(check(true, true).0);
+    }
+
+    #[test]
+    fn ports_with_an_unknown_owner_are_in_use() {
+        let scanner = MockScanner::new(Vec::new())
+            .with_warnings(vec![ScanWarning::OwnerUnknown { port: 5432 }]);
+        let run = |free| {
+            let opts = CheckOptions {
+                ports: vec![PortSpec::Single(5432)],
+                free,
+                any: false,
+                format: OutputFormat::Json,
+            };
+            execute_with(&scanner, &mut VecReporter::default(), opts).unwrap()
+        };
+        assert!(run(false));
+        assert!(!run(true));
+    }
+}
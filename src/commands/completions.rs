@@ -1,10 +1,34 @@
-use clap::CommandFactory;
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, CommandFactory};
 use clap_complete::{generate, Shell};
+use clap_complete_nushell::Nushell;
 
 use crate::cli::Cli;
 
-pub fn execute(shell: Shell) {
+/// The shells clap_complete supports, plus Nushell from clap_complete_nushell.
+pub enum CompletionShell {
+    Clap(Shell),
+    Nushell,
+}
+
+/// Generate completions on stdout. Alias names from config.toml are offered wherever a
+/// port is expected, so the script needs regenerating after aliases change.
+pub fn execute(shell: CompletionShell, aliases: Vec<String>) {
     let mut cmd = Cli::command();
+    if !aliases.is_empty() {
+        let offer_aliases = |arg: Arg| arg.value_parser(PossibleValuesParser::new(aliases.clone()));
+        cmd = cmd.mut_arg("ports", offer_aliases);
+        for sub in ["kill", "list", "watch"] {
+            cmd = cmd.mut_subcommand(sub, |c| c.mut_arg("ports", offer_aliases));
+        }
+        for sub in ["wait", "info"] {
+            cmd = cmd.mut_subcommand(sub, |c| c.mut_arg("port", offer_aliases));
+        }
+    }
     let name = cmd.get_name().to_string();
-    generate(shell, &mut cmd, name, &mut std::io::stdout());
+    let mut out = std::io::stdout();
+    match shell {
+        CompletionShell::Clap(shell) => generate(shell, &mut cmd, name, &mut out),
+        CompletionShell::Nushell => generate(Nushell, &mut cmd, name, &mut out),
+    }
 }
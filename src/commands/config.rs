@@ -0,0 +1,140 @@
+use crate::config::{Config, KEYS};
+use crate::errors::{KillportError, Result};
+use crate::output::{self, OutputFormat};
+use owo_colors::OwoColorize;
+use std::path::PathBuf;
+use std::process::Command;
+
+pub enum ConfigAction {
+    List,
+    Get(String),
+    Set(String, String),
+    Path,
+    Edit,
+}
+
+pub struct ConfigOptions {
+    pub action: ConfigAction,
+    pub format: OutputFormat,
+}
+
+pub fn execute(opts: ConfigOptions) -> Result<()> {
+    match opts.action {
+        ConfigAction::List => list(opts.format),
+        ConfigAction::Get(key) => get(&key, opts.format),
+        ConfigAction::Set(key, value) => set(&key, &value, opts.format),
+        ConfigAction::Path => {
+            let path = config_path()?;
+            match opts.format {
+                OutputFormat::Json => println!("{}", serde_json::json!({ "path": path })),
+                _ => println!("{}", path.display()),
+            }
+            Ok(())
+        }
+        ConfigAction::Edit => edit(),
+    }
+}
+
+fn list(format: OutputFormat) -> Result<()> {
+    let config = Config::load();
+    let entries = KEYS
+        .iter()
+        .map(|key| Ok((*key, config.get(key).map_err(KillportError::Config)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    match format {
+        OutputFormat::Json => {
+            let map: serde_json::Map<_, _> = entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), serde_json::json!(value)))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&map).unwrap_or_default());
+        }
+        _ => {
+            for (key, value) in entries {
+                match value {
+                    Some(value) => println!("{key} = {value}"),
+                    None => println!("{}", format!("# {key} (not set)").dimmed()),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn get(key: &str, format: OutputFormat) -> Result<()> {
+    let value = Config::load().get(key).map_err(KillportError::Config)?;
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::json!({ "key": key, "value": value }));
+        }
+        _ => match value {
+            // Print strings without TOML quoting so the output is easy to use in scripts
+            Some(toml::Value::String(s)) => println!("{s}"),
+            Some(value) => println!("{value}"),
+            None => return Err(KillportError::Config(format!("{key} is not set"))),
+        },
+    }
+    Ok(())
+}
+
+fn set(key: &str, raw: &str, format: OutputFormat) -> Result<()> {
+    let (path, value) = Config::set(key, raw).map_err(KillportError::Config)?;
+    match format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::json!({ "key": key, "value": value, "path": path })
+            );
+        }
+        _ => eprintln!("{} Set {key} = {value} in {}", "✓".green(), path.display()),
+    }
+    Ok(())
+}
+
+fn edit() -> Result<()> {
+    let path = config_path()?;
+    if !path.exists() {
+        Config::default()
+            .save()
+            .map_err(|e| KillportError::Config(e.to_string()))?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| default_editor().to_string());
+    // $EDITOR may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| KillportError::Config("$EDITOR is empty".into()))?;
+    let status = Command::new(program).args(parts).arg(&path).status()?;
+    if !status.success() {
+        return Err(KillportError::Config(format!(
+            "{editor} exited with {status}"
+        )));
+    }
+
+    let contents = std::fs::read_to_string(&path)?;
+    if let Err(e) = toml::from_str::<Config>(&contents) {
+        output::print_warning(&format!(
+            "{} is not a valid config: {}",
+            path.display(),
+            e.message()
+        ));
+    }
+    Ok(())
+}
+
+fn config_path() -> Result<PathBuf> {
+    Config::config_path()
+        .ok_or_else(|| KillportError::Config("could not determine config directory".into()))
+}
+
+fn default_editor() -> &'static str {
+    if cfg!(windows) {
+        "notepad"
+    } else {
+        "vi"
+    }
+}
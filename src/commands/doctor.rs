@@ -0,0 +1,302 @@
+use crate::commands::version::VersionInfo;
+use crate::errors::Result;
+use crate::killer::{self, KillConfig};
+use crate::output::OutputFormat;
+use crate::process::{KillSignal, ProcessInfo, Protocol};
+use crate::scanner::create_scanner;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::net::TcpListener;
+use std::time::Duration;
+
+pub struct DoctorOptions {
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    version: VersionInfo,
+    platform: &'static str,
+    checks: Vec<Check>,
+}
+
+/// Run every check and print the report. Returns false if any check failed.
+pub fn execute(opts: DoctorOptions) -> Result<bool> {
+    let mut checks = vec![check_privileges()];
+    checks.extend(check_process_access());
+    #[cfg(target_os = "linux")]
+    checks.push(check_hidepid());
+    checks.push(check_signal_delivery());
+    checks.push(check_self_listener());
+
+    let ok = checks.iter().all(|c| c.status != Status::Fail);
+    let report = Report {
+        version: VersionInfo::current(),
+        platform: std::env::consts::OS,
+        checks,
+    };
+
+    match opts.format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_default()
+            );
+        }
+        _ => print_report(&report),
+    }
+    Ok(ok)
+}
+
+fn print_report(report: &Report) {
+    println!(
+        "portzap {} ({}) on {}",
+        report.version.version, report.version.commit, report.platform
+    );
+    for check in &report.checks {
+        let marker = match check.status {
+            Status::Pass => "✓".green().to_string(),
+            Status::Warn => "!".yellow().to_string(),
+            Status::Fail => "✗".red().to_string(),
+        };
+        println!("{marker} {:<24} {}", check.name.bold(), check.detail);
+        if let Some(hint) = &check.hint {
+            println!("  {} {}", "→".dimmed(), hint.dimmed());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn check_privileges() -> Check {
+    let euid = nix::unistd::geteuid();
+    if euid.is_root() {
+        Check::new("privileges", Status::Pass, "running as root")
+    } else {
+        Check::new(
+            "privileges",
+            Status::Warn,
+            format!("running as uid {euid}, not root"),
+        )
+        .hint("run with sudo to see and kill processes owned by other users")
+    }
+}
+
+#[cfg(windows)]
+fn check_privileges() -> Check {
+    Check::new("privileges", Status::Pass, "not checked on Windows")
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn check_process_access() -> Vec<Check> {
+    #[cfg(target_os = "linux")]
+    use crate::platform::linux::probe_process_access;
+    #[cfg(target_os = "macos")]
+    use crate::platform::macos::probe_process_access;
+
+    let access = match probe_process_access() {
+        Ok(access) => access,
+        Err(e) => {
+            return vec![
+                Check::new("process enumeration", Status::Fail, e.to_string()).hint(
+                    "portzap needs to list processes; check sandboxing or container restrictions",
+                ),
+            ]
+        }
+    };
+
+    let enumeration = Check::new(
+        "process enumeration",
+        Status::Pass,
+        format!("{} processes visible", access.visible),
+    );
+    let others = if access.unreadable > 0 {
+        Check::new(
+            "other users' sockets",
+            Status::Warn,
+            format!(
+                "cannot inspect {} of {} processes owned by other users",
+                access.unreadable, access.other_users
+            ),
+        )
+        .hint("run with sudo to see processes owned by other users")
+    } else {
+        Check::new(
+            "other users' sockets",
+            Status::Pass,
+            format!(
+                "{} processes owned by other users are readable",
+                access.other_users
+            ),
+        )
+    };
+    vec![enumeration, others]
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn check_process_access() -> Vec<Check> {
+    vec![Check::new(
+        "process enumeration",
+        Status::Fail,
+        "port scanning is not implemented on this platform",
+    )]
+}
+
+#[cfg(target_os = "linux")]
+fn check_hidepid() -> Check {
+    match crate::platform::linux::proc_hidepid() {
+        None => Check::new(
+            "/proc visibility",
+            Status::Pass,
+            "/proc is not mounted with hidepid",
+        ),
+        Some(_) if nix::unistd::geteuid().is_root() => Check::new(
+            "/proc visibility",
+            Status::Pass,
+            "/proc uses hidepid, but root sees every process",
+        ),
+        Some(value) => Check::new(
+            "/proc visibility",
+            Status::Warn,
+            format!("/proc is mounted with hidepid={value}; other users' processes are hidden"),
+        )
+        .hint(
+            "run with sudo, or ask an admin to add you to the group named by /proc's gid= option",
+        ),
+    }
+}
+
+/// Spawn a short-lived child and make sure we can signal it.
+#[cfg(unix)]
+fn check_signal_delivery() -> Check {
+    let mut child = match std::process::Command::new("sleep").arg("30").spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            return Check::new(
+                "signal delivery",
+                Status::Warn,
+                format!("could not spawn a test process: {e}"),
+            )
+        }
+    };
+    let process = ProcessInfo {
+        pid: child.id(),
+        name: "sleep".into(),
+        port: 0,
+        protocol: Protocol::Tcp,
+        command: None,
+        user: None,
+        uid: None,
+        container: None,
+        managed_by: None,
+        ppid: None,
+        parent_name: None,
+        started_at: None,
+        cwd: None,
+        host: None,
+        origin: None,
+    };
+    let config = KillConfig {
+        signal: KillSignal::Term,
+        graceful: false,
+        graceful_timeout: Some(Duration::from_secs(1)),
+        dry_run: false,
+        ignore_manager: false,
+        group: false,
+    };
+    let result = killer::kill_process(&process, &config);
+    let _ = child.kill();
+    let _ = child.wait();
+
+    if result.success {
+        Check::new(
+            "signal delivery",
+            Status::Pass,
+            "sent SIGTERM to a test process",
+        )
+    } else {
+        Check::new(
+            "signal delivery",
+            Status::Fail,
+            format!(
+                "could not signal our own child: {}",
+                result.error.unwrap_or_default()
+            ),
+        )
+        .hint("a security policy (seccomp, AppArmor, SELinux) may be blocking kill(2)")
+    }
+}
+
+#[cfg(windows)]
+fn check_signal_delivery() -> Check {
+    Check::new(
+        "signal delivery",
+        Status::Fail,
+        "killing processes is not implemented on Windows",
+    )
+}
+
+/// Bind a random port ourselves and check the scanner attributes it to us.
+fn check_self_listener() -> Check {
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        Err(e) => {
+            return Check::new(
+                "self-test listener",
+                Status::Warn,
+                format!("could not bind a test port: {e}"),
+            )
+        }
+    };
+    let port = match listener.local_addr() {
+        Ok(addr) => addr.port(),
+        Err(e) => return Check::new("self-test listener", Status::Warn, e.to_string()),
+    };
+
+    match create_scanner().find_processes_by_port(port) {
+        Ok(found) if found.iter().any(|p| p.pid == std::process::id()) => Check::new(
+            "self-test listener",
+            Status::Pass,
+            format!("found our own listener on port {port}"),
+        ),
+        Ok(_) => Check::new(
+            "self-test listener",
+            Status::Fail,
+            format!("bound port {port} but the scanner did not find it"),
+        )
+        .hint("please report this with the output of `portzap doctor --format json`"),
+        Err(e) => Check::new("self-test listener", Status::Fail, e.to_string()),
+    }
+}
@@ -1,45 +1,178 @@
 use crate::errors::Result;
-use crate::output::OutputFormat;
-use crate::scanner::create_scanner;
+use crate::format;
+use crate::output::{self, OutputFormat, Reporter};
+use crate::progress::Progress;
+use crate::scanner::{create_scanner, PortScanner};
+use serde_json::json;
+use std::io::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a hold checks whether it should end early.
+const HOLD_POLL: Duration = Duration::from_millis(50);
 
 pub struct FreeOptions {
     pub start: u16,
     pub max: u16,
     pub format: OutputFormat,
+    /// Don't show a progress bar during long searches
+    pub quiet: bool,
+    /// Keep the port bound for this long after printing it
+    pub hold: Option<Duration>,
+    /// With `hold`, also release the port once stdin closes
+    pub hold_until_stdin_close: bool,
 }
 
 pub fn execute(opts: FreeOptions) -> Result<Option<u16>> {
-    let scanner = create_scanner();
+    let mut reporter = output::reporter(opts.format);
+    execute_with(create_scanner().as_ref(), reporter.as_mut(), opts)
+}
 
+/// `execute` against a given scanner and reporter, so tests can supply their own.
+pub fn execute_with(
+    scanner: &dyn PortScanner,
+    reporter: &mut dyn Reporter,
+    opts: FreeOptions,
+) -> Result<Option<u16>> {
+    let total = usize::from(opts.max.saturating_sub(opts.start)) + 1;
+    let mut progress = Progress::start(total, opts.quiet);
     for port in opts.start..=opts.max {
+        if let Some(progress) = &mut progress {
+            progress.tick(port);
+        }
         let processes = scanner.find_processes_by_port(port)?;
-        if processes.is_empty() {
-            match opts.format {
-                OutputFormat::Json => {
-                    println!(r#"{{"port": {port}}}"#);
-                }
-                _ => {
-                    println!("{port}");
+        if !processes.is_empty() {
+            continue;
+        }
+        // A hold needs the port bound, which also catches sockets the scan
+        // can't see
+        let listener = match opts.hold {
+            Some(_) => match bind_for_hold(port) {
+                Ok(listeners) => Some(listeners),
+                Err(e) => {
+                    tracing::debug!(port, error = %e, "can't bind, trying the next port");
+                    continue;
                 }
-            }
-            return Ok(Some(port));
+            },
+            None => None,
+        };
+        drop(progress);
+        reporter.result_text(&port.to_string());
+        reporter.result_json(&json!({ "port": port }));
+        if let (Some(listener), Some(duration)) = (listener, opts.hold) {
+            // Whoever reads the port may be waiting on it before we exit
+            let _ = io::stdout().flush();
+            reporter.note(&format!(
+                "Holding port {port} for {} (Ctrl+C releases it)",
+                format::humanize_duration(duration)
+            ));
+            hold(duration, opts.hold_until_stdin_close);
+            drop(listener);
+            reporter.note(&format!("Released port {port}"));
         }
+        return Ok(Some(port));
     }
 
-    // No free port found
-    match opts.format {
-        OutputFormat::Json => {
-            println!(
-                r#"{{"port": null, "error": "no free port found in range {}..={}"}}"#,
-                opts.start, opts.max
-            );
+    drop(progress);
+    let range = format!("{}..={}", opts.start, opts.max);
+    reporter.note(&format!("No free port found in range {range}"));
+    reporter.result_json(&json!({
+        "port": null,
+        "error": format!("no free port found in range {range}"),
+    }));
+    Ok(None)
+}
+
+/// Bind `port` on 0.0.0.0 and, where the OS allows it alongside, on the
+/// loopback and IPv6 addresses too. On macOS and the BSDs a server with
+/// SO_REUSEADDR, which is most of them, can still bind 127.0.0.1 while the
+/// wildcard address is taken; Linux refuses that, so the extra binds fail
+/// there and aren't needed.
+fn bind_for_hold(port: u16) -> io::Result<Vec<TcpListener>> {
+    let mut listeners = vec![TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?];
+    let also: [IpAddr; 3] = [
+        Ipv4Addr::LOCALHOST.into(),
+        Ipv6Addr::UNSPECIFIED.into(),
+        Ipv6Addr::LOCALHOST.into(),
+    ];
+    for addr in also {
+        match TcpListener::bind((addr, port)) {
+            Ok(listener) => listeners.push(listener),
+            Err(e) => tracing::debug!(%addr, port, error = %e, "not holding this address too"),
         }
-        _ => {
-            eprintln!(
-                "No free port found in range {}..={}",
-                opts.start, opts.max
-            );
+    }
+    Ok(listeners)
+}
+
+/// Wait out `duration`, or less on SIGINT, SIGTERM or, if asked, stdin
+/// closing.
+fn hold(duration: Duration, until_stdin_close: bool) {
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGINT, stop.clone())
+        .expect("failed to register SIGINT handler");
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, stop.clone())
+        .expect("failed to register SIGTERM handler");
+    if until_stdin_close {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let _ = io::copy(&mut io::stdin().lock(), &mut io::sink());
+            stop.store(true, Ordering::Relaxed);
+        });
+    }
+
+    let deadline = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
         }
+        thread::sleep(left.min(HOLD_POLL));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::VecReporter;
+    use crate::scanner::mock::{process, MockScanner};
+
+    fn options(start: u16, max: u16) -> FreeOptions {
+        FreeOptions {
+            start,
+            max,
+            format: OutputFormat::Plain,
+            quiet: true,
+            hold: None,
+            hold_until_stdin_close: false,
+        }
+    }
+
+    #[test]
+    fn reports_the_first_free_port() {
+        let scanner = MockScanner::new(vec![process(1, "node", 3000)]);
+        let mut reporter = VecReporter::default();
+        let port = execute_with(&scanner, &mut reporter, options(3000, 3005)).unwrap();
+        assert_eq!(port, Some(3001));
+        assert_eq!(reporter.lines, ["text: 3001", r#"json: {"port":3001}"#]);
+    }
+
+    #[test]
+    fn reports_a_full_range() {
+        let scanner = MockScanner::new(vec![process(1, "node", 3000), process(2, "vite", 3001)]);
+        let mut reporter = VecReporter::default();
+        assert_eq!(
+            execute_with(&scanner, &mut reporter, options(3000, 3001)).unwrap(),
+            None
+        );
+        assert_eq!(
+            reporter.lines,
+            [
+                "note: No free port found in range 3000..=3001",
+                r#"json: {"port":null,"error":"no free port found in range 3000..=3001"}"#,
+            ]
+        );
     }
-    Ok(None)
 }
@@ -0,0 +1,127 @@
+use crate::errors::{KillportError, Result};
+use crate::history::{self, History, HistoryEntry};
+use crate::output::OutputFormat;
+use crate::process::PortSpec;
+use chrono::Local;
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::{ContentArrangement, Table};
+use owo_colors::OwoColorize;
+
+pub struct HistoryOptions {
+    pub history: History,
+    /// Whether new kills are being recorded (`history = true`)
+    pub enabled: bool,
+    pub since: Option<String>,
+    pub port: Option<PortSpec>,
+    pub clear: bool,
+    pub format: OutputFormat,
+}
+
+pub fn execute(opts: HistoryOptions) -> Result<()> {
+    let history = &opts.history;
+    if opts.clear {
+        history
+            .clear()
+            .map_err(|e| KillportError::History(format!("failed to clear history: {e}")))?;
+        eprintln!("{} Cleared {}", "✓".green(), history.path().display());
+        return Ok(());
+    }
+
+    let since = opts
+        .since
+        .as_deref()
+        .map(|raw| history::parse_since(raw, Local::now()))
+        .transpose()
+        .map_err(KillportError::Usage)?;
+    let entries: Vec<HistoryEntry> = history
+        .read()
+        .map_err(|e| {
+            KillportError::History(format!("failed to read {}: {e}", history.path().display()))
+        })?
+        .into_iter()
+        .filter(|e| {
+            opts.port
+                .as_ref()
+                .map_or(true, |spec| spec.contains(e.port))
+        })
+        .filter(|e| match (since, e.time()) {
+            (Some(since), Some(at)) => at >= since,
+            (Some(_), None) => false,
+            (None, _) => true,
+        })
+        .collect();
+
+    match opts.format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&entries).unwrap_or_default()
+            );
+        }
+        OutputFormat::Plain => {
+            for e in &entries {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    e.timestamp,
+                    e.port,
+                    e.pid,
+                    e.name,
+                    e.signal,
+                    if e.success { "ok" } else { "failed" },
+                    source_label(e),
+                );
+            }
+        }
+        _ => print_table(&entries),
+    }
+
+    if entries.is_empty() && opts.format != OutputFormat::Json {
+        if opts.enabled {
+            eprintln!("No kills recorded");
+        } else {
+            eprintln!("History is off; enable it with `portzap config set history true`");
+        }
+    }
+    Ok(())
+}
+
+fn source_label(e: &HistoryEntry) -> String {
+    serde_json::to_value(e.source)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn print_table(entries: &[HistoryEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL_CONDENSED)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            "Time", "Port", "PID", "Name", "Signal", "Result", "Source",
+        ]);
+    for e in entries {
+        let time = e
+            .time()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| e.timestamp.clone());
+        let result = if e.success {
+            "ok".to_string()
+        } else {
+            e.error.clone().unwrap_or_else(|| "failed".into())
+        };
+        table.add_row(vec![
+            time,
+            e.port.to_string(),
+            e.pid.to_string(),
+            e.name.clone(),
+            e.signal.clone(),
+            result,
+            source_label(e),
+        ]);
+    }
+    println!("{table}");
+}
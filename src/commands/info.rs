@@ -0,0 +1,116 @@
+use crate::errors::Result;
+use crate::output::{self, OutputFormat};
+use crate::portinfo::{self, PortRange, Service};
+use crate::process::ProcessInfo;
+use crate::scanner::{create_scanner, PortScanner};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+pub struct InfoOptions {
+    pub port: u16,
+    pub format: OutputFormat,
+}
+
+#[derive(Serialize)]
+struct EphemeralRange {
+    start: u16,
+    end: u16,
+    contains_port: bool,
+}
+
+#[derive(Serialize)]
+struct PortReport {
+    port: u16,
+    services: Vec<Service>,
+    range: PortRange,
+    /// The OS's ephemeral range, if it could be read
+    ephemeral_range: Option<EphemeralRange>,
+    processes: Vec<ProcessInfo>,
+}
+
+pub fn execute(opts: InfoOptions) -> Result<()> {
+    execute_with(create_scanner().as_ref(), opts)
+}
+
+/// `execute` against a given scanner, so tests can supply their own.
+pub fn execute_with(scanner: &dyn PortScanner, opts: InfoOptions) -> Result<()> {
+    let port = opts.port;
+    let report = PortReport {
+        port,
+        services: portinfo::services_for(port),
+        range: PortRange::of(port),
+        ephemeral_range: portinfo::ephemeral_range().map(|(start, end)| EphemeralRange {
+            start,
+            end,
+            contains_port: (start..=end).contains(&port),
+        }),
+        processes: scanner.find_processes_by_port(port)?,
+    };
+
+    match opts.format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_default()
+            );
+        }
+        OutputFormat::Plain => {
+            println!("port\t{}", report.port);
+            for s in &report.services {
+                println!("service\t{}\t{}", s.name, s.protocols.join(","));
+            }
+            let (start, end) = report.range.bounds();
+            println!("range\t{}\t{start}-{end}", report.range.name());
+            if let Some(eph) = &report.ephemeral_range {
+                println!(
+                    "ephemeral\t{}-{}\t{}",
+                    eph.start, eph.end, eph.contains_port
+                );
+            }
+            output::print_processes(&report.processes, OutputFormat::Plain);
+        }
+        _ => print_report(&report),
+    }
+    Ok(())
+}
+
+fn print_report(report: &PortReport) {
+    println!("{} {}", "Port".bold(), report.port.bold());
+
+    if report.services.is_empty() {
+        println!("  service:    {}", "unknown".dimmed());
+    }
+    for s in &report.services {
+        let protocols = if s.protocols.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", s.protocols.join("/"))
+        };
+        match &s.description {
+            Some(d) => println!("  service:    {}{protocols} - {d}", s.name),
+            None => println!("  service:    {}{protocols}", s.name),
+        }
+    }
+
+    println!("  range:      {}", report.range);
+    match &report.ephemeral_range {
+        Some(eph) => println!(
+            "  ephemeral:  system range {}-{}; this port is {}",
+            eph.start,
+            eph.end,
+            if eph.contains_port {
+                "inside it and may be picked for outgoing connections"
+            } else {
+                "outside it"
+            }
+        ),
+        None => println!("  ephemeral:  {}", "unknown on this platform".dimmed()),
+    }
+
+    if report.processes.is_empty() {
+        println!("  in use:     {}", "no process is listening".dimmed());
+    } else {
+        println!("  in use:");
+        output::print_processes(&report.processes, OutputFormat::Table);
+    }
+}
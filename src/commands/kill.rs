@@ -1,65 +1,1462 @@
-use crate::errors::Result;
+use crate::config::Confirmation;
+use crate::errors::{exit_code, KillportError, Result};
+use crate::filter::NameFilter;
+use crate::format;
+use crate::history::{History, Source};
 use crate::interactive;
 use crate::killer::{self, KillConfig};
-use crate::output::{self, OutputFormat};
-use crate::process::{KillSignal, PortSpec};
-use crate::scanner::create_scanner;
-use std::time::Duration;
+use crate::output::{self, plural, OutputFormat, Reporter};
+use crate::platform;
+use crate::portinfo;
+use crate::process::{
+    self, KillErrorKind, KillResult, KillSignal, PortSpec, ProcessInfo, Protocol,
+};
+use crate::scanner::{self, create_scanner_for, without_self, PortScanner, PortScans, ScanWarning};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
 
 pub struct KillOptions {
     pub ports: Vec<PortSpec>,
     pub signal: KillSignal,
     pub graceful: bool,
-    pub graceful_timeout_secs: u64,
+    /// Zero waits for exit without escalating
+    pub graceful_timeout: Duration,
     pub dry_run: bool,
     pub interactive: bool,
+    /// What `interactive` does without a terminal
+    pub fallback: interactive::Fallback,
+    /// Signal socket-activated processes anyway
+    pub ignore_manager: bool,
+    /// Signal each target's process group instead of its PID
+    pub group: bool,
     pub format: OutputFormat,
+    /// Skip scan warnings and the summary after multi-port kills
+    pub quiet: bool,
+    /// Where to record kills, if history is enabled
+    pub history: Option<History>,
+    /// Kill this process instead of everything on `ports`, which then only
+    /// have to include one of its ports
+    pub pid: Option<u32>,
+    /// Abort a port unless everything on it matches; empty for no check
+    pub require_name: NameFilter,
+    /// Target ports below 1024 too
+    pub allow_privileged: bool,
+    /// Never ask before killing root's or another user's processes
+    pub yes: bool,
+    /// Under WSL, add Windows-side listeners, ended with taskkill.exe
+    pub include_windows: bool,
+    pub confirmation: Confirmation,
+    /// Kill everything listening this selects, instead of `ports`
+    pub all: Option<AllTargets>,
 }
 
-pub fn execute(opts: KillOptions) -> Result<bool> {
-    let scanner = create_scanner();
+/// `kill --all`: which of the listening processes to kill.
+#[derive(Debug, Default)]
+pub struct AllTargets {
+    pub owners: Owners,
+    /// Spared whatever else they match
+    pub except_names: NameFilter,
+    /// Only sockets of this protocol
+    pub protocol: Option<Protocol>,
+    /// `protected_ports` from the config
+    pub protected_ports: Vec<u16>,
+    /// `protected_names` from the config (case-insensitive)
+    pub protected_names: Vec<String>,
+}
+
+/// Whose processes `kill --all` targets.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Owners {
+    /// The current user's
+    #[default]
+    Me,
+    /// These users', by name or uid
+    Users(Vec<String>),
+    Everyone,
+}
+
+impl AllTargets {
+    fn selects(&self, p: &ProcessInfo) -> bool {
+        let owner = match &self.owners {
+            Owners::Me => owned_by_me(p),
+            Owners::Users(users) => users.iter().any(|user| {
+                p.user.as_deref() == Some(user.as_str())
+                    || p.uid.is_some_and(|uid| uid.to_string() == *user)
+            }),
+            Owners::Everyone => true,
+        };
+        owner
+            && self
+                .protocol
+                .map_or(true, |protocol| p.protocol == protocol)
+            && (self.except_names.is_empty() || !self.except_names.matches(p))
+    }
+
+    fn protects(&self, p: &ProcessInfo) -> bool {
+        self.protected_ports.contains(&p.port)
+            || self
+                .protected_names
+                .iter()
+                .any(|n| n.eq_ignore_ascii_case(&p.name))
+    }
+}
+
+/// How many distinct names `kill --all` spells out before "and N more".
+const CONFIRM_NAMES: usize = 5;
+
+/// How a kill run ended; `main` turns it into the exit status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillOutcome {
+    Success,
+    /// At least one process could not be killed
+    Failed,
+    /// Every process we tried to kill belongs to someone we may not signal
+    PermissionDenied,
+    /// A port was in use, but by a process we aren't allowed to see
+    OwnerUnknown,
+}
+
+impl KillOutcome {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            KillOutcome::Success => exit_code::SUCCESS,
+            KillOutcome::Failed => exit_code::FAILURE,
+            KillOutcome::PermissionDenied | KillOutcome::OwnerUnknown => {
+                exit_code::PERMISSION_DENIED
+            }
+        }
+    }
+}
+
+/// Counts across every port of a run, for the summary and the outcome.
+#[derive(Debug, Default)]
+struct Tally {
+    attempted: usize,
+    killed: usize,
+    failed: usize,
+    denied: usize,
+    /// Ports where at least one process was killed
+    ports_hit: usize,
+    empty_ports: usize,
+    /// Ports a safety check kept us from touching
+    refused: usize,
+    /// Privileged ports skipped without `--allow-privileged`
+    privileged: usize,
+    owner_unknown: bool,
+}
+
+/// The `"summary"` object of JSON output.
+#[derive(Debug, Serialize)]
+struct Summary {
+    killed: usize,
+    failed: usize,
+    empty_ports: usize,
+}
+
+/// Everything a kill run prints in JSON mode, as one document.
+#[derive(Debug, Default, Serialize)]
+struct KillReport {
+    results: Vec<Attempt>,
+    /// Requested ports with nothing listening
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    empty_ports: Vec<u16>,
+    /// Ports in use by a process we aren't allowed to see
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    owner_unknown: Vec<u16>,
+    /// Ports left alone because a safety check failed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    refused: Vec<Refusal>,
+    /// Ports below 1024, skipped without `--allow-privileged`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    privileged_ports: Vec<u16>,
+    /// Ports `kill --all` spared for `protected_ports`/`protected_names`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    protected: Vec<u16>,
+    /// What the scans couldn't see, so an empty port may not be empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<ScanWarning>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<Summary>,
+    /// The command to re-run as root after permission errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<String>,
+}
+
+/// One kill attempt. A process holding several requested ports is signalled
+/// once, and its entry lists them all.
+#[derive(Debug, Serialize)]
+struct Attempt {
+    #[serde(flatten)]
+    result: KillResult,
+    /// The requested ports the process was found on, in the order visited
+    ports: Vec<u16>,
+}
+
+/// A port nothing was killed on, and why.
+#[derive(Debug, Serialize)]
+struct Refusal {
+    port: u16,
+    reason: String,
+    /// The processes that failed the check
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    processes: Vec<ProcessInfo>,
+}
+
+impl Tally {
+    fn add(&mut self, result: &KillResult) {
+        self.attempted += 1;
+        if result.success {
+            self.killed += 1;
+        } else {
+            self.failed += 1;
+            if result.error_kind == Some(KillErrorKind::PermissionDenied) {
+                self.denied += 1;
+            }
+        }
+    }
+
+    /// A rollup only helps when there was more than one thing to look at.
+    fn wants_summary(&self, ports: usize) -> bool {
+        ports > 1 || self.attempted > 1
+    }
+
+    fn summary(&self) -> Summary {
+        Summary {
+            killed: self.killed,
+            failed: self.failed,
+            empty_ports: self.empty_ports,
+        }
+    }
+
+    /// "Zapped 12 processes across 5 ports, 2 failed (permission denied),
+    /// 3 ports had nothing listening"
+    fn message(&self, dry_run: bool) -> String {
+        let verb = if dry_run { "Would zap" } else { "Zapped" };
+        let mut msg = format!(
+            "{verb} {} across {}",
+            plural(self.killed, "process", "processes"),
+            plural(self.ports_hit, "port", "ports")
+        );
+        if self.failed > 0 {
+            msg.push_str(&format!(", {} failed", self.failed));
+            if self.denied == self.failed {
+                msg.push_str(" (permission denied)");
+            } else if self.denied > 0 {
+                msg.push_str(&format!(" ({} permission denied)", self.denied));
+            }
+        }
+        if self.empty_ports > 0 {
+            msg.push_str(&format!(
+                ", {} had nothing listening",
+                plural(self.empty_ports, "port", "ports")
+            ));
+        }
+        if self.refused > 0 {
+            msg.push_str(&format!(
+                ", {} refused",
+                plural(self.refused, "port", "ports")
+            ));
+        }
+        if self.privileged > 0 {
+            msg.push_str(&format!(
+                ", {} skipped",
+                plural(self.privileged, "privileged port", "privileged ports")
+            ));
+        }
+        msg
+    }
+
+    /// Every requested port was privileged, so nothing else was looked at.
+    fn only_privileged(&self) -> bool {
+        self.privileged > 0 && self.attempted == 0 && self.empty_ports == 0 && !self.owner_unknown
+    }
+
+    fn outcome(&self) -> KillOutcome {
+        if self.attempted > 0 && self.denied == self.attempted {
+            KillOutcome::PermissionDenied
+        } else if self.failed > 0 || self.refused > 0 || self.only_privileged() {
+            KillOutcome::Failed
+        } else if self.owner_unknown {
+            KillOutcome::OwnerUnknown
+        } else {
+            KillOutcome::Success
+        }
+    }
+}
+
+pub fn execute(opts: KillOptions) -> Result<KillOutcome> {
+    let mut reporter = output::reporter(opts.format);
+    let scanner = without_self(create_scanner_for(None, opts.include_windows)?);
+    execute_with(scanner.as_ref(), reporter.as_mut(), opts)
+}
+
+/// `execute` against a given scanner and reporter, so tests can supply their own.
+pub fn execute_with(
+    scanner: &dyn PortScanner,
+    reporter: &mut dyn Reporter,
+    opts: KillOptions,
+) -> Result<KillOutcome> {
     let kill_config = KillConfig {
         signal: opts.signal,
         graceful: opts.graceful,
-        graceful_timeout: Duration::from_secs(opts.graceful_timeout_secs),
+        graceful_timeout: killer::graceful_timeout(opts.graceful_timeout),
         dry_run: opts.dry_run,
+        ignore_manager: opts.ignore_manager,
+        group: opts.group,
     };
 
-    let ports: Vec<u16> = opts.ports.iter().flat_map(|ps| ps.expand()).collect();
-    let mut all_success = true;
+    // With --pid, the ports only narrow down one target.
+    let mut requested: usize = match opts.pid {
+        Some(_) => 1,
+        None => process::unique_ports(&opts.ports).len(),
+    };
+    let json = opts.format == OutputFormat::Json;
+    let mut tally = Tally::default();
+    let mut report = KillReport::default();
 
-    for port in &ports {
-        let mut processes = scanner.find_processes_by_port(*port)?;
+    let can_pick = opts.interactive && interactive::has_terminal();
+    if opts.interactive && !can_pick && opts.fallback == interactive::Fallback::Fail {
+        return Err(KillportError::Usage(
+            "interactive mode requires a terminal; pass --non-interactive-fallback all or none \
+             to run without one"
+                .into(),
+        ));
+    }
+    let interactive = can_pick || opts.fallback == interactive::Fallback::None;
 
-        if processes.is_empty() {
-            output::print_no_process(*port, opts.format);
-            continue;
+    if let Some(all) = &opts.all {
+        let Some(targets) = all_targets(scanner, all, &opts, reporter, &mut report)? else {
+            return Ok(KillOutcome::Success);
+        };
+        requested = targets.len();
+        for processes in targets.values() {
+            kill_batch(
+                processes,
+                &opts,
+                &kill_config,
+                reporter,
+                &mut tally,
+                &mut report,
+            );
         }
-
-        // In interactive mode, let the user pick
-        if opts.interactive {
-            // Always show what we found first
-            if opts.format == OutputFormat::Table {
-                output::print_processes(&processes, OutputFormat::Table);
+    } else if let Some(pid) = opts.pid {
+        let process = vec![target_by_pid(scanner, pid, &opts.ports)?];
+        // Port 0 is a process with no sockets, which no port guard covers
+        let port = process[0].port;
+        if port != 0 && portinfo::is_privileged(port) && !opts.allow_privileged {
+            tally.privileged += 1;
+            report.privileged_ports.push(port);
+        } else if names_as_required(port, &process, &opts, &mut tally, &mut report) {
+            let process = confirm_foreign(port, process, &opts, &mut tally, &mut report);
+            kill_batch(
+                &process,
+                &opts,
+                &kill_config,
+                reporter,
+                &mut tally,
+                &mut report,
+            );
+        }
+    } else {
+        let mut scans = PortScans::new(scanner, &opts.ports)?;
+        for port in process::unique_ports(&opts.ports) {
+            if portinfo::is_privileged(port) && !opts.allow_privileged {
+                tally.privileged += 1;
+                report.privileged_ports.push(port);
+                continue;
             }
-            processes = interactive::select_processes(&processes);
+            let scanned_at = scans.taken_at().unwrap_or_else(Instant::now);
+            let scan = scans.scan(port)?;
+            scan.collect_warnings(&mut report.warnings);
+            if scan.owner_unknown(port) {
+                if json {
+                    report.owner_unknown.push(port);
+                } else {
+                    output::print_owner_unknown(port, opts.format);
+                }
+                tally.owner_unknown = true;
+                continue;
+            }
+            let mut processes = scan.processes;
+
             if processes.is_empty() {
+                if json {
+                    report.empty_ports.push(port);
+                } else {
+                    reporter.no_process(port);
+                }
+                tally.empty_ports += 1;
                 continue;
             }
+
+            if !names_as_required(port, &processes, &opts, &mut tally, &mut report) {
+                continue;
+            }
+
+            // In interactive mode, let the user pick
+            // (or, without a terminal and with --non-interactive-fallback none, pick nothing)
+            if interactive {
+                // Always show what we found first
+                if opts.format == OutputFormat::Table {
+                    reporter.processes(&processes);
+                }
+                processes = if can_pick {
+                    let picked = interactive::select_processes(&processes);
+                    still_on_port(scanner, port, picked, scanned_at)?
+                } else {
+                    Vec::new()
+                };
+                if processes.is_empty() {
+                    continue;
+                }
+            }
+
+            let processes = confirm_foreign(port, processes, &opts, &mut tally, &mut report);
+            kill_batch(
+                &processes,
+                &opts,
+                &kill_config,
+                reporter,
+                &mut tally,
+                &mut report,
+            );
+        }
+    }
+
+    if !json && !report.privileged_ports.is_empty() {
+        output::print_privileged_skipped(&report.privileged_ports);
+    }
+    if !json && !opts.quiet {
+        output::print_scan_warnings(&report.warnings);
+    }
+
+    if opts.format.is_id_list() {
+        output::print_killed_ids(
+            report
+                .results
+                .iter()
+                .map(|a| (&a.result, a.ports.as_slice())),
+            opts.format,
+        );
+    }
+
+    // Each denied kill is reported tersely; how to get past them is said once.
+    if tally.denied > 0 && !running_as_root() {
+        report.suggestion = Some(elevated_command(&opts));
+    }
+
+    let summarize = !opts.quiet && tally.wants_summary(requested);
+    if json {
+        report.summary = summarize.then(|| tally.summary());
+        reporter.result_json(&serde_json::to_value(&report).unwrap_or_default());
+    } else {
+        if summarize {
+            let message = tally.message(opts.dry_run);
+            if tally.failed > 0 || tally.refused > 0 {
+                reporter.note(&message.red().to_string());
+            } else if tally.empty_ports > 0 {
+                reporter.note(&message.yellow().to_string());
+            } else {
+                reporter.note(&message.green().to_string());
+            }
+        }
+        if let Some(suggestion) = &report.suggestion {
+            reporter.note(&format!(
+                "{} denied; to retry as root run: {}",
+                plural(tally.denied, "kill was", "kills were"),
+                suggestion.bold()
+            ));
+        }
+    }
+
+    Ok(tally.outcome())
+}
+
+/// The `portzap` arguments that repeat a kill with these options. Settings
+/// that only come from config.toml are left to the config.
+pub fn command_args(opts: &KillOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    // --pid, --require-name and --all only exist on the kill subcommand
+    if opts.pid.is_some() || !opts.require_name.is_empty() || opts.all.is_some() {
+        args.push("kill".to_string());
+    }
+    args.extend(opts.ports.iter().map(PortSpec::to_string));
+    if let Some(all) = &opts.all {
+        args.push("--all".into());
+        match &all.owners {
+            Owners::Me => {}
+            Owners::Users(users) => {
+                for user in users {
+                    args.extend(["--user".into(), user.clone()]);
+                }
+            }
+            Owners::Everyone => args.push("--include-others".into()),
+        }
+        for pattern in all.except_names.patterns() {
+            args.extend(["--except-name".into(), pattern.to_string()]);
+        }
+        if let Some(protocol) = all.protocol {
+            args.extend([
+                "--protocol".into(),
+                protocol.to_string().to_ascii_lowercase(),
+            ]);
+        }
+    }
+    if let Some(pid) = opts.pid {
+        args.extend(["--pid".into(), pid.to_string()]);
+    }
+    for pattern in opts.require_name.patterns() {
+        args.extend(["--require-name".into(), pattern.to_string()]);
+    }
+    if opts.signal == KillSignal::Kill && !opts.graceful {
+        args.push("--force".into());
+    } else {
+        if opts.signal != KillSignal::Term {
+            let signal = opts.signal.to_string();
+            let signal = signal.trim_start_matches("SIG").to_ascii_lowercase();
+            args.extend(["--signal".into(), signal]);
+        }
+        if !opts.graceful {
+            args.push("--no-graceful".into());
+        } else if opts.graceful_timeout != killer::DEFAULT_GRACEFUL_TIMEOUT {
+            args.extend([
+                "--timeout".into(),
+                format::duration_arg(opts.graceful_timeout),
+            ]);
+        }
+    }
+    let flags = [
+        (opts.ignore_manager, "--ignore-manager"),
+        (opts.dry_run, "--dry-run"),
+        (opts.interactive, "--interactive"),
+        (opts.group, "--group"),
+        (opts.quiet, "--quiet"),
+        (opts.allow_privileged, "--allow-privileged"),
+        (opts.yes, "--yes"),
+        (opts.include_windows, "--include-windows"),
+    ];
+    args.extend(
+        flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, flag)| flag.to_string()),
+    );
+    match opts.fallback {
+        interactive::Fallback::Fail => {}
+        interactive::Fallback::All => {
+            args.extend(["--non-interactive-fallback".into(), "all".into()])
+        }
+        interactive::Fallback::None => {
+            args.extend(["--non-interactive-fallback".into(), "none".into()])
+        }
+    }
+    if opts.format != OutputFormat::Table {
+        args.extend(["--format".into(), opts.format.name().into()]);
+    }
+    args
+}
+
+/// `sudo portzap 3000-3010 --no-graceful`, ready to paste into a shell.
+fn elevated_command(opts: &KillOptions) -> String {
+    let mut words = vec!["portzap".to_string()];
+    words.extend(command_args(opts).iter().map(|arg| shell_quote(arg)));
+    let command = words.join(" ");
+    if cfg!(windows) {
+        command
+    } else {
+        format!("sudo {command}")
+    }
+}
+
+/// `arg` as one POSIX shell word.
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+fn running_as_root() -> bool {
+    #[cfg(unix)]
+    {
+        nix::unistd::geteuid().is_root()
+    }
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+/// Drop picked processes that left `port` while the user was choosing. Only
+/// scans older than [`scanner::STALE_AFTER`] are checked again.
+fn still_on_port(
+    scanner: &dyn PortScanner,
+    port: u16,
+    picked: Vec<ProcessInfo>,
+    scanned_at: Instant,
+) -> Result<Vec<ProcessInfo>> {
+    if picked.is_empty() || scanned_at.elapsed() < scanner::STALE_AFTER {
+        return Ok(picked);
+    }
+    let current = scanner.find_processes_by_port(port)?;
+    let (kept, gone): (Vec<_>, Vec<_>) = picked
+        .into_iter()
+        .partition(|p| current.iter().any(|c| c.pid == p.pid));
+    for p in &gone {
+        tracing::debug!(pid = p.pid, port, "left the port while picking");
+    }
+    Ok(kept)
+}
+
+/// `kill --all`: everything listening that `all` selects, by port, once the
+/// user has seen the list and agreed. `None` when they said no.
+fn all_targets(
+    scanner: &dyn PortScanner,
+    all: &AllTargets,
+    opts: &KillOptions,
+    reporter: &mut dyn Reporter,
+    report: &mut KillReport,
+) -> Result<Option<BTreeMap<u16, Vec<ProcessInfo>>>> {
+    let ask = !opts.yes && !opts.dry_run;
+    if ask && !interactive::has_terminal() {
+        return Err(KillportError::Usage(
+            "kill --all asks before killing and there is no terminal to ask on; pass --yes \
+             to kill without asking, or --dry-run to see the targets"
+                .into(),
+        ));
+    }
+
+    let scan = scanner.scan_all()?;
+    scan.collect_warnings(&mut report.warnings);
+    let mut targets: BTreeMap<u16, Vec<ProcessInfo>> = BTreeMap::new();
+    for p in scan.processes.into_iter().filter(|p| all.selects(p)) {
+        if all.protects(&p) {
+            if !report.protected.contains(&p.port) {
+                report.protected.push(p.port);
+            }
+        } else if portinfo::is_privileged(p.port) && !opts.allow_privileged {
+            if !report.privileged_ports.contains(&p.port) {
+                report.privileged_ports.push(p.port);
+            }
+        } else {
+            targets.entry(p.port).or_default().push(p);
+        }
+    }
+    if !report.protected.is_empty() {
+        reporter.note(&format!(
+            "Leaving protected port{} {} alone",
+            if report.protected.len() > 1 { "s" } else { "" },
+            output::compress_ports(&report.protected)
+        ));
+    }
+    if targets.is_empty() {
+        reporter.note("Nothing to kill");
+        return Ok(Some(targets));
+    }
+    if !ask {
+        return Ok(Some(targets));
+    }
+
+    let processes: Vec<ProcessInfo> = targets.values().flatten().cloned().collect();
+    output::print_targets(&processes);
+    let mut names: Vec<String> = Vec::new();
+    for p in &processes {
+        if !names.contains(&p.display_name()) {
+            names.push(p.display_name());
+        }
+    }
+    let more = names.len().saturating_sub(CONFIRM_NAMES);
+    names.truncate(CONFIRM_NAMES);
+    if more > 0 {
+        names.push(format!("{more} more"));
+    }
+    let mut pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
+    pids.sort_unstable();
+    pids.dedup();
+    let prompt = format!(
+        "Kill {} on {} ({})?",
+        plural(pids.len(), "process", "processes"),
+        plural(targets.len(), "port", "ports"),
+        names.join(", ")
+    );
+    if interactive::confirm(&prompt) {
+        Ok(Some(targets))
+    } else {
+        reporter.note("Nothing killed");
+        Ok(None)
+    }
+}
+
+/// Whether the current user owns `p`. A process whose owner we couldn't
+/// read doesn't count.
+#[cfg(unix)]
+fn owned_by_me(p: &ProcessInfo) -> bool {
+    p.uid == Some(nix::unistd::geteuid().as_raw())
+}
+
+#[cfg(windows)]
+fn owned_by_me(p: &ProcessInfo) -> bool {
+    let Ok(me) = std::env::var("USERNAME") else {
+        return false;
+    };
+    // Owners may be written DOMAIN\user
+    p.user
+        .as_deref()
+        .and_then(|user| user.rsplit('\\').next())
+        .is_some_and(|user| user.eq_ignore_ascii_case(&me))
+}
+
+/// The `--require-name` check: false, after refusing the port, if anything
+/// on it doesn't match. A dry run also says when the check passes.
+fn names_as_required(
+    port: u16,
+    processes: &[ProcessInfo],
+    opts: &KillOptions,
+    tally: &mut Tally,
+    report: &mut KillReport,
+) -> bool {
+    if opts.require_name.is_empty() {
+        return true;
+    }
+    let check = format!("--require-name {}", opts.require_name.describe());
+    let unexpected: Vec<ProcessInfo> = processes
+        .iter()
+        .filter(|p| !opts.require_name.matches(p))
+        .cloned()
+        .collect();
+    if !unexpected.is_empty() {
+        let refusal = Refusal {
+            port,
+            reason: format!("not every process matches {check}"),
+            processes: unexpected,
+        };
+        refuse(refusal, opts.format, tally, report);
+        return false;
+    }
+    if opts.dry_run && opts.format != OutputFormat::Json {
+        eprintln!("{} Everything on port {port} matches {check}", "✓".green());
+    }
+    true
+}
+
+/// Ask before killing processes that belong to root or another user, and
+/// return the ones to go ahead with. Only a terminal is asked; otherwise
+/// `require_confirmation` decides.
+fn confirm_foreign(
+    port: u16,
+    processes: Vec<ProcessInfo>,
+    opts: &KillOptions,
+    tally: &mut Tally,
+    report: &mut KillReport,
+) -> Vec<ProcessInfo> {
+    let foreign: Vec<ProcessInfo> = processes
+        .iter()
+        .filter(|p| killer::is_foreign(p))
+        .cloned()
+        .collect();
+    if opts.yes || opts.dry_run || foreign.is_empty() {
+        return processes;
+    }
+    let can_ask = std::io::stdin().is_terminal() && opts.format != OutputFormat::Json;
+
+    if can_ask {
+        output::print_processes(&foreign, OutputFormat::Table);
+        let owners = owners_label(&foreign);
+        if interactive::confirm(&format!("Owned by {owners}. Kill anyway?")) {
+            return processes;
         }
+        eprintln!(
+            "Skipped {} owned by {owners}",
+            plural(foreign.len(), "process", "processes")
+        );
+    } else if opts.confirmation == Confirmation::Always {
+        let refusal = Refusal {
+            port,
+            reason: format!(
+                "not killing {} owned by {}: require_confirmation = \"always\" and there is no terminal to ask on; pass --yes to kill them",
+                plural(foreign.len(), "process", "processes"),
+                owners_label(&foreign)
+            ),
+            processes: foreign,
+        };
+        refuse(refusal, opts.format, tally, report);
+    } else {
+        return processes;
+    }
+    processes
+        .into_iter()
+        .filter(|p| !killer::is_foreign(p))
+        .collect()
+}
+
+/// "root", or "root and alice"
+fn owners_label(processes: &[ProcessInfo]) -> String {
+    let mut owners: Vec<String> = processes
+        .iter()
+        .map(|p| match (&p.user, p.uid) {
+            (Some(user), _) => user.clone(),
+            (None, Some(uid)) => format!("uid {uid}"),
+            (None, None) => "another user".into(),
+        })
+        .collect();
+    owners.sort();
+    owners.dedup();
+    owners.join(" and ")
+}
+
+/// Leave a port alone because a safety check failed.
+fn refuse(refusal: Refusal, format: OutputFormat, tally: &mut Tally, report: &mut KillReport) {
+    tally.refused += 1;
+    if format == OutputFormat::Json {
+        report.refused.push(refusal);
+    } else {
+        output::print_refusal(&refusal.reason, &refusal.processes);
+    }
+}
+
+/// Signal `processes` (all found on one port, or one `--pid` target) and
+/// report the results.
+fn kill_batch(
+    processes: &[ProcessInfo],
+    opts: &KillOptions,
+    kill_config: &KillConfig,
+    reporter: &mut dyn Reporter,
+    tally: &mut Tally,
+    report: &mut KillReport,
+) {
+    if !opts.dry_run {
+        output::print_elevation_warning(processes, opts.format);
+        output::print_container_warning(processes, opts.format);
+    }
 
-        // Kill each process
-        let mut results = Vec::new();
-        for process in &processes {
-            let result = killer::kill_process(process, &kill_config);
-            if !result.success {
-                all_success = false;
+    let mut targets: Vec<ProcessInfo> = Vec::new();
+    let mut hit = false;
+    for process in processes {
+        // A process holding several of the ports is only signalled once
+        let earlier = report
+            .results
+            .iter_mut()
+            .find(|a| a.result.process.pid == process.pid);
+        if let Some(earlier) = earlier {
+            tracing::debug!(pid = process.pid, port = process.port, "already signalled");
+            hit |= earlier.result.success;
+            if !earlier.ports.contains(&process.port) {
+                earlier.ports.push(process.port);
             }
-            results.push(result);
+        } else if targets.iter().all(|t| t.pid != process.pid) {
+            tracing::debug!(pid = process.pid, name = %process.name, port = process.port, "killing");
+            targets.push(process.clone());
+        }
+    }
+    // Processes sharing the port (SO_REUSEPORT) are signalled and waited on
+    // together, so none keeps accepting while another shuts down.
+    let results = killer::kill_processes(&targets, kill_config);
+    for result in &results {
+        tracing::debug!(
+            pid = result.process.pid,
+            success = result.success,
+            signal = %result.signal_sent,
+            "kill finished"
+        );
+        tally.add(result);
+    }
+    if hit || results.iter().any(|r| r.success) {
+        tally.ports_hit += 1;
+    }
+
+    if let (Some(history), false) = (&opts.history, opts.dry_run) {
+        history.record_or_warn(&results, Source::Cli);
+    }
+    if opts.format != OutputFormat::Json {
+        reporter.kill_results(&results);
+    }
+    report
+        .results
+        .extend(results.into_iter().map(|result| Attempt {
+            ports: vec![result.process.port],
+            result,
+        }));
+}
+
+/// The process behind `kill --pid`. With ports it must listen on one of
+/// them; without, a process holding no sockets is still a target, on port 0.
+fn target_by_pid(scanner: &dyn PortScanner, pid: u32, ports: &[PortSpec]) -> Result<ProcessInfo> {
+    let scan = if ports.is_empty() {
+        scanner.scan_all()?
+    } else {
+        scanner.scan_ports(ports)?
+    };
+    let listening = scan
+        .processes
+        .into_iter()
+        .filter(|p| p.pid == pid)
+        .min_by_key(|p| p.port);
+    if let Some(process) = listening {
+        return Ok(process);
+    }
+    if !ports.is_empty() {
+        let ports = ports
+            .iter()
+            .map(PortSpec::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(KillportError::NotListening { pid, ports });
+    }
+
+    let identity = platform::process_identity(pid).ok_or(KillportError::NoSuchProcess { pid })?;
+    Ok(ProcessInfo {
+        pid,
+        name: identity.name,
+        port: 0,
+        protocol: Protocol::Tcp,
+        command: None,
+        user: identity
+            .uid
+            .and_then(|uid| platform::user_name(uid, &mut HashMap::new())),
+        uid: identity.uid,
+        container: None,
+        managed_by: None,
+        ppid: None,
+        parent_name: None,
+        started_at: identity.started_at,
+        cwd: None,
+        host: None,
+        origin: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::VecReporter;
+    use crate::process::{ManagedBy, ServiceManager};
+    use crate::scanner::mock::{process, MockScanner};
+
+    /// No pid can be this high, so signalling it fails with ESRCH.
+    const GONE_PID: u32 = 999_999_999;
+
+    fn options(ports: &[u16]) -> KillOptions {
+        KillOptions {
+            ports: ports.iter().map(|&port| PortSpec::Single(port)).collect(),
+            signal: KillSignal::Term,
+            graceful: false,
+            graceful_timeout: Duration::ZERO,
+            dry_run: false,
+            interactive: false,
+            fallback: interactive::Fallback::Fail,
+            ignore_manager: false,
+            group: false,
+            format: OutputFormat::Plain,
+            quiet: true,
+            history: None,
+            pid: None,
+            require_name: NameFilter::default(),
+            allow_privileged: false,
+            yes: true,
+            include_windows: false,
+            confirmation: Confirmation::Auto,
+            all: None,
+        }
+    }
+
+    fn socket_activated(pid: u32, port: u16) -> ProcessInfo {
+        ProcessInfo {
+            managed_by: Some(ManagedBy {
+                manager: ServiceManager::Systemd,
+                unit: "app.socket".into(),
+                domain: None,
+            }),
+            ..process(pid, "app", port)
+        }
+    }
+
+    fn result(error_kind: Option<KillErrorKind>) -> KillResult {
+        KillResult {
+            process: process(1, "x", 80),
+            success: matches!(error_kind, None | Some(KillErrorKind::ProcessGone)),
+            signal_sent: "SIGTERM".into(),
+            error: None,
+            error_kind,
+            target: None,
+            rule: None,
+        }
+    }
+
+    fn outcome(results: &[Option<KillErrorKind>], owner_unknown: bool) -> KillOutcome {
+        let mut tally = Tally {
+            owner_unknown,
+            ..Tally::default()
+        };
+        for kind in results {
+            tally.add(&result(*kind));
+        }
+        tally.outcome()
+    }
+
+    #[test]
+    fn outcome_distinguishes_denied_from_other_failures() {
+        let denied = Some(KillErrorKind::PermissionDenied);
+        assert_eq!(outcome(&[], false), KillOutcome::Success);
+        assert_eq!(
+            outcome(&[None, Some(KillErrorKind::ProcessGone)], false),
+            KillOutcome::Success
+        );
+        assert_eq!(
+            outcome(&[denied, denied], false),
+            KillOutcome::PermissionDenied
+        );
+        assert_eq!(outcome(&[None, denied], false), KillOutcome::Failed);
+        assert_eq!(
+            outcome(&[Some(KillErrorKind::Failed)], true),
+            KillOutcome::Failed
+        );
+        assert_eq!(outcome(&[None], true), KillOutcome::OwnerUnknown);
+        assert_eq!(KillOutcome::PermissionDenied.exit_code(), 4);
+    }
+
+    #[test]
+    fn ports_with_nothing_listening_succeed() {
+        let scanner = MockScanner::new(vec![process(GONE_PID, "node", 3000)]);
+        let mut reporter = VecReporter::default();
+        let outcome = execute_with(&scanner, &mut reporter, options(&[4000, 4001])).unwrap();
+        assert_eq!(outcome, KillOutcome::Success);
+        assert_eq!(reporter.lines, ["no process: 4000", "no process: 4001"]);
+    }
+
+    #[test]
+    fn a_process_on_several_ports_is_signalled_once() {
+        let scanner = MockScanner::new(vec![
+            process(GONE_PID, "node", 3000),
+            process(GONE_PID, "node", 3001),
+        ]);
+        let mut reporter = VecReporter::default();
+        let opts = KillOptions {
+            ports: vec![PortSpec::range(3000, 3001)],
+            ..options(&[])
+        };
+        let outcome = execute_with(&scanner, &mut reporter, opts).unwrap();
+        assert_eq!(outcome, KillOutcome::Success);
+        assert_eq!(reporter.lines, [format!("killed: {GONE_PID}")]);
+        assert_eq!(scanner.scan_count(), 1);
+    }
+
+    #[test]
+    fn json_lists_every_port_a_killed_process_was_found_on() {
+        let scanner = MockScanner::new(vec![
+            process(GONE_PID, "node", 3000),
+            process(GONE_PID, "node", 3001),
+        ]);
+        let mut reporter = VecReporter::default();
+        // Overlapping specs visit each port once
+        let specs = ["3001", "3000-3001", "3000"];
+        let opts = KillOptions {
+            ports: specs.iter().map(|s| PortSpec::parse(s).unwrap()).collect(),
+            format: OutputFormat::Json,
+            quiet: false,
+            ..options(&[])
+        };
+        execute_with(&scanner, &mut reporter, opts).unwrap();
+        let json = reporter.lines[0].strip_prefix("json: ").unwrap();
+        let report: serde_json::Value = serde_json::from_str(json).unwrap();
+        let results = report["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["process"]["pid"], GONE_PID);
+        assert_eq!(results[0]["ports"], serde_json::json!([3001, 3000]));
+        assert_eq!(report["summary"]["killed"], 1);
+    }
+
+    #[test]
+    fn pid_on_a_privileged_port_needs_allow_privileged() {
+        let scanner = MockScanner::new(vec![process(GONE_PID, "nginx", 80)]);
+        for ports in [&[][..], &[80]] {
+            let mut reporter = VecReporter::default();
+            let opts = KillOptions {
+                pid: Some(GONE_PID),
+                format: OutputFormat::Json,
+                quiet: false,
+                ..options(ports)
+            };
+            let outcome = execute_with(&scanner, &mut reporter, opts).unwrap();
+            assert_eq!(outcome, KillOutcome::Failed);
+            let json = reporter.lines[0].strip_prefix("json: ").unwrap();
+            let report: serde_json::Value = serde_json::from_str(json).unwrap();
+            assert!(report["results"].as_array().unwrap().is_empty());
+            assert_eq!(report["privileged_ports"], serde_json::json!([80]));
+        }
+
+        let mut reporter = VecReporter::default();
+        let opts = KillOptions {
+            pid: Some(GONE_PID),
+            allow_privileged: true,
+            ..options(&[80])
+        };
+        let outcome = execute_with(&scanner, &mut reporter, opts).unwrap();
+        assert_eq!(outcome, KillOutcome::Success);
+        assert_eq!(reporter.lines, [format!("killed: {GONE_PID}")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn kill_all_dry_run_selects_by_owner_protocol_name_and_protection() {
+        let me = nix::unistd::geteuid().as_raw();
+        let owned = |pid, name: &str, port, uid, user: &str| ProcessInfo {
+            uid: Some(uid),
+            user: Some(user.into()),
+            ..process(pid, name, port)
+        };
+        let scanner = MockScanner::new(vec![
+            owned(GONE_PID, "node", 3000, me, "me"),
+            owned(GONE_PID, "node", 3001, me, "me"),
+            owned(GONE_PID - 1, "postgres", 5432, me, "me"),
+            owned(GONE_PID - 2, "vite", 5173, me, "me"),
+            ProcessInfo {
+                protocol: Protocol::Udp,
+                ..owned(GONE_PID - 3, "dns", 5353, me, "me")
+            },
+            owned(GONE_PID - 4, "redis", 6379, me + 1, "alice"),
+            owned(GONE_PID - 5, "sshd", 22, me, "me"),
+        ]);
+        let run = |all: AllTargets| {
+            let mut reporter = VecReporter::default();
+            let opts = KillOptions {
+                dry_run: true,
+                yes: false,
+                format: OutputFormat::Json,
+                all: Some(all),
+                ..options(&[])
+            };
+            execute_with(&scanner, &mut reporter, opts).unwrap();
+            let json = reporter
+                .lines
+                .last()
+                .unwrap()
+                .strip_prefix("json: ")
+                .unwrap();
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        };
+        let ports = |report: &serde_json::Value| -> Vec<serde_json::Value> {
+            report["results"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["ports"].clone())
+                .collect()
+        };
+
+        let report = run(AllTargets {
+            protocol: Some(Protocol::Tcp),
+            except_names: NameFilter::parse(&["vite".into()]).unwrap(),
+            protected_names: vec!["Postgres".into()],
+            ..AllTargets::default()
+        });
+        assert_eq!(ports(&report), [serde_json::json!([3000, 3001])]);
+        assert_eq!(report["results"][0]["signal_sent"], "SIGTERM (dry-run)");
+        assert_eq!(report["protected"], serde_json::json!([5432]));
+        assert_eq!(report["privileged_ports"], serde_json::json!([22]));
+
+        let report = run(AllTargets {
+            owners: Owners::Users(vec!["alice".into()]),
+            ..AllTargets::default()
+        });
+        assert_eq!(ports(&report), [serde_json::json!([6379])]);
+
+        let report = run(AllTargets {
+            owners: Owners::Everyone,
+            protected_ports: vec![3000, 3001],
+            ..AllTargets::default()
+        });
+        // Everything but the protected and privileged ports
+        assert_eq!(report["results"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn command_args_repeat_the_options() {
+        let opts = KillOptions {
+            ports: vec![PortSpec::range(3000, 3010)],
+            graceful: false,
+            ..options(&[])
+        };
+        assert_eq!(
+            command_args(&opts),
+            [
+                "3000-3010",
+                "--no-graceful",
+                "--quiet",
+                "--yes",
+                "--format",
+                "plain"
+            ]
+        );
+
+        let opts = KillOptions {
+            signal: KillSignal::Int,
+            graceful: true,
+            graceful_timeout: Duration::from_millis(1500),
+            quiet: false,
+            yes: false,
+            format: OutputFormat::Json,
+            pid: Some(42),
+            require_name: NameFilter::parse(&["my app".into()]).unwrap(),
+            ..options(&[3000, 3001])
+        };
+        assert_eq!(
+            command_args(&opts),
+            [
+                "kill",
+                "3000",
+                "3001",
+                "--pid",
+                "42",
+                "--require-name",
+                "my app",
+                "--signal",
+                "int",
+                "--timeout",
+                "1500ms",
+                "--format",
+                "json",
+            ]
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            elevated_command(&opts),
+            "sudo portzap kill 3000 3001 --pid 42 --require-name 'my app' --signal int \
+             --timeout 1500ms --format json"
+        );
+
+        let opts = KillOptions {
+            ignore_manager: true,
+            signal: KillSignal::Kill,
+            interactive: true,
+            fallback: interactive::Fallback::None,
+            ..options(&[8080])
+        };
+        assert_eq!(
+            command_args(&opts),
+            [
+                "8080",
+                "--force",
+                "--ignore-manager",
+                "--interactive",
+                "--quiet",
+                "--yes",
+                "--non-interactive-fallback",
+                "none",
+                "--format",
+                "plain",
+            ]
+        );
+
+        let opts = KillOptions {
+            graceful: true,
+            graceful_timeout: killer::DEFAULT_GRACEFUL_TIMEOUT,
+            quiet: false,
+            yes: false,
+            format: OutputFormat::Table,
+            all: Some(AllTargets {
+                owners: Owners::Users(vec!["alice".into(), "1001".into()]),
+                except_names: NameFilter::parse(&["postgres".into()]).unwrap(),
+                protocol: Some(Protocol::Udp),
+                ..AllTargets::default()
+            }),
+            ..options(&[])
+        };
+        assert_eq!(
+            command_args(&opts),
+            [
+                "kill",
+                "--all",
+                "--user",
+                "alice",
+                "--user",
+                "1001",
+                "--except-name",
+                "postgres",
+                "--protocol",
+                "udp",
+            ]
+        );
+    }
+
+    #[test]
+    fn shell_quote_leaves_plain_words_alone() {
+        assert_eq!(shell_quote("3000-3010:2"), "3000-3010:2");
+        assert_eq!(shell_quote("/vite|next/"), "'/vite|next/'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn stale_picks_are_checked_against_a_new_scan() {
+        let scanner = MockScanner::new(vec![process(1, "node", 3000)]);
+        let picked = vec![process(1, "node", 3000), process(2, "node", 3000)];
+
+        let kept = still_on_port(&scanner, 3000, picked.clone(), Instant::now()).unwrap();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(scanner.scan_count(), 0);
+
+        let stale = Instant::now() - scanner::STALE_AFTER;
+        let kept = still_on_port(&scanner, 3000, picked, stale).unwrap();
+        let pids: Vec<u32> = kept.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, [1]);
+    }
+
+    #[test]
+    fn one_failure_among_kills_fails_the_run() {
+        let scanner = MockScanner::new(vec![
+            process(GONE_PID, "node", 3000),
+            socket_activated(GONE_PID - 1, 3001),
+        ]);
+        let mut reporter = VecReporter::default();
+        let outcome = execute_with(&scanner, &mut reporter, options(&[3000, 3001])).unwrap();
+        assert_eq!(outcome, KillOutcome::Failed);
+        assert_eq!(outcome.exit_code(), exit_code::FAILURE);
+        assert_eq!(
+            reporter.lines,
+            [
+                format!("killed: {GONE_PID}"),
+                format!("failed: {}", GONE_PID - 1)
+            ]
+        );
+
+        let outcome = execute_with(
+            &scanner,
+            &mut VecReporter::default(),
+            KillOptions {
+                ignore_manager: true,
+                ..options(&[3000, 3001])
+            },
+        )
+        .unwrap();
+        assert_eq!(outcome, KillOutcome::Success);
+    }
+
+    #[test]
+    fn json_report_carries_scan_warnings() {
+        let scanner =
+            MockScanner::new(Vec::new()).with_warnings(vec![ScanWarning::SourceUnavailable {
+                source: "/proc/net/tcp6".into(),
+            }]);
+        let mut reporter = VecReporter::default();
+        let opts = KillOptions {
+            format: OutputFormat::Json,
+            ..options(&[3000, 3001])
+        };
+        execute_with(&scanner, &mut reporter, opts).unwrap();
+        assert_eq!(
+            reporter.lines,
+            [
+                r#"json: {"results":[],"empty_ports":[3000,3001],"warnings":[{"kind":"source_unavailable","source":"/proc/net/tcp6"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn interactive_without_a_terminal_fails_fast() {
+        if interactive::has_terminal() {
+            return;
+        }
+        let scanner = MockScanner::new(vec![process(GONE_PID, "node", 3000)]);
+        let mut reporter = VecReporter::default();
+        let opts = KillOptions {
+            interactive: true,
+            ..options(&[3000])
+        };
+        let err = execute_with(&scanner, &mut reporter, opts).unwrap_err();
+        assert!(matches!(err, KillportError::Usage(_)), "{err}");
+        assert!(reporter.lines.is_empty());
+    }
+
+    #[test]
+    fn interactive_fallback_all_kills_everything() {
+        if interactive::has_terminal() {
+            return;
+        }
+        let scanner = MockScanner::new(vec![process(GONE_PID, "node", 3000)]);
+        let mut reporter = VecReporter::default();
+        let opts = KillOptions {
+            interactive: true,
+            fallback: interactive::Fallback::All,
+            ..options(&[3000])
+        };
+        let outcome = execute_with(&scanner, &mut reporter, opts).unwrap();
+        assert_eq!(outcome, KillOutcome::Success);
+        assert_eq!(reporter.lines, [format!("killed: {GONE_PID}")]);
+    }
+
+    #[test]
+    fn empty_interactive_selection_kills_nothing() {
+        // Without a terminal, the `none` fallback picks nothing.
+        if interactive::has_terminal() {
+            return;
         }
+        let scanner = MockScanner::new(vec![socket_activated(GONE_PID, 3000)]);
+        let opts = KillOptions {
+            interactive: true,
+            fallback: interactive::Fallback::None,
+            ..options(&[3000])
+        };
+        // Had the refused process been attempted, the run would have failed.
+        let mut reporter = VecReporter::default();
+        let outcome = execute_with(&scanner, &mut reporter, opts).unwrap();
+        assert_eq!(outcome, KillOutcome::Success);
+        assert!(reporter.lines.is_empty());
+    }
+
+    #[test]
+    fn owners_label_names_each_owner_once() {
+        let owned = |user: Option<&str>, uid| ProcessInfo {
+            user: user.map(Into::into),
+            uid,
+            ..result(None).process
+        };
+        assert_eq!(
+            owners_label(&[
+                owned(Some("root"), Some(0)),
+                owned(Some("alice"), Some(1000)),
+                owned(Some("root"), Some(0)),
+            ]),
+            "alice and root"
+        );
+        assert_eq!(owners_label(&[owned(None, Some(501))]), "uid 501");
+    }
 
-        output::print_kill_results(&results, opts.format);
+    #[test]
+    fn privileged_skips_fail_only_when_nothing_else_was_targeted() {
+        let mut tally = Tally {
+            privileged: 1,
+            ..Tally::default()
+        };
+        assert_eq!(tally.outcome(), KillOutcome::Failed);
+        tally.empty_ports = 1;
+        assert_eq!(tally.outcome(), KillOutcome::Success);
+        assert!(tally.message(true).ends_with("1 privileged port skipped"));
     }
 
-    Ok(all_success)
+    #[test]
+    fn summary_message_rolls_up_counts() {
+        let denied = Some(KillErrorKind::PermissionDenied);
+        let mut tally = Tally::default();
+        for kind in [None, None, denied, denied] {
+            tally.add(&result(kind));
+        }
+        tally.ports_hit = 2;
+        tally.empty_ports = 3;
+        assert_eq!(
+            tally.message(false),
+            "Zapped 2 processes across 2 ports, 2 failed (permission denied), 3 ports had nothing listening"
+        );
+
+        let mut single = Tally::default();
+        single.add(&result(None));
+        single.ports_hit = 1;
+        assert!(!single.wants_summary(1));
+        assert!(single.wants_summary(2));
+        assert_eq!(single.message(true), "Would zap 1 process across 1 port");
+    }
 }
@@ -1,35 +1,551 @@
-use crate::errors::Result;
-use crate::output::{self, OutputFormat};
-use crate::process::PortSpec;
-use crate::scanner::create_scanner;
+use crate::capture;
+use crate::diff::ProcessDiff;
+use crate::errors::{exit_code, KillportError, Result};
+use crate::filter::NameFilter;
+use crate::format;
+use crate::output::{self, Column, OutputFormat, PortColors};
+use crate::process::{self, PortSpec, ProcessGroup, ProcessInfo};
+use crate::remote::RemoteHost;
+use crate::scanner::{
+    create_scanner_for, without_self, PortScanner, PortScans, Recorded, ScanWarning,
+};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 pub struct ListOptions {
     pub ports: Vec<PortSpec>,
+    /// Table/plain columns; empty for the default set
+    pub columns: Vec<Column>,
+    pub sort: SortKey,
+    /// Collapse rows, e.g. one per process
+    pub group_by: Option<Grouping>,
+    /// Refresh every N seconds until interrupted
+    pub watch_secs: Option<u64>,
+    /// Only rows whose name or command matches
+    pub names: NameFilter,
+    /// Print only the number of matching rows
+    pub count_only: bool,
+    /// Skip scan warnings
+    pub quiet: bool,
+    /// Scan this machine over SSH instead
+    pub host: Option<RemoteHost>,
+    /// Under WSL, add Windows-side listeners
+    pub include_windows: bool,
+    /// Keep portzap's own sockets in the results
+    pub include_self: bool,
     pub format: OutputFormat,
+    /// Ports to colour as the user's own in tables
+    pub highlight_ports: Vec<u16>,
+    /// After writing `format` to the `--output` file, show the same rows as
+    /// a table on the terminal
+    pub echo_table: bool,
 }
 
-pub fn execute(opts: ListOptions) -> Result<()> {
-    let scanner = create_scanner();
+/// A port-state assertion from `--fail-if-any` / `--fail-if-none`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expectation {
+    /// Nothing may be listening
+    Empty,
+    /// Something must be listening
+    NonEmpty,
+}
+
+impl Expectation {
+    pub fn exit_code(self, rows: usize) -> i32 {
+        match (self, rows) {
+            (Expectation::Empty, 1..) => exit_code::FAILURE,
+            (Expectation::NonEmpty, 0) => exit_code::NOTHING_FOUND,
+            _ => exit_code::SUCCESS,
+        }
+    }
+}
+
+/// What `--expect-free` / `--expect-used` require of every requested port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortAssertion {
+    /// Nothing listening; with no ports, nothing listening anywhere
+    Free,
+    /// Something listening
+    Used,
+}
+
+/// The `"assertion"` object of JSON output.
+#[derive(Debug, Serialize)]
+struct AssertionReport {
+    expect: PortAssertion,
+    passed: bool,
+    violations: Vec<Violation>,
+}
+
+/// A port that broke the assertion, with whatever is on it.
+#[derive(Debug, Serialize)]
+struct Violation {
+    port: u16,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    processes: Vec<ProcessInfo>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    owner_unknown: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    Process,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Port,
+    Pid,
+    Name,
+    Protocol,
+    /// Newest first; processes without a start time go last
+    Age,
+}
+
+impl SortKey {
+    /// Sort stably, so ties keep the scanner's port/PID order.
+    pub fn sort(self, processes: &mut [ProcessInfo]) {
+        match self {
+            SortKey::Port => processes.sort_by_key(|p| (p.port, p.pid)),
+            SortKey::Pid => processes.sort_by_key(|p| p.pid),
+            SortKey::Name => processes.sort_by_key(|p| p.name.to_lowercase()),
+            SortKey::Protocol => processes.sort_by_key(|p| p.protocol.to_string()),
+            SortKey::Age => processes.sort_by_key(|p| Reverse(p.started_at)),
+        }
+    }
+}
+
+/// What one requested port turned up.
+#[derive(Debug, Serialize)]
+struct PortEntry {
+    processes: Vec<ProcessInfo>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    owner_unknown: bool,
+}
+
+impl PortEntry {
+    fn empty() -> Self {
+        Self {
+            processes: Vec::new(),
+            owner_unknown: false,
+        }
+    }
+}
+
+fn scanner_for(opts: &ListOptions) -> Result<Box<dyn PortScanner>> {
+    let scanner = create_scanner_for(opts.host.as_ref(), opts.include_windows)?;
+    Ok(if opts.include_self {
+        scanner
+    } else {
+        without_self(scanner)
+    })
+}
+
+/// List what's listening and return how many rows matched, counting each
+/// requested port whose owner couldn't be seen as one: something is there
+/// all the same. Watch mode runs until interrupted and reports no rows.
+pub fn execute(opts: ListOptions) -> Result<usize> {
+    let scanner = scanner_for(&opts)?;
+    if !opts.echo_table {
+        return execute_with(scanner.as_ref(), &opts);
+    }
+    let scanner = Recorded::new(scanner.as_ref());
+    let rows = execute_with(&scanner, &opts)?;
+    capture::finish(true)?;
+    let table = ListOptions {
+        format: OutputFormat::Table,
+        // Already printed with the first pass
+        quiet: true,
+        ..opts
+    };
+    execute_with(&scanner, &table)?;
+    Ok(rows)
+}
+
+/// `execute` against a given scanner, so tests can supply their own.
+pub fn execute_with(scanner: &dyn PortScanner, opts: &ListOptions) -> Result<usize> {
+    if let Some(secs) = opts.watch_secs {
+        watch(scanner, opts, Duration::from_secs(secs.max(1)))?;
+        return Ok(0);
+    }
 
     if opts.ports.is_empty() {
         // List ALL listening ports
-        let processes = scanner.find_all_listening()?;
-        if processes.is_empty() {
-            eprintln!("No listening processes found");
+        let mut scan = scanner.scan_all()?;
+        scan.processes.retain(|p| opts.names.matches(p));
+        tracing::debug!(processes = scan.processes.len(), "processes matched");
+        opts.sort.sort(&mut scan.processes);
+        if opts.count_only {
+            output::print_count(scan.processes.len(), opts.format);
+        } else if scan.processes.is_empty() && !opts.format.is_id_list() {
+            // Empty ID lists print nothing at all, so `kill $(...)` gets no arguments
+            if opts.names.is_empty() {
+                eprintln!("No listening processes found");
+            } else {
+                eprintln!(
+                    "No listening processes match --name {}",
+                    opts.names.describe()
+                );
+            }
+        } else if opts.group_by == Some(Grouping::Process) {
+            output::print_process_groups(&ProcessGroup::group(&scan.processes), opts.format);
+            output::print_list_footer(&scan.processes, opts.format);
         } else {
-            output::print_processes(&processes, opts.format);
+            output::print_process_columns(
+                &scan.processes,
+                opts.format,
+                &opts.columns,
+                &PortColors::new(opts.highlight_ports.clone()),
+            );
+            output::print_list_footer(&scan.processes, opts.format);
+        }
+        if !opts.quiet {
+            output::print_scan_warnings(&scan.warnings);
         }
+        Ok(scan.processes.len())
     } else {
-        let ports: Vec<u16> = opts.ports.iter().flat_map(|ps| ps.expand()).collect();
-        for port in &ports {
-            let processes = scanner.find_processes_by_port(*port)?;
-            if processes.is_empty() {
-                output::print_no_process(*port, opts.format);
-            } else {
-                output::print_processes(&processes, opts.format);
+        let mut scans = PortScans::new(scanner, &opts.ports)?;
+        let mut by_port = BTreeMap::new();
+        let mut warnings = Vec::new();
+        for port in process::unique_ports(&opts.ports) {
+            let mut scan = scans.scan(port)?;
+            scan.collect_warnings(&mut warnings);
+            scan.processes.retain(|p| opts.names.matches(p));
+            opts.sort.sort(&mut scan.processes);
+            let owner_unknown = scan.owner_unknown(port);
+            by_port.insert(
+                port,
+                PortEntry {
+                    processes: scan.processes,
+                    owner_unknown,
+                },
+            );
+        }
+        let rows = by_port.values().map(|entry| entry.processes.len()).sum();
+        tracing::debug!(processes = rows, "processes matched");
+        if opts.count_only {
+            output::print_count(rows, opts.format);
+        } else {
+            print_ports(&by_port, opts);
+        }
+        if !opts.quiet {
+            output::print_scan_warnings(&warnings);
+        }
+        // Occupied all the same, as --expect-free sees it
+        let hidden = by_port.values().filter(|entry| entry.owner_unknown).count();
+        Ok(rows + hidden)
+    }
+}
+
+/// One table, or one JSON document, for all requested ports. JSON is keyed
+/// by port however many were given, so that empty ones still show up.
+/// Grouped output is always one list of processes.
+fn print_ports(by_port: &BTreeMap<u16, PortEntry>, opts: &ListOptions) {
+    let grouped = opts.group_by == Some(Grouping::Process);
+    let json = opts.format == OutputFormat::Json;
+    if json && !grouped {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(by_port).unwrap_or_default()
+        );
+        return;
+    }
+
+    if let (1, Some((port, entry)), false) = (by_port.len(), by_port.iter().next(), grouped) {
+        if entry.owner_unknown {
+            output::print_owner_unknown(*port, opts.format);
+        } else if entry.processes.is_empty() {
+            print_empty(*port, opts);
+        } else {
+            output::print_process_columns(
+                &entry.processes,
+                opts.format,
+                &opts.columns,
+                &PortColors::new(opts.highlight_ports.clone()),
+            );
+            output::print_list_footer(&entry.processes, opts.format);
+        }
+        return;
+    }
+
+    let mut processes: Vec<ProcessInfo> = by_port
+        .values()
+        .flat_map(|entry| entry.processes.iter().cloned())
+        .collect();
+    opts.sort.sort(&mut processes);
+    if grouped {
+        output::print_process_groups(&ProcessGroup::group(&processes), opts.format);
+    } else {
+        output::print_process_columns(
+            &processes,
+            opts.format,
+            &opts.columns,
+            &PortColors::new(opts.highlight_ports.clone()),
+        );
+    }
+    output::print_list_footer(&processes, opts.format);
+    if json {
+        return;
+    }
+    for (port, entry) in by_port {
+        if entry.owner_unknown {
+            output::print_owner_unknown(*port, opts.format);
+        } else if entry.processes.is_empty() {
+            print_empty(*port, opts);
+        }
+    }
+}
+
+/// Check the requested ports against `assertion`, print what breaks it, and
+/// return whether it held.
+pub fn execute_assertion(opts: ListOptions, assertion: PortAssertion) -> Result<bool> {
+    execute_assertion_with(scanner_for(&opts)?.as_ref(), &opts, assertion)
+}
+
+/// `execute_assertion` against a given scanner, so tests can supply their own.
+pub fn execute_assertion_with(
+    scanner: &dyn PortScanner,
+    opts: &ListOptions,
+    assertion: PortAssertion,
+) -> Result<bool> {
+    let mut warnings = Vec::new();
+    let mut by_port: BTreeMap<u16, PortEntry> = BTreeMap::new();
+    if opts.ports.is_empty() {
+        if assertion == PortAssertion::Used {
+            return Err(KillportError::Usage(
+                "--expect-used needs the ports to check".into(),
+            ));
+        }
+        let scan = scanner.scan_all()?;
+        scan.collect_warnings(&mut warnings);
+        for warning in &scan.warnings {
+            if let ScanWarning::OwnerUnknown { port } = warning {
+                by_port
+                    .entry(*port)
+                    .or_insert_with(PortEntry::empty)
+                    .owner_unknown = true;
             }
         }
+        for p in scan.processes.into_iter().filter(|p| opts.names.matches(p)) {
+            by_port
+                .entry(p.port)
+                .or_insert_with(PortEntry::empty)
+                .processes
+                .push(p);
+        }
+    } else {
+        let mut scans = PortScans::new(scanner, &opts.ports)?;
+        for port in process::unique_ports(&opts.ports) {
+            let mut scan = scans.scan(port)?;
+            scan.collect_warnings(&mut warnings);
+            scan.processes.retain(|p| opts.names.matches(p));
+            let owner_unknown = scan.owner_unknown(port);
+            by_port.insert(
+                port,
+                PortEntry {
+                    processes: scan.processes,
+                    owner_unknown,
+                },
+            );
+        }
+    }
+
+    let violations: Vec<Violation> = by_port
+        .into_iter()
+        .filter(|(_, entry)| {
+            let used = entry.owner_unknown || !entry.processes.is_empty();
+            used == (assertion == PortAssertion::Free)
+        })
+        .map(|(port, mut entry)| {
+            opts.sort.sort(&mut entry.processes);
+            Violation {
+                port,
+                processes: entry.processes,
+                owner_unknown: entry.owner_unknown,
+            }
+        })
+        .collect();
+    let passed = violations.is_empty();
+    tracing::debug!(?assertion, violations = violations.len(), "checked ports");
+
+    if opts.format == OutputFormat::Json {
+        let report = AssertionReport {
+            expect: assertion,
+            passed,
+            violations,
+        };
+        output::print_json(&serde_json::json!({ "assertion": report }));
+    } else {
+        print_violations(&violations, opts, assertion);
+    }
+    if !opts.quiet {
+        output::print_scan_warnings(&warnings);
+    }
+    Ok(passed)
+}
+
+/// The processes that broke an assertion on stdout, and what was expected
+/// on stderr.
+fn print_violations(violations: &[Violation], opts: &ListOptions, assertion: PortAssertion) {
+    let ports: Vec<u16> = violations.iter().map(|v| v.port).collect();
+    let (ok, failed) = match (assertion, opts.ports.is_empty()) {
+        (PortAssertion::Free, true) => (
+            "Nothing is listening".to_string(),
+            "Expected nothing to be listening".to_string(),
+        ),
+        (PortAssertion::Free, false) => (
+            "Every requested port is free".to_string(),
+            format!(
+                "Expected free, but in use: {}",
+                output::compress_ports(&ports)
+            ),
+        ),
+        (PortAssertion::Used, _) => (
+            "Something is listening on every requested port".to_string(),
+            format!(
+                "Expected in use, but nothing listening: {}",
+                output::compress_ports(&ports)
+            ),
+        ),
+    };
+    if violations.is_empty() {
+        eprintln!("{} {ok}", "✓".green());
+        return;
+    }
+    let processes: Vec<ProcessInfo> = violations
+        .iter()
+        .flat_map(|v| v.processes.iter().cloned())
+        .collect();
+    if !processes.is_empty() {
+        output::print_process_columns(
+            &processes,
+            opts.format,
+            &opts.columns,
+            &PortColors::new(opts.highlight_ports.clone()),
+        );
+    }
+    for v in violations.iter().filter(|v| v.owner_unknown) {
+        output::print_owner_unknown(v.port, opts.format);
+    }
+    eprintln!("{} {failed}", "✗".red());
+}
+
+fn print_empty(port: u16, opts: &ListOptions) {
+    if opts.names.is_empty() {
+        output::print_no_process(port, opts.format);
+    } else {
+        output::print_no_match(port, &opts.names.describe(), opts.format);
     }
+}
 
+/// Rescan every `interval` until Ctrl+C. Table and plain output redraw the
+/// list with changes marked; JSON emits one line per added or removed row.
+fn watch(scanner: &dyn PortScanner, opts: &ListOptions, interval: Duration) -> Result<()> {
+    // signal_hook sets the flag when the signal arrives.
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+        signal_hook::flag::register(signal, stop.clone())?;
+    }
+
+    let mut previous = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        let processes = scan_for_watch(scanner, opts)?;
+        let diff = ProcessDiff::between(&previous, &processes);
+        match opts.format {
+            OutputFormat::Json => {
+                let changes = diff
+                    .added
+                    .iter()
+                    .map(|p| ("added", p))
+                    .chain(diff.removed.iter().map(|p| ("removed", p)));
+                for (change, process) in changes {
+                    let line = serde_json::json!({ "change": change, "process": process });
+                    println!("{line}");
+                }
+            }
+            OutputFormat::Table => {
+                // Clear the screen and home the cursor, like watch(1).
+                print!("\x1b[2J\x1b[H");
+                println!(
+                    "Every {}: portzap list\n",
+                    format::humanize_duration(interval)
+                );
+                output::print_watch_frame(&processes, &diff, opts.format, &opts.columns);
+            }
+            // Scripts reading plain output only hear about changes.
+            OutputFormat::Plain if !diff.is_empty() => {
+                output::print_watch_frame(&processes, &diff, opts.format, &opts.columns);
+                println!();
+            }
+            _ => {}
+        }
+        std::io::stdout().flush()?;
+        previous = processes;
+
+        let next = Instant::now() + interval;
+        while !stop.load(Ordering::Relaxed) && Instant::now() < next {
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
     Ok(())
 }
+
+fn scan_for_watch(scanner: &dyn PortScanner, opts: &ListOptions) -> Result<Vec<ProcessInfo>> {
+    let mut processes = if opts.ports.is_empty() {
+        scanner.scan_all()?.processes
+    } else {
+        let mut scans = PortScans::new(scanner, &opts.ports)?;
+        let mut processes = Vec::new();
+        for port in process::unique_ports(&opts.ports) {
+            processes.extend(scans.scan(port)?.processes);
+        }
+        processes
+    };
+    processes.retain(|p| opts.names.matches(p));
+    opts.sort.sort(&mut processes);
+    Ok(processes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::mock::{process, MockScanner};
+
+    fn options(ports: Vec<PortSpec>) -> ListOptions {
+        ListOptions {
+            ports,
+            columns: Vec::new(),
+            sort: SortKey::Port,
+            group_by: None,
+            watch_secs: None,
+            names: NameFilter::default(),
+            count_only: false,
+            quiet: true,
+            host: None,
+            include_windows: false,
+            include_self: true,
+            format: OutputFormat::Pids,
+            highlight_ports: Vec::new(),
+            echo_table: false,
+        }
+    }
+
+    #[test]
+    fn ports_with_hidden_owners_count_as_matched() {
+        let scanner = MockScanner::new(vec![process(1, "node", 3000)])
+            .with_warnings(vec![ScanWarning::OwnerUnknown { port: 3001 }]);
+        let matched = |ports| execute_with(&scanner, &options(ports)).unwrap();
+
+        assert_eq!(matched(vec![PortSpec::Single(3001)]), 1);
+        assert_eq!(matched(vec![PortSpec::Single(3000), PortSpec::Single(3001)]), 2);
+        assert_eq!(matched(vec![PortSpec::Single(3002)]), 0);
+    }
+}
@@ -1,6 +1,14 @@
+pub mod check;
 pub mod completions;
+pub mod config;
+pub mod doctor;
 pub mod free;
+pub mod history;
+pub mod info;
 pub mod kill;
 pub mod list;
+pub mod stats;
+pub mod top;
+pub mod version;
 pub mod wait;
 pub mod watch;
@@ -0,0 +1,250 @@
+use crate::errors::Result;
+use crate::format;
+use crate::output::OutputFormat;
+use crate::process::{ProcessInfo, Protocol};
+use crate::scanner::{create_scanner, PortScanner};
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::{ContentArrangement, Table};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::thread;
+use std::time::Duration;
+
+const TOP_PROCESSES: usize = 10;
+
+pub struct StatsOptions {
+    /// Refresh every N seconds until interrupted
+    pub watch_secs: Option<u64>,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub tcp: usize,
+    pub udp: usize,
+    /// Sockets bound below 1024
+    pub privileged: usize,
+    pub by_user: Vec<UserCount>,
+    pub top_processes: Vec<ProcessCount>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct UserCount {
+    pub user: String,
+    pub sockets: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ProcessCount {
+    pub pid: u32,
+    pub name: String,
+    pub ports: usize,
+}
+
+impl Stats {
+    pub fn from_processes(processes: &[ProcessInfo]) -> Self {
+        let mut users: HashMap<&str, usize> = HashMap::new();
+        let mut ports: HashMap<u32, (&str, BTreeSet<u16>)> = HashMap::new();
+        let mut stats = Stats {
+            total: processes.len(),
+            ..Default::default()
+        };
+
+        for p in processes {
+            match p.protocol {
+                Protocol::Tcp => stats.tcp += 1,
+                Protocol::Udp => stats.udp += 1,
+            }
+            if p.port < 1024 {
+                stats.privileged += 1;
+            }
+            *users
+                .entry(p.user.as_deref().unwrap_or("unknown"))
+                .or_default() += 1;
+            ports
+                .entry(p.pid)
+                .or_insert_with(|| (&p.name, BTreeSet::new()))
+                .1
+                .insert(p.port);
+        }
+
+        stats.by_user = users
+            .into_iter()
+            .map(|(user, sockets)| UserCount {
+                user: user.to_string(),
+                sockets,
+            })
+            .collect();
+        stats
+            .by_user
+            .sort_by(|a, b| b.sockets.cmp(&a.sockets).then_with(|| a.user.cmp(&b.user)));
+
+        stats.top_processes = ports
+            .into_iter()
+            .map(|(pid, (name, ports))| ProcessCount {
+                pid,
+                name: name.to_string(),
+                ports: ports.len(),
+            })
+            .collect();
+        stats
+            .top_processes
+            .sort_by(|a, b| b.ports.cmp(&a.ports).then_with(|| a.pid.cmp(&b.pid)));
+        stats.top_processes.truncate(TOP_PROCESSES);
+        stats
+    }
+}
+
+pub fn execute(opts: StatsOptions) -> Result<()> {
+    execute_with(create_scanner().as_ref(), opts)
+}
+
+/// `execute` against a given scanner, so tests can supply their own.
+pub fn execute_with(scanner: &dyn PortScanner, opts: StatsOptions) -> Result<()> {
+    let Some(secs) = opts.watch_secs else {
+        let stats = Stats::from_processes(&scanner.find_all_listening()?);
+        print_stats(&stats, opts.format);
+        return Ok(());
+    };
+
+    let interval = Duration::from_secs(secs.max(1));
+    loop {
+        let stats = Stats::from_processes(&scanner.find_all_listening()?);
+        if opts.format == OutputFormat::Table {
+            // Clear the screen and home the cursor, like watch(1).
+            print!("\x1b[2J\x1b[H");
+            println!(
+                "Every {}: portzap stats\n",
+                format::humanize_duration(interval)
+            );
+        }
+        print_stats(&stats, opts.format);
+        thread::sleep(interval);
+    }
+}
+
+fn print_stats(stats: &Stats, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(stats).unwrap_or_default()
+            );
+        }
+        OutputFormat::Plain => {
+            println!("total\t{}", stats.total);
+            println!("tcp\t{}", stats.tcp);
+            println!("udp\t{}", stats.udp);
+            println!("privileged\t{}", stats.privileged);
+            for u in &stats.by_user {
+                println!("user\t{}\t{}", u.user, u.sockets);
+            }
+            for p in &stats.top_processes {
+                println!("process\t{}\t{}\t{}", p.pid, p.name, p.ports);
+            }
+        }
+        _ => print_stats_tables(stats),
+    }
+}
+
+fn print_stats_tables(stats: &Stats) {
+    if stats.total == 0 {
+        eprintln!("No listening processes found");
+        return;
+    }
+
+    let mut summary = new_table(vec!["Sockets", "TCP", "UDP", "Privileged (<1024)"]);
+    summary.add_row(vec![
+        stats.total.to_string(),
+        stats.tcp.to_string(),
+        stats.udp.to_string(),
+        stats.privileged.to_string(),
+    ]);
+    println!("{summary}");
+
+    let mut users = new_table(vec!["User", "Sockets"]);
+    for u in &stats.by_user {
+        users.add_row(vec![u.user.clone(), u.sockets.to_string()]);
+    }
+    println!("{users}");
+
+    let mut top = new_table(vec!["PID", "Name", "Ports"]);
+    for p in &stats.top_processes {
+        top.add_row(vec![p.pid.to_string(), p.name.clone(), p.ports.to_string()]);
+    }
+    println!("{top}");
+}
+
+fn new_table(header: Vec<&str>) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL_CONDENSED)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header);
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::mock;
+
+    fn process(
+        pid: u32,
+        name: &str,
+        port: u16,
+        protocol: Protocol,
+        user: Option<&str>,
+    ) -> ProcessInfo {
+        ProcessInfo {
+            protocol,
+            user: user.map(Into::into),
+            ..mock::process(pid, name, port)
+        }
+    }
+
+    #[test]
+    fn empty_scan_gives_zeroed_stats() {
+        assert_eq!(Stats::from_processes(&[]), Stats::default());
+    }
+
+    #[test]
+    fn counts_protocols_users_and_privileged_ports() {
+        let stats = Stats::from_processes(&[
+            process(1, "nginx", 80, Protocol::Tcp, Some("root")),
+            process(1, "nginx", 443, Protocol::Tcp, Some("root")),
+            process(2, "mdns", 5353, Protocol::Udp, None),
+            process(3, "node", 3000, Protocol::Tcp, Some("alice")),
+        ]);
+        assert_eq!(
+            (stats.total, stats.tcp, stats.udp, stats.privileged),
+            (4, 3, 1, 2)
+        );
+        let users: Vec<_> = stats
+            .by_user
+            .iter()
+            .map(|u| (u.user.as_str(), u.sockets))
+            .collect();
+        assert_eq!(users, [("root", 2), ("alice", 1), ("unknown", 1)]);
+    }
+
+    #[test]
+    fn top_processes_count_distinct_ports() {
+        let mut processes = vec![
+            process(7, "dual", 53, Protocol::Tcp, None),
+            process(7, "dual", 53, Protocol::Udp, None),
+        ];
+        processes
+            .extend((0..12).map(|i| process(100 + i, "svc", 8000 + i as u16, Protocol::Tcp, None)));
+        processes.push(process(9, "busy", 9000, Protocol::Tcp, None));
+        processes.push(process(9, "busy", 9001, Protocol::Tcp, None));
+
+        let stats = Stats::from_processes(&processes);
+        assert_eq!(stats.top_processes.len(), TOP_PROCESSES);
+        assert_eq!(stats.top_processes[0].name, "busy");
+        assert_eq!(stats.top_processes[0].ports, 2);
+        assert_eq!(stats.top_processes[1].pid, 7);
+        assert_eq!(stats.top_processes[1].ports, 1);
+    }
+}
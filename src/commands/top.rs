@@ -0,0 +1,233 @@
+use crate::errors::Result;
+use crate::output::{self, OutputFormat};
+use crate::process::ProcessInfo;
+use crate::resources::{self, ResourceSampler, ResourceUsage};
+use crate::scanner::{create_scanner, PortScanner};
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::{CellAlignment, ContentArrangement, Table};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::thread;
+use std::time::Duration;
+
+/// How long CPU time is measured over. CPU% comes from the difference between
+/// two samples, so this is most of what `top` costs beyond the scan.
+const CPU_WINDOW: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopBy {
+    Memory,
+    Cpu,
+    Ports,
+}
+
+pub struct TopOptions {
+    pub by: TopBy,
+    pub limit: usize,
+    pub format: OutputFormat,
+}
+
+/// One listening process with every port it holds.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TopProcess {
+    pub pid: u32,
+    pub name: String,
+    pub user: Option<String>,
+    pub ports: Vec<u16>,
+    pub rss_bytes: Option<u64>,
+    pub cpu_percent: Option<f32>,
+}
+
+/// Group `processes` by PID and keep the `limit` heaviest by `by`. Processes
+/// without a figure sort last; ties go to the lower PID.
+pub fn rank(
+    processes: &[ProcessInfo],
+    usage: &HashMap<u32, ResourceUsage>,
+    by: TopBy,
+    limit: usize,
+) -> Vec<TopProcess> {
+    let mut grouped: HashMap<u32, (&ProcessInfo, BTreeSet<u16>)> = HashMap::new();
+    for p in processes {
+        grouped
+            .entry(p.pid)
+            .or_insert_with(|| (p, BTreeSet::new()))
+            .1
+            .insert(p.port);
+    }
+
+    let mut top: Vec<TopProcess> = grouped
+        .into_iter()
+        .map(|(pid, (p, ports))| {
+            let usage = usage.get(&pid).copied().unwrap_or_default();
+            TopProcess {
+                pid,
+                name: p.name.clone(),
+                user: p.user.clone(),
+                ports: ports.into_iter().collect(),
+                rss_bytes: usage.rss_bytes,
+                cpu_percent: usage.cpu_percent,
+            }
+        })
+        .collect();
+    top.sort_by(|a, b| {
+        let heavier = match by {
+            TopBy::Memory => b.rss_bytes.cmp(&a.rss_bytes),
+            TopBy::Cpu => b
+                .cpu_percent
+                .unwrap_or(-1.0)
+                .total_cmp(&a.cpu_percent.unwrap_or(-1.0)),
+            TopBy::Ports => Ordering::Equal,
+        };
+        heavier
+            .then_with(|| b.ports.len().cmp(&a.ports.len()))
+            .then_with(|| a.pid.cmp(&b.pid))
+    });
+    top.truncate(limit);
+    top
+}
+
+pub fn execute(opts: TopOptions) -> Result<()> {
+    execute_with(create_scanner().as_ref(), opts)
+}
+
+/// `execute` against a given scanner, so tests can supply their own.
+pub fn execute_with(scanner: &dyn PortScanner, opts: TopOptions) -> Result<()> {
+    let processes = scanner.find_all_listening()?;
+    let pids = || processes.iter().map(|p| p.pid);
+
+    let mut sampler = ResourceSampler::new();
+    let mut usage = sampler.sample(pids());
+    let mut by = opts.by;
+    if usage.values().any(|u| u.rss_bytes.is_some()) {
+        thread::sleep(CPU_WINDOW);
+        usage = sampler.sample(pids());
+    } else if by != TopBy::Ports && !processes.is_empty() {
+        eprintln!("Resource usage isn't available here; sorting by port count");
+        by = TopBy::Ports;
+    }
+
+    let top = rank(&processes, &usage, by, opts.limit);
+    print_top(&top, opts.format);
+    Ok(())
+}
+
+fn print_top(top: &[TopProcess], format: OutputFormat) {
+    let memory = |p: &TopProcess| p.rss_bytes.map(resources::format_bytes);
+    let cpu = |p: &TopProcess| p.cpu_percent.map(|cpu| format!("{cpu:.1}%"));
+    let ports = |p: &TopProcess| {
+        p.ports
+            .iter()
+            .map(u16::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(top).unwrap_or_default());
+        }
+        OutputFormat::Plain => {
+            for p in top {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    p.pid,
+                    p.name,
+                    ports(p),
+                    p.rss_bytes.map_or_else(|| "-".into(), |b| b.to_string()),
+                    p.cpu_percent
+                        .map_or_else(|| "-".into(), |cpu| format!("{cpu:.1}")),
+                );
+            }
+        }
+        OutputFormat::Pids | OutputFormat::Ports => {
+            let rows = top
+                .iter()
+                .flat_map(|p| p.ports.iter().map(move |&port| (p.pid, port)));
+            output::print_ids(rows, format);
+        }
+        OutputFormat::Table => {
+            if top.is_empty() {
+                eprintln!("No listening processes found");
+                return;
+            }
+            let mut table = Table::new();
+            table
+                .load_preset(UTF8_FULL_CONDENSED)
+                .set_content_arrangement(ContentArrangement::Dynamic)
+                .set_header(vec!["PID", "Name", "User", "Memory", "CPU", "Ports"]);
+            for p in top {
+                table.add_row(vec![
+                    p.pid.to_string(),
+                    p.name.clone(),
+                    p.user.clone().unwrap_or_else(|| "-".into()),
+                    memory(p).unwrap_or_else(|| "-".into()),
+                    cpu(p).unwrap_or_else(|| "-".into()),
+                    ports(p),
+                ]);
+            }
+            for column in [3, 4] {
+                if let Some(column) = table.column_mut(column) {
+                    column.set_cell_alignment(CellAlignment::Right);
+                }
+            }
+            println!("{table}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::mock::process;
+
+    fn usage(rss: Option<u64>, cpu: Option<f32>) -> ResourceUsage {
+        ResourceUsage {
+            rss_bytes: rss,
+            cpu_percent: cpu,
+        }
+    }
+
+    #[test]
+    fn ranks_one_row_per_process_by_the_chosen_figure() {
+        let processes = [
+            process(1, "nginx", 80),
+            process(1, "nginx", 443),
+            process(2, "node", 3000),
+            process(3, "java", 8080),
+        ];
+        let figures = HashMap::from([
+            (1, usage(Some(10), Some(50.0))),
+            (2, usage(Some(30), None)),
+            (3, usage(Some(20), Some(1.0))),
+        ]);
+        let pids = |by| -> Vec<u32> {
+            rank(&processes, &figures, by, 10)
+                .iter()
+                .map(|p| p.pid)
+                .collect()
+        };
+        assert_eq!(pids(TopBy::Memory), [2, 3, 1]);
+        assert_eq!(pids(TopBy::Cpu), [1, 3, 2]);
+        assert_eq!(pids(TopBy::Ports), [1, 2, 3]);
+
+        let top = rank(&processes, &figures, TopBy::Memory, 1);
+        assert_eq!(top.len(), 1);
+        assert_eq!(
+            rank(&processes, &figures, TopBy::Ports, 1)[0].ports,
+            [80, 443]
+        );
+    }
+
+    #[test]
+    fn missing_figures_fall_back_to_port_count() {
+        let processes = [
+            process(5, "one", 3000),
+            process(4, "two", 4000),
+            process(4, "two", 4001),
+        ];
+        let top = rank(&processes, &HashMap::new(), TopBy::Memory, 10);
+        let pids: Vec<u32> = top.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, [4, 5]);
+    }
+}
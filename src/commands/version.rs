@@ -0,0 +1,55 @@
+use crate::output::OutputFormat;
+use serde::Serialize;
+
+/// Version and build metadata captured by build.rs.
+#[derive(Debug, Serialize)]
+pub struct VersionInfo {
+    pub version: &'static str,
+    pub commit: &'static str,
+    pub build_date: &'static str,
+    pub target: &'static str,
+    pub features: Vec<&'static str>,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: env!("PORTZAP_GIT_COMMIT"),
+            build_date: env!("PORTZAP_BUILD_DATE"),
+            target: env!("PORTZAP_TARGET"),
+            features: env!("PORTZAP_FEATURES")
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .collect(),
+        }
+    }
+}
+
+pub struct VersionOptions {
+    pub format: OutputFormat,
+}
+
+pub fn execute(opts: VersionOptions) {
+    let info = VersionInfo::current();
+    match opts.format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&info).unwrap_or_default()
+            );
+        }
+        _ => {
+            let features = if info.features.is_empty() {
+                "none".to_string()
+            } else {
+                info.features.join(", ")
+            };
+            println!("portzap {}", info.version);
+            println!("commit:   {}", info.commit);
+            println!("built:    {}", info.build_date);
+            println!("target:   {}", info.target);
+            println!("features: {features}");
+        }
+    }
+}
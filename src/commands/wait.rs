@@ -1,7 +1,12 @@
 use crate::errors::Result;
-use crate::output::OutputFormat;
+use crate::format;
+use crate::health::{HttpCheck, Probe};
+use crate::output::{self, OutputFormat, Reporter};
 use crate::process::WaitCondition;
-use crate::scanner::create_scanner;
+use crate::remote::RemoteHost;
+use crate::scanner::{create_scanner_for, PortScanner};
+use chrono::{DateTime, Local, SecondsFormat};
+use serde_json::json;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -10,103 +15,289 @@ use std::time::{Duration, Instant};
 pub struct WaitOptions {
     pub port: u16,
     pub condition: WaitCondition,
-    pub timeout_secs: u64,
+    /// Zero waits forever
+    pub timeout: Duration,
     pub poll_interval_ms: u64,
     pub format: OutputFormat,
+    /// Watch this machine over SSH instead
+    pub host: Option<RemoteHost>,
+    /// Under WSL, count Windows-side listeners too
+    pub include_windows: bool,
+    /// Count the port as up only once this request passes
+    pub http: Option<HttpCheck>,
 }
 
 pub fn execute(opts: WaitOptions) -> Result<bool> {
-    let scanner = create_scanner();
+    let mut reporter = output::reporter(opts.format);
+    let scanner = create_scanner_for(opts.host.as_ref(), opts.include_windows)?;
+    execute_with(scanner.as_ref(), reporter.as_mut(), opts)
+}
+
+/// `execute` against a given scanner and reporter, so tests can supply their own.
+pub fn execute_with(
+    scanner: &dyn PortScanner,
+    reporter: &mut dyn Reporter,
+    opts: WaitOptions,
+) -> Result<bool> {
     let poll_interval = Duration::from_millis(opts.poll_interval_ms);
-    let timeout = if opts.timeout_secs == 0 {
-        None
-    } else {
-        Some(Duration::from_secs(opts.timeout_secs))
-    };
+    let timeout = timeout(opts.timeout);
     let start = Instant::now();
 
     // Handle Ctrl+C gracefully
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    signal_hook::flag::register(signal_hook::consts::SIGINT, r)
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGINT, stop.clone())
         .expect("failed to register SIGINT handler");
-    let r2 = running.clone();
-    signal_hook::flag::register(signal_hook::consts::SIGTERM, r2)
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, stop.clone())
         .expect("failed to register SIGTERM handler");
 
-    let state_label = match opts.condition {
-        WaitCondition::Free => "free",
-        WaitCondition::Occupied => "occupied",
+    let goal = match (&opts.http, opts.condition) {
+        (Some(check), _) => format!("answer GET {}", check.path),
+        (None, WaitCondition::Free) => "become free".into(),
+        (None, WaitCondition::Occupied) => "become occupied".into(),
+        (None, WaitCondition::Restart { .. }) => "restart".into(),
+    };
+    let mut restart = match opts.condition {
+        WaitCondition::Restart { require_down } => Some(Restart::new(require_down)),
+        _ => None,
     };
 
-    eprintln!(
-        "Waiting for port {} to become {} (timeout: {}, poll: {}ms)",
+    reporter.status(&format!(
+        "Waiting for port {} to {goal} (timeout: {}, poll: {})",
         opts.port,
-        state_label,
-        if opts.timeout_secs == 0 {
-            "infinite".to_string()
-        } else {
-            format!("{}s", opts.timeout_secs)
+        match timeout {
+            None => "infinite".to_string(),
+            Some(timeout) => format::humanize_duration(timeout),
         },
-        opts.poll_interval_ms,
-    );
-
-    while running.load(Ordering::Relaxed) {
-        let processes = scanner.find_processes_by_port(opts.port)?;
-        let is_free = processes.is_empty();
-
-        let condition_met = match opts.condition {
-            WaitCondition::Free => is_free,
-            WaitCondition::Occupied => !is_free,
-        };
-
-        if condition_met {
-            let status = if is_free { "free" } else { "occupied" };
-            match opts.format {
-                OutputFormat::Json => {
-                    println!(
-                        r#"{{"port": {}, "status": "{}"}}"#,
-                        opts.port, status
-                    );
-                }
-                _ => {
-                    eprintln!("Port {} is {}", opts.port, status);
-                }
+        format::humanize_duration(poll_interval),
+    ));
+
+    let mut last_probe = None;
+
+    while !stop.load(Ordering::Relaxed) {
+        if let Some(check) = &opts.http {
+            let probe = check.probe(opts.port);
+            if check.passes(&probe) {
+                reporter.note(&format!(
+                    "Port {} is up: GET {} returned {probe}",
+                    opts.port, check.path
+                ));
+                reporter.result_json(&http_json(opts.port, "occupied", &probe));
+                return Ok(true);
+            }
+            last_probe = Some(probe);
+        } else if let Some(restart) = &mut restart {
+            let is_free = scanner.find_processes_by_port(opts.port)?.is_empty();
+            if restart.observe(is_free, Local::now()) {
+                reporter.note(&format!(
+                    "Port {} restarted (free at {}, taken again at {})",
+                    opts.port,
+                    clock(restart.down_at),
+                    clock(restart.up_at)
+                ));
+                reporter.result_json(&restart.to_json(opts.port, "restarted"));
+                return Ok(true);
+            }
+        } else {
+            let is_free = scanner.find_processes_by_port(opts.port)?.is_empty();
+            if condition_met(opts.condition, is_free) {
+                let status = if is_free { "free" } else { "occupied" };
+                reporter.note(&format!("Port {} is {status}", opts.port));
+                reporter.result_json(&json!({ "port": opts.port, "status": status }));
+                return Ok(true);
             }
-            return Ok(true);
         }
 
-        if let Some(t) = timeout {
-            if start.elapsed() >= t {
-                match opts.format {
-                    OutputFormat::Json => {
-                        println!(
-                            r#"{{"port": {}, "status": "timeout"}}"#,
-                            opts.port
-                        );
-                    }
-                    _ => {
-                        eprintln!("Timeout: port {} did not become {}", opts.port, state_label);
-                    }
-                }
-                return Ok(false);
+        if timed_out(start.elapsed(), timeout) {
+            match (&restart, &last_probe) {
+                (Some(restart), _) if restart.down_at.is_some() => reporter.note(&format!(
+                    "Timeout: port {} went free at {} but was not taken again",
+                    opts.port,
+                    clock(restart.down_at)
+                )),
+                (_, Some(probe)) => reporter.note(&format!(
+                    "Timeout: port {} did not {goal} (last try: {probe})",
+                    opts.port
+                )),
+                _ => reporter.note(&format!("Timeout: port {} did not {goal}", opts.port)),
             }
+            reporter.result_json(&match (&restart, &last_probe) {
+                (Some(restart), _) => restart.to_json(opts.port, "timeout"),
+                (_, Some(probe)) => http_json(opts.port, "timeout", probe),
+                _ => json!({ "port": opts.port, "status": "timeout" }),
+            });
+            return Ok(false);
         }
 
         thread::sleep(poll_interval);
     }
 
     // Interrupted by signal
-    match opts.format {
-        OutputFormat::Json => {
-            println!(
-                r#"{{"port": {}, "status": "timeout"}}"#,
-                opts.port
-            );
+    reporter.note("\nInterrupted.");
+    reporter.result_json(&json!({ "port": opts.port, "status": "timeout" }));
+    Ok(false)
+}
+
+/// The `--timeout` as a duration; 0 waits forever.
+fn timeout(timeout: Duration) -> Option<Duration> {
+    (!timeout.is_zero()).then_some(timeout)
+}
+
+fn timed_out(elapsed: Duration, timeout: Option<Duration>) -> bool {
+    timeout.is_some_and(|t| elapsed >= t)
+}
+
+/// Whether one poll satisfies `--until free` or `up`. Restarts take more
+/// than one poll and are followed by [`Restart`] instead.
+fn condition_met(condition: WaitCondition, is_free: bool) -> bool {
+    matches!(condition, WaitCondition::Free) == is_free
+}
+
+/// `--until restart`: the port has to go free, then be taken again. A port
+/// that is already free counts as gone down unless `require_down` is set,
+/// in which case something has to be seen listening first.
+#[derive(Debug, Default)]
+struct Restart {
+    require_down: bool,
+    seen_up: bool,
+    down_at: Option<DateTime<Local>>,
+    up_at: Option<DateTime<Local>>,
+}
+
+impl Restart {
+    fn new(require_down: bool) -> Self {
+        Self {
+            require_down,
+            ..Self::default()
+        }
+    }
+
+    /// Record one poll; `true` once the port has gone down and come back up.
+    fn observe(&mut self, is_free: bool, now: DateTime<Local>) -> bool {
+        if self.down_at.is_none() {
+            if !is_free {
+                self.seen_up = true;
+            } else if self.seen_up || !self.require_down {
+                self.down_at = Some(now);
+            }
+            return false;
         }
-        _ => {
-            eprintln!("\nInterrupted.");
+        if !is_free {
+            self.up_at = Some(now);
         }
+        self.up_at.is_some()
+    }
+
+    fn to_json(&self, port: u16, status: &str) -> serde_json::Value {
+        let timestamp =
+            |t: Option<DateTime<Local>>| t.map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, false));
+        json!({
+            "port": port,
+            "status": status,
+            "down_at": timestamp(self.down_at),
+            "up_at": timestamp(self.up_at),
+        })
+    }
+}
+
+/// A wait result with what the last health check got back.
+fn http_json(port: u16, status: &str, probe: &Probe) -> serde_json::Value {
+    match probe {
+        Probe::Status(code) => json!({ "port": port, "status": status, "http_status": code }),
+        Probe::Unreachable(reason) => json!({
+            "port": port,
+            "status": status,
+            "http_status": null,
+            "http_error": reason,
+        }),
+    }
+}
+
+/// Time of day for notes.
+fn clock(t: Option<DateTime<Local>>) -> String {
+    t.map(|t| t.format("%H:%M:%S%.3f").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::VecReporter;
+    use crate::scanner::mock::{process, MockScanner};
+
+    fn options(condition: WaitCondition) -> WaitOptions {
+        WaitOptions {
+            port: 3000,
+            condition,
+            timeout: Duration::from_secs(5),
+            poll_interval_ms: 10,
+            format: OutputFormat::Plain,
+            host: None,
+            include_windows: false,
+            http: None,
+        }
+    }
+
+    #[test]
+    fn conditions_follow_port_state() {
+        assert!(condition_met(WaitCondition::Free, true));
+        assert!(!condition_met(WaitCondition::Free, false));
+        assert!(condition_met(WaitCondition::Occupied, false));
+        assert!(!condition_met(WaitCondition::Occupied, true));
+    }
+
+    #[test]
+    fn restart_needs_down_then_up() {
+        let now = Local::now();
+        let mut restart = Restart::new(false);
+        assert!(!restart.observe(false, now));
+        assert!(!restart.observe(true, now));
+        assert!(restart.down_at.is_some());
+        assert!(!restart.observe(true, now));
+        assert!(restart.observe(false, now));
+        assert!(restart.up_at.is_some());
+
+        // Already free: the down phase is skipped unless it's required
+        let mut lenient = Restart::new(false);
+        assert!(!lenient.observe(true, now));
+        assert!(lenient.observe(false, now));
+        let mut strict = Restart::new(true);
+        assert!(!strict.observe(true, now));
+        assert!(!strict.observe(false, now));
+        assert!(!strict.observe(true, now));
+        assert!(strict.observe(false, now));
+    }
+
+    #[test]
+    fn zero_timeout_never_expires() {
+        let five = Duration::from_secs(5);
+        assert_eq!(timeout(Duration::ZERO), None);
+        assert!(!timed_out(
+            Duration::from_secs(86_400),
+            timeout(Duration::ZERO)
+        ));
+        assert!(!timed_out(Duration::from_millis(4_999), timeout(five)));
+        assert!(timed_out(five, timeout(five)));
+    }
+
+    #[test]
+    fn returns_as_soon_as_the_condition_holds() {
+        let scanner = MockScanner::new(vec![process(1, "node", 3000)]);
+        let mut reporter = VecReporter::default();
+        assert!(execute_with(&scanner, &mut reporter, options(WaitCondition::Occupied)).unwrap());
+        assert_eq!(
+            reporter.lines[1..],
+            [
+                "note: Port 3000 is occupied",
+                r#"json: {"port":3000,"status":"occupied"}"#,
+            ]
+        );
+
+        scanner.remove(1);
+        let mut reporter = VecReporter::default();
+        assert!(execute_with(&scanner, &mut reporter, options(WaitCondition::Free)).unwrap());
+        assert_eq!(
+            reporter.lines[0],
+            "status: Waiting for port 3000 to become free (timeout: 5s, poll: 10ms)"
+        );
     }
-    Ok(false)
 }
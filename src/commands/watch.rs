@@ -1,8 +1,18 @@
-use crate::errors::Result;
+use crate::daemon::{self, Watcher};
+use crate::errors::{KillportError, Result};
+use crate::event_log::EventLog;
+use crate::format;
+use crate::history::{History, Source};
 use crate::killer::{self, KillConfig};
-use crate::output::{self, OutputFormat};
-use crate::process::{KillSignal, PortSpec};
-use crate::scanner::create_scanner;
+use crate::output::{self, IgnoredEvent, OutputFormat, Reporter};
+use crate::process::{self, KillErrorKind, KillResult, KillSignal, PortSpec};
+use crate::scanner::{create_scanner, without_self, PortScanner, ScanSnapshot};
+use crate::service_unit::{ServiceFile, ServiceKind};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -12,32 +22,400 @@ pub struct WatchOptions {
     pub ports: Vec<PortSpec>,
     pub signal: KillSignal,
     pub graceful: bool,
-    pub graceful_timeout_secs: u64,
+    /// Zero waits for exit without escalating
+    pub graceful_timeout: Duration,
     pub poll_interval_ms: u64,
+    pub allow_names: Vec<String>,
     pub format: OutputFormat,
+    /// Where to record kills, if history is enabled
+    pub history: Option<History>,
+    /// Per-port overrides of `signal`/`graceful`/`graceful_timeout`; their
+    /// ports are watched too
+    pub rules: Vec<WatchRule>,
+    /// Report what would be killed, once per process, without signalling
+    pub dry_run: bool,
+    /// Also append every event to this file
+    pub log: Option<EventLog>,
+    /// Files holding the PID of a port's rightful owner, never killed
+    pub pidfiles: Vec<PathBuf>,
+}
+
+impl WatchOptions {
+    /// Every port spec watched: the positional ones, then those only a rule names.
+    pub fn watched(&self) -> Vec<PortSpec> {
+        let mut specs = self.ports.clone();
+        for rule in &self.rules {
+            if !specs.contains(&rule.ports) {
+                specs.push(rule.ports.clone());
+            }
+        }
+        specs
+    }
+
+    /// The rule covering `port`, if any.
+    fn rule_for(&self, port: u16) -> Option<&WatchRule> {
+        self.rules.iter().find(|rule| rule.ports.contains(port))
+    }
+}
+
+/// What a rule does to new processes on its ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    /// Send this signal and nothing else
+    Signal(KillSignal),
+    /// SIGTERM, then SIGKILL after this timeout, or the global one
+    Graceful(Option<Duration>),
+}
+
+/// `--rule PORTS:ACTION`, e.g. `80:kill` or `8080:graceful=10s`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchRule {
+    pub ports: PortSpec,
+    pub action: RuleAction,
+}
+
+impl WatchRule {
+    /// Parse `PORTS:ACTION`, where ACTION is a signal (`term`, `kill`,
+    /// `int`, `hup`), `graceful`, or `graceful=DURATION`. PORTS is any port
+    /// spec, steps included: `3000-3010:2:kill`.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let invalid = |why: &str| KillportError::Usage(format!("rule '{raw}': {why}"));
+        let (ports, action) = raw
+            .rsplit_once(':')
+            .ok_or_else(|| invalid("expected PORTS:ACTION, e.g. 80:kill"))?;
+        let ports = PortSpec::parse(ports.trim()).map_err(|e| invalid(&e.to_string()))?;
+        let action = action.trim().to_ascii_lowercase();
+        let action =
+            match action.split_once('=') {
+                Some(("graceful", timeout)) => RuleAction::Graceful(Some(
+                    format::parse_seconds(timeout).map_err(|e| invalid(&e))?,
+                )),
+                None if action == "graceful" => RuleAction::Graceful(None),
+                _ => RuleAction::Signal(match action.trim_start_matches("sig") {
+                    "term" => KillSignal::Term,
+                    "kill" => KillSignal::Kill,
+                    "int" => KillSignal::Int,
+                    "hup" => KillSignal::Hup,
+                    _ => return Err(invalid(
+                        "the action must be term, kill, int, hup, graceful or graceful=DURATION",
+                    )),
+                }),
+            };
+        Ok(Self { ports, action })
+    }
+
+    /// The kill settings for this rule's ports, on top of the global ones.
+    fn apply(&self, base: &KillConfig) -> KillConfig {
+        let mut config = base.clone();
+        match self.action {
+            RuleAction::Signal(signal) => {
+                config.signal = signal;
+                config.graceful = false;
+            }
+            RuleAction::Graceful(timeout) => {
+                config.graceful = true;
+                if let Some(timeout) = timeout {
+                    config.graceful_timeout = killer::graceful_timeout(timeout);
+                }
+            }
+        }
+        config
+    }
+}
+
+impl fmt::Display for WatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.action {
+            RuleAction::Signal(signal) => {
+                let signal = signal.to_string();
+                let name = signal.trim_start_matches("SIG").to_ascii_lowercase();
+                write!(f, "{}:{name}", self.ports)
+            }
+            RuleAction::Graceful(None) => write!(f, "{}:graceful", self.ports),
+            RuleAction::Graceful(Some(timeout)) => write!(
+                f,
+                "{}:graceful={}",
+                self.ports,
+                format::duration_arg(timeout)
+            ),
+        }
+    }
+}
+
+/// `--ignore-pidfile`: the PIDs a supervisor says are legitimate. The files
+/// are read again every poll, since they change when the server restarts.
+struct Pidfiles<'a> {
+    paths: &'a [PathBuf],
+    /// Files already warned about, until they can be read again
+    warned: HashSet<&'a Path>,
+}
+
+impl<'a> Pidfiles<'a> {
+    fn new(paths: &'a [PathBuf]) -> Self {
+        Self {
+            paths,
+            warned: HashSet::new(),
+        }
+    }
+
+    /// Each PID named by a readable pidfile, with its file. A missing or
+    /// malformed file exempts nothing and is warned about once.
+    fn read(&mut self) -> HashMap<u32, &'a Path> {
+        let mut pids = HashMap::new();
+        for path in self.paths {
+            let pid = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    let first = contents.lines().next().unwrap_or_default().trim();
+                    first
+                        .parse::<u32>()
+                        .map_err(|_| format!("expected a PID, found '{first}'"))
+                });
+            match pid {
+                Ok(pid) => {
+                    self.warned.remove(path.as_path());
+                    pids.insert(pid, path.as_path());
+                }
+                Err(e) => {
+                    if self.warned.insert(path) {
+                        output::print_warning(&format!("ignoring pidfile {}: {e}", path.display()));
+                    }
+                }
+            }
+        }
+        pids
+    }
+}
+
+/// A `--rules-file`: a `[rules]` table of port specs and actions.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    rules: BTreeMap<String, String>,
+}
+
+/// The rules from `--rule` and `--rules-file`, checked against each other:
+/// two rules giving one port different actions are an error.
+pub fn rules(raw: &[String], file: Option<&Path>) -> Result<Vec<WatchRule>> {
+    let mut rules = raw
+        .iter()
+        .map(|raw| WatchRule::parse(raw))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(path) = file {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| KillportError::Usage(format!("--rules-file {}: {e}", path.display())))?;
+        let parsed: RulesFile = toml::from_str(&contents).map_err(|e| {
+            KillportError::Usage(format!("--rules-file {}: {}", path.display(), e.message()))
+        })?;
+        for (ports, action) in parsed.rules {
+            rules.push(WatchRule::parse(&format!("{ports}:{action}"))?);
+        }
+    }
+
+    for (i, rule) in rules.iter().enumerate() {
+        for other in &rules[..i] {
+            if other.action == rule.action {
+                continue;
+            }
+            if let Some(port) = rule.ports.expand().find(|&p| other.ports.contains(p)) {
+                return Err(KillportError::Usage(format!(
+                    "rules {other} and {rule} conflict on port {port}"
+                )));
+            }
+        }
+    }
+    Ok(rules)
 }
 
 pub fn execute(opts: WatchOptions) -> Result<()> {
-    let scanner = create_scanner();
+    let mut reporter = output::reporter(opts.format);
+    // Never report or kill ourselves, whatever the rules say
+    execute_with(
+        without_self(create_scanner()).as_ref(),
+        reporter.as_mut(),
+        opts,
+    )
+}
+
+/// `watch --daemon`: run these options in a detached `portzap watch`.
+pub fn start_daemon(opts: &WatchOptions) -> Result<()> {
+    let mut reporter = output::reporter(opts.format);
+    let specs = opts.watched();
+    let watcher = daemon::spawn(&specs, &command_args(opts))?;
+    reporter.note(&format!(
+        "Watching {} in the background (PID {})\nLog: {}\nStop it with `portzap watch --stop {}`",
+        watcher.ports.join(", "),
+        watcher.pid,
+        watcher.log.display(),
+        specs[0].expand().next().unwrap_or_default()
+    ));
+    reporter.result_json(&json!(watcher));
+    Ok(())
+}
+
+/// `watch --emit-service`: print a service definition running these
+/// options, or with `install`, write it where the service manager looks.
+pub fn emit_service(opts: &WatchOptions, kind: ServiceKind, install: bool) -> Result<()> {
+    let mut reporter = output::reporter(opts.format);
+    let ports: Vec<String> = opts.watched().iter().map(|spec| spec.to_string()).collect();
+    let program = std::env::current_exe()?;
+    let service = ServiceFile::render(kind, &ports, &program, &command_args(opts));
+    if !install {
+        reporter.result_text(service.contents.trim_end());
+        reporter.result_json(&json!({ "name": service.name, "contents": service.contents }));
+        return Ok(());
+    }
+
+    let path = service.install_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, &service.contents)?;
+    let enable = service.enable_command(&path);
+    reporter.note(&format!(
+        "Wrote {}\nStart it now and at login with:\n  {enable}",
+        path.display()
+    ));
+    reporter.result_json(&json!({ "name": service.name, "path": path, "enable": enable }));
+    Ok(())
+}
+
+/// The `portzap` arguments that run a watcher with these options.
+pub fn command_args(opts: &WatchOptions) -> Vec<String> {
+    let mut args = vec!["watch".to_string()];
+    args.extend(opts.ports.iter().map(|spec| spec.to_string()));
+    let signal = opts.signal.to_string();
+    args.extend([
+        "--signal".into(),
+        signal.trim_start_matches("SIG").to_ascii_lowercase(),
+        "--timeout".into(),
+        format::duration_arg(opts.graceful_timeout),
+        "--poll".into(),
+        opts.poll_interval_ms.to_string(),
+        if opts.graceful {
+            "--graceful"
+        } else {
+            "--no-graceful"
+        }
+        .into(),
+        "--format".into(),
+        opts.format.name().into(),
+    ]);
+    for name in &opts.allow_names {
+        args.extend(["--allow-name".into(), name.clone()]);
+    }
+    for rule in &opts.rules {
+        args.extend(["--rule".into(), rule.to_string()]);
+    }
+    if opts.dry_run {
+        args.push("--dry-run".into());
+    }
+    for path in &opts.pidfiles {
+        args.extend(["--ignore-pidfile".into(), path.display().to_string()]);
+    }
+    if let Some(log) = &opts.log {
+        args.extend(["--log-file".into(), log.path.display().to_string()]);
+        if let Some(size) = log.rotate_size {
+            args.extend([
+                "--log-rotate-size".into(),
+                size.to_string(),
+                "--log-keep".into(),
+                log.keep.to_string(),
+            ]);
+        }
+        if log.sync {
+            args.push("--log-fsync".into());
+        }
+    }
+    args
+}
+
+/// `watch --status`: the background watchers that are still running.
+pub fn status(format: OutputFormat) -> Result<()> {
+    let mut reporter = output::reporter(format);
+    let (running, stale) = daemon::watchers()?;
+    note_stale(reporter.as_mut(), &stale);
+    if running.is_empty() {
+        reporter.note("No watchers running in the background");
+    }
+    for watcher in &running {
+        reporter.result_text(&format!(
+            "PID {}\t{}\tsince {}\tlog {}",
+            watcher.pid,
+            watcher.ports.join(", "),
+            watcher.started_at,
+            watcher.log.display()
+        ));
+    }
+    reporter.result_json(&json!(running));
+    Ok(())
+}
+
+/// `watch --stop [PORT]`: stop the background watchers guarding `port`, or
+/// all of them. `false` when none were running.
+pub fn stop(port: Option<u16>, format: OutputFormat) -> Result<bool> {
+    let mut reporter = output::reporter(format);
+    let (running, stale) = daemon::watchers()?;
+    note_stale(reporter.as_mut(), &stale);
+    let targets: Vec<Watcher> = running
+        .into_iter()
+        .filter(|w| port.map_or(true, |port| w.watches(port)))
+        .collect();
+    if targets.is_empty() {
+        reporter.note(&match port {
+            Some(port) => format!("No background watcher is watching port {port}"),
+            None => "No watchers running in the background".into(),
+        });
+    }
+    for watcher in &targets {
+        daemon::stop(watcher)?;
+        reporter.note(&format!(
+            "Stopped the watcher for {} (PID {})",
+            watcher.ports.join(", "),
+            watcher.pid
+        ));
+    }
+    reporter.result_json(&json!({ "stopped": targets }));
+    Ok(!targets.is_empty())
+}
+
+fn note_stale(reporter: &mut dyn Reporter, stale: &[Watcher]) {
+    for watcher in stale {
+        reporter.note(&format!(
+            "Cleaned up after the watcher for {}: PID {} is no longer running",
+            watcher.ports.join(", "),
+            watcher.pid
+        ));
+    }
+}
+
+/// `execute` against a given scanner and reporter, so tests can supply their own.
+pub fn execute_with(
+    scanner: &dyn PortScanner,
+    reporter: &mut dyn Reporter,
+    mut opts: WatchOptions,
+) -> Result<()> {
+    let mut log = opts.log.take();
     let kill_config = KillConfig {
         signal: opts.signal,
         graceful: opts.graceful,
-        graceful_timeout: Duration::from_secs(opts.graceful_timeout_secs),
-        dry_run: false,
+        graceful_timeout: killer::graceful_timeout(opts.graceful_timeout),
+        dry_run: opts.dry_run,
+        ignore_manager: false,
+        group: false,
     };
-    let ports: Vec<u16> = opts.ports.iter().flat_map(|ps| ps.expand()).collect();
+    let specs = opts.watched();
+    let ports = process::unique_ports(&specs);
     let poll_interval = Duration::from_millis(opts.poll_interval_ms);
 
-    // Handle Ctrl+C gracefully
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    signal_hook::flag::register(signal_hook::consts::SIGINT, r)
+    // Ctrl+C, or `watch --stop` for a background watcher, ends the loop
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGINT, stop.clone())
         .expect("failed to register SIGINT handler");
-    let r2 = running.clone();
-    signal_hook::flag::register(signal_hook::consts::SIGTERM, r2)
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, stop.clone())
         .expect("failed to register SIGTERM handler");
 
-    eprintln!(
+    reporter.status(&format!(
         "Watching port{} {} (poll every {}ms, Ctrl+C to stop)",
         if ports.len() > 1 { "s" } else { "" },
         ports
@@ -46,19 +424,168 @@ pub fn execute(opts: WatchOptions) -> Result<()> {
             .collect::<Vec<_>>()
             .join(", "),
         opts.poll_interval_ms
-    );
+    ));
+    for rule in &opts.rules {
+        reporter.status(&format!("Rule {rule}"));
+    }
+
+    // Processes we may not signal, or a dry run already reported; retrying
+    // every poll would only repeat the same line.
+    let mut skip = HashSet::new();
+    let mut pidfiles = Pidfiles::new(&opts.pidfiles);
+    // Pidfile owners already reported as ignored
+    let mut ignored = HashSet::new();
 
-    while running.load(Ordering::Relaxed) {
+    while !stop.load(Ordering::Relaxed) {
+        // Several ports are covered by one scan per poll
+        let snapshot = match ports.len() {
+            1 => None,
+            _ => Some(ScanSnapshot::take(scanner, &specs)?),
+        };
+        let mut signalled = HashSet::new();
+        let exempt = pidfiles.read();
         for port in &ports {
-            let processes = scanner.find_processes_by_port(*port)?;
+            let processes = match &snapshot {
+                Some(snapshot) => snapshot.processes_on(*port).to_vec(),
+                None => scanner.find_processes_by_port(*port)?,
+            };
             for process in &processes {
-                let result = killer::kill_process(process, &kill_config);
-                output::print_kill_results(&[result], opts.format);
+                if opts
+                    .allow_names
+                    .iter()
+                    .any(|n| n.eq_ignore_ascii_case(&process.name))
+                {
+                    continue;
+                }
+                if let Some(pidfile) = exempt.get(&process.pid) {
+                    if ignored.insert(process.pid) {
+                        let event = IgnoredEvent {
+                            process,
+                            ignored: "pidfile",
+                            pidfile,
+                        };
+                        if let Some(log) = &mut log {
+                            log.record_or_warn(std::slice::from_ref(&event));
+                        }
+                        reporter.ignored(&event);
+                    }
+                    continue;
+                }
+                if skip.contains(&process.pid) || !signalled.insert(process.pid) {
+                    continue;
+                }
+                let rule = opts.rule_for(*port);
+                let result = match rule {
+                    Some(rule) => {
+                        reporter.status(&format!(
+                            "Rule {rule} matched {} (PID {}) on port {port}",
+                            process.name, process.pid
+                        ));
+                        KillResult {
+                            rule: Some(rule.to_string()),
+                            ..killer::kill_process(process, &rule.apply(&kill_config))
+                        }
+                    }
+                    None => killer::kill_process(process, &kill_config),
+                };
+                if opts.dry_run || result.error_kind == Some(KillErrorKind::PermissionDenied) {
+                    skip.insert(process.pid);
+                }
+                let results = std::slice::from_ref(&result);
+                if let (Some(history), false) = (&opts.history, opts.dry_run) {
+                    history.record_or_warn(results, Source::Watch);
+                }
+                if let Some(log) = &mut log {
+                    log.record_or_warn(results);
+                }
+                reporter.kill_results(results);
             }
         }
         thread::sleep(poll_interval);
     }
 
-    eprintln!("\nWatch mode stopped.");
+    reporter.status("\nWatch mode stopped.");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn rules_parse_and_print_back() {
+        for raw in [
+            "80:kill",
+            "8080:graceful",
+            "3000-3010:graceful=10",
+            "9000:graceful=500ms",
+            "3000-3010:2:hup",
+        ] {
+            assert_eq!(WatchRule::parse(raw).unwrap().to_string(), raw);
+        }
+        let rule = WatchRule::parse("443:SIGINT").unwrap();
+        assert_eq!(rule.ports, PortSpec::Single(443));
+        assert_eq!(rule.action, RuleAction::Signal(KillSignal::Int));
+
+        for bad in ["80", "80:stop", "0:kill", "80:graceful=soon", ":kill"] {
+            assert!(WatchRule::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn rules_override_the_global_kill_settings() {
+        let base = KillConfig {
+            signal: KillSignal::Term,
+            graceful: true,
+            graceful_timeout: Some(Duration::from_secs(5)),
+            dry_run: false,
+            ignore_manager: false,
+            group: false,
+        };
+        let kill = WatchRule::parse("80:kill").unwrap().apply(&base);
+        assert_eq!((kill.signal, kill.graceful), (KillSignal::Kill, false));
+        let graceful = WatchRule::parse("80:graceful=0").unwrap().apply(&base);
+        assert!(graceful.graceful && graceful.graceful_timeout.is_none());
+    }
+
+    #[test]
+    fn pidfiles_exempt_what_they_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("app.pid");
+        let bad = dir.path().join("bad.pid");
+        std::fs::write(&good, "4321\n").unwrap();
+        std::fs::write(&bad, "not a pid\n").unwrap();
+        let paths = [good.clone(), bad.clone(), dir.path().join("missing.pid")];
+        let mut pidfiles = Pidfiles::new(&paths);
+
+        let pids = pidfiles.read();
+        assert_eq!(pids.len(), 1);
+        assert_eq!(pids[&4321], good.as_path());
+        assert_eq!(pidfiles.warned.len(), 2);
+
+        // Re-read each poll: a restart writes a new PID
+        std::fs::write(&good, "99").unwrap();
+        std::fs::write(&bad, "7").unwrap();
+        let pids = pidfiles.read();
+        assert_eq!(
+            pids.keys().copied().collect::<BTreeSet<_>>(),
+            [7, 99].into()
+        );
+        assert_eq!(pidfiles.warned.len(), 1);
+    }
+
+    #[test]
+    fn conflicting_rules_are_rejected() {
+        let raw = |rules: &[&str]| rules.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            rules(&raw(&["80:kill", "8080:term"]), None).unwrap().len(),
+            2
+        );
+        // The same action twice isn't a conflict
+        assert!(rules(&raw(&["80:kill", "70-90:kill"]), None).is_ok());
+        let err = rules(&raw(&["3000-3010:2:kill", "3004:term"]), None).unwrap_err();
+        assert!(err.to_string().contains("conflict on port 3004"), "{err}");
+        assert!(rules(&raw(&["3000-3010:2:kill", "3005:term"]), None).is_ok());
+    }
+}
@@ -1,7 +1,10 @@
+use crate::aliases;
+use crate::process::ProcessInfo;
 use crate::theme::ThemeVariant;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
@@ -13,31 +16,338 @@ pub struct Config {
 
     #[serde(default)]
     pub animation_duration_ms: u64,
+
+    /// Collect memory/CPU usage for the TUI columns on every refresh
+    #[serde(default = "default_true")]
+    pub show_resource_usage: bool,
+
+    /// Seconds between automatic TUI refreshes
+    #[serde(default = "default_refresh_secs")]
+    pub refresh_secs: u64,
+
+    /// Ports that bulk kills never touch
+    #[serde(default)]
+    pub protected_ports: Vec<u16>,
+
+    /// Process names (case-insensitive) that bulk kills never touch
+    #[serde(default)]
+    pub protected_names: Vec<String>,
+
+    /// Ports shown in the accent colour in `list` tables and the GUI
+    #[serde(default)]
+    pub highlight_ports: Vec<u16>,
+
+    /// Hide TUI rows owned by other users that can't be killed without sudo
+    #[serde(default)]
+    pub hide_unkillable: bool,
+
+    /// Offer to retry permission-denied TUI kills with sudo
+    #[serde(default = "default_true")]
+    pub sudo_retry: bool,
+
+    /// Let kill target ports below 1024 without `--allow-privileged`
+    #[serde(default)]
+    pub allow_privileged: bool,
+
+    /// When kill asks before signalling root's or another user's process
+    #[serde(default)]
+    pub require_confirmation: Confirmation,
+
+    /// Signal used when `--signal` isn't given (term, kill, int, hup)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_signal: Option<String>,
+
+    /// Graceful timeout used when `--timeout` isn't given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_timeout_secs: Option<u64>,
+
+    /// Skip graceful shutdown unless `--graceful` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_no_graceful: Option<bool>,
+
+    /// Output format used when `--format` isn't given (table, json, plain)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_format: Option<String>,
+
+    /// What bare `portzap` does: print help, open the GUI, or list
+    #[serde(default)]
+    pub default_command: DefaultCommand,
+
+    /// Record every kill in history.jsonl for `portzap history`
+    #[serde(default)]
+    pub history: bool,
+
+    /// Entries kept in history.jsonl before it is rotated
+    #[serde(default = "default_history_max_entries")]
+    pub history_max_entries: usize,
+
+    /// Named port groups usable wherever a port is expected, e.g. `dev = "3000-3001,5432"`
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    #[serde(default)]
+    pub watch: WatchConfig,
+
+    #[serde(default)]
+    pub wait: WaitConfig,
+
+    #[serde(default)]
+    pub free: FreeConfig,
+}
+
+/// `require_confirmation`: whether kill asks before touching a process owned
+/// by root or another user.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confirmation {
+    /// Ask on a terminal; scripts and JSON output go ahead
+    #[default]
+    Auto,
+    /// Ask on a terminal; without one, leave those processes alone
+    Always,
+}
+
+/// `default_command`: what `portzap` runs with no command and no ports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultCommand {
+    #[default]
+    Help,
+    /// The dashboard, when stdout is a terminal; help otherwise
+    Gui,
+    List,
+}
+
+/// `[watch]` defaults for `portzap watch`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchConfig {
+    /// Poll interval in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_ms: Option<u64>,
+
+    /// Graceful timeout, taking precedence over `default_timeout_secs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+
+    /// Process names (case-insensitive) that watch never kills
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_names: Vec<String>,
+}
+
+/// `[wait]` defaults for `portzap wait`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaitConfig {
+    /// Poll interval in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_ms: Option<u64>,
+
+    /// How long to wait before giving up (0 = forever)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+/// `[free]` defaults for `portzap free`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FreeConfig {
+    /// Upper bound for the search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u16>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_refresh_secs() -> u64 {
+    2
+}
+
+fn default_history_max_entries() -> usize {
+    10_000
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            theme: ThemeVariant::Dark,
+            theme: ThemeVariant::Auto,
             skip_confirm_dialog: false,
             animation_duration_ms: 1000,
+            show_resource_usage: true,
+            refresh_secs: default_refresh_secs(),
+            protected_ports: Vec::new(),
+            protected_names: Vec::new(),
+            highlight_ports: Vec::new(),
+            hide_unkillable: false,
+            sudo_retry: true,
+            allow_privileged: false,
+            require_confirmation: Confirmation::Auto,
+            default_signal: None,
+            default_timeout_secs: None,
+            default_no_graceful: None,
+            default_format: None,
+            default_command: DefaultCommand::Help,
+            history: false,
+            history_max_entries: default_history_max_entries(),
+            aliases: BTreeMap::new(),
+            watch: WatchConfig::default(),
+            wait: WaitConfig::default(),
+            free: FreeConfig::default(),
+        }
+    }
+}
+
+/// Environment variable pointing at an alternate config file.
+pub const CONFIG_ENV: &str = "PORTZAP_CONFIG";
+
+/// Every key `Config` understands, in the order `config list` prints them.
+pub const KEYS: &[&str] = &[
+    "theme",
+    "skip_confirm_dialog",
+    "animation_duration_ms",
+    "show_resource_usage",
+    "refresh_secs",
+    "protected_ports",
+    "protected_names",
+    "highlight_ports",
+    "hide_unkillable",
+    "sudo_retry",
+    "allow_privileged",
+    "require_confirmation",
+    "default_signal",
+    "default_timeout_secs",
+    "default_no_graceful",
+    "default_format",
+    "default_command",
+    "history",
+    "history_max_entries",
+    "aliases",
+    "watch",
+    "wait",
+    "free",
+];
+
+/// Keys accepted inside the per-command sections, addressed as `section.key`.
+const SECTION_KEYS: &[(&str, &[&str])] = &[
+    ("watch", &["poll_ms", "timeout_secs", "allow_names"]),
+    ("wait", &["poll_ms", "timeout_secs"]),
+    ("free", &["max"]),
+];
+
+/// What happened to the config file during [`Config::load_with_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigFile {
+    Missing,
+    Loaded,
+    /// The file exists but couldn't be read or isn't valid TOML; defaults are used.
+    Invalid(String),
+}
+
+/// Problems found while loading config.toml, for the caller to surface once.
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    pub file: ConfigFile,
+    /// Unknown keys and fields whose values were rejected (and defaulted)
+    pub warnings: Vec<String>,
+}
+
+impl LoadReport {
+    pub fn messages(&self) -> Vec<String> {
+        let mut messages = Vec::new();
+        if let ConfigFile::Invalid(e) = &self.file {
+            messages.push(format!("{e}; using default settings"));
         }
+        messages.extend(self.warnings.iter().cloned());
+        messages
+    }
+
+    /// A single line suitable for a status bar, if there is anything to report.
+    pub fn summary(&self) -> Option<String> {
+        let messages = self.messages();
+        let first = messages
+            .first()?
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Some(match messages.len() {
+            1 => first,
+            n => format!("{first} (+{} more)", n - 1),
+        })
     }
 }
 
 impl Config {
     pub fn load() -> Self {
-        match Self::config_path() {
-            Some(path) => {
-                if let Ok(contents) = fs::read_to_string(&path) {
-                    if let Ok(config) = toml::from_str(&contents) {
-                        return config;
+        Self::load_with_report().0
+    }
+
+    /// Load config.toml field by field: unknown keys and invalid values are
+    /// reported and skipped rather than discarding the whole file.
+    pub fn load_with_report() -> (Self, LoadReport) {
+        let mut report = LoadReport {
+            file: ConfigFile::Missing,
+            warnings: Vec::new(),
+        };
+        let table = match Self::config_path().map(|path| read_table(&path)) {
+            Some(Ok(Some(table))) => table,
+            Some(Err(e)) => {
+                report.file = ConfigFile::Invalid(e);
+                return (Self::default(), report);
+            }
+            None | Some(Ok(None)) => return (Self::default(), report),
+        };
+        report.file = ConfigFile::Loaded;
+        let (config, warnings) = Self::from_table(table);
+        report.warnings = warnings;
+        (config, report)
+    }
+
+    fn from_table(table: toml::Table) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut valid = toml::Table::new();
+        for (key, value) in table {
+            if !KEYS.contains(&key.as_str()) {
+                warnings.push(unknown_key_warning(&key, &key, KEYS));
+                continue;
+            }
+            // Check sections key by key so one typo doesn't drop the whole table
+            if let (Some(fields), toml::Value::Table(section)) = (section_keys(&key), &value) {
+                let mut kept = toml::Table::new();
+                for (field, field_value) in section {
+                    let path = format!("{key}.{field}");
+                    if !fields.contains(&field.as_str()) {
+                        warnings.push(unknown_key_warning(&path, field, fields));
+                    } else if let Err(e) = check_value(&path, field_value.clone()) {
+                        warnings.push(invalid_value_warning(&path, &e));
+                    } else {
+                        kept.insert(field.clone(), field_value.clone());
                     }
                 }
+                valid.insert(key, toml::Value::Table(kept));
+                continue;
+            }
+            match check_value(&key, value.clone()) {
+                Ok(()) => {
+                    valid.insert(key, value);
+                }
+                Err(e) => warnings.push(invalid_value_warning(&key, &e)),
             }
-            None => {}
         }
-        Self::default()
+        // Every remaining field deserialized on its own, so this can't fail
+        let config = valid.try_into().unwrap_or_default();
+        (config, warnings)
+    }
+
+    /// Whether the process is covered by `protected_ports` or `protected_names`.
+    pub fn is_protected(&self, p: &ProcessInfo) -> bool {
+        self.protected_ports.contains(&p.port)
+            || self
+                .protected_names
+                .iter()
+                .any(|n| n.eq_ignore_ascii_case(&p.name))
     }
 
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
@@ -45,21 +355,381 @@ impl Config {
             Some(p) => p,
             None => return Err("Could not determine config directory".into()),
         };
+        self.save_to(&path)
+    }
 
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
+    /// Write the settings to `path`. A file that isn't valid TOML is left
+    /// alone rather than replaced by the settings alone.
+    fn save_to(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        // Keep keys this version doesn't know about
+        let mut table = read_table(path)?.unwrap_or_default();
+        table.extend(toml::Table::try_from(self)?);
 
-        let contents = toml::to_string_pretty(self)?;
-        fs::write(path, contents)?;
+        write_atomic(path, &toml::to_string_pretty(&table)?)?;
         Ok(())
     }
 
-    fn config_path() -> Option<PathBuf> {
+    /// Look up a single key (`theme`, `watch.poll_ms`). `Ok(None)` means the
+    /// key is valid but unset.
+    pub fn get(&self, key: &str) -> Result<Option<toml::Value>, String> {
+        check_key(key)?;
+        let table = toml::Table::try_from(self).map_err(|e| e.to_string())?;
+        Ok(match key.split_once('.') {
+            Some((section, field)) => table.get(section).and_then(|s| s.get(field)).cloned(),
+            None => table.get(key).cloned(),
+        })
+    }
+
+    /// Validate `raw` as a value for `key` and write it to the config file,
+    /// preserving every other key already in the file. Returns the file path.
+    pub fn set(key: &str, raw: &str) -> Result<(PathBuf, toml::Value), String> {
+        check_key(key)?;
+        let path = Self::config_path().ok_or("could not determine config directory")?;
+        let mut table = read_table(&path)?.unwrap_or_default();
+        let value = parse_value(key, raw)?;
+        match key.split_once('.') {
+            Some((section, field)) => {
+                let entry = table
+                    .entry(section)
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+                if !entry.is_table() {
+                    *entry = toml::Value::Table(toml::Table::new());
+                }
+                if let toml::Value::Table(section) = entry {
+                    section.insert(field.to_string(), value.clone());
+                }
+            }
+            None => {
+                table.insert(key.to_string(), value.clone());
+            }
+        }
+
+        let contents = toml::to_string_pretty(&table).map_err(|e| e.to_string())?;
+        write_atomic(&path, &contents)
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        Ok((path, value))
+    }
+
+    /// Directory holding config.toml and other portzap state files.
+    pub fn config_dir() -> Option<PathBuf> {
         let mut path = dirs::config_dir()?;
         path.push("portzap");
+        Some(path)
+    }
+
+    /// Config file named by `PORTZAP_CONFIG`, if set. Unlike the default
+    /// location, this file is expected to exist.
+    pub fn env_path() -> Option<PathBuf> {
+        std::env::var_os(CONFIG_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    }
+
+    pub fn config_path() -> Option<PathBuf> {
+        if let Some(path) = Self::env_path() {
+            return Some(path);
+        }
+        let mut path = Self::config_dir()?;
         path.push("config.toml");
         Some(path)
     }
 }
+
+fn section_keys(section: &str) -> Option<&'static [&'static str]> {
+    SECTION_KEYS
+        .iter()
+        .find(|(name, _)| *name == section)
+        .map(|(_, fields)| *fields)
+}
+
+fn check_key(key: &str) -> Result<(), String> {
+    if let Some((section, field)) = key.split_once('.') {
+        return match section_keys(section) {
+            Some(fields) if fields.contains(&field) => Ok(()),
+            Some(fields) => Err(format!(
+                "unknown key '{key}' (valid keys in [{section}]: {})",
+                fields.join(", ")
+            )),
+            None => Err(format!(
+                "unknown section '{section}' (sections: {})",
+                SECTION_KEYS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        };
+    }
+    if KEYS.contains(&key) {
+        Ok(())
+    } else {
+        Err(format!(
+            "unknown key '{key}' (valid keys: {})",
+            KEYS.join(", ")
+        ))
+    }
+}
+
+/// Check that `value` deserializes into `Config` when placed at `key`,
+/// which may be a dotted `section.field` path.
+fn check_value(key: &str, value: toml::Value) -> Result<(), String> {
+    let mut table = toml::Table::new();
+    match key.split_once('.') {
+        Some((section, field)) => {
+            let mut inner = toml::Table::new();
+            inner.insert(field.to_string(), value);
+            table.insert(section.to_string(), toml::Value::Table(inner));
+        }
+        None => {
+            table.insert(key.to_string(), value);
+        }
+    }
+    table
+        .try_into::<Config>()
+        .map(|_| ())
+        .map_err(|e| e.message().to_string())
+}
+
+fn unknown_key_warning(path: &str, key: &str, candidates: &[&str]) -> String {
+    let mut message = format!("config.toml: unknown key '{path}' ignored");
+    if let Some(close) = candidates
+        .iter()
+        .min_by_key(|k| aliases::edit_distance(key, k))
+        .filter(|k| aliases::edit_distance(key, k) <= 3)
+    {
+        message.push_str(&format!(" (did you mean '{close}'?)"));
+    }
+    message
+}
+
+fn invalid_value_warning(path: &str, error: &str) -> String {
+    format!("config.toml: invalid value for {path}: {error}; using the default")
+}
+
+fn allowed_values(key: &str) -> Option<&'static [&'static str]> {
+    match key {
+        "theme" => Some(&ThemeVariant::NAMES),
+        "default_signal" => Some(&["term", "kill", "int", "hup"]),
+        "default_format" => Some(&["table", "json", "plain"]),
+        "require_confirmation" => Some(&["auto", "always"]),
+        "skip_confirm_dialog"
+        | "show_resource_usage"
+        | "hide_unkillable"
+        | "sudo_retry"
+        | "allow_privileged"
+        | "default_no_graceful" => Some(&["true", "false"]),
+        _ => None,
+    }
+}
+
+/// Interpret `raw` as a TOML literal (`10`, `true`, `[80, 443]`) or, failing
+/// that, a bare string, and check it deserializes into `Config`.
+fn parse_value(key: &str, raw: &str) -> Result<toml::Value, String> {
+    let literal = toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut t| t.remove("value"));
+    let candidates = literal
+        .into_iter()
+        .chain(std::iter::once(toml::Value::String(raw.to_string())));
+
+    let mut first_error = None;
+    for value in candidates {
+        let valid = match check_value(key, value.clone()) {
+            Ok(()) => match (allowed_values(key), value.as_str()) {
+                (Some(allowed), Some(s)) => allowed.iter().any(|a| a.eq_ignore_ascii_case(s)),
+                _ => true,
+            },
+            Err(e) => {
+                first_error.get_or_insert(e);
+                false
+            }
+        };
+        if valid {
+            return Ok(value);
+        }
+    }
+
+    Err(match allowed_values(key) {
+        Some(allowed) => format!(
+            "invalid value '{raw}' for {key} (allowed: {})",
+            allowed.join(", ")
+        ),
+        None => format!(
+            "invalid value '{raw}' for {key}: {}",
+            first_error.unwrap_or_default()
+        ),
+    })
+}
+
+/// Read the config file as a raw table. A missing file is `Ok(None)`.
+fn read_table(path: &Path) -> Result<Option<toml::Table>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("failed to read {}: {e}", path.display())),
+    };
+    contents.parse().map(Some).map_err(|e: toml::de::Error| {
+        let line = e
+            .span()
+            .map(|span| contents[..span.start].lines().count().max(1));
+        match line {
+            Some(line) => format!(
+                "{} is not valid TOML (line {line}: {})",
+                path.display(),
+                e.message()
+            ),
+            None => format!("{} is not valid TOML: {}", path.display(), e.message()),
+        }
+    })
+}
+
+/// Write via a temp file and rename so a crash never leaves a half-written config.
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("toml.tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_keeps_unknown_keys_and_refuses_broken_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let config = Config {
+            theme: ThemeVariant::Light,
+            ..Config::default()
+        };
+
+        fs::write(&path, "future_key = 1\n").unwrap();
+        config.save_to(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("future_key = 1") && saved.contains("theme = \"light\""));
+
+        fs::write(&path, "theme = [oops\n").unwrap();
+        let err = config.save_to(&path).unwrap_err();
+        assert!(err.to_string().contains("not valid TOML"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "theme = [oops\n");
+    }
+
+    #[test]
+    fn keys_cover_every_field() {
+        let config = Config {
+            default_signal: Some("kill".into()),
+            default_timeout_secs: Some(1),
+            default_no_graceful: Some(true),
+            default_format: Some("json".into()),
+            ..Config::default()
+        };
+        let table = toml::Table::try_from(&config).unwrap();
+        let mut fields: Vec<&str> = table.keys().map(String::as_str).collect();
+        let mut keys = KEYS.to_vec();
+        fields.sort_unstable();
+        keys.sort_unstable();
+        assert_eq!(fields, keys);
+    }
+
+    #[test]
+    fn parse_value_uses_field_types() {
+        assert_eq!(
+            parse_value("refresh_secs", "10").unwrap().as_integer(),
+            Some(10)
+        );
+        assert_eq!(
+            parse_value("theme", "light").unwrap().as_str(),
+            Some("light")
+        );
+        assert_eq!(
+            parse_value("protected_ports", "[80, 443]")
+                .unwrap()
+                .as_array()
+                .map(Vec::len),
+            Some(2)
+        );
+        assert_eq!(
+            parse_value("default_signal", "kill").unwrap().as_str(),
+            Some("kill")
+        );
+    }
+
+    #[test]
+    fn parse_value_rejects_invalid_values() {
+        let err = parse_value("theme", "blue").unwrap_err();
+        assert!(err.contains("allowed: auto, dark, light"), "{err}");
+        assert!(parse_value("default_signal", "sigfoo").is_err());
+        assert!(parse_value("refresh_secs", "soon").is_err());
+        assert!(check_key("colour").unwrap_err().contains("valid keys"));
+    }
+
+    #[test]
+    fn from_table_keeps_valid_fields_of_partial_config() {
+        let table: toml::Table = "theme = \"light\"\nrefresh_secs = 7".parse().unwrap();
+        let (config, warnings) = Config::from_table(table);
+        assert!(warnings.is_empty());
+        assert_eq!(config.theme, ThemeVariant::Light);
+        assert_eq!(config.refresh_secs, 7);
+        assert!(config.show_resource_usage);
+        assert!(config.sudo_retry);
+    }
+
+    #[test]
+    fn from_table_reports_unknown_keys() {
+        let table: toml::Table = "skip_confirm_dailog = true\ntheme = \"light\""
+            .parse()
+            .unwrap();
+        let (config, warnings) = Config::from_table(table);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("unknown key 'skip_confirm_dailog'"));
+        assert!(warnings[0].contains("did you mean 'skip_confirm_dialog'"));
+        assert_eq!(config.theme, ThemeVariant::Light);
+        assert!(!config.skip_confirm_dialog);
+    }
+
+    #[test]
+    fn from_table_defaults_only_fields_with_wrong_types() {
+        let table: toml::Table =
+            "refresh_secs = \"soon\"\ntheme = \"blue\"\nhide_unkillable = true"
+                .parse()
+                .unwrap();
+        let (config, warnings) = Config::from_table(table);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings.iter().any(|w| w.contains("refresh_secs")));
+        assert!(warnings.iter().any(|w| w.contains("theme")));
+        assert_eq!(config.refresh_secs, 2);
+        assert_eq!(config.theme, ThemeVariant::Auto);
+        assert!(config.hide_unkillable);
+    }
+
+    #[test]
+    fn report_summary_counts_extra_messages() {
+        let report = LoadReport {
+            file: ConfigFile::Invalid("config.toml is not valid TOML".into()),
+            warnings: vec!["config.toml: unknown key 'x' ignored".into()],
+        };
+        assert_eq!(
+            report.summary().unwrap(),
+            "config.toml is not valid TOML; using default settings (+1 more)"
+        );
+    }
+
+    #[test]
+    fn section_keys_use_dotted_paths() {
+        assert!(check_key("watch.poll_ms").is_ok());
+        assert!(check_key("free.poll_ms")
+            .unwrap_err()
+            .contains("valid keys in [free]"));
+        assert!(check_key("kill.signal")
+            .unwrap_err()
+            .contains("unknown section"));
+        assert_eq!(
+            parse_value("free.max", "61000").unwrap().as_integer(),
+            Some(61000)
+        );
+        assert!(parse_value("free.max", "70000").is_err());
+    }
+}
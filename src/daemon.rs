@@ -0,0 +1,280 @@
+//! `watch --daemon`: watchers that keep running after the terminal closes.
+//! Each one leaves three files in `<config dir>/watch/`, named after the
+//! ports it guards: `<name>.pid`, `<name>.json` describing it, and
+//! `<name>.log` with everything it printed. A watcher that died without
+//! cleaning up is noticed the next time the files are read, and its pidfile
+//! and state are removed; the log is kept.
+
+use crate::config::Config;
+use crate::errors::{KillportError, Result};
+use crate::platform;
+use crate::process::PortSpec;
+use chrono::{Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long `--stop` waits for a watcher to exit before sending SIGKILL.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What `<name>.json` records about a running watcher.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watcher {
+    pub pid: u32,
+    /// Port specs as given, e.g. `3000-3010`
+    pub ports: Vec<String>,
+    /// RFC 3339 timestamp in local time
+    pub started_at: String,
+    pub log: PathBuf,
+    #[serde(skip)]
+    name: String,
+}
+
+impl Watcher {
+    /// Whether this watcher guards `port`.
+    pub fn watches(&self, port: u16) -> bool {
+        self.ports
+            .iter()
+            .filter_map(|spec| PortSpec::parse(spec).ok())
+            .any(|spec| spec.contains(port))
+    }
+}
+
+/// The directory holding watcher files.
+pub fn dir() -> Result<PathBuf> {
+    let mut path = Config::config_dir().ok_or_else(|| {
+        KillportError::PlatformError("no config directory to keep watcher state in".into())
+    })?;
+    path.push("watch");
+    Ok(path)
+}
+
+/// File stem for a watcher of these ports.
+fn name(ports: &[PortSpec]) -> String {
+    ports
+        .iter()
+        .map(|spec| spec.to_string())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn file(dir: &Path, name: &str, extension: &str) -> PathBuf {
+    dir.join(format!("{name}.{extension}"))
+}
+
+/// Start `portzap <args>` detached from the terminal, watching `ports`.
+/// Refuses when a watcher of the same ports is already running.
+#[cfg(unix)]
+pub fn spawn(ports: &[PortSpec], args: &[String]) -> Result<Watcher> {
+    use std::fs::OpenOptions;
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    let dir = dir()?;
+    let name = name(ports);
+    let (running, _) = watchers()?;
+    if let Some(existing) = running.iter().find(|w| w.name == name) {
+        return Err(KillportError::Usage(format!(
+            "{} is already watched in the background (PID {}); stop it with `portzap watch --stop {}`",
+            existing.ports.join(", "),
+            existing.pid,
+            ports[0].expand().next().unwrap_or_default()
+        )));
+    }
+
+    fs::create_dir_all(&dir)?;
+    let log = file(&dir, &name, "log");
+    let output = OpenOptions::new().create(true).append(true).open(&log)?;
+    let exe = std::env::current_exe()?;
+    let mut command = Command::new(exe);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(output.try_clone()?)
+        .stderr(output);
+    // SAFETY: setsid is async-signal-safe and touches no memory of ours.
+    // A new session has no controlling terminal, so closing the terminal
+    // doesn't send the watcher SIGHUP.
+    unsafe {
+        command.pre_exec(|| {
+            nix::unistd::setsid()?;
+            Ok(())
+        });
+    }
+    let child = command.spawn()?;
+    tracing::debug!(pid = child.id(), ?args, "started background watcher");
+
+    let watcher = Watcher {
+        pid: child.id(),
+        ports: ports.iter().map(|spec| spec.to_string()).collect(),
+        started_at: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+        log,
+        name,
+    };
+    fs::write(
+        file(&dir, &watcher.name, "pid"),
+        format!("{}\n", watcher.pid),
+    )?;
+    fs::write(
+        file(&dir, &watcher.name, "json"),
+        serde_json::to_string_pretty(&watcher).map_err(std::io::Error::other)?,
+    )?;
+    Ok(watcher)
+}
+
+#[cfg(not(unix))]
+pub fn spawn(_ports: &[PortSpec], _args: &[String]) -> Result<Watcher> {
+    Err(KillportError::Usage(
+        "--daemon is only supported on Unix; have a service manager run `portzap watch` instead \
+         (--emit-service prints a definition for systemd or launchd)"
+            .into(),
+    ))
+}
+
+/// Watchers whose pidfiles name a running portzap, and the stale ones whose
+/// files were just removed.
+pub fn watchers() -> Result<(Vec<Watcher>, Vec<Watcher>)> {
+    let dir = dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut pidfiles: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pid"))
+        .collect();
+    pidfiles.sort();
+
+    let (mut running, mut stale) = (Vec::new(), Vec::new());
+    for pidfile in pidfiles {
+        let Some(watcher) = read(&dir, &pidfile) else {
+            continue;
+        };
+        if is_running(watcher.pid) {
+            running.push(watcher);
+        } else {
+            tracing::debug!(pid = watcher.pid, name = %watcher.name, "removing stale pidfile");
+            remove(&dir, &watcher.name);
+            stale.push(watcher);
+        }
+    }
+    Ok((running, stale))
+}
+
+/// The watcher a pidfile belongs to. Its state file is only informational,
+/// so a missing or unreadable one is filled in from the file name.
+fn read(dir: &Path, pidfile: &Path) -> Option<Watcher> {
+    let name = pidfile.file_stem()?.to_string_lossy().into_owned();
+    let pid = fs::read_to_string(pidfile).ok()?.trim().parse().ok();
+    let Some(pid) = pid else {
+        // Not something we wrote; leave it for the user to look at.
+        tracing::debug!(path = %pidfile.display(), "ignoring unreadable pidfile");
+        return None;
+    };
+    let state = fs::read_to_string(file(dir, &name, "json"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<Watcher>(&json).ok());
+    Some(match state {
+        Some(state) => Watcher { pid, name, ..state },
+        None => Watcher {
+            pid,
+            ports: name.split('_').map(String::from).collect(),
+            started_at: String::new(),
+            log: file(dir, &name, "log"),
+            name,
+        },
+    })
+}
+
+fn remove(dir: &Path, name: &str) {
+    for extension in ["pid", "json"] {
+        let _ = fs::remove_file(file(dir, name, extension));
+    }
+}
+
+/// Whether `pid` is a live portzap. Checking the name keeps a reused PID
+/// from being mistaken for the watcher, and from being signalled by `--stop`.
+fn is_running(pid: u32) -> bool {
+    let ours = platform::process_identity(std::process::id()).map(|me| me.name);
+    match (platform::process_identity(pid), ours) {
+        (Some(theirs), Some(ours)) => theirs.name == ours,
+        _ => false,
+    }
+}
+
+/// Ask a watcher to stop, escalating to SIGKILL if it hasn't exited after
+/// a few seconds, then remove its pidfile and state.
+#[cfg(unix)]
+pub fn stop(watcher: &Watcher) -> Result<()> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+
+    let pid = Pid::from_raw(watcher.pid as i32);
+    let failed = |e: nix::Error| match e {
+        nix::Error::EPERM => KillportError::PermissionDenied {
+            pid: watcher.pid,
+            name: "portzap".into(),
+        },
+        e => KillportError::KillFailed {
+            pid: watcher.pid,
+            name: "portzap".into(),
+            reason: e.to_string(),
+        },
+    };
+    match kill(pid, Signal::SIGTERM) {
+        Ok(()) | Err(nix::Error::ESRCH) => {}
+        Err(e) => return Err(failed(e)),
+    }
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    while is_running(watcher.pid) {
+        if Instant::now() >= deadline {
+            tracing::debug!(pid = watcher.pid, "watcher ignored SIGTERM");
+            match kill(pid, Signal::SIGKILL) {
+                Ok(()) | Err(nix::Error::ESRCH) => {}
+                Err(e) => return Err(failed(e)),
+            }
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    remove(&dir()?, &watcher.name);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn stop(_watcher: &Watcher) -> Result<()> {
+    Err(KillportError::Usage(
+        "--stop is only supported on Unix".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_files_after_the_port_specs() {
+        let ports = [
+            PortSpec::parse("3000").unwrap(),
+            PortSpec::parse("8000-8010").unwrap(),
+        ];
+        assert_eq!(name(&ports), "3000_8000-8010");
+    }
+
+    #[test]
+    fn fills_in_a_missing_state_file_from_the_pidfile_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let pidfile = file(dir.path(), "3000_8000-8010", "pid");
+        fs::write(&pidfile, "4321\n").unwrap();
+        let watcher = read(dir.path(), &pidfile).unwrap();
+        assert_eq!(watcher.pid, 4321);
+        assert_eq!(watcher.ports, ["3000", "8000-8010"]);
+        assert!(watcher.watches(8005));
+        assert!(!watcher.watches(3001));
+
+        fs::write(&pidfile, "not a pid").unwrap();
+        assert!(read(dir.path(), &pidfile).is_none());
+    }
+}
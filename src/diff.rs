@@ -0,0 +1,83 @@
+//! What changed between two scans, for live views (`list --watch`, the GUI).
+
+use crate::process::ProcessInfo;
+use std::collections::HashSet;
+
+/// Rows that appeared or disappeared, matched by `ProcessInfo::key`.
+#[derive(Debug, Default, Clone)]
+pub struct ProcessDiff {
+    pub added: Vec<ProcessInfo>,
+    pub removed: Vec<ProcessInfo>,
+}
+
+impl ProcessDiff {
+    /// Compare two scans. Both lists keep their order.
+    pub fn between(previous: &[ProcessInfo], current: &[ProcessInfo]) -> Self {
+        let before: HashSet<_> = previous.iter().map(ProcessInfo::key).collect();
+        let after: HashSet<_> = current.iter().map(ProcessInfo::key).collect();
+        Self {
+            added: current
+                .iter()
+                .filter(|p| !before.contains(&p.key()))
+                .cloned()
+                .collect(),
+            removed: previous
+                .iter()
+                .filter(|p| !after.contains(&p.key()))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn is_added(&self, p: &ProcessInfo) -> bool {
+        self.added.iter().any(|a| a.key() == p.key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Protocol;
+    use crate::scanner::mock;
+
+    fn process(pid: u32, port: u16, protocol: Protocol) -> ProcessInfo {
+        ProcessInfo {
+            protocol,
+            ..mock::process(pid, "x", port)
+        }
+    }
+
+    #[test]
+    fn matches_rows_by_pid_port_and_protocol() {
+        let before = [
+            process(1, 80, Protocol::Tcp),
+            process(2, 53, Protocol::Udp),
+            process(3, 8080, Protocol::Tcp),
+        ];
+        let mut renamed = process(1, 80, Protocol::Tcp);
+        renamed.name = "nginx".into();
+        let after = [
+            renamed,
+            process(2, 53, Protocol::Tcp),
+            process(4, 8080, Protocol::Tcp),
+        ];
+
+        let diff = ProcessDiff::between(&before, &after);
+        let keys = |ps: &[ProcessInfo]| ps.iter().map(ProcessInfo::key).collect::<Vec<_>>();
+        assert_eq!(
+            keys(&diff.added),
+            [(2, 53, Protocol::Tcp), (4, 8080, Protocol::Tcp)]
+        );
+        assert_eq!(
+            keys(&diff.removed),
+            [(2, 53, Protocol::Udp), (3, 8080, Protocol::Tcp)]
+        );
+        assert!(diff.is_added(&after[1]));
+        assert!(!diff.is_added(&after[0]));
+        assert!(ProcessDiff::between(&after, &after).is_empty());
+    }
+}
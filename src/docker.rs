@@ -0,0 +1,168 @@
+//! Resolve Docker port-forwarding processes (`docker-proxy`, Docker Desktop's
+//! backend) to the container publishing the port, by asking the Docker
+//! daemon over its Unix socket. Any failure just means no enrichment.
+
+use crate::process::{Container, ProcessInfo, Protocol};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Process names that forward a published port into a container.
+const PROXY_NAMES: &[&str] = &[
+    "docker-proxy",
+    "com.docker.backend",
+    "com.docker.vpnkit",
+    "vpnkit-bridge",
+    "rootlesskit",
+];
+
+pub fn is_proxy(name: &str) -> bool {
+    PROXY_NAMES.contains(&name)
+}
+
+/// Attach containers to proxy processes. Only talks to Docker if there is a proxy.
+pub fn enrich(processes: &mut [ProcessInfo]) {
+    if !processes.iter().any(|p| is_proxy(&p.name)) {
+        return;
+    }
+    let Some(published) = query_containers().map(|c| published_ports(&c)) else {
+        return;
+    };
+    for p in processes.iter_mut().filter(|p| is_proxy(&p.name)) {
+        p.container = published.get(&(p.port, p.protocol)).cloned();
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiContainer {
+    id: String,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    ports: Vec<ApiPort>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiPort {
+    #[serde(rename = "PublicPort")]
+    public_port: Option<u16>,
+    #[serde(rename = "Type")]
+    kind: String,
+}
+
+fn published_ports(containers: &[ApiContainer]) -> HashMap<(u16, Protocol), Container> {
+    let mut published = HashMap::new();
+    for c in containers {
+        let container = Container {
+            id: c.id.chars().take(12).collect(),
+            name: c
+                .names
+                .first()
+                .map(|n| n.trim_start_matches('/').to_string())
+                .unwrap_or_else(|| c.id.chars().take(12).collect()),
+            image: c.image.clone(),
+        };
+        for port in &c.ports {
+            let protocol = match port.kind.as_str() {
+                "tcp" => Protocol::Tcp,
+                "udp" => Protocol::Udp,
+                _ => continue,
+            };
+            if let Some(public) = port.public_port {
+                published.insert((public, protocol), container.clone());
+            }
+        }
+    }
+    published
+}
+
+#[cfg(unix)]
+fn query_containers() -> Option<Vec<ApiContainer>> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let timeout = Some(Duration::from_millis(500));
+    let mut stream = socket_paths()
+        .into_iter()
+        .find_map(|path| UnixStream::connect(path).ok())?;
+    stream.set_read_timeout(timeout).ok()?;
+    stream.set_write_timeout(timeout).ok()?;
+    // HTTP/1.0 so the daemon closes the connection and doesn't chunk the body
+    stream
+        .write_all(b"GET /containers/json HTTP/1.0\r\nHost: docker\r\n\r\n")
+        .ok()?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).ok()?;
+    serde_json::from_slice(response_body(&response)?).ok()
+}
+
+#[cfg(not(unix))]
+fn query_containers() -> Option<Vec<ApiContainer>> {
+    None
+}
+
+/// `$DOCKER_HOST` when it is a unix:// URL, then the usual daemon and Docker Desktop sockets.
+#[cfg(unix)]
+fn socket_paths() -> Vec<std::path::PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(path) = std::env::var("DOCKER_HOST")
+        .ok()
+        .and_then(|host| host.strip_prefix("unix://").map(Into::into))
+    {
+        paths.push(path);
+    }
+    paths.push("/var/run/docker.sock".into());
+    if let Some(home) = dirs::home_dir() {
+        paths.push(home.join(".docker/run/docker.sock"));
+    }
+    paths
+}
+
+/// The body of a successful HTTP response.
+fn response_body(response: &[u8]) -> Option<&[u8]> {
+    let status_ok = response.starts_with(b"HTTP/1.") && response.get(9..12) == Some(b"200");
+    let split = response.windows(4).position(|w| w == b"\r\n\r\n")?;
+    status_ok.then(|| &response[split + 4..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINERS: &str = r#"[
+        {"Id": "4f66ad9a0b2e8e1b", "Names": ["/web"], "Image": "nginx:latest",
+         "Ports": [{"IP": "0.0.0.0", "PrivatePort": 80, "PublicPort": 8080, "Type": "tcp"},
+                   {"IP": "::", "PrivatePort": 80, "PublicPort": 8080, "Type": "tcp"},
+                   {"PrivatePort": 443, "Type": "tcp"}]},
+        {"Id": "9c1d", "Names": ["/dns"], "Image": "coredns",
+         "Ports": [{"PrivatePort": 53, "PublicPort": 5353, "Type": "udp"}]}
+    ]"#;
+
+    #[test]
+    fn maps_published_ports_to_containers() {
+        let containers: Vec<ApiContainer> = serde_json::from_str(CONTAINERS).unwrap();
+        let published = published_ports(&containers);
+        assert_eq!(published.len(), 2);
+        let web = &published[&(8080, Protocol::Tcp)];
+        assert_eq!(
+            (web.name.as_str(), web.image.as_str()),
+            ("web", "nginx:latest")
+        );
+        assert_eq!(web.id, "4f66ad9a0b2e");
+        assert_eq!(published[&(5353, Protocol::Udp)].name, "dns");
+        assert!(!published.contains_key(&(5353, Protocol::Tcp)));
+    }
+
+    #[test]
+    fn response_body_requires_success() {
+        assert_eq!(
+            response_body(b"HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n[]"),
+            Some(&b"[]"[..])
+        );
+        assert_eq!(response_body(b"HTTP/1.0 404 Not Found\r\n\r\n{}"), None);
+        assert_eq!(response_body(b"garbage"), None);
+    }
+}
@@ -12,6 +12,13 @@ pub enum KillportError {
     #[error("no process found on port {port}")]
     NoProcessFound { port: u16 },
 
+    #[error("no process with PID {pid}")]
+    NoSuchProcess { pid: u32 },
+
+    /// `kill --pid` with ports the process doesn't listen on
+    #[error("PID {pid} is not listening on {ports}")]
+    NotListening { pid: u32, ports: String },
+
     #[error("failed to kill process {pid} ({name}): {reason}")]
     KillFailed {
         pid: u32,
@@ -22,11 +29,121 @@ pub enum KillportError {
     #[error("permission denied: cannot kill process {pid} ({name}). Try running with sudo")]
     PermissionDenied { pid: u32, name: String },
 
+    #[error("process {pid} ({name}) already exited")]
+    ProcessGone { pid: u32, name: String },
+
+    /// A service manager owns the socket and would re-create it
+    #[error("{explanation}")]
+    ServiceManaged { pid: u32, explanation: String },
+
+    /// Bad arguments or port specs
+    #[error("{0}")]
+    Usage(String),
+
     #[error("platform error: {0}")]
     PlatformError(String),
 
+    /// `--host` couldn't reach the machine or scan it
+    #[error("{0}")]
+    Remote(String),
+
+    #[error("config: {0}")]
+    Config(String),
+
+    #[error("history: {0}")]
+    History(String),
+
+    /// `--output` couldn't write its file
+    #[error("could not write {}", path.display())]
+    Output {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 }
 
 pub type Result<T> = std::result::Result<T, KillportError>;
+
+/// Process exit statuses. Documented in the README; don't renumber.
+pub mod exit_code {
+    pub const SUCCESS: i32 = 0;
+    /// Some kills failed, or a command's check didn't pass
+    pub const FAILURE: i32 = 1;
+    /// Invalid arguments, port specs or configuration
+    pub const USAGE: i32 = 2;
+    /// `list --expect-free`/`--expect-used` found a port in the wrong state.
+    /// Shares its code with `USAGE`, as CI scripts asked for.
+    pub const ASSERTION_FAILED: i32 = 2;
+    /// Nothing was listening where something was required
+    pub const NOTHING_FOUND: i32 = 3;
+    /// Every target was off-limits to the current user
+    pub const PERMISSION_DENIED: i32 = 4;
+    /// The process or socket tables couldn't be read, here or on a `--host`
+    pub const PLATFORM: i32 = 5;
+}
+
+impl KillportError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            KillportError::InvalidPort(_)
+            | KillportError::InvalidPortRange(_)
+            | KillportError::Usage(_)
+            | KillportError::Config(_) => exit_code::USAGE,
+            KillportError::NoProcessFound { .. }
+            | KillportError::NoSuchProcess { .. }
+            | KillportError::NotListening { .. } => exit_code::NOTHING_FOUND,
+            KillportError::PermissionDenied { .. } => exit_code::PERMISSION_DENIED,
+            KillportError::PlatformError(_) | KillportError::Remote(_) => exit_code::PLATFORM,
+            KillportError::KillFailed { .. }
+            | KillportError::ProcessGone { .. }
+            | KillportError::ServiceManaged { .. }
+            | KillportError::History(_)
+            | KillportError::Output { .. }
+            | KillportError::Io(_) => exit_code::FAILURE,
+        }
+    }
+
+    /// Stable name for the `kind` of JSON error objects.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            KillportError::InvalidPort(_) => "invalid_port",
+            KillportError::InvalidPortRange(_) => "invalid_port_range",
+            KillportError::NoProcessFound { .. } => "no_process_found",
+            KillportError::NoSuchProcess { .. } => "no_such_process",
+            KillportError::NotListening { .. } => "not_listening",
+            KillportError::KillFailed { .. } => "kill_failed",
+            KillportError::PermissionDenied { .. } => "permission_denied",
+            KillportError::ProcessGone { .. } => "process_gone",
+            KillportError::ServiceManaged { .. } => "service_managed",
+            KillportError::Usage(_) => "usage",
+            KillportError::PlatformError(_) => "platform",
+            KillportError::Remote(_) => "remote",
+            KillportError::Config(_) => "config",
+            KillportError::History(_) => "history",
+            KillportError::Output { .. } => "output",
+            KillportError::Io(_) => "io",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes_follow_the_documented_contract() {
+        assert_eq!(KillportError::InvalidPort(70000).exit_code(), 2);
+        assert_eq!(KillportError::Config("bad".into()).exit_code(), 2);
+        assert_eq!(KillportError::NoProcessFound { port: 1 }.exit_code(), 3);
+        let denied = KillportError::PermissionDenied {
+            pid: 1,
+            name: "init".into(),
+        };
+        assert_eq!(denied.exit_code(), 4);
+        assert_eq!(denied.kind(), "permission_denied");
+        assert_eq!(KillportError::PlatformError("x".into()).exit_code(), 5);
+        assert_eq!(KillportError::History("x".into()).exit_code(), 1);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::output;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// `watch --log-file`: the NDJSON lines of `--format json`, appended to a
+/// file whatever the output format. With `rotate_size`, a file that would
+/// grow past it is moved to `<path>.1` first, shifting older rotations up to
+/// `<path>.<keep>` and dropping the one beyond.
+#[derive(Debug)]
+pub struct EventLog {
+    pub path: PathBuf,
+    pub rotate_size: Option<u64>,
+    pub keep: usize,
+    /// fsync after every write
+    pub sync: bool,
+    warned: bool,
+}
+
+impl EventLog {
+    /// A relative `path` is taken from the current directory now, so a
+    /// service manager starting the watcher elsewhere writes the same file.
+    pub fn new(path: PathBuf, rotate_size: Option<u64>, keep: usize, sync: bool) -> Self {
+        let path = match std::env::current_dir() {
+            Ok(dir) => dir.join(path),
+            Err(_) => path,
+        };
+        Self {
+            path,
+            rotate_size,
+            keep: keep.max(1),
+            sync,
+            warned: false,
+        }
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    }
+
+    /// Append kill results or [`output::IgnoredEvent`]s, one line each.
+    pub fn record<T: Serialize>(&self, events: &[T]) -> io::Result<()> {
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&output::event_line(event));
+            lines.push('\n');
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if let Some(limit) = self.rotate_size {
+            let size = fs::metadata(&self.path).map_or(0, |m| m.len());
+            if size > 0 && size + lines.len() as u64 > limit {
+                self.rotate()?;
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(lines.as_bytes())?;
+        if self.sync {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn rotate(&self) -> io::Result<()> {
+        for n in (1..self.keep).rev() {
+            match fs::rename(self.rotated_path(n), self.rotated_path(n + 1)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        if self.sync {
+            if let Some(parent) = self.path.parent() {
+                // Make the renames themselves durable; not every platform
+                // can open a directory, and that's fine.
+                let _ = File::open(parent).and_then(|dir| dir.sync_all());
+            }
+        }
+        Ok(())
+    }
+
+    /// Record results, warning the first time a write fails: a broken log
+    /// must not stop the watcher. A successful write clears the warning, so
+    /// the log breaking again (the disk filling up a second time, say) is
+    /// warned about again rather than lost in the debug log.
+    pub fn record_or_warn<T: Serialize>(&mut self, events: &[T]) {
+        match self.record(events) {
+            Ok(()) => self.warned = false,
+            Err(e) if !self.warned => {
+                self.warned = true;
+                output::print_warning(&format!(
+                    "could not write the watch log {}: {e}",
+                    self.path.display()
+                ));
+            }
+            Err(e) => tracing::debug!(error = %e, "watch log still failing"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::KillResult;
+    use crate::scanner::mock::process;
+
+    fn result(pid: u32) -> KillResult {
+        KillResult {
+            process: process(pid, "node", 3000),
+            success: true,
+            signal_sent: "SIGTERM".into(),
+            error: None,
+            error_kind: None,
+            target: None,
+            rule: None,
+        }
+    }
+
+    #[test]
+    fn rotates_by_size_and_keeps_the_newest_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let line_len = output::event_line(&result(1)).len() as u64 + 1;
+        let log = EventLog::new(dir.path().join("watch.ndjson"), Some(line_len * 2), 2, true);
+        for pid in 1..=7 {
+            log.record(&[result(pid)]).unwrap();
+        }
+
+        let pids = |path: PathBuf| -> Vec<u64> {
+            fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| {
+                    let event: serde_json::Value = serde_json::from_str(line).unwrap();
+                    event["process"]["pid"].as_u64().unwrap()
+                })
+                .collect()
+        };
+        assert_eq!(pids(log.path.clone()), [7]);
+        assert_eq!(pids(log.rotated_path(1)), [5, 6]);
+        assert_eq!(pids(log.rotated_path(2)), [3, 4]);
+        assert!(!log.rotated_path(3).exists());
+    }
+}
@@ -0,0 +1,334 @@
+use crate::process::{PortSpec, ProcessInfo};
+use regex::{Regex, RegexBuilder};
+
+/// A parsed filter query such as `name:node port:3000-3010 /vite|next/`.
+///
+/// Terms are separated by whitespace and combined with AND semantics:
+/// - `port:<spec>` matches a port or port range exactly
+/// - `pid:<n>` matches a PID exactly
+/// - `name:<text>` / `user:<text>` / `cwd:<text>` match a case-insensitive substring
+/// - `/<regex>/` matches the name, command or working directory (case-insensitive)
+/// - anything else is a substring match across name, port, PID, command and
+///   working directory
+#[derive(Debug, Default)]
+pub struct ProcessFilter {
+    terms: Vec<Term>,
+}
+
+/// `list --name` patterns. A row is kept if any pattern matches its name,
+/// command or working directory: plain text as a case-insensitive substring,
+/// `/<regex>/` as a case-insensitive regex.
+#[derive(Debug, Default)]
+pub struct NameFilter {
+    patterns: Vec<(String, Term)>,
+}
+
+impl NameFilter {
+    pub fn parse(patterns: &[String]) -> Result<Self, String> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let term = if pattern.starts_with('/') {
+                    parse_term(pattern.clone())?
+                } else {
+                    Term::Process(pattern.to_lowercase())
+                };
+                Ok((pattern.clone(), term))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn matches(&self, p: &ProcessInfo) -> bool {
+        self.is_empty() || self.patterns.iter().any(|(_, term)| term.matches(p))
+    }
+
+    /// The patterns as given.
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.patterns.iter().map(|(pattern, _)| pattern.as_str())
+    }
+
+    /// The patterns as given, e.g. "node or /vite|next/".
+    pub fn describe(&self) -> String {
+        self.patterns().collect::<Vec<_>>().join(" or ")
+    }
+}
+
+#[derive(Debug)]
+enum Term {
+    Text(String),
+    /// Name, command or working directory substring
+    Process(String),
+    Port(PortSpec),
+    Pid(u32),
+    Name(String),
+    User(String),
+    Cwd(String),
+    Regex(Regex),
+}
+
+impl ProcessFilter {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let terms = tokenize(input)
+            .into_iter()
+            .map(parse_term)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { terms })
+    }
+
+    pub fn matches(&self, p: &ProcessInfo) -> bool {
+        self.terms.iter().all(|term| term.matches(p))
+    }
+}
+
+impl Term {
+    fn matches(&self, p: &ProcessInfo) -> bool {
+        match self {
+            Term::Text(query) => {
+                p.name.to_lowercase().contains(query)
+                    || p.port.to_string().contains(query)
+                    || p.pid.to_string().contains(query)
+                    || p.command
+                        .as_deref()
+                        .map(|c| c.to_lowercase().contains(query))
+                        .unwrap_or(false)
+                    || cwd_contains(p, query)
+            }
+            Term::Process(query) => {
+                p.name.to_lowercase().contains(query)
+                    || p.command
+                        .as_deref()
+                        .map(|c| c.to_lowercase().contains(query))
+                        .unwrap_or(false)
+                    || cwd_contains(p, query)
+            }
+            Term::Port(spec) => spec.contains(p.port),
+            Term::Pid(pid) => p.pid == *pid,
+            Term::Name(query) => p.name.to_lowercase().contains(query),
+            Term::User(query) => p
+                .user
+                .as_deref()
+                .map(|u| u.to_lowercase().contains(query))
+                .unwrap_or(false),
+            Term::Cwd(query) => cwd_contains(p, query),
+            Term::Regex(re) => {
+                re.is_match(&p.name)
+                    || p.command
+                        .as_deref()
+                        .map(|c| re.is_match(c))
+                        .unwrap_or(false)
+                    || p.cwd
+                        .as_deref()
+                        .map(|cwd| re.is_match(&cwd.to_string_lossy()))
+                        .unwrap_or(false)
+            }
+        }
+    }
+}
+
+fn cwd_contains(p: &ProcessInfo, query: &str) -> bool {
+    p.cwd
+        .as_deref()
+        .map(|cwd| cwd.to_string_lossy().to_lowercase().contains(query))
+        .unwrap_or(false)
+}
+
+/// Split on whitespace, keeping `/.../` regex terms together even if they contain spaces.
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut words = input.split_whitespace();
+    while let Some(word) = words.next() {
+        if word.starts_with('/') && !(word.len() > 1 && word.ends_with('/')) {
+            let mut token = word.to_string();
+            for next in words.by_ref() {
+                token.push(' ');
+                token.push_str(next);
+                if next.ends_with('/') {
+                    break;
+                }
+            }
+            tokens.push(token);
+        } else {
+            tokens.push(word.to_string());
+        }
+    }
+    tokens
+}
+
+fn parse_term(token: String) -> Result<Term, String> {
+    if let Some(pattern) = token.strip_prefix('/') {
+        let pattern = pattern
+            .strip_suffix('/')
+            .ok_or_else(|| format!("unterminated regex: {token}"))?;
+        return RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(Term::Regex)
+            .map_err(|e| format!("invalid regex: {}", error_summary(&e.to_string())));
+    }
+
+    let Some((field, value)) = token.split_once(':') else {
+        return Ok(Term::Text(token.to_lowercase()));
+    };
+    match field.to_lowercase().as_str() {
+        "port" => PortSpec::parse(value)
+            .map(Term::Port)
+            .map_err(|_| format!("invalid port: {value}")),
+        "pid" => value
+            .parse()
+            .map(Term::Pid)
+            .map_err(|_| format!("invalid pid: {value}")),
+        "name" => Ok(Term::Name(value.to_lowercase())),
+        "user" => Ok(Term::User(value.to_lowercase())),
+        "cwd" => Ok(Term::Cwd(value.to_lowercase())),
+        _ => Ok(Term::Text(token.to_lowercase())),
+    }
+}
+
+/// Regex errors span several lines with a caret diagram; keep just the message.
+fn error_summary(s: &str) -> &str {
+    s.lines().last().unwrap_or(s).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::mock;
+
+    fn process(pid: u32, name: &str, port: u16, command: &str) -> ProcessInfo {
+        ProcessInfo {
+            command: Some(command.into()),
+            user: Some("alice".into()),
+            uid: Some(1000),
+            ..mock::process(pid, name, port)
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let f = ProcessFilter::parse("   ").unwrap();
+        assert!(f.matches(&process(1, "node", 80, "node")));
+    }
+
+    #[test]
+    fn plain_text_matches_any_field() {
+        let f = ProcessFilter::parse("80").unwrap();
+        assert!(f.matches(&process(8012, "x", 1, "x")));
+        assert!(f.matches(&process(1, "node-80s-playlist", 1, "x")));
+        assert!(f.matches(&process(1, "x", 80, "x")));
+    }
+
+    #[test]
+    fn port_prefix_is_exact() {
+        let f = ProcessFilter::parse("port:80").unwrap();
+        assert!(f.matches(&process(1, "nginx", 80, "nginx")));
+        assert!(!f.matches(&process(8012, "x", 8080, "x")));
+    }
+
+    #[test]
+    fn port_prefix_accepts_ranges() {
+        let f = ProcessFilter::parse("port:3000-3010").unwrap();
+        assert!(f.matches(&process(1, "x", 3005, "x")));
+        assert!(!f.matches(&process(1, "x", 3011, "x")));
+    }
+
+    #[test]
+    fn pid_name_and_user_prefixes() {
+        let p = process(1234, "Node", 3000, "node server.js");
+        assert!(ProcessFilter::parse("pid:1234").unwrap().matches(&p));
+        assert!(!ProcessFilter::parse("pid:123").unwrap().matches(&p));
+        assert!(ProcessFilter::parse("name:node").unwrap().matches(&p));
+        assert!(ProcessFilter::parse("user:ali").unwrap().matches(&p));
+        assert!(!ProcessFilter::parse("user:root").unwrap().matches(&p));
+    }
+
+    #[test]
+    fn cwd_prefix_and_plain_text_match_working_directory() {
+        let mut p = process(1, "node", 3000, "node server.js");
+        p.cwd = Some("/home/alice/src/MyRepo/packages/web".into());
+        assert!(ProcessFilter::parse("cwd:myrepo").unwrap().matches(&p));
+        assert!(ProcessFilter::parse("packages/web").unwrap().matches(&p));
+        assert!(!ProcessFilter::parse("cwd:other").unwrap().matches(&p));
+        p.cwd = None;
+        assert!(!ProcessFilter::parse("cwd:myrepo").unwrap().matches(&p));
+    }
+
+    #[test]
+    fn terms_combine_with_and() {
+        let p = process(1234, "node", 3000, "node server.js");
+        assert!(ProcessFilter::parse("name:node port:3000")
+            .unwrap()
+            .matches(&p));
+        assert!(!ProcessFilter::parse("name:node port:3001")
+            .unwrap()
+            .matches(&p));
+    }
+
+    #[test]
+    fn regex_matches_name_or_command() {
+        let p = process(1, "python3", 8000, "python3 manage.py runserver");
+        assert!(ProcessFilter::parse("/^py.*3$/").unwrap().matches(&p));
+        assert!(ProcessFilter::parse("/manage\\.py run/")
+            .unwrap()
+            .matches(&p));
+        assert!(!ProcessFilter::parse("/^node/").unwrap().matches(&p));
+    }
+
+    #[test]
+    fn invalid_inputs_report_errors() {
+        assert!(ProcessFilter::parse("/(unclosed/")
+            .unwrap_err()
+            .contains("invalid regex"));
+        assert!(ProcessFilter::parse("/abc")
+            .unwrap_err()
+            .contains("unterminated"));
+        assert!(ProcessFilter::parse("port:abc")
+            .unwrap_err()
+            .contains("invalid port"));
+        assert!(ProcessFilter::parse("pid:-1")
+            .unwrap_err()
+            .contains("invalid pid"));
+    }
+
+    #[test]
+    fn name_filter_ors_substrings_and_regexes() {
+        let node = process(1, "node", 3000, "node server.js");
+        let vite = process(2, "esbuild", 5173, "npm exec vite");
+        let names = |patterns: &[&str]| {
+            NameFilter::parse(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()).unwrap()
+        };
+
+        assert!(names(&[]).matches(&node));
+        assert!(names(&["NODE"]).matches(&node));
+        assert!(!names(&["NODE"]).matches(&vite));
+        assert!(names(&["vite"]).matches(&vite));
+        assert!(names(&["/^node$/", "vite"]).matches(&node));
+        assert!(names(&["/^node$/", "vite"]).matches(&vite));
+        assert!(!names(&["3000"]).matches(&node));
+        assert_eq!(names(&["node", "/vite/"]).describe(), "node or /vite/");
+        assert!(NameFilter::parse(&["/(/".into()]).is_err());
+    }
+
+    #[test]
+    fn name_filter_and_regex_match_working_directory() {
+        let mut p = process(1, "node", 3000, "node server.js");
+        p.cwd = Some("/home/alice/src/MyRepo/packages/web".into());
+        let names = |pattern: &str| NameFilter::parse(&[pattern.to_string()]).unwrap();
+        assert!(names("myrepo").matches(&p));
+        assert!(names("/myrepo/packages/").matches(&p));
+        assert!(ProcessFilter::parse("/repo/pack/").unwrap().matches(&p));
+        assert!(!names("other-repo").matches(&p));
+        p.cwd = None;
+        assert!(!names("myrepo").matches(&p));
+    }
+
+    #[test]
+    fn unknown_prefix_is_plain_text() {
+        let p = process(1, "x", 1, "http://localhost");
+        assert!(ProcessFilter::parse("http://local").unwrap().matches(&p));
+    }
+}
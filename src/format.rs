@@ -0,0 +1,200 @@
+//! Durations for people: what output prints ("1.2s", "3m 20s", "2d 4h") and
+//! what flags such as `--timeout` accept ("500ms", "2s", "5m", "1h", "1d").
+//! Anything printed with [`humanize_duration`] parses back with
+//! [`parse_duration`], give or take the units it leaves off.
+
+use std::time::{Duration, Instant};
+
+const UNITS: [(&str, u64); 5] = [
+    ("ms", 1),
+    ("s", 1000),
+    ("m", 60_000),
+    ("h", 3_600_000),
+    ("d", 86_400_000),
+];
+
+/// Format a duration with at most two units, rounding down: "0s", "250ms",
+/// "1.2s", "42s", "3m 20s", "5h 0m", "2d 4h". Days are the largest unit.
+pub fn humanize_duration(duration: Duration) -> String {
+    if duration.is_zero() {
+        return "0s".into();
+    }
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        return format!("{millis}ms");
+    }
+    if millis < 10_000 {
+        let tenths = millis / 100;
+        return match tenths % 10 {
+            0 => format!("{}s", tenths / 10),
+            t => format!("{}.{t}s", tenths / 10),
+        };
+    }
+    let secs = duration.as_secs();
+    let (days, hours, mins) = (secs / 86_400, secs / 3600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {mins}m")
+    } else if mins > 0 {
+        format!("{mins}m {}s", secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// How long ago `at` was, e.g. "3m 20s ago".
+pub fn humanize_instant_ago(at: Instant) -> String {
+    format!("{} ago", humanize_duration(at.elapsed()))
+}
+
+/// `duration` as a flag value that parses back exactly: whole seconds
+/// ("5"), or milliseconds ("1500ms").
+pub fn duration_arg(duration: Duration) -> String {
+    match duration.subsec_millis() {
+        0 => duration.as_secs().to_string(),
+        _ => format!("{}ms", duration.as_millis()),
+    }
+}
+
+/// Parse a duration such as "500ms", "2s", "1.5m", "1h" or "3m 20s". Each
+/// amount needs a unit: ms, s, m, h or d.
+pub fn parse_duration(raw: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration '{raw}' (expected e.g. 500ms, 2s, 5m, 1h, 1d)");
+    let mut rest = raw.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (amount, tail) = rest.split_at(split);
+        let amount: f64 = amount.parse().map_err(|_| invalid())?;
+        let split = tail
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(split);
+        if unit.is_empty() {
+            return Err(format!(
+                "missing unit in duration '{raw}' (use ms, s, m, h or d)"
+            ));
+        }
+        let Some(&(_, millis)) = UNITS.iter().find(|(name, _)| *name == unit) else {
+            return Err(format!(
+                "invalid duration unit '{unit}' in '{raw}' (use ms, s, m, h or d)"
+            ));
+        };
+        let part =
+            Duration::try_from_secs_f64(amount * millis as f64 / 1000.0).map_err(|_| invalid())?;
+        total = total.checked_add(part).ok_or_else(invalid)?;
+        rest = tail.trim_start();
+    }
+    Ok(total)
+}
+
+/// `parse_duration`, but a bare number counts as seconds, as flags like
+/// `--timeout 5` always have.
+pub fn parse_seconds(raw: &str) -> Result<Duration, String> {
+    match raw.trim().parse::<u64>() {
+        Ok(secs) => Ok(Duration::from_secs(secs)),
+        Err(_) => parse_duration(raw),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn humanize_uses_two_largest_units() {
+        assert_eq!(humanize_duration(Duration::ZERO), "0s");
+        assert_eq!(humanize_duration(Duration::from_micros(300)), "0ms");
+        assert_eq!(humanize_duration(Duration::from_millis(250)), "250ms");
+        assert_eq!(humanize_duration(Duration::from_millis(1234)), "1.2s");
+        assert_eq!(humanize_duration(Duration::from_millis(5000)), "5s");
+        assert_eq!(humanize_duration(Duration::from_secs(12)), "12s");
+        assert_eq!(humanize_duration(Duration::from_secs(200)), "3m 20s");
+        assert_eq!(
+            humanize_duration(Duration::from_secs(2 * 3600 + 59)),
+            "2h 0m"
+        );
+        assert_eq!(
+            humanize_duration(Duration::from_secs(2 * 86_400 + 4 * 3600 + 1)),
+            "2d 4h"
+        );
+        // Past a year, days keep counting.
+        assert_eq!(
+            humanize_duration(Duration::from_secs(400 * 86_400 + 3 * 3600)),
+            "400d 3h"
+        );
+    }
+
+    #[test]
+    fn parse_accepts_units_decimals_and_compounds() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2s"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(86_400)));
+        assert_eq!(parse_duration(" 3m 20s "), Ok(Duration::from_secs(200)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("0s"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_rejects_bad_input() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5").unwrap_err().contains("missing unit"));
+        assert!(parse_duration("5y").unwrap_err().contains("unit 'y'"));
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("1..2s").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("99999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn bare_numbers_are_seconds_for_flags() {
+        assert_eq!(parse_seconds("5"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_seconds("0"), Ok(Duration::ZERO));
+        assert_eq!(parse_seconds("750ms"), Ok(Duration::from_millis(750)));
+        assert!(parse_seconds("soon").is_err());
+    }
+
+    #[test]
+    fn duration_args_parse_back_exactly() {
+        for millis in [0, 500, 1000, 1500, 90_000] {
+            let duration = Duration::from_millis(millis);
+            assert_eq!(parse_seconds(&duration_arg(duration)), Ok(duration));
+        }
+        assert_eq!(duration_arg(Duration::from_millis(1500)), "1500ms");
+    }
+
+    #[test]
+    fn formatted_durations_parse_back_within_rounding() {
+        // Spread samples over every magnitude from microseconds to years.
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let exponent = (state % 47) as u32;
+            let duration = Duration::from_micros(state % 2u64.pow(exponent).max(1));
+
+            let text = humanize_duration(duration);
+            let parsed = parse_duration(&text).unwrap_or_else(|e| panic!("{text}: {e}"));
+            // Only the smallest unit shown is rounded away.
+            let unit = match duration.as_millis() {
+                0..=999 => Duration::from_millis(1),
+                1000..=9_999 => Duration::from_millis(100),
+                10_000..=3_599_999 => Duration::from_secs(1),
+                3_600_000..=86_399_999 => Duration::from_secs(60),
+                _ => Duration::from_secs(3600),
+            };
+            assert!(parsed <= duration, "{duration:?} -> {text}");
+            assert!(duration - parsed < unit, "{duration:?} -> {text}");
+        }
+    }
+}
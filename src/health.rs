@@ -0,0 +1,160 @@
+//! `wait --http`: a port only counts as up once an HTTP GET on it returns an
+//! acceptable status. Services that bind early and answer 502 while they boot
+//! pass a plain socket check long before they're ready. Requests go to
+//! `127.0.0.1` over plain HTTP; the client is behind the `http` feature.
+
+use crate::errors::{KillportError, Result};
+use std::fmt;
+
+/// How long one request may take before it counts as a failed check.
+#[cfg(feature = "http")]
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Status codes that pass, as inclusive ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusSet(Vec<(u16, u16)>);
+
+impl Default for StatusSet {
+    /// Any 2xx or 3xx
+    fn default() -> Self {
+        Self(vec![(200, 399)])
+    }
+}
+
+impl StatusSet {
+    /// A comma-separated list of codes (`204`), ranges (`200-299`) and
+    /// classes (`2xx`).
+    pub fn parse(s: &str) -> Result<Self> {
+        let invalid = |part: &str| {
+            KillportError::Usage(format!(
+                "invalid status `{part}` in --expect-status; use codes like 200, ranges like 200-299 or classes like 2xx"
+            ))
+        };
+        let code = |part: &str| {
+            part.parse::<u16>()
+                .ok()
+                .filter(|c| (100..=599).contains(c))
+                .ok_or_else(|| invalid(part))
+        };
+        let mut ranges = Vec::new();
+        for part in s.split(',').map(str::trim) {
+            let range = if let Some(class) = part.strip_suffix("xx") {
+                let class = code(&format!("{class}00"))?;
+                (class, class + 99)
+            } else if let Some((start, end)) = part.split_once('-') {
+                let (start, end) = (code(start)?, code(end)?);
+                if start > end {
+                    return Err(invalid(part));
+                }
+                (start, end)
+            } else {
+                let code = code(part)?;
+                (code, code)
+            };
+            ranges.push(range);
+        }
+        Ok(Self(ranges))
+    }
+
+    pub fn contains(&self, status: u16) -> bool {
+        self.0
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&status))
+    }
+}
+
+/// What one request got back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+pub enum Probe {
+    Status(u16),
+    /// Refused, timed out, or not HTTP at all
+    Unreachable(String),
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Probe::Status(status) => write!(f, "HTTP {status}"),
+            Probe::Unreachable(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+/// A GET on `path` that passes with a status in `expect`.
+#[derive(Debug, Clone)]
+pub struct HttpCheck {
+    pub path: String,
+    pub expect: StatusSet,
+}
+
+impl HttpCheck {
+    /// Fails when portzap was built without the `http` feature, so the flag
+    /// is rejected before any waiting starts.
+    pub fn new(path: &str, expect: StatusSet) -> Result<Self> {
+        if !cfg!(feature = "http") {
+            return Err(KillportError::Usage(
+                "--http needs portzap built with the `http` feature".into(),
+            ));
+        }
+        let path = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{path}")
+        };
+        Ok(Self { path, expect })
+    }
+
+    pub fn passes(&self, probe: &Probe) -> bool {
+        matches!(probe, Probe::Status(status) if self.expect.contains(*status))
+    }
+
+    /// One GET on this machine's `port`. Redirects aren't followed: a 3xx
+    /// already shows the server is answering.
+    #[cfg(feature = "http")]
+    pub fn probe(&self, port: u16) -> Probe {
+        let url = format!("http://127.0.0.1:{port}{}", self.path);
+        let agent = ureq::AgentBuilder::new()
+            .timeout(REQUEST_TIMEOUT)
+            .redirects(0)
+            .build();
+        let probe = match agent.get(&url).call() {
+            Ok(response) => Probe::Status(response.status()),
+            Err(ureq::Error::Status(status, _)) => Probe::Status(status),
+            Err(ureq::Error::Transport(e)) => Probe::Unreachable(match e.kind() {
+                ureq::ErrorKind::ConnectionFailed => "connection refused".into(),
+                ureq::ErrorKind::Io => "no response".into(),
+                _ => e.to_string(),
+            }),
+        };
+        tracing::trace!(%url, %probe, "health check");
+        probe
+    }
+
+    #[cfg(not(feature = "http"))]
+    pub fn probe(&self, _port: u16) -> Probe {
+        Probe::Unreachable("built without the `http` feature".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_codes_ranges_and_classes() {
+        let set = StatusSet::parse("2xx, 301-302,404").unwrap();
+        for status in [200, 250, 299, 301, 302, 404] {
+            assert!(set.contains(status), "{status}");
+        }
+        for status in [300, 303, 403, 500] {
+            assert!(!set.contains(status), "{status}");
+        }
+        assert!(StatusSet::default().contains(302));
+        assert!(!StatusSet::default().contains(502));
+
+        for bad in ["", "abc", "6xx", "299-200", "42"] {
+            assert!(StatusSet::parse(bad).is_err(), "{bad}");
+        }
+    }
+}
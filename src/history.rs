@@ -0,0 +1,330 @@
+use crate::config::Config;
+use crate::output;
+use crate::process::KillResult;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeDelta, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Where a kill was triggered from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Cli,
+    Tui,
+    Watch,
+}
+
+/// One line of history.jsonl.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// RFC 3339 timestamp in local time
+    pub timestamp: String,
+    pub port: u16,
+    pub pid: u32,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    pub signal: String,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub source: Source,
+}
+
+impl HistoryEntry {
+    pub fn from_result(result: &KillResult, source: Source, at: DateTime<Local>) -> Self {
+        Self {
+            timestamp: at.to_rfc3339(),
+            port: result.process.port,
+            pid: result.process.pid,
+            name: result.process.name.clone(),
+            command: result.process.command.clone(),
+            signal: result.signal_sent.clone(),
+            success: result.success,
+            error: result.error.clone(),
+            source,
+        }
+    }
+
+    pub fn time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.timestamp).ok()
+    }
+}
+
+/// Append-only kill log in the config directory. When the current file
+/// reaches `max_entries` lines it is moved to `history.jsonl.1`, replacing
+/// any older rotation, so at most two files' worth of entries are kept.
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+    max_entries: usize,
+    /// Lines in the current file, counted on the first record and kept up
+    /// to date after that so a long-running GUI or watcher reads it once
+    lines: Cell<Option<usize>>,
+}
+
+impl History {
+    pub fn new(path: PathBuf, max_entries: usize) -> Self {
+        Self {
+            path,
+            max_entries: max_entries.max(1),
+            lines: Cell::new(None),
+        }
+    }
+
+    /// The log location, whether or not recording is enabled.
+    pub fn open(config: &Config) -> Option<Self> {
+        let mut path = Config::config_dir()?;
+        path.push("history.jsonl");
+        Some(Self::new(path, config.history_max_entries))
+    }
+
+    /// The log to record into, if `history = true`.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if config.history {
+            Self::open(config)
+        } else {
+            None
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rotated_path(&self) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(".1");
+        PathBuf::from(name)
+    }
+
+    pub fn record(&self, results: &[KillResult], source: Source) -> io::Result<()> {
+        if results.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut lines_in_file = match self.lines.get() {
+            Some(n) => n,
+            None => count_lines(&self.path)?,
+        };
+        if lines_in_file >= self.max_entries {
+            fs::rename(&self.path, self.rotated_path())?;
+            lines_in_file = 0;
+        }
+        // Recount next time if the append below fails partway
+        self.lines.set(None);
+
+        let now = Local::now();
+        let mut lines = String::new();
+        for result in results {
+            let entry = HistoryEntry::from_result(result, source, now);
+            lines.push_str(&serde_json::to_string(&entry).map_err(io::Error::other)?);
+            lines.push('\n');
+        }
+        // One write per batch so concurrent portzap processes don't interleave lines
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(lines.as_bytes())?;
+        self.lines.set(Some(lines_in_file + results.len()));
+        Ok(())
+    }
+
+    /// Record results, printing a warning instead of failing: history must
+    /// never get in the way of a kill.
+    pub fn record_or_warn(&self, results: &[KillResult], source: Source) {
+        if let Err(e) = self.record(results, source) {
+            output::print_warning(&format!(
+                "could not write kill history to {}: {e}",
+                self.path.display()
+            ));
+        }
+    }
+
+    /// All entries, oldest first. Lines that don't parse are skipped.
+    pub fn read(&self) -> io::Result<Vec<HistoryEntry>> {
+        let mut entries = Vec::new();
+        for path in [self.rotated_path(), self.path.clone()] {
+            let file = match fs::File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for line in BufReader::new(file).lines() {
+                if let Ok(entry) = serde_json::from_str(&line?) {
+                    entries.push(entry);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        self.lines.set(None);
+        for path in [self.path.clone(), self.rotated_path()] {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn count_lines(path: &Path) -> io::Result<usize> {
+    match fs::File::open(path) {
+        Ok(file) => Ok(BufReader::new(file).lines().count()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Parse `--since`: a relative age (`30m`, `12h`, `7d`), a date
+/// (`2024-05-01`, local midnight) or an RFC 3339 timestamp.
+pub fn parse_since(raw: &str, now: DateTime<Local>) -> Result<DateTime<FixedOffset>, String> {
+    let raw = raw.trim();
+    if let Ok(at) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(at);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        return Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|at| at.fixed_offset())
+            .ok_or_else(|| format!("invalid date: '{raw}'"));
+    }
+
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (amount, unit) = raw.split_at(split);
+    let invalid = || format!("invalid --since '{raw}' (expected e.g. 30m, 12h, 7d, 2024-05-01)");
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let age = match unit {
+        "s" => TimeDelta::try_seconds(amount),
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => {
+            return Err(format!(
+                "invalid --since unit '{unit}' (use s, m, h, d or w)"
+            ))
+        }
+    };
+    age.and_then(|age| now.checked_sub_signed(age))
+        .map(|at| at.fixed_offset())
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::ProcessInfo;
+    use crate::scanner::mock::process;
+
+    fn result(pid: u32, port: u16) -> KillResult {
+        KillResult {
+            process: ProcessInfo {
+                command: Some("node server.js".into()),
+                ..process(pid, "node", port)
+            },
+            success: true,
+            signal_sent: "SIGTERM".into(),
+            error: None,
+            error_kind: None,
+            target: None,
+            rule: None,
+        }
+    }
+
+    #[test]
+    fn record_appends_and_rotates() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::new(dir.path().join("history.jsonl"), 2);
+
+        history
+            .record(&[result(1, 3000), result(2, 3001)], Source::Cli)
+            .unwrap();
+        history.record(&[result(3, 3002)], Source::Watch).unwrap();
+        let entries = history.read().unwrap();
+        assert_eq!(entries.iter().map(|e| e.pid).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(entries[2].source, Source::Watch);
+
+        // The next rotation drops the first batch
+        history
+            .record(&[result(4, 3003), result(5, 3004)], Source::Tui)
+            .unwrap();
+        history.record(&[result(6, 3005)], Source::Tui).unwrap();
+        let pids: Vec<_> = history.read().unwrap().iter().map(|e| e.pid).collect();
+        assert_eq!(pids, [3, 4, 5, 6]);
+
+        history.clear().unwrap();
+        assert!(history.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_skips_corrupt_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::new(dir.path().join("history.jsonl"), 100);
+        history.record(&[result(1, 3000)], Source::Cli).unwrap();
+        fs::write(
+            history.path(),
+            format!("{}not json\n", fs::read_to_string(history.path()).unwrap()),
+        )
+        .unwrap();
+        assert_eq!(history.read().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn parse_since_accepts_ages_dates_and_timestamps() {
+        let now = Local::now();
+        let hour_ago = parse_since("1h", now).unwrap();
+        assert_eq!((now.fixed_offset() - hour_ago).num_minutes(), 60);
+        assert_eq!(
+            (now.fixed_offset() - parse_since("2d", now).unwrap()).num_hours(),
+            48
+        );
+        assert!(parse_since("2024-05-01", now).is_ok());
+        assert_eq!(
+            parse_since("2024-05-01T15:00:00+02:00", now)
+                .unwrap()
+                .to_rfc3339(),
+            "2024-05-01T15:00:00+02:00"
+        );
+        assert!(parse_since("yesterday", now).is_err());
+        assert!(parse_since("5y", now).unwrap_err().contains("unit"));
+    }
+
+    #[test]
+    fn parse_since_rejects_ages_out_of_range() {
+        let now = Local::now();
+        assert!(parse_since("99999999999d", now)
+            .unwrap_err()
+            .contains("invalid --since"));
+        assert!(parse_since("999999999999999999d", now)
+            .unwrap_err()
+            .contains("invalid --since"));
+        assert!(parse_since("9223372036854775807s", now).is_err());
+    }
+
+    #[test]
+    fn record_keeps_counting_after_the_first_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::new(dir.path().join("history.jsonl"), 3);
+        history.record(&[result(1, 3000)], Source::Cli).unwrap();
+        history
+            .record(&[result(2, 3001), result(3, 3002)], Source::Tui)
+            .unwrap();
+        assert_eq!(history.lines.get(), Some(3));
+        history.record(&[result(4, 3003)], Source::Tui).unwrap();
+        assert_eq!(history.lines.get(), Some(1));
+        let pids: Vec<_> = history.read().unwrap().iter().map(|e| e.pid).collect();
+        assert_eq!(pids, [1, 2, 3, 4]);
+    }
+}
@@ -1,5 +1,34 @@
 use crate::process::ProcessInfo;
-use dialoguer::MultiSelect;
+use dialoguer::{Confirm, MultiSelect};
+use std::io::IsTerminal;
+
+/// What `-i` does when there is no terminal to ask on (cron, CI, a pipe).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fallback {
+    /// Kill everything that matched, as without `-i`
+    All,
+    /// Kill nothing
+    None,
+    /// Stop with a usage error
+    #[default]
+    Fail,
+}
+
+/// Whether prompts can be shown and answered. They're drawn on stderr, so
+/// stdout can be redirected freely.
+pub fn has_terminal() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no. Any error
+/// (no terminal, Ctrl+C) counts as no.
+pub fn confirm(prompt: &str) -> bool {
+    Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .unwrap_or(false)
+}
 
 /// Present processes to the user and let them pick which to kill.
 pub fn select_processes(processes: &[ProcessInfo]) -> Vec<ProcessInfo> {
@@ -20,7 +49,11 @@ pub fn select_processes(processes: &[ProcessInfo]) -> Vec<ProcessInfo> {
                 .unwrap_or_default();
             format!(
                 "PID {:>6} | {:>5}/{} | {}{}",
-                p.pid, p.port, p.protocol, p.name, cmd
+                p.pid,
+                p.port,
+                p.protocol,
+                p.display_name(),
+                cmd
             )
         })
         .collect();
@@ -1,4 +1,12 @@
-use crate::process::{KillResult, KillSignal, ProcessInfo};
+use crate::errors::{KillportError, Result};
+use crate::platform::{self, ProcessIdentity};
+use crate::process::{KillErrorKind, KillResult, KillSignal, Origin, ProcessInfo};
+use crate::wsl;
+use signal_hook::consts::SIGINT;
+use signal_hook::SigId;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -6,129 +14,667 @@ use std::time::{Duration, Instant};
 pub struct KillConfig {
     pub signal: KillSignal,
     pub graceful: bool,
-    pub graceful_timeout: Duration,
+    /// How long SIGTERM gets before SIGKILL; `None` waits for the process
+    /// to exit however long it takes
+    pub graceful_timeout: Option<Duration>,
     pub dry_run: bool,
+    /// Signal processes whose socket a service manager would just re-create
+    pub ignore_manager: bool,
+    /// Signal each process's whole process group instead (Unix)
+    pub group: bool,
 }
 
+/// How long a graceful kill waits before SIGKILL unless told otherwise.
+pub const DEFAULT_GRACEFUL_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl Default for KillConfig {
     fn default() -> Self {
         Self {
             signal: KillSignal::Term,
             graceful: true,
-            graceful_timeout: Duration::from_secs(5),
+            graceful_timeout: Some(DEFAULT_GRACEFUL_TIMEOUT),
             dry_run: false,
+            ignore_manager: false,
+            group: false,
+        }
+    }
+}
+
+/// What a kill signals: the process, or with `--group` its process group.
+#[derive(Clone, Copy)]
+enum Target {
+    Process,
+    Group(u32),
+}
+
+impl Target {
+    fn signal(self, process: &ProcessInfo, signal: KillSignal) -> Result<()> {
+        match self {
+            Target::Process => send_signal(process, signal),
+            Target::Group(pgid) => send_group_signal(process, pgid, signal),
+        }
+    }
+
+    /// For a group, whether any member is left.
+    fn alive(self, process: &ProcessInfo) -> bool {
+        match self {
+            Target::Process => is_process_alive(process.pid),
+            Target::Group(pgid) => is_group_alive(pgid),
+        }
+    }
+
+    /// Only a single PID can be handed to another process meanwhile.
+    fn reused(self, process: &ProcessInfo) -> bool {
+        matches!(self, Target::Process) && pid_reused(process)
+    }
+
+    /// Note a process group on the result, so output can say what was signalled.
+    fn label(self, result: KillResult) -> KillResult {
+        match self {
+            Target::Process => result,
+            Target::Group(pgid) => KillResult {
+                target: Some(format!("pgid {pgid}")),
+                ..result
+            },
+        }
+    }
+}
+
+/// The `--timeout` as a graceful timeout; 0 waits forever.
+pub fn graceful_timeout(timeout: Duration) -> Option<Duration> {
+    (!timeout.is_zero()).then_some(timeout)
+}
+
+/// Ctrl+C while waiting for a process to exit. A second Ctrl+C exits
+/// portzap as usual.
+struct Interrupt {
+    requested: Arc<AtomicBool>,
+    handlers: Vec<SigId>,
+}
+
+impl Interrupt {
+    fn watch() -> Self {
+        let requested = Arc::new(AtomicBool::new(false));
+        let handlers = [
+            signal_hook::flag::register_conditional_default(SIGINT, requested.clone()),
+            signal_hook::flag::register(SIGINT, requested.clone()),
+        ]
+        .into_iter()
+        .filter_map(|handler| handler.ok())
+        .collect();
+        Self {
+            requested,
+            handlers,
         }
     }
+
+    fn requested(&self) -> bool {
+        self.requested.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Interrupt {
+    fn drop(&mut self) {
+        for handler in self.handlers.drain(..) {
+            signal_hook::low_level::unregister(handler);
+        }
+    }
+}
+
+/// How waiting for a process to exit ended.
+#[derive(Debug, PartialEq)]
+enum Waited {
+    Exited,
+    TimedOut,
+    Interrupted,
+}
+
+/// Poll `running` every `interval` until it's false, `timeout` passes
+/// (`None` waits forever) or Ctrl+C.
+fn wait_for_exit(
+    mut running: impl FnMut() -> bool,
+    timeout: Option<Duration>,
+    interval: Duration,
+) -> Waited {
+    let start = Instant::now();
+    let interrupt = Interrupt::watch();
+    loop {
+        if !running() {
+            return Waited::Exited;
+        }
+        if interrupt.requested() {
+            return Waited::Interrupted;
+        }
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            return Waited::TimedOut;
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// The error for a process left running because Ctrl+C ended the wait
+/// before `skipped` (the escalation) happened.
+fn interrupted(process: &ProcessInfo, skipped: &str) -> KillportError {
+    KillportError::KillFailed {
+        pid: process.pid,
+        name: process.name.clone(),
+        reason: format!("interrupted while waiting for it to exit; {skipped}"),
+    }
 }
 
 pub fn kill_process(process: &ProcessInfo, config: &KillConfig) -> KillResult {
-    if config.dry_run {
-        return KillResult {
-            process: process.clone(),
-            success: true,
-            signal_sent: format!("{} (dry-run)", config.signal),
-            error: None,
-        };
+    kill_processes(std::slice::from_ref(process), config)
+        .pop()
+        .expect("one result per process")
+}
+
+/// Kill several processes as a set, such as everything sharing a port
+/// through SO_REUSEPORT: SIGTERM goes to all of them first, the graceful
+/// wait covers the whole set, and only the stragglers get SIGKILL. Waiting
+/// on them one by one would leave the others accepting connections
+/// meanwhile, and can take the full timeout per process.
+/// Returns one result per process, in order.
+pub fn kill_processes(processes: &[ProcessInfo], config: &KillConfig) -> Vec<KillResult> {
+    let mut results: Vec<Option<KillResult>> = vec![None; processes.len()];
+    let mut graceful = Vec::new();
+    for (i, process) in processes.iter().enumerate() {
+        match prepare(process, config) {
+            Ok(target) if config.graceful => graceful.push((i, target)),
+            Ok(target) => results[i] = Some(target.label(force_kill(process, config, target))),
+            Err(result) => results[i] = Some(*result),
+        }
+    }
+    let set: Vec<(&ProcessInfo, Target)> = graceful
+        .iter()
+        .map(|&(i, target)| (&processes[i], target))
+        .collect();
+    for ((i, target), result) in graceful.iter().zip(graceful_kill(&set, config)) {
+        results[*i] = Some(target.label(result));
+    }
+    results.into_iter().flatten().collect()
+}
+
+/// What to signal for `process`, or its result when there's nothing left to
+/// signal: it's refused, a dry run, or a Windows process (ended separately).
+fn prepare(
+    process: &ProcessInfo,
+    config: &KillConfig,
+) -> std::result::Result<Target, Box<KillResult>> {
+    if let (Some(managed), false) = (&process.managed_by, config.ignore_manager) {
+        return Err(Box::new(failed(
+            process,
+            "none".into(),
+            KillportError::ServiceManaged {
+                pid: process.pid,
+                explanation: managed.explain(process.port),
+            },
+        )));
     }
 
-    if config.graceful {
-        graceful_kill(process, config)
+    if process.origin == Some(Origin::Windows) {
+        return Err(Box::new(windows_kill(process, config)));
+    }
+
+    // `--group`: signal the process group the process belongs to, so
+    // siblings such as the rest of an `npm run dev` go too.
+    let target = if config.group {
+        match process_group(process) {
+            Ok(pgid) => Target::Group(pgid),
+            Err(e) => return Err(Box::new(failed(process, "none".into(), e))),
+        }
     } else {
-        force_kill(process, config)
+        Target::Process
+    };
+
+    if config.dry_run {
+        let result = succeeded(process, format!("{} (dry-run)", config.signal));
+        return Err(Box::new(target.label(result)));
     }
+    Ok(target)
 }
 
-fn graceful_kill(process: &ProcessInfo, config: &KillConfig) -> KillResult {
-    // Step 1: Send SIGTERM
-    if let Err(e) = send_signal(process.pid, KillSignal::Term) {
-        return KillResult {
-            process: process.clone(),
-            success: false,
-            signal_sent: KillSignal::Term.to_string(),
-            error: Some(e),
-        };
+/// SIGTERM each of `set`, wait for them to exit together, then SIGKILL
+/// whatever is left. Results come back in the order of `set`.
+fn graceful_kill(set: &[(&ProcessInfo, Target)], config: &KillConfig) -> Vec<KillResult> {
+    let term = || KillSignal::Term.to_string();
+    let mut results: Vec<Option<KillResult>> = vec![None; set.len()];
+
+    // Step 1: Send SIGTERM to all of them
+    let mut pending = Vec::new();
+    for (i, &(process, target)) in set.iter().enumerate() {
+        match target.signal(process, KillSignal::Term) {
+            Ok(()) => pending.push(i),
+            Err(e) => results[i] = Some(failed(process, term(), e)),
+        }
     }
 
-    // Step 2: Poll until process exits or timeout
+    // Step 2: Poll until they exit, the timeout passes or Ctrl+C
     let start = Instant::now();
     let poll_interval = Duration::from_millis(100);
+    let interrupt = Interrupt::watch();
 
-    while start.elapsed() < config.graceful_timeout {
-        if !is_process_alive(process.pid) {
-            return KillResult {
-                process: process.clone(),
-                success: true,
-                signal_sent: KillSignal::Term.to_string(),
-                error: None,
-            };
+    while !pending.is_empty()
+        && config
+            .graceful_timeout
+            .map_or(true, |timeout| start.elapsed() < timeout)
+    {
+        pending.retain(|&i| {
+            let (process, target) = set[i];
+            if target.alive(process) {
+                return true;
+            }
+            tracing::debug!(pid = process.pid, elapsed = ?start.elapsed(), "process exited");
+            results[i] = Some(succeeded(process, term()));
+            false
+        });
+        if interrupt.requested() {
+            for i in pending.drain(..) {
+                let process = set[i].0;
+                let error = interrupted(process, "SIGKILL was not sent");
+                results[i] = Some(failed(process, term(), error));
+            }
         }
-        thread::sleep(poll_interval);
+        if !pending.is_empty() {
+            thread::sleep(poll_interval);
+        }
+    }
+
+    // Step 3: Escalate the stragglers to SIGKILL, unless one exited after
+    // the last poll and its PID now belongs to something else.
+    let mut escalated = false;
+    for i in pending {
+        let (process, target) = set[i];
+        tracing::debug!(
+            pid = process.pid,
+            "still running after the graceful timeout"
+        );
+        if !target.alive(process) || target.reused(process) {
+            results[i] = Some(succeeded(process, term()));
+            continue;
+        }
+        results[i] = Some(match target.signal(process, KillSignal::Kill) {
+            Ok(()) => {
+                escalated = true;
+                succeeded(
+                    process,
+                    format!("{} -> {}", KillSignal::Term, KillSignal::Kill),
+                )
+            }
+            Err(e) => failed(process, KillSignal::Kill.to_string(), e),
+        });
+    }
+    if escalated {
+        // Give them a moment to actually die
+        thread::sleep(Duration::from_millis(100));
+    }
+    results.into_iter().flatten().collect()
+}
+
+fn force_kill(process: &ProcessInfo, config: &KillConfig, target: Target) -> KillResult {
+    // Interactive mode and the TUI can sit on a scan for a long time.
+    if target.reused(process) {
+        return failed(process, "none".into(), gone(process));
+    }
+    match target.signal(process, config.signal) {
+        Ok(()) => succeeded(process, config.signal.to_string()),
+        Err(e) => failed(process, config.signal.to_string(), e),
+    }
+}
+
+/// Windows-side processes under WSL can't be signalled. Plain `taskkill.exe`
+/// asks them to close, which console programs refuse, and `/F` terminates
+/// them; that follows the graceful timeout like SIGKILL does.
+fn windows_kill(process: &ProcessInfo, config: &KillConfig) -> KillResult {
+    let force = !config.graceful && config.signal == KillSignal::Kill;
+    let first = if force { "taskkill /F" } else { "taskkill" };
+    if config.dry_run {
+        return succeeded(process, format!("{first} (dry-run)"));
     }
 
-    // Step 3: Escalate to SIGKILL
-    match send_signal(process.pid, KillSignal::Kill) {
+    let asked = wsl::taskkill(process, force);
+    if !config.graceful {
+        return match asked {
+            Ok(()) => succeeded(process, first.into()),
+            Err(e) => failed(process, first.into(), e),
+        };
+    }
+    match asked {
+        Err(e @ KillportError::ProcessGone { .. }) => return failed(process, first.into(), e),
+        Err(_) => {}
         Ok(()) => {
-            // Give it a moment to actually die
-            thread::sleep(Duration::from_millis(100));
-            KillResult {
-                process: process.clone(),
-                success: true,
-                signal_sent: format!("{} -> {}", KillSignal::Term, KillSignal::Kill),
-                error: None,
+            // tasklist.exe is slow to start, so poll less often than kill(0).
+            let running = || wsl::is_running(process.pid);
+            match wait_for_exit(running, config.graceful_timeout, Duration::from_millis(500)) {
+                Waited::Exited => return succeeded(process, first.into()),
+                Waited::Interrupted => {
+                    let error = interrupted(process, "taskkill /F was not run");
+                    return failed(process, first.into(), error);
+                }
+                Waited::TimedOut => {}
             }
         }
-        Err(e) => KillResult {
-            process: process.clone(),
-            success: false,
-            signal_sent: KillSignal::Kill.to_string(),
-            error: Some(e),
-        },
+    }
+    match wsl::taskkill(process, true) {
+        Ok(()) => succeeded(process, "taskkill -> taskkill /F".into()),
+        Err(e) => failed(process, "taskkill /F".into(), e),
     }
 }
 
-fn force_kill(process: &ProcessInfo, config: &KillConfig) -> KillResult {
-    match send_signal(process.pid, config.signal) {
-        Ok(()) => KillResult {
-            process: process.clone(),
-            success: true,
-            signal_sent: config.signal.to_string(),
-            error: None,
-        },
-        Err(e) => KillResult {
-            process: process.clone(),
-            success: false,
-            signal_sent: config.signal.to_string(),
-            error: Some(e),
-        },
+fn succeeded(process: &ProcessInfo, signal_sent: String) -> KillResult {
+    KillResult {
+        process: process.clone(),
+        success: true,
+        signal_sent,
+        error: None,
+        error_kind: None,
+        target: None,
+        rule: None,
+    }
+}
+
+/// Turn a kill error into a result. A process that is already gone is what
+/// the caller wanted, so that counts as success.
+fn failed(process: &ProcessInfo, signal_sent: String, error: KillportError) -> KillResult {
+    let kind = error_kind(&error);
+    if kind == KillErrorKind::ProcessGone {
+        return KillResult {
+            signal_sent: "already exited".into(),
+            error_kind: Some(kind),
+            ..succeeded(process, signal_sent)
+        };
+    }
+    KillResult {
+        process: process.clone(),
+        success: false,
+        signal_sent,
+        error: Some(reason(&error)),
+        error_kind: Some(kind),
+        target: None,
+        rule: None,
     }
 }
 
+fn error_kind(error: &KillportError) -> KillErrorKind {
+    match error {
+        KillportError::PermissionDenied { .. } => KillErrorKind::PermissionDenied,
+        KillportError::ProcessGone { .. } => KillErrorKind::ProcessGone,
+        KillportError::ServiceManaged { .. } => KillErrorKind::ServiceManaged,
+        _ => KillErrorKind::Failed,
+    }
+}
+
+/// The part of an error worth printing next to the process it concerns,
+/// which output already names.
+fn reason(error: &KillportError) -> String {
+    match error {
+        KillportError::PermissionDenied { .. } => "permission denied".into(),
+        KillportError::KillFailed { reason, .. } => reason.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn gone(process: &ProcessInfo) -> KillportError {
+    KillportError::ProcessGone {
+        pid: process.pid,
+        name: process.name.clone(),
+    }
+}
+
+/// Whether the scanned process has exited and another one now has its PID.
+/// A PID that can't be inspected is assumed to still be ours.
+fn pid_reused(process: &ProcessInfo) -> bool {
+    platform::process_identity(process.pid)
+        .is_some_and(|current| !is_same_process(process, &current))
+}
+
+/// Whether the process we scanned still runs under its PID, whoever owns it.
+fn still_running(process: &ProcessInfo) -> bool {
+    platform::process_identity(process.pid)
+        .is_some_and(|current| is_same_process(process, &current))
+}
+
+/// Compare the process we scanned with what currently runs under its PID.
+/// Start times identify a process exactly. When either side lacks one (the
+/// scan couldn't read it, or the platform doesn't report it) only the names
+/// are compared, which can't tell a process from a same-named successor.
+fn is_same_process(scanned: &ProcessInfo, current: &ProcessIdentity) -> bool {
+    match (scanned.started_at, current.started_at) {
+        (Some(then), Some(now)) => then == now,
+        _ => scanned.name == current.name,
+    }
+}
+
+/// Whether signalling this process will likely fail without elevated privileges,
+/// i.e. it is owned by another user and we are not running as root.
 #[cfg(unix)]
-fn send_signal(pid: u32, signal: KillSignal) -> std::result::Result<(), String> {
-    use nix::sys::signal::{self, Signal};
-    use nix::unistd::Pid;
+pub fn needs_elevation(process: &ProcessInfo) -> bool {
+    let euid = nix::unistd::geteuid();
+    !euid.is_root() && process.uid.is_some_and(|uid| uid != euid.as_raw())
+}
 
-    let nix_signal = match signal {
+#[cfg(windows)]
+pub fn needs_elevation(_process: &ProcessInfo) -> bool {
+    false
+}
+
+/// Whether the process belongs to root or to a user other than us, which
+/// kill confirms before signalling.
+#[cfg(unix)]
+pub fn is_foreign(process: &ProcessInfo) -> bool {
+    let euid = nix::unistd::geteuid().as_raw();
+    process.uid.is_some_and(|uid| uid == 0 || uid != euid)
+}
+
+#[cfg(windows)]
+pub fn is_foreign(_process: &ProcessInfo) -> bool {
+    false
+}
+
+/// Signals `$1` with `$2` as root, escalating to KILL if it is still there
+/// after `$3` tenths of a second (0 for no escalation, without waiting). Exits 3 when the
+/// first signal can't be delivered and 5 after escalating.
+const SUDO_KILL_SCRIPT: &str = r#"kill -s "$2" "$1" 2>/dev/null || exit 3
+[ "$3" -gt 0 ] || exit 0
+i=0
+while [ "$i" -lt "$3" ]; do
+    kill -0 "$1" 2>/dev/null || exit 0
+    sleep 0.1
+    i=$((i + 1))
+done
+kill -s KILL "$1" 2>/dev/null && exit 5
+exit 0"#;
+
+/// `kill_process` through `sudo -n`, for processes owned by someone we may
+/// not signal. sudo never prompts here: it must allow this without a
+/// password, or credentials must be cached (see [`sudo_validate`]).
+/// Without a graceful timeout, only SIGTERM goes through sudo and the wait
+/// for the process to exit happens here, where Ctrl+C can end it.
+pub fn kill_with_sudo(process: &ProcessInfo, config: &KillConfig) -> KillResult {
+    if pid_reused(process) {
+        return failed(process, "none".into(), gone(process));
+    }
+    let (signal, ticks) = if config.graceful {
+        // Without a timeout, SIGTERM is sent and never escalated
+        let timeout = config.graceful_timeout.unwrap_or_default();
+        (KillSignal::Term, timeout.as_millis() / 100)
+    } else {
+        (config.signal, 0)
+    };
+    let name = signal.to_string();
+    tracing::debug!(pid = process.pid, %signal, "sending signal with sudo");
+    let status = Command::new("sudo")
+        .args(["-n", "sh", "-c", SUDO_KILL_SCRIPT, "sh"])
+        .arg(process.pid.to_string())
+        .arg(name.trim_start_matches("SIG"))
+        .arg(ticks.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let sent = format!("sudo {signal}");
+    match status.map(|s| s.code()) {
+        Ok(Some(0)) if config.graceful && config.graceful_timeout.is_none() => {
+            let running = || still_running(process);
+            match wait_for_exit(running, None, Duration::from_millis(100)) {
+                Waited::Interrupted => {
+                    failed(process, sent, interrupted(process, "SIGKILL was not sent"))
+                }
+                _ => succeeded(process, sent),
+            }
+        }
+        Ok(Some(0)) => succeeded(process, sent),
+        Ok(Some(5)) => succeeded(process, format!("sudo {signal} -> {}", KillSignal::Kill)),
+        Ok(Some(3)) => failed(process, sent, gone(process)),
+        Ok(_) => failed(
+            process,
+            sent,
+            KillportError::KillFailed {
+                pid: process.pid,
+                name: process.name.clone(),
+                reason: "sudo refused or needs a password".into(),
+            },
+        ),
+        Err(e) => failed(
+            process,
+            sent,
+            KillportError::KillFailed {
+                pid: process.pid,
+                name: process.name.clone(),
+                reason: format!("could not run sudo: {e}"),
+            },
+        ),
+    }
+}
+
+/// Whether `sudo -n` would run without asking for a password.
+pub fn sudo_ready() -> bool {
+    Command::new("sudo")
+        .args(["-n", "true"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Ask for the sudo password on the terminal and cache the credentials.
+/// False when the prompt was cancelled or the password was wrong.
+pub fn sudo_validate() -> bool {
+    Command::new("sudo")
+        .arg("-v")
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+#[cfg(unix)]
+fn nix_signal(signal: KillSignal) -> nix::sys::signal::Signal {
+    use nix::sys::signal::Signal;
+    match signal {
         KillSignal::Term => Signal::SIGTERM,
         KillSignal::Kill => Signal::SIGKILL,
         KillSignal::Int => Signal::SIGINT,
         KillSignal::Hup => Signal::SIGHUP,
+    }
+}
+
+#[cfg(unix)]
+fn signal_error(process: &ProcessInfo, errno: nix::errno::Errno) -> KillportError {
+    use nix::errno::Errno;
+    match errno {
+        Errno::EPERM => KillportError::PermissionDenied {
+            pid: process.pid,
+            name: process.name.clone(),
+        },
+        Errno::ESRCH => gone(process),
+        e => KillportError::KillFailed {
+            pid: process.pid,
+            name: process.name.clone(),
+            reason: e.to_string(),
+        },
+    }
+}
+
+#[cfg(unix)]
+fn send_signal(process: &ProcessInfo, signal: KillSignal) -> Result<()> {
+    use nix::sys::signal;
+    use nix::unistd::Pid;
+
+    tracing::debug!(pid = process.pid, %signal, "sending signal");
+    signal::kill(Pid::from_raw(process.pid as i32), nix_signal(signal)).map_err(|e| {
+        tracing::trace!(pid = process.pid, errno = ?e, "kill(2) failed");
+        signal_error(process, e)
+    })
+}
+
+#[cfg(unix)]
+fn send_group_signal(process: &ProcessInfo, pgid: u32, signal: KillSignal) -> Result<()> {
+    use nix::sys::signal;
+    use nix::unistd::Pid;
+
+    tracing::debug!(pid = process.pid, pgid, %signal, "signalling process group");
+    signal::killpg(Pid::from_raw(pgid as i32), nix_signal(signal)).map_err(|e| {
+        tracing::trace!(pgid, errno = ?e, "killpg(3) failed");
+        signal_error(process, e)
+    })
+}
+
+/// The process group `--group` would signal, once it's clear that's safe:
+/// not portzap's own group or init's, and not a group led by the session
+/// leader, which is usually the shell the process was started from.
+#[cfg(unix)]
+fn process_group(process: &ProcessInfo) -> Result<u32> {
+    use nix::errno::Errno;
+    use nix::unistd::{getpgid, getpgrp, getsid, Pid};
+
+    let pid = Pid::from_raw(process.pid as i32);
+    let pgid = getpgid(Some(pid)).map_err(|e| match e {
+        Errno::ESRCH => gone(process),
+        e => KillportError::KillFailed {
+            pid: process.pid,
+            name: process.name.clone(),
+            reason: format!("could not read its process group: {e}"),
+        },
+    })?;
+    let refuse = |why: &str| {
+        Err(KillportError::KillFailed {
+            pid: process.pid,
+            name: process.name.clone(),
+            reason: format!("won't signal process group {pgid}: {why}"),
+        })
     };
+    if pgid == getpgrp() {
+        return refuse("portzap is in it");
+    }
+    if pgid.as_raw() <= 1 {
+        return refuse("it belongs to init");
+    }
+    if pgid != pid && getsid(Some(pid)).is_ok_and(|sid| sid == pgid) {
+        return refuse("its leader is the session leader (likely a shell); kill without --group");
+    }
+    Ok(pgid.as_raw() as u32)
+}
 
-    signal::kill(Pid::from_raw(pid as i32), nix_signal).map_err(|e| {
-        if e == nix::errno::Errno::EPERM {
-            "permission denied. Try running with sudo".to_string()
-        } else if e == nix::errno::Errno::ESRCH {
-            "process no longer exists".to_string()
-        } else {
-            e.to_string()
-        }
+#[cfg(windows)]
+fn process_group(process: &ProcessInfo) -> Result<u32> {
+    Err(KillportError::KillFailed {
+        pid: process.pid,
+        name: process.name.clone(),
+        reason: "--group needs Unix process groups".into(),
     })
 }
 
 #[cfg(windows)]
-fn send_signal(pid: u32, _signal: KillSignal) -> std::result::Result<(), String> {
-    Err("Windows kill not yet implemented".into())
+fn send_group_signal(process: &ProcessInfo, _pgid: u32, signal: KillSignal) -> Result<()> {
+    send_signal(process, signal)
+}
+
+#[cfg(windows)]
+fn send_signal(process: &ProcessInfo, _signal: KillSignal) -> Result<()> {
+    Err(KillportError::KillFailed {
+        pid: process.pid,
+        name: process.name.clone(),
+        reason: "Windows kill not yet implemented".into(),
+    })
 }
 
 #[cfg(unix)]
@@ -143,3 +689,213 @@ fn is_process_alive(pid: u32) -> bool {
 fn is_process_alive(_pid: u32) -> bool {
     false
 }
+
+#[cfg(unix)]
+fn is_group_alive(pgid: u32) -> bool {
+    use nix::sys::signal;
+    use nix::unistd::Pid;
+    signal::killpg(Pid::from_raw(pgid as i32), None).is_ok()
+}
+
+#[cfg(windows)]
+fn is_group_alive(_pgid: u32) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{ManagedBy, ServiceManager};
+    use crate::scanner::mock::process;
+
+    fn owned_by(uid: Option<u32>) -> ProcessInfo {
+        ProcessInfo {
+            uid,
+            ..process(1, "x", 80)
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn elevation_needed_only_for_other_users() {
+        let euid = nix::unistd::geteuid();
+        assert!(!needs_elevation(&owned_by(Some(euid.as_raw()))));
+        assert!(!needs_elevation(&owned_by(None)));
+        assert_eq!(
+            needs_elevation(&owned_by(Some(euid.as_raw() + 1))),
+            !euid.is_root()
+        );
+    }
+
+    #[test]
+    fn identity_check_prefers_start_time_over_name() {
+        let started = std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut scanned = owned_by(None);
+        scanned.started_at = Some(started);
+        let current = |name: &str, started_at| ProcessIdentity {
+            name: name.into(),
+            started_at,
+            uid: None,
+        };
+
+        assert!(is_same_process(&scanned, &current("x", Some(started))));
+        // exec() renames a process without restarting it
+        assert!(is_same_process(&scanned, &current("y", Some(started))));
+        assert!(!is_same_process(
+            &scanned,
+            &current("x", Some(started + Duration::from_secs(1)))
+        ));
+
+        // Without a start time on either side, fall back to the name.
+        assert!(is_same_process(&scanned, &current("x", None)));
+        assert!(!is_same_process(&scanned, &current("y", None)));
+        scanned.started_at = None;
+        assert!(is_same_process(&scanned, &current("x", Some(started))));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn own_identity_is_stable() {
+        let pid = std::process::id();
+        let first = platform::process_identity(pid).unwrap();
+        assert!(first.started_at.is_some());
+        assert_eq!(platform::process_identity(pid), Some(first));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exited_process_counts_as_success() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        child.wait().unwrap();
+        let mut process = owned_by(None);
+        process.pid = child.id();
+        let config = KillConfig {
+            graceful: false,
+            ..KillConfig::default()
+        };
+
+        let result = kill_process(&process, &config);
+        assert!(result.success);
+        assert_eq!(result.error_kind, Some(KillErrorKind::ProcessGone));
+        assert_eq!(result.signal_sent, "already exited");
+        assert!(result.error.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn group_kill_signals_the_process_group() {
+        use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 30 & wait"])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let mut process = owned_by(None);
+        process.pid = child.id();
+        let mut config = KillConfig {
+            signal: KillSignal::Kill,
+            graceful: false,
+            dry_run: true,
+            group: true,
+            ..KillConfig::default()
+        };
+
+        let planned = kill_process(&process, &config);
+        assert_eq!(planned.target, Some(format!("pgid {}", child.id())));
+        assert_eq!(planned.signal_sent, "SIGKILL (dry-run)");
+
+        config.dry_run = false;
+        let result = kill_process(&process, &config);
+        assert!(result.success, "{result:?}");
+        assert_eq!(child.wait().unwrap().signal(), Some(9));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn group_kill_refuses_our_own_group() {
+        let mut process = owned_by(None);
+        process.pid = std::process::id();
+        let config = KillConfig {
+            dry_run: true,
+            group: true,
+            ..KillConfig::default()
+        };
+        let refused = kill_process(&process, &config);
+        assert!(!refused.success);
+        assert!(refused.error.unwrap().contains("portzap is in it"));
+    }
+
+    #[test]
+    fn waits_until_exit_or_the_timeout() {
+        let mut polls = 0;
+        let running = || {
+            polls += 1;
+            polls < 3
+        };
+        let waited = wait_for_exit(running, None, Duration::from_millis(1));
+        assert_eq!(waited, Waited::Exited);
+        assert_eq!(polls, 3);
+
+        let timeout = Some(Duration::from_millis(20));
+        let waited = wait_for_exit(|| true, timeout, Duration::from_millis(1));
+        assert_eq!(waited, Waited::TimedOut);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zero_timeout_waits_for_exit_without_sigkill() {
+        use std::io::BufRead;
+
+        // Ignores SIGTERM and exits on its own a second later
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; echo ready; sleep 1"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut ready = String::new();
+        std::io::BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut ready)
+            .unwrap();
+        let mut process = owned_by(None);
+        process.pid = child.id();
+        // Reap it as soon as it exits, so it doesn't linger as a zombie
+        let waiter = thread::spawn(move || child.wait().unwrap());
+        let config = KillConfig {
+            graceful_timeout: graceful_timeout(Duration::ZERO),
+            ..KillConfig::default()
+        };
+
+        let start = Instant::now();
+        let result = kill_process(&process, &config);
+        assert!(result.success, "{result:?}");
+        assert_eq!(result.signal_sent, "SIGTERM");
+        assert!(start.elapsed() >= Duration::from_millis(800));
+        assert!(waiter.join().unwrap().success());
+    }
+
+    #[test]
+    fn socket_activated_processes_need_ignore_manager() {
+        let mut process = owned_by(None);
+        process.managed_by = Some(ManagedBy {
+            manager: ServiceManager::Systemd,
+            unit: "cups.socket".into(),
+            domain: None,
+        });
+        let mut config = KillConfig {
+            dry_run: true,
+            ..KillConfig::default()
+        };
+
+        let refused = kill_process(&process, &config);
+        assert!(!refused.success);
+        assert_eq!(refused.error_kind, Some(KillErrorKind::ServiceManaged));
+        assert!(refused
+            .error
+            .unwrap()
+            .contains("use `systemctl stop cups.socket`"));
+
+        config.ignore_manager = true;
+        assert!(kill_process(&process, &config).success);
+    }
+}
@@ -1,126 +1,780 @@
+mod address;
+mod aliases;
+mod capture;
 mod cli;
 mod commands;
 mod config;
+mod daemon;
+mod diff;
+mod docker;
 mod errors;
+mod event_log;
+mod filter;
+mod format;
+mod health;
+mod history;
 mod interactive;
 mod killer;
 mod output;
 mod platform;
+mod port_input;
+mod portinfo;
 mod process;
+mod progress;
+mod remote;
+mod resources;
 mod scanner;
+mod service_manager;
+mod service_unit;
 mod theme;
 mod tui;
+mod wsl;
 
-use anyhow::{Context, Result};
-use clap::Parser;
-use cli::{Cli, Commands, Format, Signal, WaitUntil};
+use anyhow::Result;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, ValueEnum};
+use cli::{
+    Cli, Commands, CompletionShell, ConfigAction, Fallback, Field, Format, GroupBy, GuiArgs,
+    InitSystem, ListArgs, RemoteArgs, Signal, SortBy, ThemeName, TopBy, WaitUntil,
+};
+use commands::kill::Owners;
+use commands::list::{Expectation, Grouping, PortAssertion, SortKey};
+use config::{Config, DefaultCommand, LoadReport};
+use errors::{exit_code, KillportError};
+use event_log::EventLog;
+use filter::NameFilter;
+use history::History;
 use output::OutputFormat;
 use process::{KillSignal, PortSpec, WaitCondition};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::time::Duration;
+use theme::ThemeVariant;
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() {
+    restore_sigpipe();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    drop_env_signal(&mut cli, &matches);
+    let (config, report) = Config::load_with_report();
+    if cli.command.is_none() && cli.ports.is_empty() {
+        cli.command = bare_command(&cli, &config);
+    }
+    init_tracing(cli.verbose, matches!(cli.command, Some(Commands::Gui(_))));
+    let requested_format = requested_format(&cli);
+    let configured_format = config.default_format.clone();
+    let code = match run_captured(cli, config, report) {
+        Ok(code) => code,
+        Err(e) => {
+            let (kind, code) = match e.downcast_ref::<KillportError>() {
+                Some(err) => (err.kind(), err.exit_code()),
+                None => ("error", exit_code::FAILURE),
+            };
+            let format = fatal_format(requested_format, configured_format.as_deref());
+            output::print_fatal_error(kind, &format!("{e:#}"), format);
+            code
+        }
+    };
+    std::process::exit(code);
+}
+
+/// What `portzap` with no command and no ports runs: `default_command` from
+/// config.toml, or `None` for help. The GUI needs a terminal, so scripts and
+/// pipes get help rather than a dashboard nobody can see.
+fn bare_command(cli: &Cli, config: &Config) -> Option<Commands> {
+    if cli.no_gui {
+        return None;
+    }
+    match config.default_command {
+        DefaultCommand::Help => None,
+        DefaultCommand::Gui => std::io::stdout()
+            .is_terminal()
+            .then_some(Commands::Gui(GuiArgs { theme: None })),
+        DefaultCommand::List => Some(Commands::List(ListArgs {
+            format: cli.format,
+            ..ListArgs::default()
+        })),
+    }
+}
+
+/// Rust starts with SIGPIPE ignored, so printing after the reader went away
+/// (`portzap list | head -1`) makes println! panic. With the default action
+/// the process just ends, like other command-line tools.
+#[cfg(unix)]
+fn restore_sigpipe() {
+    use nix::sys::signal::{signal, SigHandler, Signal};
+    // Safety: runs first thing in main, before any other thread exists.
+    let _ = unsafe { signal(Signal::SIGPIPE, SigHandler::SigDfl) };
+}
+
+#[cfg(not(unix))]
+fn restore_sigpipe() {}
 
-    let format = convert_format(cli.format);
+/// `run`, with stdout going to `--output` if given. The file is only
+/// replaced when the command succeeds, and fatal errors reach the terminal.
+fn run_captured(cli: Cli, config: Config, report: LoadReport) -> Result<i32> {
+    if let Some(path) = &cli.output {
+        capture::start(path, cli.append)?;
+    }
+    let result = capture::guard(|| run(cli, config, report)).unwrap_or_else(|e| Err(e.into()));
+    let finished = capture::finish(result.is_ok());
+    let code = result?;
+    finished?;
+    Ok(code)
+}
+
+/// Run a command and return the exit status for everything but fatal errors.
+/// `config` is loaded once in `main`; `report` says what happened to its file.
+fn run(cli: Cli, config: Config, report: LoadReport) -> Result<i32> {
+    if let Some(path) = Config::env_path() {
+        if !path.is_file() {
+            return Err(KillportError::Config(format!(
+                "{} points to {}, which does not exist",
+                config::CONFIG_ENV,
+                path.display()
+            ))
+            .into());
+        }
+    }
+    for message in report.messages() {
+        output::print_warning(&message);
+    }
+    let defaults = Defaults::from_config(&config);
+
+    let format = convert_format(cli.format.unwrap_or(defaults.format));
+    check_id_format(&cli)?;
+    let capturing = cli.output.is_some();
 
     match cli.command {
         Some(Commands::Kill(args)) => {
-            let ports = parse_ports(&args.ports)?;
-            let success = commands::kill::execute(commands::kill::KillOptions {
+            let ports = parse_port_args(&args.ports, args.from_file.as_deref(), &config)?;
+            if args.dry_run {
+                note_ephemeral(&args.ports, &config);
+            }
+            if args.pid.is_none() && !args.all {
+                require_ports(&ports)?;
+            }
+            let all = if args.all {
+                Some(commands::kill::AllTargets {
+                    owners: if args.include_others {
+                        Owners::Everyone
+                    } else if args.user.is_empty() {
+                        Owners::Me
+                    } else {
+                        Owners::Users(args.user.clone())
+                    },
+                    except_names: NameFilter::parse(&args.except_name)
+                        .map_err(|e| KillportError::Usage(format!("--except-name: {e}")))?,
+                    protocol: args.protocol.map(convert_protocol),
+                    protected_ports: config.protected_ports.clone(),
+                    protected_names: config.protected_names.clone(),
+                })
+            } else {
+                None
+            };
+            let outcome = commands::kill::execute(commands::kill::KillOptions {
                 ports,
-                signal: convert_signal(args.signal),
-                graceful: !args.no_graceful,
-                graceful_timeout_secs: args.timeout,
+                signal: kill_signal(args.force, args.signal, &defaults)?,
+                graceful: !args.force && defaults.graceful(args.graceful, args.no_graceful),
+                graceful_timeout: args.timeout.unwrap_or(defaults.timeout),
                 dry_run: args.dry_run,
                 interactive: args.interactive,
-                format: convert_format(args.format),
+                fallback: args
+                    .non_interactive_fallback
+                    .map(convert_fallback)
+                    .unwrap_or_default(),
+                ignore_manager: args.ignore_manager,
+                group: args.group,
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+                quiet: args.quiet,
+                history: History::from_config(&config),
+                pid: args.pid,
+                require_name: NameFilter::parse(&args.require_name)
+                    .map_err(|e| KillportError::Usage(format!("--require-name: {e}")))?,
+                allow_privileged: args.allow_privileged || config.allow_privileged,
+                yes: args.yes,
+                include_windows: args.include_windows,
+                confirmation: config.require_confirmation,
+                all,
             })?;
-            if !success {
-                std::process::exit(1);
-            }
+            return Ok(outcome.exit_code());
         }
 
         Some(Commands::List(args)) => {
-            let ports = parse_ports(&args.ports)?;
-            commands::list::execute(commands::list::ListOptions {
+            let ports = parse_port_args(&args.ports, args.from_file.as_deref(), &config)?;
+            let assertion = if args.expect_free {
+                Some(PortAssertion::Free)
+            } else if args.expect_used {
+                Some(PortAssertion::Used)
+            } else {
+                None
+            };
+            let opts = commands::list::ListOptions {
                 ports,
-                format: convert_format(args.format),
-            })?;
+                columns: args.fields.into_iter().map(convert_field).collect(),
+                sort: args.sort.map_or(SortKey::Port, convert_sort),
+                group_by: args.group_by.map(convert_group_by),
+                watch_secs: args.watch,
+                names: NameFilter::parse(&args.name)
+                    .map_err(|e| KillportError::Usage(format!("--name: {e}")))?,
+                count_only: args.count,
+                quiet: args.quiet,
+                host: convert_remote(args.remote),
+                include_windows: args.include_windows,
+                include_self: args.include_self,
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+                highlight_ports: config.highlight_ports.clone(),
+                // Only when the terminal would show a table without --format
+                echo_table: capturing
+                    && defaults.format == Format::Table
+                    && !matches!(args.format, None | Some(Format::Table))
+                    && args.watch.is_none()
+                    && !args.count
+                    && assertion.is_none(),
+            };
+            if let Some(assertion) = assertion {
+                let passed = commands::list::execute_assertion(opts, assertion)?;
+                return Ok(if passed {
+                    exit_code::SUCCESS
+                } else {
+                    exit_code::ASSERTION_FAILED
+                });
+            }
+            let rows = commands::list::execute(opts)?;
+            let expectation = if args.fail_if_any {
+                Some(Expectation::Empty)
+            } else if args.fail_if_none {
+                Some(Expectation::NonEmpty)
+            } else {
+                None
+            };
+            if let Some(expectation) = expectation {
+                return Ok(expectation.exit_code(rows));
+            }
         }
 
         Some(Commands::Free(args)) => {
+            let raw = args.port.or(args.start).unwrap_or_default();
+            let (start, end) = parse_free_start(&raw, &config)?;
             let result = commands::free::execute(commands::free::FreeOptions {
-                start: args.port,
-                max: args.max,
-                format: convert_format(args.format),
+                start,
+                max: args.max.or(end).or(config.free.max).unwrap_or(65535),
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+                quiet: args.quiet,
+                hold: args.hold,
+                hold_until_stdin_close: args.hold_until_stdin_close,
             })?;
             if result.is_none() {
-                std::process::exit(1);
+                return Ok(exit_code::FAILURE);
             }
         }
 
         Some(Commands::Wait(args)) => {
+            let port = parse_single_port(&args.port, &config)?;
+            if args.require_down && !matches!(args.until, Some(WaitUntil::Restart)) {
+                return Err(
+                    KillportError::Usage("--require-down only applies to --until restart".into())
+                        .into(),
+                );
+            }
+            let http = match &args.http {
+                Some(_) if matches!(args.until, Some(WaitUntil::Down | WaitUntil::Restart)) => {
+                    return Err(KillportError::Usage(
+                        "--http waits for the port to come up; drop --until".into(),
+                    )
+                    .into());
+                }
+                Some(path) => {
+                    let expect = match &args.expect_status {
+                        Some(codes) => health::StatusSet::parse(codes)?,
+                        None => health::StatusSet::default(),
+                    };
+                    Some(health::HttpCheck::new(path, expect)?)
+                }
+                None => None,
+            };
+            let until = args.until.unwrap_or(if http.is_some() {
+                WaitUntil::Up
+            } else {
+                WaitUntil::Down
+            });
             let success = commands::wait::execute(commands::wait::WaitOptions {
-                port: args.port,
-                condition: convert_wait_until(args.until),
-                timeout_secs: args.timeout,
-                poll_interval_ms: args.poll,
-                format: convert_format(args.format),
+                port,
+                condition: convert_wait_until(until, args.require_down),
+                timeout: args
+                    .timeout
+                    .or(config.wait.timeout_secs.map(Duration::from_secs))
+                    .unwrap_or(Duration::from_secs(30)),
+                poll_interval_ms: args.poll.or(config.wait.poll_ms).unwrap_or(250),
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+                host: convert_remote(args.remote),
+                include_windows: args.include_windows,
+                http,
             })?;
             if !success {
-                std::process::exit(1);
+                return Ok(exit_code::FAILURE);
+            }
+        }
+
+        Some(Commands::Check(args)) => {
+            let ports = parse_ports(&args.ports, &config)?;
+            require_ports(&ports)?;
+            let passed = commands::check::execute(commands::check::CheckOptions {
+                ports,
+                free: args.free,
+                any: args.any,
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+            })?;
+            if !passed {
+                return Ok(exit_code::FAILURE);
             }
         }
 
+        Some(Commands::Info(args)) => {
+            commands::info::execute(commands::info::InfoOptions {
+                port: parse_single_port(&args.port, &config)?,
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+            })?;
+        }
+
+        Some(Commands::Stats(args)) => {
+            commands::stats::execute(commands::stats::StatsOptions {
+                watch_secs: args.watch,
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+            })?;
+        }
+
+        Some(Commands::Top(args)) => {
+            commands::top::execute(commands::top::TopOptions {
+                by: convert_top_by(args.by),
+                limit: args.limit,
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+            })?;
+        }
+
+        Some(Commands::History(args)) => {
+            let history = History::open(&config).ok_or_else(|| {
+                KillportError::Config("could not determine config directory".into())
+            })?;
+            commands::history::execute(commands::history::HistoryOptions {
+                history,
+                enabled: config.history,
+                since: args.since,
+                port: args.port.as_deref().map(PortSpec::parse).transpose()?,
+                clear: args.clear,
+                format: convert_format(args.format.unwrap_or(defaults.format)),
+            })?;
+        }
+
         Some(Commands::Completions(args)) => {
-            commands::completions::execute(args.shell);
+            commands::completions::execute(
+                convert_completion_shell(args.shell),
+                config.aliases.keys().cloned().collect(),
+            );
+        }
+
+        Some(Commands::Config(args)) => {
+            commands::config::execute(commands::config::ConfigOptions {
+                action: convert_config_action(args.action),
+                format,
+            })?;
         }
 
-        Some(Commands::Gui) => {
-            tui::run()?;
+        Some(Commands::Doctor) => {
+            let ok = commands::doctor::execute(commands::doctor::DoctorOptions { format })?;
+            if !ok {
+                return Ok(exit_code::FAILURE);
+            }
+        }
+
+        Some(Commands::Version) => {
+            commands::version::execute(commands::version::VersionOptions { format });
+        }
+
+        Some(Commands::Gui(args)) => {
+            tui::run(
+                config.clone(),
+                report.summary(),
+                args.theme.map(convert_theme),
+            )?;
         }
 
         Some(Commands::Watch(args)) => {
-            let ports = parse_ports(&args.ports)?;
-            commands::watch::execute(commands::watch::WatchOptions {
+            let format = convert_format(args.format.unwrap_or(defaults.format));
+            if args.status {
+                commands::watch::status(format)?;
+                return Ok(exit_code::SUCCESS);
+            }
+            if let Some(port) = args.stop {
+                let stopped = commands::watch::stop(port, format)?;
+                return Ok(if stopped {
+                    exit_code::SUCCESS
+                } else {
+                    exit_code::FAILURE
+                });
+            }
+            let ports = parse_port_args(&args.ports, args.from_file.as_deref(), &config)?;
+            let rules = commands::watch::rules(&args.rules, args.rules_file.as_deref())?;
+            if rules.is_empty() {
+                require_ports(&ports)?;
+            }
+            let opts = commands::watch::WatchOptions {
                 ports,
-                signal: convert_signal(args.signal),
-                graceful: !args.no_graceful,
-                graceful_timeout_secs: args.timeout,
-                poll_interval_ms: args.poll,
-                format: convert_format(args.format),
-            })?;
+                signal: convert_signal(args.signal.unwrap_or(defaults.signal)),
+                graceful: defaults.graceful(args.graceful, args.no_graceful),
+                graceful_timeout: args
+                    .timeout
+                    .or(config.watch.timeout_secs.map(Duration::from_secs))
+                    .unwrap_or(defaults.timeout),
+                poll_interval_ms: args.poll.or(config.watch.poll_ms).unwrap_or(1000),
+                allow_names: if args.allow_names.is_empty() {
+                    config.watch.allow_names.clone()
+                } else {
+                    args.allow_names
+                },
+                format,
+                history: History::from_config(&config),
+                rules,
+                dry_run: args.dry_run,
+                // Absolute, so a service manager's watcher reads the same files
+                pidfiles: match std::env::current_dir() {
+                    Ok(dir) => args.ignore_pidfiles.iter().map(|p| dir.join(p)).collect(),
+                    Err(_) => args.ignore_pidfiles,
+                },
+                log: args.log_file.map(|path| {
+                    EventLog::new(path, args.log_rotate_size, args.log_keep, args.log_fsync)
+                }),
+            };
+            if let Some(kind) = args.emit_service {
+                commands::watch::emit_service(&opts, convert_init_system(kind), args.install)?;
+            } else if args.daemon {
+                commands::watch::start_daemon(&opts)?;
+            } else {
+                commands::watch::execute(opts)?;
+            }
         }
 
         None => {
             // Default action: kill (bare `portzap 3000 8080`)
             if cli.ports.is_empty() {
-                // arg_required_else_help should prevent this
-                return Ok(());
+                let help = Cli::command().render_help();
+                if std::io::stderr().is_terminal() {
+                    eprint!("{}", help.ansi());
+                } else {
+                    eprint!("{help}");
+                }
+                return Ok(exit_code::USAGE);
             }
 
-            let ports = parse_ports(&cli.ports)?;
-            let success = commands::kill::execute(commands::kill::KillOptions {
+            let ports = parse_ports(&cli.ports, &config)?;
+            require_ports(&ports)?;
+            if cli.dry_run {
+                note_ephemeral(&cli.ports, &config);
+            }
+            let outcome = commands::kill::execute(commands::kill::KillOptions {
                 ports,
-                signal: convert_signal(cli.signal),
-                graceful: !cli.no_graceful,
-                graceful_timeout_secs: cli.timeout,
+                signal: kill_signal(cli.force, cli.signal, &defaults)?,
+                graceful: !cli.force && defaults.graceful(cli.graceful, cli.no_graceful),
+                graceful_timeout: cli.timeout.unwrap_or(defaults.timeout),
                 dry_run: cli.dry_run,
                 interactive: cli.interactive,
+                fallback: cli
+                    .non_interactive_fallback
+                    .map(convert_fallback)
+                    .unwrap_or_default(),
+                ignore_manager: cli.ignore_manager,
+                group: cli.group,
                 format,
+                quiet: cli.quiet,
+                history: History::from_config(&config),
+                pid: None,
+                require_name: NameFilter::default(),
+                allow_privileged: cli.allow_privileged || config.allow_privileged,
+                yes: cli.yes,
+                include_windows: cli.include_windows,
+                confirmation: config.require_confirmation,
+                all: None,
             })?;
-            if !success {
-                std::process::exit(1);
-            }
+            return Ok(outcome.exit_code());
         }
     }
 
+    Ok(exit_code::SUCCESS)
+}
+
+/// Install the tracing subscriber. `RUST_LOG` takes precedence over `-v`;
+/// with neither, nothing is logged. The TUI owns the terminal, so it logs to
+/// a file in the config directory instead of stderr.
+fn init_tracing(verbose: u8, tui: bool) {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(directives) if !directives.is_empty() => EnvFilter::new(directives),
+        _ => match verbose {
+            0 => return,
+            1 => EnvFilter::new("portzap=debug"),
+            _ => EnvFilter::new("portzap=trace"),
+        },
+    };
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false);
+    if !tui {
+        let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        subscriber
+            .with_ansi(color)
+            .with_writer(std::io::stderr)
+            .init();
+        return;
+    }
+    let Some(dir) = Config::config_dir() else {
+        return;
+    };
+    let path = dir.join(TUI_LOG);
+    let file = std::fs::create_dir_all(&dir).and_then(|()| {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+    });
+    match file {
+        Ok(file) => subscriber
+            .with_ansi(false)
+            .with_writer(std::sync::Mutex::new(file))
+            .init(),
+        Err(e) => output::print_warning(&format!("can't log to {}: {e}", path.display())),
+    }
+}
+
+/// Log file the TUI writes to with `-v` or `RUST_LOG`.
+const TUI_LOG: &str = "tui.log";
+
+/// The `--format` given on the command line, before config defaults apply.
+fn requested_format(cli: &Cli) -> Option<Format> {
+    let command_format = match &cli.command {
+        Some(Commands::Kill(args)) => args.format,
+        Some(Commands::List(args)) => args.format,
+        Some(Commands::Watch(args)) => args.format,
+        Some(Commands::Free(args)) => args.format,
+        Some(Commands::Wait(args)) => args.format,
+        Some(Commands::Check(args)) => args.format,
+        Some(Commands::Info(args)) => args.format,
+        Some(Commands::Stats(args)) => args.format,
+        Some(Commands::Top(args)) => args.format,
+        Some(Commands::History(args)) => args.format,
+        _ => None,
+    };
+    command_format.or(cli.format)
+}
+
+/// Format for reporting a fatal error. The config may be what failed, so an
+/// unusable `default_format` is passed over quietly.
+fn fatal_format(requested: Option<Format>, configured: Option<&str>) -> OutputFormat {
+    let configured = configured.and_then(|f| Format::from_str(f, true).ok());
+    convert_format(requested.or(configured).unwrap_or(Format::Table))
+}
+
+/// Fallbacks for flags the user didn't pass. `--signal`, `--timeout` and `--format`
+/// already pick up their PORTZAP_* variables through clap, so only the config file
+/// (and PORTZAP_NO_GRACEFUL, which a plain flag can't express) is layered here.
+struct Defaults {
+    signal: Signal,
+    timeout: Duration,
+    no_graceful: bool,
+    format: Format,
+}
+
+impl Defaults {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            signal: parse_config_value("default_signal", config.default_signal.as_deref())
+                .unwrap_or(Signal::Term),
+            timeout: config
+                .default_timeout_secs
+                .map_or(killer::DEFAULT_GRACEFUL_TIMEOUT, Duration::from_secs),
+            no_graceful: env_bool("PORTZAP_NO_GRACEFUL")
+                .or(config.default_no_graceful)
+                .unwrap_or(false),
+            format: parse_config_value("default_format", config.default_format.as_deref())
+                .unwrap_or(Format::Table),
+        }
+    }
+
+    /// Explicit `--graceful` / `--no-graceful` flags win over the config default.
+    fn graceful(&self, graceful: bool, no_graceful: bool) -> bool {
+        if graceful || no_graceful {
+            graceful
+        } else {
+            !self.no_graceful
+        }
+    }
+}
+
+/// `--force` always means SIGKILL, so it can't be combined with `--signal`.
+/// Checked here rather than by clap, which would also count PORTZAP_SIGNAL
+/// as passing `--signal`; see `drop_env_signal`.
+fn kill_signal(force: bool, signal: Option<Signal>, defaults: &Defaults) -> Result<KillSignal> {
+    if !force {
+        return Ok(convert_signal(signal.unwrap_or(defaults.signal)));
+    }
+    if signal.is_some() {
+        return Err(KillportError::Usage(
+            "--force always sends SIGKILL; it can't be combined with --signal".into(),
+        )
+        .into());
+    }
+    Ok(KillSignal::Kill)
+}
+
+/// PORTZAP_SIGNAL is only a default, which `--force` overrides. Forget it
+/// then, so `kill_signal` only rejects a `--signal` typed on the command line.
+fn drop_env_signal(cli: &mut Cli, matches: &ArgMatches) {
+    let from_env = |matches: Option<&ArgMatches>| {
+        matches.and_then(|m| m.value_source("signal")) == Some(ValueSource::EnvVariable)
+    };
+    match &mut cli.command {
+        Some(Commands::Kill(args)) if args.force && from_env(matches.subcommand_matches("kill")) => {
+            args.signal = None;
+        }
+        None if cli.force && from_env(Some(matches)) => cli.signal = None,
+        _ => {}
+    }
+}
+
+fn parse_config_value<T: ValueEnum>(key: &str, value: Option<&str>) -> Option<T> {
+    let value = value?;
+    let parsed = T::from_str(value, true).ok();
+    if parsed.is_none() {
+        output::print_warning(&format!(
+            "invalid {key} '{value}' in config, using the built-in default"
+        ));
+    }
+    parsed
+}
+
+fn env_bool(name: &str) -> Option<bool> {
+    let value = std::env::var(name).ok().filter(|v| !v.trim().is_empty())?;
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => {
+            output::print_warning(&format!(
+                "ignoring {name}='{value}', expected true or false"
+            ));
+            None
+        }
+    }
+}
+
+fn parse_ports(raw: &[String], config: &Config) -> Result<Vec<PortSpec>> {
+    let mut specs = Vec::new();
+    for s in raw {
+        if s == "-" {
+            let stdin = std::io::stdin().lock();
+            specs.extend(
+                port_input::read_specs(stdin, "<stdin>", &config.aliases)
+                    .map_err(KillportError::Usage)?,
+            );
+            continue;
+        }
+        specs.extend(aliases::resolve(s, &config.aliases).map_err(KillportError::Usage)?);
+    }
+    Ok(specs)
+}
+
+/// Port arguments plus any `--from-file` specs.
+fn parse_port_args(
+    raw: &[String],
+    from_file: Option<&Path>,
+    config: &Config,
+) -> Result<Vec<PortSpec>> {
+    let mut specs = parse_ports(raw, config)?;
+    if let Some(path) = from_file {
+        let file = std::fs::File::open(path)
+            .map_err(|e| KillportError::Usage(format!("failed to open {}: {e}", path.display())))?;
+        specs.extend(
+            port_input::read_specs(
+                std::io::BufReader::new(file),
+                &path.display().to_string(),
+                &config.aliases,
+            )
+            .map_err(KillportError::Usage)?,
+        );
+    }
+    Ok(specs)
+}
+
+/// Commands that act on ports need at least one, even when they all came
+/// from an empty stdin or file.
+fn require_ports(specs: &[PortSpec]) -> Result<()> {
+    if specs.is_empty() {
+        return Err(KillportError::Usage("no ports given".into()).into());
+    }
     Ok(())
 }
 
-fn parse_ports(raw: &[String]) -> Result<Vec<PortSpec>> {
-    raw.iter()
-        .map(|s| PortSpec::parse(s).with_context(|| format!("invalid port: '{s}'")))
-        .collect()
+/// `--format pids` and `--format ports` print what list found or kill hit;
+/// other commands, and the redrawing `list --watch`, have no such list.
+fn check_id_format(cli: &Cli) -> Result<()> {
+    let Some(format @ (Format::Pids | Format::Ports)) = requested_format(cli) else {
+        return Ok(());
+    };
+    let supported = match &cli.command {
+        None | Some(Commands::Kill(_)) => true,
+        Some(Commands::List(args)) => args.watch.is_none(),
+        Some(Commands::Top(_)) => true,
+        _ => false,
+    };
+    if supported {
+        return Ok(());
+    }
+    let name = format.to_possible_value().map(|v| v.get_name().to_string());
+    Err(KillportError::Usage(format!(
+        "--format {} only works with list (without --watch), kill and top",
+        name.unwrap_or_default()
+    ))
+    .into())
+}
+
+/// Where `free` starts searching, plus the end of the range when one was
+/// given (`3000-3100`, `ephemeral`).
+fn parse_free_start(raw: &str, config: &Config) -> Result<(u16, Option<u16>)> {
+    match parse_ports(&[raw.to_string()], config)?.as_slice() {
+        [PortSpec::Single(port)] => Ok((*port, None)),
+        [PortSpec::Range {
+            start,
+            end,
+            step: 1,
+        }] => Ok((*start, Some(*end))),
+        _ => Err(KillportError::Usage(format!(
+            "'{raw}' is not a single port or a range without a step"
+        ))
+        .into()),
+    }
+}
+
+/// Dry runs say what `ephemeral` stood for, since it differs between machines.
+fn note_ephemeral(raw: &[String], config: &Config) {
+    let keyword = aliases::EPHEMERAL;
+    if raw.iter().any(|s| s == keyword) && !config.aliases.contains_key(keyword) {
+        let ((start, end), source) = portinfo::ephemeral_or_default();
+        eprintln!("{keyword} = ports {start}-{end} ({source})");
+    }
+}
+
+fn parse_single_port(raw: &str, config: &Config) -> Result<u16> {
+    let ports: Vec<u16> = parse_ports(&[raw.to_string()], config)?
+        .iter()
+        .flat_map(PortSpec::expand)
+        .collect();
+    match ports.as_slice() {
+        [port] => Ok(*port),
+        _ => Err(KillportError::Usage(format!(
+            "'{raw}' expands to {} ports, expected exactly one",
+            ports.len()
+        ))
+        .into()),
+    }
 }
 
 fn convert_signal(s: Signal) -> KillSignal {
@@ -132,17 +786,128 @@ fn convert_signal(s: Signal) -> KillSignal {
     }
 }
 
+fn convert_protocol(p: cli::Protocol) -> process::Protocol {
+    match p {
+        cli::Protocol::Tcp => process::Protocol::Tcp,
+        cli::Protocol::Udp => process::Protocol::Udp,
+    }
+}
+
+fn convert_fallback(f: Fallback) -> interactive::Fallback {
+    match f {
+        Fallback::All => interactive::Fallback::All,
+        Fallback::None => interactive::Fallback::None,
+        Fallback::Fail => interactive::Fallback::Fail,
+    }
+}
+
+fn convert_theme(t: ThemeName) -> ThemeVariant {
+    match t {
+        ThemeName::Auto => ThemeVariant::Auto,
+        ThemeName::Dark => ThemeVariant::Dark,
+        ThemeName::Light => ThemeVariant::Light,
+        ThemeName::Solarized => ThemeVariant::Solarized,
+        ThemeName::Dracula => ThemeVariant::Dracula,
+        ThemeName::Gruvbox => ThemeVariant::Gruvbox,
+        ThemeName::Nord => ThemeVariant::Nord,
+    }
+}
+
+fn convert_init_system(s: InitSystem) -> service_unit::ServiceKind {
+    match s {
+        InitSystem::Systemd => service_unit::ServiceKind::Systemd,
+        InitSystem::Launchd => service_unit::ServiceKind::Launchd,
+    }
+}
+
 fn convert_format(f: Format) -> OutputFormat {
     match f {
         Format::Table => OutputFormat::Table,
         Format::Json => OutputFormat::Json,
         Format::Plain => OutputFormat::Plain,
+        Format::Pids => OutputFormat::Pids,
+        Format::Ports => OutputFormat::Ports,
+    }
+}
+
+fn convert_remote(args: RemoteArgs) -> Option<remote::RemoteHost> {
+    args.host.map(|target| remote::RemoteHost {
+        target,
+        ssh_options: args.ssh_options,
+    })
+}
+
+fn convert_field(f: Field) -> output::Column {
+    use output::Column;
+    match f {
+        Field::Pid => Column::Pid,
+        Field::Name => Column::Name,
+        Field::Port => Column::Port,
+        Field::Protocol => Column::Protocol,
+        Field::Command => Column::Command,
+        Field::User => Column::User,
+        Field::Container => Column::Container,
+        Field::Ppid => Column::Ppid,
+        Field::Parent => Column::Parent,
+        Field::Age => Column::Age,
+        Field::Cwd => Column::Cwd,
+        Field::Host => Column::Host,
+        Field::Origin => Column::Origin,
+    }
+}
+
+fn convert_sort(s: SortBy) -> SortKey {
+    match s {
+        SortBy::Port => SortKey::Port,
+        SortBy::Pid => SortKey::Pid,
+        SortBy::Name => SortKey::Name,
+        SortBy::Protocol => SortKey::Protocol,
+        SortBy::Age => SortKey::Age,
+    }
+}
+
+fn convert_top_by(b: TopBy) -> commands::top::TopBy {
+    match b {
+        TopBy::Mem => commands::top::TopBy::Memory,
+        TopBy::Cpu => commands::top::TopBy::Cpu,
+        TopBy::Ports => commands::top::TopBy::Ports,
+    }
+}
+
+fn convert_group_by(g: GroupBy) -> Grouping {
+    match g {
+        GroupBy::Process => Grouping::Process,
+    }
+}
+
+fn convert_config_action(a: ConfigAction) -> commands::config::ConfigAction {
+    use commands::config::ConfigAction as Action;
+    match a {
+        ConfigAction::List => Action::List,
+        ConfigAction::Get { key } => Action::Get(key),
+        ConfigAction::Set { key, value } => Action::Set(key, value),
+        ConfigAction::Path => Action::Path,
+        ConfigAction::Edit => Action::Edit,
+    }
+}
+
+fn convert_completion_shell(s: CompletionShell) -> commands::completions::CompletionShell {
+    use clap_complete::Shell;
+    use commands::completions::CompletionShell as Target;
+    match s {
+        CompletionShell::Bash => Target::Clap(Shell::Bash),
+        CompletionShell::Elvish => Target::Clap(Shell::Elvish),
+        CompletionShell::Fish => Target::Clap(Shell::Fish),
+        CompletionShell::Powershell => Target::Clap(Shell::PowerShell),
+        CompletionShell::Zsh => Target::Clap(Shell::Zsh),
+        CompletionShell::Nushell => Target::Nushell,
     }
 }
 
-fn convert_wait_until(w: WaitUntil) -> WaitCondition {
+fn convert_wait_until(w: WaitUntil, require_down: bool) -> WaitCondition {
     match w {
         WaitUntil::Down => WaitCondition::Free,
         WaitUntil::Up => WaitCondition::Occupied,
+        WaitUntil::Restart => WaitCondition::Restart { require_down },
     }
 }
@@ -1,36 +1,477 @@
-use crate::process::{KillResult, ProcessInfo};
+use crate::diff::ProcessDiff;
+use crate::format;
+use crate::killer;
+use crate::portinfo::{self, PortCategory};
+use crate::process::{KillErrorKind, KillResult, ProcessGroup, ProcessInfo, Protocol};
+use crate::scanner::ScanWarning;
 use comfy_table::presets::UTF8_FULL_CONDENSED;
-use comfy_table::{ContentArrangement, Table};
+use comfy_table::{Cell, Color, ContentArrangement, Table};
 use owo_colors::OwoColorize;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
     Table,
     Json,
     Plain,
+    /// Unique PIDs in numeric order, one per line
+    Pids,
+    /// Unique ports in numeric order, one per line
+    Ports,
+}
+
+impl OutputFormat {
+    /// Formats that print bare values for `kill $(...)` and nothing else on stdout.
+    pub fn is_id_list(self) -> bool {
+        matches!(self, Self::Pids | Self::Ports)
+    }
+
+    /// The `--format` value that selects this format.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Table => "table",
+            Self::Json => "json",
+            Self::Plain => "plain",
+            Self::Pids => "pids",
+            Self::Ports => "ports",
+        }
+    }
+}
+
+/// Everything a command has to say, routed by output format so commands
+/// don't branch on it themselves. Messages for people go to stderr and
+/// results to stdout, so stdout always parses as the chosen format; whether
+/// a command succeeded is left to the exit status.
+pub trait Reporter {
+    /// Processes found by a scan
+    fn processes(&mut self, processes: &[ProcessInfo]);
+    /// Kill attempts, as each batch finishes. JSON output prints one line
+    /// per attempt, so a long-running command streams NDJSON
+    fn kill_results(&mut self, results: &[KillResult]);
+    /// A process `watch` spared; JSON output streams it like a kill attempt
+    fn ignored(&mut self, event: &IgnoredEvent);
+    /// Nothing is listening on `port`
+    fn no_process(&mut self, port: u16);
+    /// Progress such as a banner, printed in every format
+    fn status(&mut self, message: &str);
+    /// A result worded for people; JSON output leaves it out
+    fn note(&mut self, message: &str);
+    /// A bare result for scripts reading plain output, e.g. a port number
+    fn result_text(&mut self, text: &str);
+    /// A result as one JSON document; only JSON output prints it
+    fn result_json(&mut self, value: &serde_json::Value);
+}
+
+/// The reporter for `format`.
+pub fn reporter(format: OutputFormat) -> Box<dyn Reporter> {
+    match format {
+        OutputFormat::Json => Box::new(JsonReporter),
+        format => Box::new(TextReporter { format }),
+    }
+}
+
+/// Everything but JSON: tables, tab-separated output and the bare
+/// `pids`/`ports` lists, which differ only in how results are printed.
+pub struct TextReporter {
+    format: OutputFormat,
+}
+
+impl Reporter for TextReporter {
+    fn processes(&mut self, processes: &[ProcessInfo]) {
+        print_processes(processes, self.format);
+    }
+
+    fn kill_results(&mut self, results: &[KillResult]) {
+        print_kill_results(results, self.format);
+    }
+
+    fn ignored(&mut self, event: &IgnoredEvent) {
+        eprintln!("{}", event.describe());
+    }
+
+    fn no_process(&mut self, port: u16) {
+        print_no_process(port, self.format);
+    }
+
+    fn status(&mut self, message: &str) {
+        eprintln!("{message}");
+    }
+
+    fn note(&mut self, message: &str) {
+        eprintln!("{message}");
+    }
+
+    fn result_text(&mut self, text: &str) {
+        println!("{text}");
+    }
+
+    fn result_json(&mut self, _value: &serde_json::Value) {}
+}
+
+pub struct JsonReporter;
+
+impl Reporter for JsonReporter {
+    fn processes(&mut self, processes: &[ProcessInfo]) {
+        print_processes(processes, OutputFormat::Json);
+    }
+
+    fn kill_results(&mut self, results: &[KillResult]) {
+        print_kill_results(results, OutputFormat::Json);
+    }
+
+    fn ignored(&mut self, event: &IgnoredEvent) {
+        println!("{}", event_line(event));
+    }
+
+    fn no_process(&mut self, port: u16) {
+        print_no_process(port, OutputFormat::Json);
+    }
+
+    fn status(&mut self, message: &str) {
+        eprintln!("{message}");
+    }
+
+    fn note(&mut self, _message: &str) {}
+
+    fn result_text(&mut self, _text: &str) {}
+
+    fn result_json(&mut self, value: &serde_json::Value) {
+        print_json(value);
+    }
+}
+
+/// Records what a command reported, one line per call, for unit tests.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct VecReporter {
+    pub lines: Vec<String>,
+}
+
+#[cfg(test)]
+impl Reporter for VecReporter {
+    fn processes(&mut self, processes: &[ProcessInfo]) {
+        let pids: Vec<String> = processes.iter().map(|p| p.pid.to_string()).collect();
+        self.lines.push(format!("processes: {}", pids.join(" ")));
+    }
+
+    fn kill_results(&mut self, results: &[KillResult]) {
+        for r in results {
+            let outcome = if r.success { "killed" } else { "failed" };
+            self.lines.push(format!("{outcome}: {}", r.process.pid));
+        }
+    }
+
+    fn ignored(&mut self, event: &IgnoredEvent) {
+        self.lines.push(format!("ignored: {}", event.process.pid));
+    }
+
+    fn no_process(&mut self, port: u16) {
+        self.lines.push(format!("no process: {port}"));
+    }
+
+    fn status(&mut self, message: &str) {
+        self.lines.push(format!("status: {message}"));
+    }
+
+    fn note(&mut self, message: &str) {
+        self.lines.push(format!("note: {message}"));
+    }
+
+    fn result_text(&mut self, text: &str) {
+        self.lines.push(format!("text: {text}"));
+    }
+
+    fn result_json(&mut self, value: &serde_json::Value) {
+        self.lines.push(format!("json: {value}"));
+    }
+}
+
+/// A column of the process table, chosen with `list --fields`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Pid,
+    Name,
+    Port,
+    Protocol,
+    Command,
+    User,
+    Container,
+    Ppid,
+    Parent,
+    Age,
+    Cwd,
+    Host,
+    Origin,
+}
+
+/// Table cells shorten working directories to this many characters.
+const CWD_CELL_WIDTH: usize = 40;
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::Name => "Name",
+            Column::Port => "Port",
+            Column::Protocol => "Protocol",
+            Column::Command => "Command",
+            Column::User => "User",
+            Column::Container => "Container",
+            Column::Ppid => "PPID",
+            Column::Parent => "Parent",
+            Column::Age => "Age",
+            Column::Cwd => "Cwd",
+            Column::Host => "Host",
+            Column::Origin => "Origin",
+        }
+    }
+
+    fn cell(self, p: &ProcessInfo) -> String {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".into());
+        match self {
+            Column::Pid => p.pid.to_string(),
+            Column::Name => p.name.clone(),
+            Column::Port => p.port.to_string(),
+            Column::Protocol => p.protocol.to_string(),
+            Column::Command => or_dash(p.command.clone()),
+            Column::User => or_dash(p.user.clone()),
+            Column::Container => or_dash(
+                p.container
+                    .as_ref()
+                    .map(|c| format!("{} ({})", c.name, c.image)),
+            ),
+            Column::Ppid => or_dash(p.ppid.map(|ppid| ppid.to_string())),
+            Column::Parent => or_dash(parent_label(p)),
+            Column::Age => or_dash(p.age().map(format::humanize_duration)),
+            Column::Cwd => or_dash(p.cwd.as_ref().map(|cwd| cwd.display().to_string())),
+            Column::Host => or_dash(p.host.clone()),
+            Column::Origin => or_dash(p.origin.map(|o| o.to_string())),
+        }
+    }
+}
+
+/// "npm (PID 812)", or just the PID if the parent's name is unknown.
+pub fn parent_label(p: &ProcessInfo) -> Option<String> {
+    match (&p.parent_name, p.ppid) {
+        (Some(name), Some(ppid)) => Some(format!("{name} (PID {ppid})")),
+        (None, Some(ppid)) => Some(format!("PID {ppid}")),
+        _ => None,
+    }
 }
 
 pub fn print_processes(processes: &[ProcessInfo], format: OutputFormat) {
+    print_process_columns(processes, format, &[], &PortColors::none());
+}
+
+/// How the Port cell of a process table is coloured, by [`PortCategory`]:
+/// privileged ports red, `highlight_ports` yellow, ephemeral ones dimmed.
+pub struct PortColors {
+    highlight: Vec<u16>,
+    ephemeral: (u16, u16),
+    enabled: bool,
+}
+
+impl PortColors {
+    /// Colours for this machine's ephemeral range, unless `NO_COLOR` is set.
+    /// Tables piped elsewhere are never coloured.
+    pub fn new(highlight: Vec<u16>) -> Self {
+        Self {
+            highlight,
+            ephemeral: portinfo::ephemeral_or_default().0,
+            enabled: std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+
+    /// Plain port cells, for tables outside `list`.
+    pub fn none() -> Self {
+        Self {
+            highlight: Vec::new(),
+            ephemeral: (0, 0),
+            enabled: false,
+        }
+    }
+
+    fn cell(&self, port: u16) -> Cell {
+        let cell = Cell::new(port);
+        if !self.enabled {
+            return cell;
+        }
+        match PortCategory::of(port, &self.highlight, self.ephemeral) {
+            PortCategory::Privileged => cell.fg(Color::Red),
+            PortCategory::Highlighted => cell.fg(Color::Yellow),
+            PortCategory::Ephemeral => cell.fg(Color::DarkGrey),
+            PortCategory::Other => cell,
+        }
+    }
+}
+
+/// Print processes with the given table/plain columns, or the default set
+/// when `columns` is empty. JSON always includes every field.
+pub fn print_process_columns(
+    processes: &[ProcessInfo],
+    format: OutputFormat,
+    columns: &[Column],
+    ports: &PortColors,
+) {
+    match format {
+        OutputFormat::Table if columns.is_empty() => print_process_table(processes, ports),
+        OutputFormat::Table => print_custom_table(processes, columns, ports),
+        OutputFormat::Json => {
+            println!("{}", processes_to_json(processes));
+        }
+        OutputFormat::Plain if columns.is_empty() => {
+            for p in processes {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    p.pid, p.name, p.port, p.protocol
+                );
+            }
+        }
+        OutputFormat::Plain => {
+            for p in processes {
+                let cells: Vec<String> = columns.iter().map(|c| c.cell(p)).collect();
+                println!("{}", cells.join("\t"));
+            }
+        }
+        OutputFormat::Pids | OutputFormat::Ports => {
+            print_ids(processes.iter().map(|p| (p.pid, p.port)), format);
+        }
+    }
+}
+
+/// Unique PIDs or ports in numeric order, one per line. Port 0 stands for a
+/// process found without a socket, so it is left out.
+pub fn print_ids(rows: impl Iterator<Item = (u32, u16)>, format: OutputFormat) {
+    let ids: BTreeSet<u32> = match format {
+        OutputFormat::Ports => rows
+            .filter(|&(_, port)| port != 0)
+            .map(|(_, port)| u32::from(port))
+            .collect(),
+        _ => rows.map(|(pid, _)| pid).collect(),
+    };
+    for id in ids {
+        println!("{id}");
+    }
+}
+
+/// Print one row per process with its ports compressed into ranges.
+pub fn print_process_groups(groups: &[ProcessGroup], format: OutputFormat) {
+    let ports =
+        |g: &ProcessGroup| compress_ports(&g.ports.iter().map(|b| b.port).collect::<Vec<_>>());
     match format {
-        OutputFormat::Table => print_process_table(processes),
         OutputFormat::Json => {
             println!(
                 "{}",
-                serde_json::to_string_pretty(processes).unwrap_or_default()
+                serde_json::to_string_pretty(groups).unwrap_or_default()
             );
         }
         OutputFormat::Plain => {
-            for p in processes {
+            for g in groups {
                 println!(
-                    "{}\t{}\t{}\t{}",
-                    p.pid, p.name, p.port, p.protocol
+                    "{}\t{}\t{}\t{}\t{}",
+                    g.pid,
+                    g.name,
+                    ports(g),
+                    g.protocols(),
+                    g.count
                 );
             }
         }
+        OutputFormat::Pids | OutputFormat::Ports => {
+            let rows = groups
+                .iter()
+                .flat_map(|g| g.ports.iter().map(|b| (g.pid, b.port)));
+            print_ids(rows, format);
+        }
+        OutputFormat::Table => {
+            if groups.is_empty() {
+                return;
+            }
+            let mut table = Table::new();
+            table
+                .load_preset(UTF8_FULL_CONDENSED)
+                .set_content_arrangement(ContentArrangement::Dynamic)
+                .set_header(["PID", "Name", "Ports", "Protocol", "Count", "Command"]);
+            for g in groups {
+                table.add_row([
+                    g.pid.to_string(),
+                    g.name.clone(),
+                    ports(g),
+                    g.protocols(),
+                    g.count.to_string(),
+                    g.command
+                        .as_deref()
+                        .map(truncate_command)
+                        .unwrap_or_else(|| "-".into()),
+                ]);
+            }
+            println!("{table}");
+        }
     }
 }
 
-fn print_process_table(processes: &[ProcessInfo]) {
+/// "14 processes on 9 ports (11 TCP, 3 UDP)", counting one per row.
+pub fn list_footer(processes: &[ProcessInfo]) -> String {
+    let ports: HashSet<u16> = processes.iter().map(|p| p.port).collect();
+    let tcp = processes
+        .iter()
+        .filter(|p| p.protocol == Protocol::Tcp)
+        .count();
+    format!(
+        "{} on {} ({tcp} TCP, {} UDP)",
+        plural(processes.len(), "process", "processes"),
+        plural(ports.len(), "port", "ports"),
+        processes.len() - tcp
+    )
+}
+
+/// Summary line under a table; other formats are left untouched.
+pub fn print_list_footer(processes: &[ProcessInfo], format: OutputFormat) {
+    if format == OutputFormat::Table && !processes.is_empty() {
+        eprintln!("{}", list_footer(processes).dimmed());
+    }
+}
+
+/// The number of matching rows and nothing else, for `list --count`.
+pub fn print_count(count: usize, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!(r#"{{"count": {count}}}"#),
+        _ => println!("{count}"),
+    }
+}
+
+pub fn plural(n: usize, one: &str, many: &str) -> String {
+    format!("{n} {}", if n == 1 { one } else { many })
+}
+
+/// Collapse ports into sorted ranges: `[7000, 5001, 5000, 5002]` becomes
+/// "5000-5002, 7000".
+pub fn compress_ports(ports: &[u16]) -> String {
+    let mut ports = ports.to_vec();
+    ports.sort_unstable();
+    ports.dedup();
+    let mut ranges: Vec<(u16, u16)> = Vec::new();
+    for port in ports {
+        match ranges.last_mut() {
+            Some((_, end)) if u32::from(*end) + 1 == u32::from(port) => *end = port,
+            _ => ranges.push((port, port)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_custom_table(processes: &[ProcessInfo], columns: &[Column], ports: &PortColors) {
     if processes.is_empty() {
         return;
     }
@@ -38,49 +479,240 @@ fn print_process_table(processes: &[ProcessInfo]) {
     table
         .load_preset(UTF8_FULL_CONDENSED)
         .set_content_arrangement(ContentArrangement::Dynamic)
-        .set_header(vec!["PID", "Name", "Port", "Protocol", "Command"]);
+        .set_header(columns.iter().map(|c| c.header()));
+    for p in processes {
+        table.add_row(columns.iter().map(|&c| match c {
+            Column::Port => ports.cell(p.port),
+            _ => Cell::new(table_cell(c, p)),
+        }));
+    }
+    println!("{table}");
+}
+
+/// A cell sized for a table, where long commands and paths are shortened
+/// and interpreters name their script.
+fn table_cell(column: Column, p: &ProcessInfo) -> String {
+    match column {
+        Column::Name => p.display_name(),
+        Column::Command => truncate_command(&column.cell(p)),
+        Column::Cwd => p
+            .cwd
+            .as_deref()
+            .map(|cwd| shorten_path(cwd, CWD_CELL_WIDTH))
+            .unwrap_or_else(|| "-".into()),
+        _ => column.cell(p),
+    }
+}
+
+/// One refresh of `list --watch` in table or plain format: the current rows,
+/// with those that appeared since the last refresh marked `+`, followed by
+/// the rows that went away marked `-`.
+pub fn print_watch_frame(
+    processes: &[ProcessInfo],
+    diff: &ProcessDiff,
+    format: OutputFormat,
+    columns: &[Column],
+) {
+    const DEFAULT: [Column; 6] = [
+        Column::Pid,
+        Column::Name,
+        Column::Port,
+        Column::Protocol,
+        Column::Age,
+        Column::Command,
+    ];
+    let columns = if columns.is_empty() {
+        &DEFAULT[..]
+    } else {
+        columns
+    };
+    let rows = processes
+        .iter()
+        .map(|p| (if diff.is_added(p) { "+" } else { " " }, p))
+        .chain(diff.removed.iter().map(|p| ("-", p)));
+
+    if format == OutputFormat::Plain {
+        for (marker, p) in rows {
+            let cells: Vec<String> = columns.iter().map(|c| c.cell(p)).collect();
+            println!("{marker}\t{}", cells.join("\t"));
+        }
+        return;
+    }
 
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL_CONDENSED)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(std::iter::once("").chain(columns.iter().map(|c| c.header())));
+    for (marker, p) in rows {
+        let color = match marker {
+            "+" => Some(Color::Green),
+            "-" => Some(Color::Red),
+            _ => None,
+        };
+        let cells = std::iter::once(marker.to_string())
+            .chain(columns.iter().map(|&c| table_cell(c, p)))
+            .map(|text| match color {
+                Some(color) => Cell::new(text).fg(color),
+                None => Cell::new(text),
+            });
+        table.add_row(cells.collect::<Vec<_>>());
+    }
+    println!("{table}");
+}
+
+/// One pretty-printed JSON document on stdout.
+pub fn print_json(value: &impl serde::Serialize) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    );
+}
+
+pub fn processes_to_json(processes: &[ProcessInfo]) -> String {
+    serde_json::to_string_pretty(processes).unwrap_or_default()
+}
+
+/// Render processes as CSV with the same columns as the JSON output.
+pub fn processes_to_csv(processes: &[ProcessInfo]) -> String {
+    let mut out = String::from("pid,name,port,protocol,command,user\n");
     for p in processes {
-        table.add_row(vec![
+        let fields = [
             p.pid.to_string(),
-            p.name.clone(),
+            csv_field(&p.name),
             p.port.to_string(),
-            p.protocol.to_string(),
-            p.command
-                .as_deref()
-                .map(truncate_command)
-                .unwrap_or_else(|| "-".into()),
-        ]);
+            p.protocol.to_string().to_lowercase(),
+            csv_field(p.command.as_deref().unwrap_or("")),
+            csv_field(p.user.as_deref().unwrap_or("")),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
     }
-    println!("{table}");
+    out
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn print_process_table(processes: &[ProcessInfo], ports: &PortColors) {
+    if !processes.is_empty() {
+        println!("{}", process_table(processes, ports));
+    }
+}
+
+/// The processes a command is about to act on, as a table on stderr so it
+/// stays out of the command's own output, e.g. before a confirmation.
+pub fn print_targets(processes: &[ProcessInfo]) {
+    if !processes.is_empty() {
+        eprintln!("{}", process_table(processes, &PortColors::none()));
+    }
+}
+
+fn process_table(processes: &[ProcessInfo], ports: &PortColors) -> Table {
+    // Only show the container column when a Docker proxy was resolved
+    let show_container = processes.iter().any(|p| p.container.is_some());
+    let show_host = processes.iter().any(|p| p.host.is_some());
+    let show_origin = processes.iter().any(|p| p.origin.is_some());
+    let mut header = vec!["PID", "Name", "Port", "Protocol", "Age", "Command"];
+    if show_container {
+        header.push("Container");
+    }
+    if show_origin {
+        header.push("Origin");
+    }
+    if show_host {
+        header.insert(0, "Host");
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL_CONDENSED)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header);
+
+    for p in processes {
+        let mut row = vec![
+            Cell::new(p.pid),
+            Cell::new(p.display_name()),
+            ports.cell(p.port),
+            Cell::new(p.protocol),
+            Cell::new(Column::Age.cell(p)),
+            Cell::new(
+                p.command
+                    .as_deref()
+                    .map(truncate_command)
+                    .unwrap_or_else(|| "-".into()),
+            ),
+        ];
+        if show_container {
+            row.push(Cell::new(
+                p.container
+                    .as_ref()
+                    .map(|c| format!("{} ({})", c.name, c.image))
+                    .unwrap_or_else(|| "-".into()),
+            ));
+        }
+        if show_origin {
+            row.push(Cell::new(Column::Origin.cell(p)));
+        }
+        if show_host {
+            row.insert(0, Cell::new(Column::Host.cell(p)));
+        }
+        table.add_row(row);
+    }
+    table
+}
+
+/// What `kill --format pids` or `ports` prints once every port is done: the
+/// PIDs of the processes killed (or that would be, in a dry run), or every
+/// port they were found on.
+pub fn print_killed_ids<'a>(
+    results: impl Iterator<Item = (&'a KillResult, &'a [u16])>,
+    format: OutputFormat,
+) {
+    let killed = results.filter(|(r, _)| r.success && r.error_kind.is_none());
+    print_ids(
+        killed.flat_map(|(r, ports)| ports.iter().map(move |&port| (r.process.pid, port))),
+        format,
+    );
 }
 
 pub fn print_kill_results(results: &[KillResult], format: OutputFormat) {
     match format {
         OutputFormat::Json => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(results).unwrap_or_default()
-            );
+            for r in results {
+                println!("{}", event_line(r));
+            }
         }
-        OutputFormat::Table | OutputFormat::Plain => {
+        _ => {
             for r in results {
-                if r.success {
+                if r.error_kind == Some(KillErrorKind::ProcessGone) {
                     eprintln!(
-                        "{} Killed {} (PID {}) on port {}/{} [{}]",
+                        "{} {} ({}){} had already exited",
+                        "ℹ".blue(),
+                        r.process.name.bold(),
+                        pid_label(r),
+                        on_port(&r.process),
+                    );
+                } else if r.success {
+                    eprintln!(
+                        "{} Killed {} ({}){} [{}]",
                         "✓".green(),
                         r.process.name.bold(),
-                        r.process.pid,
-                        r.process.port,
-                        r.process.protocol,
+                        pid_label(r),
+                        on_port(&r.process),
                         r.signal_sent.dimmed(),
                     );
                 } else {
                     eprintln!(
-                        "{} Failed to kill {} (PID {}): {}",
+                        "{} Failed to kill {} ({}): {}",
                         "✗".red(),
                         r.process.name.bold(),
-                        r.process.pid,
+                        pid_label(r),
                         r.error.as_deref().unwrap_or("unknown error"),
                     );
                 }
@@ -89,17 +721,219 @@ pub fn print_kill_results(results: &[KillResult], format: OutputFormat) {
     }
 }
 
+/// "PID 4322", plus what was signalled instead, e.g. "PID 4322, pgid 4321".
+fn pid_label(r: &KillResult) -> String {
+    match &r.target {
+        Some(target) => format!("PID {}, {target}", r.process.pid),
+        None => format!("PID {}", r.process.pid),
+    }
+}
+
+/// " on port 3000/TCP", or nothing for a process killed by PID that holds
+/// no sockets (port 0).
+fn on_port(p: &ProcessInfo) -> String {
+    if p.port == 0 {
+        String::new()
+    } else {
+        format!(" on port {}/{}", p.port, p.protocol)
+    }
+}
+
+/// Report an error that ends the run. JSON output also gets an
+/// `{"error": {"kind", "message"}}` object on stdout for scripts.
+pub fn print_fatal_error(kind: &str, message: &str, format: OutputFormat) {
+    if format == OutputFormat::Json {
+        let error = serde_json::json!({ "error": { "kind": kind, "message": message } });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&error).unwrap_or_default()
+        );
+    }
+    eprintln!("{} {message}", "Error:".red().bold());
+}
+
+/// One kill attempt or [`IgnoredEvent`] as a line of NDJSON, as streamed by
+/// `--format json` and written to `watch --log-file`.
+pub fn event_line(event: &impl serde::Serialize) -> String {
+    serde_json::to_string(event).unwrap_or_default()
+}
+
+/// A process `watch` left alone because a pidfile names it.
+#[derive(Debug, serde::Serialize)]
+pub struct IgnoredEvent<'a> {
+    pub process: &'a ProcessInfo,
+    /// Why it was spared; always `pidfile` for now
+    pub ignored: &'static str,
+    pub pidfile: &'a Path,
+}
+
+impl IgnoredEvent<'_> {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} (PID {}) on port {}: ignored (pidfile {})",
+            self.process.name,
+            self.process.pid,
+            self.process.port,
+            self.pidfile.display()
+        )
+    }
+}
+
+pub fn print_warning(message: &str) {
+    eprintln!("{} {message}", "warning:".yellow().bold());
+}
+
+/// Warn that killing a Docker proxy leaves its container running.
+pub fn print_container_warning(processes: &[ProcessInfo], format: OutputFormat) {
+    if format == OutputFormat::Json {
+        return;
+    }
+    for p in processes {
+        if let Some(c) = &p.container {
+            eprintln!(
+                "{} {} (PID {}) on port {} forwards to container {}; killing it only stops the proxy. Use `docker stop {}` to stop the container",
+                "!".yellow(),
+                p.name.bold(),
+                p.pid,
+                p.port,
+                c.name.bold(),
+                c.name,
+            );
+        }
+    }
+}
+
+/// Warn before signalling processes owned by other users, which will likely fail.
+pub fn print_elevation_warning(processes: &[ProcessInfo], format: OutputFormat) {
+    if format == OutputFormat::Json {
+        return;
+    }
+    let locked: Vec<&ProcessInfo> = processes
+        .iter()
+        .filter(|p| killer::needs_elevation(p))
+        .collect();
+    if locked.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{} {} of {} targets are owned by other users and will likely fail without sudo",
+        "!".yellow(),
+        locked.len(),
+        processes.len(),
+    );
+    for p in locked {
+        eprintln!(
+            "  {} (PID {}) on port {} is owned by {}",
+            p.name.bold(),
+            p.pid,
+            p.port,
+            p.user.as_deref().unwrap_or("another user"),
+        );
+    }
+}
+
+/// Nothing on `port`. JSON gets the empty process list a single port's
+/// results would otherwise be.
 pub fn print_no_process(port: u16, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("[]"),
+        OutputFormat::Pids | OutputFormat::Ports => {}
+        _ => {
+            eprintln!("No processes found on port {port}");
+        }
+    }
+}
+
+/// A safety check kept kill away from a port; list what tripped it.
+pub fn print_refusal(reason: &str, processes: &[ProcessInfo]) {
+    eprintln!("{} {reason}", "✗".red());
+    for p in processes {
+        eprintln!(
+            "  {} (PID {}){}",
+            p.name.bold(),
+            p.pid,
+            p.command
+                .as_deref()
+                .map(|c| format!(": {}", truncate_command(c)))
+                .unwrap_or_default()
+        );
+    }
+}
+
+/// Ports below 1024 that kill skipped, collapsed into one line for ranges.
+pub fn print_privileged_skipped(ports: &[u16]) {
+    let message = match ports {
+        [port] => format!("port {port} is a privileged port"),
+        _ => format!("ports {} are privileged ports", compress_ports(ports)),
+    };
+    eprintln!(
+        "{} {message}; pass --allow-privileged to target {}",
+        "✗".red(),
+        if ports.len() == 1 { "it" } else { "them" }
+    );
+}
+
+/// Something may be on `port`, but nothing that passes the `--name` filter.
+pub fn print_no_match(port: u16, filter: &str, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("[]"),
+        OutputFormat::Pids | OutputFormat::Ports => {}
+        _ => {
+            eprintln!("No processes matching --name {filter} found on port {port}");
+        }
+    }
+}
+
+/// A socket is bound to `port` but its owner is hidden from us, which is not
+/// the same as the port being free.
+pub fn print_owner_unknown(port: u16, format: OutputFormat) {
     match format {
         OutputFormat::Json => {
-            println!(r#"{{"port": {port}, "processes": []}}"#);
+            println!(r#"{{"port": {port}, "processes": [], "owner_unknown": true}}"#);
         }
         _ => {
-            eprintln!("No processes found on port {port}");
+            eprintln!(
+                "A process is listening on port {port} but its owner could not be determined (run with sudo)"
+            );
         }
     }
 }
 
+/// Print scan warnings to stderr, once each.
+pub fn print_scan_warnings(warnings: &[ScanWarning]) {
+    for warning in warnings {
+        print_warning(&warning.to_string());
+    }
+}
+
+/// Shorten a path from the left to at most `max` characters, keeping whole
+/// trailing components where possible: "…/myrepo/packages/web".
+pub fn shorten_path(path: &Path, max: usize) -> String {
+    let full = path.display().to_string();
+    if full.chars().count() <= max {
+        return full;
+    }
+    let mut kept = String::new();
+    for component in full.rsplit('/').filter(|c| !c.is_empty()) {
+        let candidate = if kept.is_empty() {
+            component.to_string()
+        } else {
+            format!("{component}/{kept}")
+        };
+        // Room for the "…/" prefix
+        if candidate.chars().count() + 2 > max {
+            break;
+        }
+        kept = candidate;
+    }
+    if kept.is_empty() {
+        // Even the last component is too long; keep its tail.
+        let tail: String = full.chars().rev().take(max.saturating_sub(1)).collect();
+        return format!("…{}", tail.chars().rev().collect::<String>());
+    }
+    format!("…/{kept}")
+}
+
 fn truncate_command(s: &str) -> String {
     if s.len() > 80 {
         format!("{}...", &s[..77])
@@ -107,3 +941,65 @@ fn truncate_command(s: &str) -> String {
         s.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::mock::process;
+
+    #[test]
+    fn compress_ports_collapses_consecutive_runs() {
+        assert_eq!(compress_ports(&[]), "");
+        assert_eq!(compress_ports(&[80]), "80");
+        assert_eq!(
+            compress_ports(&[7000, 5001, 5000, 5004, 5002, 5003, 5003]),
+            "5000-5004, 7000"
+        );
+        assert_eq!(compress_ports(&[1, 3, 65534, 65535]), "1, 3, 65534-65535");
+    }
+
+    #[test]
+    fn shorten_path_keeps_trailing_components() {
+        let path = Path::new("/Users/alice/src/work/myrepo/packages/web");
+        assert_eq!(shorten_path(path, 80), path.display().to_string());
+        assert_eq!(shorten_path(path, 25), "…/myrepo/packages/web");
+        assert_eq!(shorten_path(path, 6), "…/web");
+        assert_eq!(
+            shorten_path(Path::new("/a/very-long-directory"), 8),
+            "…rectory"
+        );
+    }
+
+    #[test]
+    fn list_footer_counts_rows_ports_and_protocols() {
+        let row = |pid, port, protocol| ProcessInfo {
+            protocol,
+            ..process(pid, "x", port)
+        };
+        let processes = [
+            row(1, 3000, Protocol::Tcp),
+            row(2, 3000, Protocol::Tcp),
+            row(3, 5353, Protocol::Udp),
+        ];
+        assert_eq!(
+            list_footer(&processes),
+            "3 processes on 2 ports (2 TCP, 1 UDP)"
+        );
+        assert_eq!(
+            list_footer(&processes[..1]),
+            "1 process on 1 port (1 TCP, 0 UDP)"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_separators() {
+        let processes = vec![ProcessInfo {
+            command: Some(r#"node -e "a,b""#.into()),
+            ..process(42, "node", 3000)
+        }];
+        assert_eq!(
+            processes_to_csv(&processes),
+            "pid,name,port,protocol,command,user\n42,node,3000,tcp,\"node -e \"\"a,b\"\"\",\n"
+        );
+    }
+}
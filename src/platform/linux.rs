@@ -1,7 +1,10 @@
+use super::{ProcessAccess, ProcessIdentity};
 use crate::errors::{KillportError, Result};
-use crate::process::{ProcessInfo, Protocol};
-use crate::scanner::PortScanner;
-use std::collections::HashMap;
+use crate::process::{PortSpec, ProcessInfo, Protocol};
+use crate::scanner::{self, PortScanner, ScanResult, ScanWarning};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub struct LinuxScanner;
 
@@ -10,196 +13,671 @@ impl LinuxScanner {
         Self
     }
 
-    /// Read /proc/net/{tcp,tcp6,udp,udp6} and find inodes for the target port.
-    fn find_inodes_for_port(target_port: u16) -> Result<HashMap<u64, Protocol>> {
-        let mut inodes: HashMap<u64, Protocol> = HashMap::new();
-
-        if let Ok(tcp) = procfs::net::tcp() {
-            for entry in tcp {
-                if entry.local_address.port() == target_port {
-                    inodes.insert(entry.inode, Protocol::Tcp);
-                }
-            }
-        }
-        if let Ok(tcp6) = procfs::net::tcp6() {
-            for entry in tcp6 {
-                if entry.local_address.port() == target_port {
-                    inodes.insert(entry.inode, Protocol::Tcp);
-                }
-            }
-        }
-        if let Ok(udp) = procfs::net::udp() {
-            for entry in udp {
-                if entry.local_address.port() == target_port {
-                    inodes.insert(entry.inode, Protocol::Udp);
-                }
-            }
-        }
-        if let Ok(udp6) = procfs::net::udp6() {
-            for entry in udp6 {
-                if entry.local_address.port() == target_port {
-                    inodes.insert(entry.inode, Protocol::Udp);
-                }
-            }
-        }
+    /// Find inodes of sockets bound to the target port.
+    fn find_inodes_for_port(
+        target_port: u16,
+        warnings: &mut Vec<ScanWarning>,
+    ) -> Result<HashMap<u64, (u16, Protocol)>> {
+        Ok(socket_table(warnings)?
+            .into_iter()
+            .filter(|entry| entry.port == target_port)
+            .map(|entry| (entry.inode, (entry.port, entry.protocol)))
+            .collect())
+    }
 
-        Ok(inodes)
+    /// Collect all inodes with their port and protocol from /proc/net/*.
+    fn all_listening_inodes(
+        warnings: &mut Vec<ScanWarning>,
+    ) -> Result<HashMap<u64, (u16, Protocol)>> {
+        Ok(socket_table(warnings)?
+            .into_iter()
+            .filter(|entry| entry.port > 0)
+            .map(|entry| (entry.inode, (entry.port, entry.protocol)))
+            .collect())
     }
 
     /// Walk all processes and find which ones own any of the target inodes.
-    fn find_processes_by_inodes(
-        inodes: &HashMap<u64, Protocol>,
-        target_port: u16,
-    ) -> Result<Vec<ProcessInfo>> {
-        let mut results = Vec::new();
+    /// With `one_per_process`, stop at each process's first matching socket.
+    fn find_owners(
+        inodes: &HashMap<u64, (u16, Protocol)>,
+        one_per_process: bool,
+    ) -> Result<Owners> {
+        let all_procs: Vec<_> = procfs::process::all_processes()
+            .map_err(|e| KillportError::PlatformError(format!("failed to read /proc: {e}")))?
+            .filter_map(|p| p.ok())
+            .collect();
 
-        let all_procs = procfs::process::all_processes()
-            .map_err(|e| KillportError::PlatformError(format!("failed to read /proc: {e}")))?;
-
-        for proc_result in all_procs {
-            let proc_entry = match proc_result {
-                Ok(p) => p,
-                Err(_) => continue,
-            };
+        let per_process = scanner::scan_parallel(all_procs, |proc_entry| {
+            Self::owned_sockets(&proc_entry, inodes, one_per_process)
+        });
 
-            let fds = match proc_entry.fd() {
-                Ok(fds) => fds,
-                Err(_) => continue,
+        let mut owners = Owners::default();
+        let mut users = HashMap::new();
+        for fds in per_process {
+            let Some(fds) = fds else {
+                owners.skipped += 1;
+                continue;
             };
-
-            for fd_result in fds {
-                let fd_info = match fd_result {
-                    Ok(f) => f,
-                    Err(_) => continue,
-                };
-
-                if let procfs::process::FDTarget::Socket(inode) = fd_info.target {
-                    if let Some(&protocol) = inodes.get(&inode) {
-                        let pid = proc_entry.pid() as u32;
-                        let proc_name = proc_entry
-                            .stat()
-                            .map(|s| s.comm.clone())
-                            .unwrap_or_else(|_| "<unknown>".into());
-                        let command = proc_entry.cmdline().ok().map(|parts| parts.join(" "));
-
-                        results.push(ProcessInfo {
-                            pid,
-                            name: proc_name,
-                            port: target_port,
-                            protocol,
-                            command,
-                            user: None,
-                        });
-                        break; // Found this PID, move to next process
-                    }
-                }
+            if let Some(pid) = fds.truncated {
+                owners.truncated.push(pid);
+            }
+            for (inode, mut info) in fds.sockets {
+                owners.matched.insert(inode);
+                info.user = info.uid.and_then(|uid| super::user_name(uid, &mut users));
+                owners.processes.push(info);
             }
         }
 
-        Ok(results)
+        tracing::debug!(
+            owners = owners.processes.len(),
+            skipped = owners.skipped,
+            "walked /proc fd tables"
+        );
+        Ok(owners)
     }
 
-    /// Collect all inodes with their port and protocol from /proc/net/*.
-    fn all_listening_inodes() -> Result<HashMap<u64, (u16, Protocol)>> {
-        let mut inode_map: HashMap<u64, (u16, Protocol)> = HashMap::new();
-
-        if let Ok(tcp) = procfs::net::tcp() {
-            for entry in tcp {
-                let port = entry.local_address.port();
-                if port > 0 {
-                    inode_map.insert(entry.inode, (port, Protocol::Tcp));
-                }
+    /// The target sockets one process holds, or `None` if we may not read its
+    /// fd table. Other errors (the process exited mid-scan) count as no sockets.
+    fn owned_sockets(
+        proc_entry: &procfs::process::Process,
+        inodes: &HashMap<u64, (u16, Protocol)>,
+        one_per_process: bool,
+    ) -> Option<FdScan> {
+        let pid = proc_entry.pid();
+        let fds = match proc_entry.fd() {
+            Ok(fds) => fds,
+            Err(procfs::ProcError::PermissionDenied(_)) => {
+                tracing::trace!(pid, "fd table unreadable: permission denied");
+                return None;
             }
-        }
-        if let Ok(tcp6) = procfs::net::tcp6() {
-            for entry in tcp6 {
-                let port = entry.local_address.port();
-                if port > 0 {
-                    inode_map.insert(entry.inode, (port, Protocol::Tcp));
-                }
+            Err(e) => {
+                tracing::trace!(pid, error = %e, "fd table unreadable");
+                return Some(FdScan::default());
             }
-        }
-        if let Ok(udp) = procfs::net::udp() {
-            for entry in udp {
-                let port = entry.local_address.port();
-                if port > 0 {
-                    inode_map.insert(entry.inode, (port, Protocol::Udp));
+        };
+
+        let mut scan = FdScan::default();
+        for fd_info in fds {
+            let fd_info = match fd_info {
+                Ok(fd_info) => fd_info,
+                // The fd was closed while we were reading the table.
+                Err(procfs::ProcError::NotFound(_)) => continue,
+                Err(e) => {
+                    tracing::trace!(pid, error = %e, "fd unreadable");
+                    scan.truncated = Some(pid as u32);
+                    continue;
                 }
-            }
-        }
-        if let Ok(udp6) = procfs::net::udp6() {
-            for entry in udp6 {
-                let port = entry.local_address.port();
-                if port > 0 {
-                    inode_map.insert(entry.inode, (port, Protocol::Udp));
+            };
+            if let procfs::process::FDTarget::Socket(inode) = fd_info.target {
+                if let Some(&(port, protocol)) = inodes.get(&inode) {
+                    tracing::trace!(pid, port, ?protocol, inode, "process holds target socket");
+                    scan.sockets
+                        .push((inode, Self::process_info(proc_entry, port, protocol)));
+                    if one_per_process {
+                        break; // Found this PID, move to next process
+                    }
                 }
             }
         }
+        Some(scan)
+    }
+
+    /// Details for one process. `user` is filled in by the caller, which
+    /// caches uid lookups across the whole scan.
+    fn process_info(
+        proc_entry: &procfs::process::Process,
+        port: u16,
+        protocol: Protocol,
+    ) -> ProcessInfo {
+        let stat = proc_entry.stat().ok();
+        let proc_name = stat
+            .as_ref()
+            .map(|s| s.comm.clone())
+            .unwrap_or_else(|| "<unknown>".into());
+        let command = proc_entry.cmdline().ok().map(|parts| parts.join(" "));
+        let uid = proc_entry.uid().ok();
+        // An orphan is re-parented as soon as its parent exits, so a ppid we
+        // can still open is the live parent.
+        let ppid = stat.as_ref().map(|s| s.ppid).filter(|&ppid| ppid > 0);
+        let parent_name = ppid
+            .and_then(|ppid| procfs::process::Process::new(ppid).ok())
+            .and_then(|parent| parent.stat().ok())
+            .map(|s| s.comm);
+        let started_at = stat.as_ref().and_then(|s| start_time(s.starttime));
+
+        ProcessInfo {
+            pid: proc_entry.pid() as u32,
+            name: proc_name,
+            port,
+            protocol,
+            command,
+            user: None,
+            uid,
+            ppid: ppid.map(|ppid| ppid as u32),
+            parent_name,
+            started_at,
+            cwd: proc_entry.cwd().ok(),
+            host: None,
+            origin: None,
+            container: None,
+            managed_by: None,
+        }
+    }
+}
+
+pub(super) fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    let process = procfs::process::Process::new(pid as i32).ok()?;
+    let stat = process.stat().ok()?;
+    // A zombie has exited; only its exit status is left to collect.
+    if stat.state == 'Z' {
+        return None;
+    }
+    Some(ProcessIdentity {
+        started_at: start_time(stat.starttime),
+        name: stat.comm,
+        uid: process.uid().ok(),
+    })
+}
+
+/// Convert a process's start time in clock ticks since boot to wall-clock
+/// time. Boot time comes from /proc/stat's `btime`, which shifts when the
+/// wall clock is changed, so the result is approximate.
+fn start_time(ticks_since_boot: u64) -> Option<SystemTime> {
+    static BOOT_TIME: OnceLock<Option<u64>> = OnceLock::new();
+    let boot = (*BOOT_TIME.get_or_init(|| procfs::boot_time_secs().ok()))?;
+    let since_boot = Duration::from_millis(
+        ticks_since_boot.saturating_mul(1000) / procfs::ticks_per_second().max(1),
+    );
+    UNIX_EPOCH
+        .checked_add(Duration::from_secs(boot))?
+        .checked_add(since_boot)
+}
+
+/// What the fd walk found.
+#[derive(Default)]
+struct Owners {
+    processes: Vec<ProcessInfo>,
+    /// Socket inodes that some visible process holds
+    matched: HashSet<u64>,
+    /// Processes whose fd table we weren't allowed to read
+    skipped: usize,
+    /// Processes with fds we couldn't read, so some sockets may be missing
+    truncated: Vec<u32>,
+}
+
+/// The target sockets found in one process's fd table.
+#[derive(Default)]
+struct FdScan {
+    sockets: Vec<(u64, ProcessInfo)>,
+    /// Set to the pid when some of its fds couldn't be read
+    truncated: Option<u32>,
+}
+
+impl Owners {
+    /// Sockets nobody we could inspect owns. Inode 0 means the socket has no
+    /// owner at all (e.g. TIME_WAIT), so those don't count.
+    fn unowned(&self, inodes: &HashMap<u64, (u16, Protocol)>) -> usize {
+        inodes
+            .keys()
+            .filter(|inode| **inode != 0 && !self.matched.contains(inode))
+            .count()
+    }
 
-        Ok(inode_map)
+    fn truncation_warnings(&self) -> impl Iterator<Item = ScanWarning> + '_ {
+        self.truncated
+            .iter()
+            .map(|&pid| ScanWarning::Truncated { pid })
     }
 }
 
 impl PortScanner for LinuxScanner {
     fn find_processes_by_port(&self, port: u16) -> Result<Vec<ProcessInfo>> {
-        let inodes = Self::find_inodes_for_port(port)?;
+        Ok(self.scan_port(port)?.processes)
+    }
+
+    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_all()?.processes)
+    }
+
+    fn scan_port(&self, port: u16) -> Result<ScanResult> {
+        let mut warnings = Vec::new();
+        let inodes = Self::find_inodes_for_port(port, &mut warnings)?;
+        tracing::debug!(sockets = inodes.len(), "sockets bound to port");
         if inodes.is_empty() {
-            return Ok(Vec::new());
+            return Ok(ScanResult {
+                processes: Vec::new(),
+                warnings,
+            });
         }
-        Self::find_processes_by_inodes(&inodes, port)
+        let owners = Self::find_owners(&inodes, true)?;
+        warnings.extend(owners.truncation_warnings());
+        // Under hidepid the owner isn't even listed, so an unreadable fd table
+        // isn't required for the socket to be orphaned from our point of view.
+        if owners.processes.is_empty() && owners.unowned(&inodes) > 0 {
+            warnings.push(ScanWarning::OwnerUnknown { port });
+        }
+        Ok(ScanResult {
+            processes: owners.processes,
+            warnings,
+        })
     }
 
-    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
-        let inode_map = Self::all_listening_inodes()?;
+    fn scan_ports(&self, specs: &[PortSpec]) -> Result<ScanResult> {
+        let mut warnings = Vec::new();
+        let inodes: HashMap<u64, (u16, Protocol)> = socket_table(&mut warnings)?
+            .into_iter()
+            .filter(|entry| specs.iter().any(|spec| spec.contains(entry.port)))
+            .map(|entry| (entry.inode, (entry.port, entry.protocol)))
+            .collect();
+        tracing::debug!(sockets = inodes.len(), "sockets bound to requested ports");
+        if inodes.is_empty() {
+            return Ok(ScanResult {
+                processes: Vec::new(),
+                warnings,
+            });
+        }
+        let mut owners = Self::find_owners(&inodes, false)?;
+        // One row per process and port, as scan_port reports it.
+        let mut seen = HashSet::new();
+        owners.processes.retain(|p| seen.insert((p.pid, p.port)));
+        owners.processes.sort_by_key(|p| (p.port, p.pid));
+
+        let owned_ports: HashSet<u16> = owners.processes.iter().map(|p| p.port).collect();
+        let mut unknown: Vec<u16> = inodes
+            .iter()
+            .filter(|(inode, _)| **inode != 0 && !owners.matched.contains(inode))
+            .map(|(_, (port, _))| *port)
+            .filter(|port| !owned_ports.contains(port))
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+        warnings.extend(owners.truncation_warnings());
+        warnings.extend(
+            unknown
+                .into_iter()
+                .map(|port| ScanWarning::OwnerUnknown { port }),
+        );
+        Ok(ScanResult {
+            processes: owners.processes,
+            warnings,
+        })
+    }
+
+    fn scan_all(&self) -> Result<ScanResult> {
+        let mut warnings = Vec::new();
+        let inode_map = Self::all_listening_inodes(&mut warnings)?;
         if inode_map.is_empty() {
-            return Ok(Vec::new());
+            return Ok(ScanResult {
+                processes: Vec::new(),
+                warnings,
+            });
         }
 
-        let mut results = Vec::new();
+        let mut owners = Self::find_owners(&inode_map, false)?;
+        warnings.extend(owners.truncation_warnings());
+        let hidden = owners.unowned(&inode_map);
+        if hidden > 0 {
+            warnings.push(ScanWarning::HiddenSockets { count: hidden });
+        }
+        owners.processes.sort_by_key(|p| (p.port, p.pid));
+        Ok(ScanResult {
+            processes: owners.processes,
+            warnings,
+        })
+    }
+}
 
-        let all_procs = procfs::process::all_processes()
-            .map_err(|e| KillportError::PlatformError(format!("failed to read /proc: {e}")))?;
+/// One socket from netlink sock_diag or a row of /proc/net/{tcp,tcp6,udp,udp6}.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct SocketEntry {
+    pub(super) port: u16,
+    pub(super) protocol: Protocol,
+    /// Kernel socket state (0x0A is TCP_LISTEN, 0x07 is an unconnected UDP socket)
+    pub(super) state: u8,
+    pub(super) uid: u32,
+    pub(super) inode: u64,
+}
 
-        for proc_result in all_procs {
-            let proc_entry = match proc_result {
-                Ok(p) => p,
-                Err(_) => continue,
-            };
+const SOCKET_TABLES: [(&str, Protocol); 4] = [
+    ("tcp", Protocol::Tcp),
+    ("tcp6", Protocol::Tcp),
+    ("udp", Protocol::Udp),
+    ("udp6", Protocol::Udp),
+];
 
-            let fds = match proc_entry.fd() {
-                Ok(fds) => fds,
-                Err(_) => continue,
-            };
+/// Enumerate sockets over netlink sock_diag, falling back to /proc/net when
+/// netlink is unavailable (old kernels, seccomp filters, gVisor). Tables the
+/// fallback couldn't read are added to `warnings`.
+fn socket_table(warnings: &mut Vec<ScanWarning>) -> Result<Vec<SocketEntry>> {
+    match super::netlink::socket_table() {
+        Ok(entries) => {
+            tracing::debug!(rows = entries.len(), "read sockets over netlink sock_diag");
+            Ok(entries)
+        }
+        Err(e) => {
+            tracing::debug!(error = %e, "netlink sock_diag unavailable, reading /proc/net");
+            let (entries, unreadable) = proc_net_socket_table()?;
+            warnings.extend(
+                unreadable
+                    .into_iter()
+                    .map(|source| ScanWarning::SourceUnavailable { source }),
+            );
+            Ok(entries)
+        }
+    }
+}
 
-            for fd_result in fds {
-                let fd_info = match fd_result {
-                    Ok(f) => f,
-                    Err(_) => continue,
-                };
-
-                if let procfs::process::FDTarget::Socket(inode) = fd_info.target {
-                    if let Some(&(port, protocol)) = inode_map.get(&inode) {
-                        let pid = proc_entry.pid() as u32;
-                        let proc_name = proc_entry
-                            .stat()
-                            .map(|s| s.comm.clone())
-                            .unwrap_or_else(|_| "<unknown>".into());
-                        let command = proc_entry.cmdline().ok().map(|parts| parts.join(" "));
-
-                        results.push(ProcessInfo {
-                            pid,
-                            name: proc_name,
-                            port,
-                            protocol,
-                            command,
-                            user: None,
-                        });
+/// Read every /proc/net socket table, falling back to parsing the text
+/// ourselves when procfs can't (unusual kernels, Termux, WSL1). A missing
+/// table is fine (e.g. IPv6 disabled), but if none can be read that is an
+/// error rather than an empty result. Tables that exist but couldn't be read
+/// are returned alongside the entries.
+fn proc_net_socket_table() -> Result<(Vec<SocketEntry>, Vec<String>)> {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    let mut unreadable = Vec::new();
+    for (table, protocol) in SOCKET_TABLES {
+        match read_with_procfs(table, protocol) {
+            Ok(rows) => {
+                tracing::debug!(table, rows = rows.len(), "read socket table with procfs");
+                entries.extend(rows);
+            }
+            Err(procfs_err) => match read_proc_net_text(table, protocol) {
+                Ok(rows) => {
+                    tracing::debug!(
+                        table,
+                        rows = rows.len(),
+                        error = %procfs_err,
+                        "procfs failed, parsed socket table text directly"
+                    );
+                    entries.extend(rows);
+                }
+                Err(text_err) => {
+                    tracing::debug!(table, error = %text_err, "socket table unreadable");
+                    if text_err.kind() != std::io::ErrorKind::NotFound {
+                        unreadable.push(format!("/proc/net/{table}"));
                     }
+                    errors.push(format!("/proc/net/{table}: {text_err}"));
                 }
+            },
+        }
+    }
+    if errors.len() == SOCKET_TABLES.len() {
+        return Err(KillportError::PlatformError(format!(
+            "could not read any socket table ({})",
+            errors.join("; ")
+        )));
+    }
+    Ok((entries, unreadable))
+}
+
+fn read_with_procfs(table: &str, protocol: Protocol) -> procfs::ProcResult<Vec<SocketEntry>> {
+    let tcp = |rows: Vec<procfs::net::TcpNetEntry>| {
+        rows.into_iter()
+            .map(|e| SocketEntry {
+                port: e.local_address.port(),
+                protocol,
+                state: e.state.to_u8(),
+                uid: e.uid,
+                inode: e.inode,
+            })
+            .collect()
+    };
+    let udp = |rows: Vec<procfs::net::UdpNetEntry>| {
+        rows.into_iter()
+            .map(|e| SocketEntry {
+                port: e.local_address.port(),
+                protocol,
+                state: e.state.to_u8(),
+                uid: e.uid,
+                inode: e.inode,
+            })
+            .collect()
+    };
+    match table {
+        "tcp" => procfs::net::tcp().map(tcp),
+        "tcp6" => procfs::net::tcp6().map(tcp),
+        "udp" => procfs::net::udp().map(udp),
+        _ => procfs::net::udp6().map(udp),
+    }
+}
+
+fn read_proc_net_text(table: &str, protocol: Protocol) -> std::io::Result<Vec<SocketEntry>> {
+    let text = std::fs::read_to_string(format!("/proc/net/{table}"))?;
+    parse_proc_net(&text, protocol)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Parse the text of a /proc/net/{tcp,udp}[6] table:
+///
+/// ```text
+///   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+///    0: 0100007F:0CEA 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 12345 ...
+/// ```
+///
+/// Addresses are hex (32 digits for IPv6) followed by a hex port.
+fn parse_proc_net(text: &str, protocol: Protocol) -> std::result::Result<Vec<SocketEntry>, String> {
+    let mut lines = text.lines();
+    let header = lines.next().ok_or("empty socket table")?;
+    if !header.contains("local_address") {
+        return Err(format!("unexpected header: {}", header.trim()));
+    }
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            parse_proc_net_line(line, protocol)
+                .ok_or_else(|| format!("malformed line: {}", line.trim()))
+        })
+        .collect()
+}
+
+fn parse_proc_net_line(line: &str, protocol: Protocol) -> Option<SocketEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (_, port) = fields.get(1)?.rsplit_once(':')?;
+    Some(SocketEntry {
+        port: u16::from_str_radix(port, 16).ok()?,
+        protocol,
+        state: u8::from_str_radix(fields.get(3)?, 16).ok()?,
+        uid: fields.get(7)?.parse().ok()?,
+        inode: fields.get(9)?.parse().ok()?,
+    })
+}
+
+/// Count the processes we can see and whose fd tables we can read.
+pub fn probe_process_access() -> Result<ProcessAccess> {
+    let euid = nix::unistd::geteuid().as_raw();
+    let all_procs = procfs::process::all_processes()
+        .map_err(|e| KillportError::PlatformError(format!("failed to read /proc: {e}")))?;
+
+    let mut access = ProcessAccess::default();
+    for proc_entry in all_procs.flatten() {
+        access.visible += 1;
+        if proc_entry.uid().map_or(true, |uid| uid == euid) {
+            continue;
+        }
+        access.other_users += 1;
+        if proc_entry.fd().is_err() && proc_entry.is_alive() {
+            access.unreadable += 1;
+        }
+    }
+    Ok(access)
+}
+
+/// The `hidepid=` option /proc is mounted with, if any.
+pub fn proc_hidepid() -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+    mounts
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 4 && fields[1] == "/proc" && fields[2] == "proc")
+        .and_then(|fields| {
+            fields[3]
+                .split(',')
+                .find_map(|opt| opt.strip_prefix("hidepid="))
+                .map(str::to_string)
+        })
+        .filter(|value| value != "0" && value != "off")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = format!(
+            "{}/tests/fixtures/proc_net/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn parses_ipv4_tcp_table() {
+        let rows = parse_proc_net(&fixture("tcp"), Protocol::Tcp).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[0],
+            SocketEntry {
+                port: 48271,
+                protocol: Protocol::Tcp,
+                state: 0x0A,
+                uid: 65534,
+                inode: 938,
             }
+        );
+        assert_eq!(rows[1].port, 2024);
+        assert_eq!(rows[2].state, 0x01);
+        assert_eq!((rows[3].port, rows[3].uid), (65535, 1000));
+    }
+
+    #[test]
+    fn parses_ipv6_tables() {
+        let tcp6 = parse_proc_net(&fixture("tcp6"), Protocol::Tcp).unwrap();
+        assert_eq!(
+            tcp6.iter().map(|r| r.port).collect::<Vec<_>>(),
+            [8080, 50000, 8080]
+        );
+        assert_eq!(tcp6[1].inode, 55230);
+
+        let udp6 = parse_proc_net(&fixture("udp6"), Protocol::Udp).unwrap();
+        assert_eq!(udp6[1].port, 59122);
+        assert!(udp6
+            .iter()
+            .all(|r| r.protocol == Protocol::Udp && r.state == 0x07));
+    }
+
+    #[test]
+    fn parses_udp_table_with_extra_columns() {
+        let rows = parse_proc_net(&fixture("udp"), Protocol::Udp).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            (rows[0].port, rows[0].uid, rows[0].inode),
+            (5353, 104, 20114)
+        );
+        assert_eq!(rows[1].port, 53);
+    }
+
+    #[test]
+    fn unowned_ignores_matched_and_ownerless_sockets() {
+        let inodes: HashMap<u64, (u16, Protocol)> = [
+            (0, (80, Protocol::Tcp)),
+            (11, (80, Protocol::Tcp)),
+            (12, (443, Protocol::Tcp)),
+            (13, (53, Protocol::Udp)),
+        ]
+        .into_iter()
+        .collect();
+        let owners = Owners {
+            matched: [11].into_iter().collect(),
+            ..Owners::default()
+        };
+        assert_eq!(owners.unowned(&inodes), 2);
+    }
+
+    /// Timing of a full scan with and without the thread pool. Run with
+    /// `cargo test --release parallel_scan -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_parallel_scan() {
+        use std::time::Instant;
+
+        // A sandbox or CI box has too few processes for threads to matter.
+        let mut children: Vec<_> = (0..200)
+            .filter_map(|_| std::process::Command::new("sleep").arg("30").spawn().ok())
+            .collect();
+        const ROUNDS: u32 = 10;
+        let time = |label: &str| {
+            let rows = LinuxScanner::new().scan_all().unwrap().processes.len();
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                LinuxScanner::new().scan_all().unwrap();
+            }
+            println!(
+                "{label:>8}: {rows} sockets, {:?}/scan",
+                start.elapsed() / ROUNDS
+            );
+        };
+        std::env::set_var(scanner::SCAN_THREADS_ENV, "1");
+        time("serial");
+        std::env::remove_var(scanner::SCAN_THREADS_ENV);
+        time("parallel");
+        for child in &mut children {
+            let _ = child.kill();
+            let _ = child.wait();
         }
+    }
+
+    /// Benchmark of the two socket enumeration paths. Run with
+    /// `cargo test --release socket_backends -- --ignored --nocapture`;
+    /// `PORTZAP_BENCH_SOCKETS` sets how many extra listeners to open.
+    #[test]
+    #[ignore]
+    fn bench_socket_backends() {
+        use std::time::Instant;
+
+        let count = std::env::var("PORTZAP_BENCH_SOCKETS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(500);
+        let _listeners: Vec<_> = (0..count)
+            .map_while(|_| std::net::TcpListener::bind("127.0.0.1:0").ok())
+            .collect();
+        const ROUNDS: u32 = 20;
+
+        let time = |label: &str, scan: &dyn Fn() -> usize| {
+            let rows = scan();
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                scan();
+            }
+            println!(
+                "{label:>8}: {rows} sockets, {:?}/scan",
+                start.elapsed() / ROUNDS
+            );
+        };
+        time("netlink", &|| {
+            super::super::netlink::socket_table().map_or(0, |e| e.len())
+        });
+        time("procfs", &|| {
+            proc_net_socket_table().map_or(0, |(e, _)| e.len())
+        });
+        time("text", &|| {
+            SOCKET_TABLES
+                .iter()
+                .filter_map(|&(table, protocol)| read_proc_net_text(table, protocol).ok())
+                .map(|rows| rows.len())
+                .sum()
+        });
+    }
 
-        results.sort_by_key(|p| (p.port, p.pid));
-        Ok(results)
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse_proc_net("", Protocol::Tcp).is_err());
+        assert!(parse_proc_net("hello\n", Protocol::Tcp).is_err());
+        let header = fixture("tcp").lines().next().unwrap().to_string();
+        assert!(parse_proc_net(&format!("{header}\n   0: nonsense\n"), Protocol::Tcp).is_err());
+        assert!(parse_proc_net(&format!("{header}\n"), Protocol::Tcp)
+            .unwrap()
+            .is_empty());
     }
 }
@@ -1,22 +1,47 @@
+use super::{ProcessAccess, ProcessIdentity};
 use crate::errors::{KillportError, Result};
 use crate::process::{ProcessInfo, Protocol};
-use crate::scanner::PortScanner;
-use libproc::file_info::{pidfdinfo, ListFDs, ProcFDType};
+use crate::scanner::{self, PortScanner};
+use libproc::bsd_info::BSDInfo;
+use libproc::file_info::{pidfdinfo, ListFDs, ProcFDInfo, ProcFDType};
 use libproc::net_info::{SocketFDInfo, SocketInfoKind};
-use libproc::proc_pid::{listpidinfo, name, pidpath};
+use libproc::proc_pid::{listpidinfo, name, pidinfo, pidpath};
 use libproc::processes::{pids_by_type, ProcFilter};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-pub struct MacosScanner;
+pub struct MacosScanner {
+    /// Report TCP sockets in every state, not just LISTEN
+    all_states: bool,
+}
+
+/// `TCPS_LISTEN` from <netinet/tcp_fsm.h>.
+const TCPS_LISTEN: i32 = 1;
+
+/// Upper bound on the fd buffer, well above the default kern.maxfilesperproc.
+const MAX_FDS: usize = 1 << 20;
 
 impl MacosScanner {
     pub fn new() -> Self {
-        Self
+        Self { all_states: false }
     }
 
+    /// Details for one process. `user` is filled in by `resolve_users` once
+    /// the parallel part of the scan is done.
     fn get_process_info(pid: i32, port: u16, protocol: Protocol) -> ProcessInfo {
         let proc_name = name(pid).unwrap_or_else(|_| "<unknown>".into());
         let command = pidpath(pid).ok();
+        let info = pidinfo::<BSDInfo>(pid, 0).ok();
+        let uid = info.as_ref().map(|info| info.pbi_uid);
+        let ppid = info
+            .as_ref()
+            .map(|info| info.pbi_ppid)
+            .filter(|&ppid| ppid > 0);
+        // name() fails once the parent is gone, so no stale name is reported.
+        let parent_name = ppid.and_then(|ppid| name(ppid as i32).ok());
+        let started_at = info.as_ref().and_then(start_time);
         ProcessInfo {
             pid: pid as u32,
             name: proc_name,
@@ -24,20 +49,39 @@ impl MacosScanner {
             protocol,
             command,
             user: None,
+            uid,
+            ppid,
+            parent_name,
+            started_at,
+            cwd: process_cwd(pid),
+            host: None,
+            origin: None,
+            container: None,
+            managed_by: None,
+        }
+    }
+
+    /// Look up user names with one cache for the whole scan.
+    fn resolve_users(processes: &mut [ProcessInfo]) {
+        let mut users = HashMap::new();
+        for p in processes {
+            p.user = p.uid.and_then(|uid| super::user_name(uid, &mut users));
         }
     }
 
     /// Extract local port and protocol from a socket's info, if applicable.
-    fn extract_port_info(socket_info: &SocketFDInfo) -> Option<(u16, Protocol)> {
+    /// Established and other non-listening TCP sockets are skipped unless
+    /// `all_states` is set, like `lsof -iTCP -sTCP:LISTEN`.
+    fn extract_port_info(&self, socket_info: &SocketFDInfo) -> Option<(u16, Protocol)> {
         let kind = socket_info.psi.soi_kind;
         if kind == SocketInfoKind::Tcp as i32 {
             let tcp = unsafe { socket_info.psi.soi_proto.pri_tcp };
             let port = u16::from_be(tcp.tcpsi_ini.insi_lport as u16);
-            if port > 0 {
+            if port > 0 && (self.all_states || tcp.tcpsi_state == TCPS_LISTEN) {
                 return Some((port, Protocol::Tcp));
             }
         } else if kind == SocketInfoKind::In as i32 {
-            // UDP sockets show up as SocketInfoKind::In
+            // UDP sockets show up as SocketInfoKind::In and have no state
             let inp = unsafe { socket_info.psi.soi_proto.pri_in };
             let port = u16::from_be(inp.insi_lport as u16);
             if port > 0 {
@@ -47,14 +91,35 @@ impl MacosScanner {
         None
     }
 
+    /// List every file descriptor of a process. The buffer is sized from
+    /// [`fd_count`], but descriptors can be opened between the two calls, so
+    /// a full buffer is retried with more room.
+    fn list_fds(pid: i32) -> Option<Vec<ProcFDInfo>> {
+        let count = fd_count(pid);
+        if count == 0 {
+            tracing::trace!(pid, "no descriptors, or none we can read");
+            return None;
+        }
+        let mut capacity = count + 32;
+        loop {
+            let fds = match listpidinfo::<ListFDs>(pid, capacity) {
+                Ok(fds) => fds,
+                Err(e) => {
+                    tracing::trace!(pid, error = %e, "fd table unreadable");
+                    return None;
+                }
+            };
+            if fds.len() < capacity || capacity >= MAX_FDS {
+                return Some(fds);
+            }
+            capacity = (capacity * 2).min(MAX_FDS);
+        }
+    }
+
     /// Iterate all file descriptors of a process and collect port bindings.
-    fn scan_process_fds(
-        pid: i32,
-        port_filter: Option<u16>,
-    ) -> Vec<(u16, Protocol)> {
-        let fds = match listpidinfo::<ListFDs>(pid, 256) {
-            Ok(fds) => fds,
-            Err(_) => return Vec::new(),
+    fn scan_process_fds(&self, pid: i32, port_filter: Option<u16>) -> Vec<(u16, Protocol)> {
+        let Some(fds) = Self::list_fds(pid) else {
+            return Vec::new();
         };
 
         let mut results = Vec::new();
@@ -69,7 +134,7 @@ impl MacosScanner {
                 Err(_) => continue,
             };
 
-            if let Some((port, protocol)) = Self::extract_port_info(&socket_info) {
+            if let Some((port, protocol)) = self.extract_port_info(&socket_info) {
                 if let Some(target) = port_filter {
                     if port == target {
                         results.push((port, protocol));
@@ -86,61 +151,200 @@ impl MacosScanner {
 }
 
 impl PortScanner for MacosScanner {
+    // Every PID is still visited: a port can have several owners (forked
+    // workers sharing a socket, SO_REUSEPORT), so the first hit isn't the end.
     fn find_processes_by_port(&self, target_port: u16) -> Result<Vec<ProcessInfo>> {
-        let pids = pids_by_type(ProcFilter::All)
-            .map_err(|e| KillportError::PlatformError(format!("failed to list PIDs: {e}")))?;
+        let pids = scannable_pids()?;
 
-        let mut results: HashMap<u32, ProcessInfo> = HashMap::new();
+        let found = scanner::scan_parallel(pids, |pid| {
+            self.scan_process_fds(pid as i32, Some(target_port))
+                .first()
+                .map(|&(port, protocol)| {
+                    tracing::trace!(pid, port, ?protocol, "process holds target socket");
+                    Self::get_process_info(pid as i32, port, protocol)
+                })
+        });
 
-        for pid in pids {
-            if pid == 0 {
-                continue;
-            }
+        let mut results: Vec<ProcessInfo> = found.into_iter().flatten().collect();
+        Self::resolve_users(&mut results);
+        tracing::debug!(owners = results.len(), "walked fd tables");
+        Ok(results)
+    }
 
-            let matches = Self::scan_process_fds(pid as i32, Some(target_port));
-            for (port, protocol) in matches {
-                let pid_u32 = pid;
-                results
-                    .entry(pid_u32)
-                    .or_insert_with(|| Self::get_process_info(pid as i32, port, protocol));
-            }
-        }
+    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
+        let pids = scannable_pids()?;
 
-        Ok(results.into_values().collect())
+        let found = scanner::scan_parallel(pids, |pid| {
+            let mut seen = HashSet::new();
+            self.scan_process_fds(pid as i32, None)
+                .into_iter()
+                .filter(|binding| seen.insert(*binding))
+                .map(|(port, protocol)| Self::get_process_info(pid as i32, port, protocol))
+                .collect::<Vec<_>>()
+        });
+
+        let mut results: Vec<ProcessInfo> = found.into_iter().flatten().collect();
+        Self::resolve_users(&mut results);
+        tracing::debug!(owners = results.len(), "walked fd tables");
+        // Sort by port, then PID
+        results.sort_by_key(|p| (p.port, p.pid));
+        Ok(results)
     }
+}
 
-    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
-        let pids = pids_by_type(ProcFilter::All)
-            .map_err(|e| KillportError::PlatformError(format!("failed to list PIDs: {e}")))?;
+/// The PIDs whose fd tables we're allowed to read. Without root that's only
+/// processes running as our own user: macOS refuses `PROC_PIDLISTFDS` for
+/// anyone else's, and asking each of the hundreds of system daemons just to
+/// be refused was most of a scan's time.
+fn scannable_pids() -> Result<Vec<u32>> {
+    let euid = nix::unistd::geteuid();
+    let filter = if euid.is_root() {
+        ProcFilter::All
+    } else {
+        ProcFilter::ByUID { uid: euid.as_raw() }
+    };
+    let pids = pids_by_type(filter)
+        .map_err(|e| KillportError::PlatformError(format!("failed to list PIDs: {e}")))?;
+    let pids: Vec<u32> = pids.into_iter().filter(|&pid| pid != 0).collect();
 
-        let mut results: Vec<ProcessInfo> = Vec::new();
-        let mut seen: HashMap<(u32, u16, u8), bool> = HashMap::new();
+    tracing::debug!(
+        pids = pids.len(),
+        root = euid.is_root(),
+        "listing file descriptors of every readable process"
+    );
+    Ok(pids)
+}
 
-        for pid in pids {
-            if pid == 0 {
-                continue;
-            }
+/// How many descriptors `PROC_PIDLISTFDS` would return, asked without a
+/// buffer. That's one cheap call, where listing the table means copying it
+/// out, so processes with nothing open are dropped before any real work.
+/// Zero also covers a process that exited or whose table we can't read.
+fn fd_count(pid: i32) -> usize {
+    let size = unsafe {
+        libc::proc_pidinfo(pid, libc::PROC_PIDLISTFDS, 0, std::ptr::null_mut(), 0)
+    };
+    usize::try_from(size).unwrap_or(0) / libc::PROC_PIDLISTFD_SIZE as usize
+}
 
-            let matches = Self::scan_process_fds(pid as i32, None);
-            for (port, protocol) in matches {
-                let key = (
-                    pid,
-                    port,
-                    match protocol {
-                        Protocol::Tcp => 0,
-                        Protocol::Udp => 1,
-                    },
-                );
-                if seen.contains_key(&key) {
-                    continue;
-                }
-                seen.insert(key, true);
-                results.push(Self::get_process_info(pid as i32, port, protocol));
-            }
+pub(super) fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    let info = pidinfo::<BSDInfo>(pid as i32, 0).ok()?;
+    Some(ProcessIdentity {
+        name: name(pid as i32).unwrap_or_else(|_| "<unknown>".into()),
+        started_at: start_time(&info),
+        uid: Some(info.pbi_uid),
+    })
+}
+
+fn start_time(info: &BSDInfo) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(
+        Duration::from_secs(info.pbi_start_tvsec) + Duration::from_micros(info.pbi_start_tvusec),
+    )
+}
+
+/// A process's working directory via `PROC_PIDVNODEPATHINFO`, which libproc
+/// doesn't wrap on macOS.
+fn process_cwd(pid: i32) -> Option<PathBuf> {
+    let mut info: libc::proc_vnodepathinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_vnodepathinfo>() as libc::c_int;
+    let ret = unsafe {
+        libc::proc_pidinfo(
+            pid,
+            libc::PROC_PIDVNODEPATHINFO,
+            0,
+            (&mut info as *mut libc::proc_vnodepathinfo).cast(),
+            size,
+        )
+    };
+    if ret != size {
+        return None;
+    }
+    // vip_path is MAXPATHLEN bytes, split into rows only for old compilers.
+    let path = &info.pvi_cdir.vip_path;
+    let bytes = unsafe {
+        std::slice::from_raw_parts(path.as_ptr().cast::<u8>(), std::mem::size_of_val(path))
+    };
+    let path = std::ffi::CStr::from_bytes_until_nul(bytes).ok()?;
+    if path.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(path.to_bytes())))
+}
+
+/// Count the processes we can see and whose fd tables we can read.
+pub fn probe_process_access() -> Result<ProcessAccess> {
+    let euid = nix::unistd::geteuid().as_raw();
+    let pids = pids_by_type(ProcFilter::All)
+        .map_err(|e| KillportError::PlatformError(format!("failed to list PIDs: {e}")))?;
+
+    let mut access = ProcessAccess::default();
+    for pid in pids.into_iter().filter(|&pid| pid != 0) {
+        access.visible += 1;
+        let Ok(info) = pidinfo::<BSDInfo>(pid as i32, 0) else {
+            continue;
+        };
+        if info.pbi_uid == euid {
+            continue;
+        }
+        access.other_users += 1;
+        if listpidinfo::<ListFDs>(pid as i32, info.pbi_nfiles as usize).is_err() {
+            access.unreadable += 1;
+        }
+    }
+    Ok(access)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_sockets_past_the_first_256_descriptors() {
+        // The default soft limit on macOS is 256, too low for this test.
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        unsafe {
+            libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit);
+            limit.rlim_cur = limit.rlim_max.min(4096);
+            libc::setrlimit(libc::RLIMIT_NOFILE, &limit);
         }
 
-        // Sort by port, then PID
-        results.sort_by_key(|p| (p.port, p.pid));
-        Ok(results)
+        let _files: Vec<_> = (0..300)
+            .map(|_| std::fs::File::open("/dev/null").unwrap())
+            .collect();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let found = MacosScanner::new().scan_process_fds(std::process::id() as i32, Some(port));
+        assert_eq!(found, [(port, Protocol::Tcp)]);
+    }
+
+    #[test]
+    fn fd_count_skips_processes_we_cannot_read() {
+        let _listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        assert!(fd_count(std::process::id() as i32) > 0);
+        // No such process
+        assert_eq!(fd_count(i32::MAX), 0);
+    }
+
+    #[test]
+    fn skips_outbound_connections() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let (_server_side, _) = listener.accept().unwrap();
+        let ephemeral = client.local_addr().unwrap().port();
+
+        let pid = std::process::id() as i32;
+        let scanner = MacosScanner::new();
+        assert!(scanner.scan_process_fds(pid, Some(ephemeral)).is_empty());
+        assert_eq!(
+            scanner.scan_process_fds(pid, Some(port)),
+            [(port, Protocol::Tcp)]
+        );
+
+        let all_states = MacosScanner { all_states: true };
+        assert!(!all_states.scan_process_fds(pid, Some(ephemeral)).is_empty());
     }
 }
@@ -4,5 +4,74 @@ pub mod macos;
 #[cfg(target_os = "linux")]
 pub mod linux;
 
+#[cfg(target_os = "linux")]
+mod netlink;
+
 #[cfg(target_os = "windows")]
 pub mod windows;
+
+/// How much of the process table the current user can inspect, for `portzap doctor`.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessAccess {
+    /// Processes visible to us at all
+    pub visible: usize,
+    /// Visible processes owned by other users
+    pub other_users: usize,
+    /// Processes owned by other users whose file descriptors we couldn't read
+    pub unreadable: usize,
+}
+
+/// What identifies a process beyond its PID, which the kernel hands out again
+/// once the process exits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessIdentity {
+    pub name: String,
+    pub started_at: Option<std::time::SystemTime>,
+    /// Owner, for callers that only have a PID
+    pub uid: Option<u32>,
+}
+
+/// Name and start time of a running process, or `None` if there is no
+/// process with this PID (or it can't be inspected).
+#[cfg(target_os = "linux")]
+pub fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    linux::process_identity(pid)
+}
+
+#[cfg(target_os = "macos")]
+pub fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    macos::process_identity(pid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn process_identity(_pid: u32) -> Option<ProcessIdentity> {
+    None
+}
+
+/// Resolve a uid to a login name, caching lookups for the duration of a scan.
+#[cfg(unix)]
+pub fn user_name(
+    uid: u32,
+    cache: &mut std::collections::HashMap<u32, Option<String>>,
+) -> Option<String> {
+    use nix::unistd::{Uid, User};
+
+    cache
+        .entry(uid)
+        .or_insert_with(|| {
+            User::from_uid(Uid::from_raw(uid))
+                .ok()
+                .flatten()
+                .map(|u| u.name)
+        })
+        .clone()
+}
+
+#[cfg(not(unix))]
+pub fn user_name(
+    _uid: u32,
+    _cache: &mut std::collections::HashMap<u32, Option<String>>,
+) -> Option<String> {
+    None
+}
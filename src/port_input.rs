@@ -0,0 +1,67 @@
+//! Port specs read from stdin (a `-` argument) or from `--from-file`.
+
+use crate::aliases;
+use crate::process::PortSpec;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+/// Read whitespace-separated port specs, ignoring blank lines and `#` comments.
+/// Errors name the source and line, e.g. `ports.txt:3: invalid port: 'abc'`.
+pub fn read_specs(
+    reader: impl BufRead,
+    source: &str,
+    aliases: &BTreeMap<String, String>,
+) -> Result<Vec<PortSpec>, String> {
+    let mut specs = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("{source}: {e}"))?;
+        let content = line.split('#').next().unwrap_or_default();
+        for token in content.split_whitespace() {
+            let resolved = aliases::resolve(token, aliases)
+                .map_err(|e| format!("{source}:{}: {e}", idx + 1))?;
+            specs.extend(resolved);
+        }
+    }
+    Ok(specs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let input = "# dev servers\n3000 3001\n\n  5432  # postgres\n8000-8002\n";
+        let specs = read_specs(input.as_bytes(), "ports.txt", &BTreeMap::new()).unwrap();
+        assert_eq!(
+            specs,
+            [
+                PortSpec::Single(3000),
+                PortSpec::Single(3001),
+                PortSpec::Single(5432),
+                PortSpec::range(8000, 8002),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_report_line_numbers() {
+        let err =
+            read_specs("3000\n\n70000\n".as_bytes(), "ports.txt", &BTreeMap::new()).unwrap_err();
+        assert!(err.starts_with("ports.txt:3: invalid port"), "{err}");
+    }
+
+    #[test]
+    fn resolves_aliases() {
+        let aliases = BTreeMap::from([("db".to_string(), "5432,6379".to_string())]);
+        let specs = read_specs("db\n".as_bytes(), "<stdin>", &aliases).unwrap();
+        assert_eq!(specs.len(), 2);
+    }
+
+    #[test]
+    fn empty_input_gives_no_specs() {
+        assert!(read_specs("".as_bytes(), "<stdin>", &BTreeMap::new())
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+use serde::Serialize;
+use std::fmt;
+
+/// IANA port ranges (RFC 6335).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PortRange {
+    /// 0-1023, binding usually requires root
+    WellKnown,
+    /// 1024-49151
+    Registered,
+    /// 49152-65535, the IANA suggestion for ephemeral ports
+    Dynamic,
+}
+
+impl PortRange {
+    pub fn of(port: u16) -> Self {
+        match port {
+            0..=1023 => PortRange::WellKnown,
+            1024..=49151 => PortRange::Registered,
+            _ => PortRange::Dynamic,
+        }
+    }
+
+    /// The name `--format json` uses.
+    pub fn name(self) -> &'static str {
+        match self {
+            PortRange::WellKnown => "well-known",
+            PortRange::Registered => "registered",
+            PortRange::Dynamic => "dynamic",
+        }
+    }
+
+    pub fn bounds(self) -> (u16, u16) {
+        match self {
+            PortRange::WellKnown => (0, 1023),
+            PortRange::Registered => (1024, 49151),
+            PortRange::Dynamic => (49152, 65535),
+        }
+    }
+}
+
+/// Ports below 1024. Whatever holds one is usually a system service, so
+/// kill only targets them when asked to explicitly.
+pub fn is_privileged(port: u16) -> bool {
+    PortRange::of(port) == PortRange::WellKnown
+}
+
+/// How a port number is coloured in `list` tables and the GUI's port column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortCategory {
+    /// One of the configured `highlight_ports`
+    Highlighted,
+    /// Below 1024
+    Privileged,
+    /// In the OS ephemeral range, so most likely auto-assigned
+    Ephemeral,
+    Other,
+}
+
+impl PortCategory {
+    /// `highlight` wins over the ranges, so a configured port stands out
+    /// even when it is privileged or ephemeral.
+    pub fn of(port: u16, highlight: &[u16], ephemeral: (u16, u16)) -> Self {
+        if highlight.contains(&port) {
+            PortCategory::Highlighted
+        } else if is_privileged(port) {
+            PortCategory::Privileged
+        } else if (ephemeral.0..=ephemeral.1).contains(&port) {
+            PortCategory::Ephemeral
+        } else {
+            PortCategory::Other
+        }
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (start, end) = self.bounds();
+        let name = match self {
+            PortRange::WellKnown => "well-known (privileged)",
+            PortRange::Registered => "registered",
+            PortRange::Dynamic => "dynamic/private",
+        };
+        write!(f, "{name}, {start}-{end}")
+    }
+}
+
+/// A service name a port is known by.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Service {
+    pub name: String,
+    /// Lowercase protocols the name is registered for, e.g. `["tcp", "udp"]`
+    pub protocols: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Well-known and common development ports, used to describe entries from
+/// /etc/services and as a fallback when it is missing or incomplete.
+const BUILTIN_SERVICES: &[(u16, &str, &str)] = &[
+    (20, "ftp-data", "FTP data transfer"),
+    (21, "ftp", "FTP control"),
+    (22, "ssh", "Secure Shell"),
+    (23, "telnet", "Telnet"),
+    (25, "smtp", "Mail transfer (SMTP)"),
+    (53, "domain", "DNS"),
+    (67, "bootps", "DHCP server"),
+    (68, "bootpc", "DHCP client"),
+    (80, "http", "HTTP"),
+    (110, "pop3", "POP3 mail"),
+    (123, "ntp", "Network Time Protocol"),
+    (137, "netbios-ns", "NetBIOS name service"),
+    (143, "imap", "IMAP mail"),
+    (161, "snmp", "SNMP"),
+    (389, "ldap", "LDAP"),
+    (443, "https", "HTTPS"),
+    (445, "microsoft-ds", "SMB file sharing"),
+    (465, "submissions", "SMTP over TLS"),
+    (514, "syslog", "Syslog"),
+    (548, "afp", "Apple Filing Protocol"),
+    (587, "submission", "Mail submission"),
+    (631, "ipp", "Internet Printing Protocol (CUPS)"),
+    (993, "imaps", "IMAP over TLS"),
+    (995, "pop3s", "POP3 over TLS"),
+    (1433, "ms-sql-s", "Microsoft SQL Server"),
+    (1883, "mqtt", "MQTT broker"),
+    (2375, "docker", "Docker API (plain)"),
+    (2376, "docker-s", "Docker API (TLS)"),
+    (
+        3000,
+        "dev-server",
+        "Common dev server (Node, Rails, Grafana)",
+    ),
+    (3306, "mysql", "MySQL / MariaDB"),
+    (3389, "ms-wbt-server", "Remote Desktop (RDP)"),
+    (4200, "angular", "Angular dev server"),
+    (
+        5000,
+        "dev-server",
+        "Common dev server (Flask); AirPlay receiver on macOS",
+    ),
+    (5173, "vite", "Vite dev server"),
+    (5353, "mdns", "Multicast DNS (Bonjour/Avahi)"),
+    (5432, "postgresql", "PostgreSQL"),
+    (5672, "amqp", "RabbitMQ (AMQP)"),
+    (5900, "rfb", "VNC / Screen Sharing"),
+    (6379, "redis", "Redis"),
+    (6443, "kube-apiserver", "Kubernetes API server"),
+    (7000, "airplay", "AirPlay receiver on macOS"),
+    (8000, "http-alt", "Common dev server (Django, http.server)"),
+    (8080, "http-alt", "Alternate HTTP, proxies and dev servers"),
+    (8443, "https-alt", "Alternate HTTPS"),
+    (8888, "jupyter", "Jupyter Notebook"),
+    (9000, "dev-server", "Common dev server (PHP-FPM, SonarQube)"),
+    (9090, "prometheus", "Prometheus"),
+    (9200, "elasticsearch", "Elasticsearch"),
+    (11211, "memcache", "Memcached"),
+    (27017, "mongodb", "MongoDB"),
+];
+
+/// Service names for `port`, from /etc/services with the built-in table as
+/// description source and fallback.
+pub fn services_for(port: u16) -> Vec<Service> {
+    let contents = std::fs::read_to_string(services_path()).unwrap_or_default();
+    let mut services = parse_services(&contents, port);
+    let builtin = BUILTIN_SERVICES.iter().find(|(p, _, _)| *p == port);
+
+    if let Some((_, name, description)) = builtin {
+        match services.first_mut() {
+            Some(first) => first.description = Some(description.to_string()),
+            None => services.push(Service {
+                name: name.to_string(),
+                protocols: Vec::new(),
+                description: Some(description.to_string()),
+            }),
+        }
+    }
+    services
+}
+
+fn services_path() -> &'static str {
+    if cfg!(windows) {
+        r"C:\Windows\System32\drivers\etc\services"
+    } else {
+        "/etc/services"
+    }
+}
+
+/// Parse `name port/proto [aliases...] [# comment]` lines, merging protocols per name.
+fn parse_services(contents: &str, port: u16) -> Vec<Service> {
+    let mut services: Vec<Service> = Vec::new();
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let (Some(name), Some(port_proto)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some((p, proto)) = port_proto.split_once('/') else {
+            continue;
+        };
+        if p.parse::<u16>() != Ok(port) {
+            continue;
+        }
+        let proto = proto.to_lowercase();
+        match services.iter_mut().find(|s| s.name == name) {
+            Some(existing) if !existing.protocols.contains(&proto) => {
+                existing.protocols.push(proto)
+            }
+            Some(_) => {}
+            None => services.push(Service {
+                name: name.to_string(),
+                protocols: vec![proto],
+                description: None,
+            }),
+        }
+    }
+    services
+}
+
+/// What the `ephemeral` port keyword stands for: the OS range, or the IANA
+/// dynamic range when the OS one can't be read. The label says which.
+pub fn ephemeral_or_default() -> ((u16, u16), &'static str) {
+    let (range, source) = match ephemeral_range() {
+        Some(range) => (range, "system ephemeral range"),
+        None => (PortRange::Dynamic.bounds(), "IANA default"),
+    };
+    tracing::debug!(start = range.0, end = range.1, source, "resolved ephemeral");
+    (range, source)
+}
+
+/// The range the OS picks ephemeral (outgoing/auto-assigned) ports from.
+#[cfg(target_os = "linux")]
+pub fn ephemeral_range() -> Option<(u16, u16)> {
+    let raw = std::fs::read_to_string("/proc/sys/net/ipv4/ip_local_port_range").ok()?;
+    let mut fields = raw.split_whitespace().map(str::parse::<u16>);
+    Some((fields.next()?.ok()?, fields.next()?.ok()?))
+}
+
+#[cfg(target_os = "macos")]
+pub fn ephemeral_range() -> Option<(u16, u16)> {
+    fn sysctl_int(name: &str) -> Option<u16> {
+        let name = std::ffi::CString::new(name).ok()?;
+        let mut value: libc::c_int = 0;
+        let mut len = std::mem::size_of::<libc::c_int>();
+        let rc = unsafe {
+            libc::sysctlbyname(
+                name.as_ptr(),
+                &mut value as *mut _ as *mut libc::c_void,
+                &mut len,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if rc == 0 {
+            u16::try_from(value).ok()
+        } else {
+            None
+        }
+    }
+    Some((
+        sysctl_int("net.inet.ip.portrange.first")?,
+        sysctl_int("net.inet.ip.portrange.last")?,
+    ))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn ephemeral_range() -> Option<(u16, u16)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICES: &str = "\
+# comment line
+domain\t\t53/tcp\t\t\t\t# Domain Name Server
+domain\t\t53/udp
+mdns\t\t5353/udp\t\t\t# Multicast DNS
+http\t\t80/tcp\t\twww\t\t# WorldWideWeb HTTP
+";
+
+    #[test]
+    fn categorizes_ports_for_colouring() {
+        let linux = (32768, 60999);
+        assert_eq!(PortCategory::of(22, &[], linux), PortCategory::Privileged);
+        assert_eq!(PortCategory::of(3000, &[], linux), PortCategory::Other);
+        assert_eq!(PortCategory::of(40000, &[], linux), PortCategory::Ephemeral);
+        assert_eq!(PortCategory::of(61000, &[], linux), PortCategory::Other);
+        for port in [80, 3000, 40000] {
+            assert_eq!(
+                PortCategory::of(port, &[80, 3000, 40000], linux),
+                PortCategory::Highlighted
+            );
+        }
+    }
+
+    #[test]
+    fn range_boundaries() {
+        assert_eq!(PortRange::of(0), PortRange::WellKnown);
+        assert_eq!(PortRange::of(1023), PortRange::WellKnown);
+        assert_eq!(PortRange::of(1024), PortRange::Registered);
+        assert_eq!(PortRange::of(49151), PortRange::Registered);
+        assert_eq!(PortRange::of(49152), PortRange::Dynamic);
+        assert_eq!(PortRange::of(65535), PortRange::Dynamic);
+    }
+
+    #[test]
+    fn range_names_match_json() {
+        for range in [PortRange::WellKnown, PortRange::Registered, PortRange::Dynamic] {
+            assert_eq!(serde_json::to_value(range).unwrap(), range.name());
+        }
+    }
+
+    #[test]
+    fn parse_services_merges_protocols() {
+        let services = parse_services(SERVICES, 53);
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].name, "domain");
+        assert_eq!(services[0].protocols, ["tcp", "udp"]);
+        assert_eq!(parse_services(SERVICES, 5353)[0].protocols, ["udp"]);
+        assert!(parse_services(SERVICES, 5354).is_empty());
+    }
+
+    #[test]
+    fn builtin_table_is_sorted_and_unique() {
+        assert!(BUILTIN_SERVICES.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
@@ -1,8 +1,14 @@
 use crate::errors::{KillportError, Result};
-use serde::Serialize;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashSet;
 use std::fmt;
+use std::iter::StepBy;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Protocol {
     Tcp,
@@ -28,6 +34,253 @@ pub struct ProcessInfo {
     pub command: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    /// Parent process ID; `None` for PID 1, kernel threads, or when unreadable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ppid: Option<u32>,
+    /// Name of the parent process, if it is still running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_name: Option<String>,
+    /// When the process started, serialized as RFC 3339
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_time"
+    )]
+    pub started_at: Option<SystemTime>,
+    /// Working directory, when we're allowed to read it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// The Docker container behind a port-forwarding proxy process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<Container>,
+    /// The service manager that owns this socket and would re-create it if killed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub managed_by: Option<ManagedBy>,
+    /// The SSH target this process was found on, for `--host` scans
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Set for Windows-side listeners found with `--include-windows` under WSL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
+}
+
+impl ProcessInfo {
+    /// How long the process has been running. A start time in the future
+    /// (the clock was set back) counts as just started.
+    pub fn age(&self) -> Option<Duration> {
+        self.started_at
+            .map(|t| SystemTime::now().duration_since(t).unwrap_or_default())
+    }
+
+    /// What identifies a row across scans: the same process on the same socket.
+    pub fn key(&self) -> (u32, u16, Protocol) {
+        (self.pid, self.port, self.protocol)
+    }
+
+    /// Whether this row is the running portzap. PIDs from another host or
+    /// the Windows side can't be.
+    pub fn is_self(&self) -> bool {
+        self.pid == std::process::id() && self.host.is_none() && self.origin.is_none()
+    }
+
+    /// The name to show people: "node (server.js)" when an interpreter runs
+    /// a script, otherwise just `name`, which JSON keeps as it is.
+    pub fn display_name(&self) -> String {
+        match self
+            .command
+            .as_deref()
+            .and_then(|command| script_name(&self.name, command))
+        {
+            Some(script) => format!("{} ({script})", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// What the interpreter `name` is running, going by its command line: the
+/// script's file name ("server.js"), a module run with `python -m`, or for
+/// package managers and app servers what they were asked to run ("pnpm dev",
+/// "uvicorn main:app"). `None` when `name` isn't an interpreter, or it runs
+/// inline code (`-e`, `-c`) or nothing at all.
+pub fn script_name(name: &str, command: &str) -> Option<String> {
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    let family = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    // Flags followed by inline code, and flags that take a separate value
+    let (code_flags, value_flags): (&[&str], &[&str]) = match family {
+        "node" | "nodejs" | "bun" | "deno" => (
+            &["-e", "--eval", "-p", "--print"],
+            &[
+                "-r",
+                "--require",
+                "--import",
+                "--loader",
+                "-C",
+                "--conditions",
+            ],
+        ),
+        "python" | "pypy" => (&["-c"], &["-W", "-X"]),
+        "ruby" => (&["-e"], &["-I", "-r", "-C"]),
+        "perl" => (&["-e", "-E"], &["-I"]),
+        "php" => (&["-r"], &["-c", "-d", "-S", "-t"]),
+        "java" => (
+            &[],
+            &["-cp", "-classpath", "--class-path", "-p", "--module-path"],
+        ),
+        _ => return None,
+    };
+
+    let mut words = command.split_whitespace().skip(1);
+    let mut subcommand_skipped = false;
+    while let Some(word) = words.next() {
+        if code_flags.contains(&word) {
+            return None;
+        }
+        if value_flags.contains(&word) {
+            words.next();
+        } else if word == "-m" && matches!(family, "python" | "pypy") {
+            let module = words.next()?;
+            return Some(with_arguments(module, words));
+        } else if word.starts_with('-') {
+            continue;
+        } else if matches!(family, "bun" | "deno") && word == "run" && !subcommand_skipped {
+            subcommand_skipped = true;
+        } else {
+            let script = word.rsplit(['/', '\\']).next().unwrap_or(word);
+            return Some(with_arguments(script, words));
+        }
+    }
+    None
+}
+
+/// `script`, plus the arguments that say what it runs when it's a package
+/// manager ("npm exec serve") or an app server ("gunicorn app:wsgi").
+fn with_arguments<'a>(script: &str, rest: impl Iterator<Item = &'a str>) -> String {
+    let script = match script {
+        "npm-cli.js" => "npm",
+        "npx-cli.js" => "npx",
+        "pnpm.cjs" | "pnpm.js" => "pnpm",
+        "yarn.js" | "yarn.cjs" => "yarn",
+        other => other,
+    };
+    let mut args = rest.filter(|word| !word.starts_with('-'));
+    let mut shown = vec![script];
+    match script {
+        "npm" | "npx" | "pnpm" | "yarn" => {
+            if let Some(first) = args.next() {
+                shown.push(first);
+                if matches!(first, "exec" | "run" | "run-script" | "dlx" | "x") {
+                    shown.extend(args.next());
+                }
+            }
+        }
+        "uvicorn" | "gunicorn" | "hypercorn" | "daphne" => {
+            // The app is `module:attribute`, unlike a `host:port` value
+            shown.extend(args.find(|word| {
+                word.split_once(':').is_some_and(|(module, attr)| {
+                    !module.is_empty() && attr.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                })
+            }));
+        }
+        _ => {}
+    }
+    shown.join(" ")
+}
+
+fn serialize_time<S: Serializer>(
+    time: &Option<SystemTime>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match time {
+        Some(t) => serializer
+            .serialize_str(&DateTime::<Utc>::from(*t).to_rfc3339_opts(SecondsFormat::Secs, true)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Where a process lives when it isn't an ordinary process on this machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    /// The Windows host of a WSL distribution; killed with `taskkill.exe`
+    Windows,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Windows => write!(f, "windows"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceManager {
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    Systemd,
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Launchd,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManagedBy {
+    pub manager: ServiceManager,
+    /// Unit or job label, e.g. `cups.socket` or `com.apple.AirPlayXPCHelper`
+    pub unit: String,
+    /// launchd domain the job lives in (`system`, `gui/501`), when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+}
+
+impl ManagedBy {
+    /// The command that actually frees the port, if we know enough to build it.
+    pub fn stop_command(&self) -> Option<String> {
+        match (self.manager, &self.domain) {
+            (ServiceManager::Systemd, _) => Some(format!("systemctl stop {}", self.unit)),
+            (ServiceManager::Launchd, Some(domain)) => {
+                let sudo = if domain == "system" { "sudo " } else { "" };
+                Some(format!("{sudo}launchctl bootout {domain}/{}", self.unit))
+            }
+            (ServiceManager::Launchd, None) => None,
+        }
+    }
+
+    /// Why signalling the listener on `port` won't free it.
+    pub fn explain(&self, port: u16) -> String {
+        let reason = match self.manager {
+            ServiceManager::Systemd => format!("port {port} is socket-activated by {}", self.unit),
+            ServiceManager::Launchd => {
+                format!(
+                    "port {port} is owned by launchd job {}, which restarts it",
+                    self.unit
+                )
+            }
+        };
+        match self.stop_command() {
+            Some(command) => format!("{reason} — use `{command}` (or --ignore-manager to signal anyway)"),
+            None => format!(
+                "{reason} — find the job with `launchctl list` (or --ignore-manager to signal anyway)"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ManagedBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.manager {
+            ServiceManager::Systemd => write!(f, "systemd: {}", self.unit),
+            ServiceManager::Launchd => write!(f, "launchd: {}", self.unit),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Container {
+    /// Short (12 character) container ID
+    pub id: String,
+    pub name: String,
+    pub image: String,
 }
 
 impl fmt::Display for ProcessInfo {
@@ -40,6 +293,64 @@ impl fmt::Display for ProcessInfo {
     }
 }
 
+/// A process with every port it holds, for `list --group-by process`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessGroup {
+    pub pid: u32,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub ports: Vec<PortBinding>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PortBinding {
+    pub port: u16,
+    pub protocol: Protocol,
+}
+
+impl ProcessGroup {
+    /// One group per PID, in the order each PID first appears.
+    pub fn group(processes: &[ProcessInfo]) -> Vec<ProcessGroup> {
+        let mut groups: Vec<ProcessGroup> = Vec::new();
+        for p in processes {
+            let binding = PortBinding {
+                port: p.port,
+                protocol: p.protocol,
+            };
+            match groups.iter_mut().find(|g| g.pid == p.pid) {
+                Some(group) if group.ports.contains(&binding) => {}
+                Some(group) => {
+                    group.ports.push(binding);
+                    group.count += 1;
+                }
+                None => groups.push(ProcessGroup {
+                    pid: p.pid,
+                    name: p.name.clone(),
+                    command: p.command.clone(),
+                    user: p.user.clone(),
+                    ports: vec![binding],
+                    count: 1,
+                }),
+            }
+        }
+        groups
+    }
+
+    /// "TCP", "UDP" or "TCP/UDP".
+    pub fn protocols(&self) -> String {
+        let has = |protocol| self.ports.iter().any(|b| b.protocol == protocol);
+        match (has(Protocol::Tcp), has(Protocol::Udp)) {
+            (true, true) => "TCP/UDP".into(),
+            (false, true) => Protocol::Udp.to_string(),
+            _ => Protocol::Tcp.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KillSignal {
     Term,
@@ -65,6 +376,9 @@ pub enum WaitCondition {
     Free,
     /// Wait until the port is occupied (a process is listening)
     Occupied,
+    /// Wait until the port goes free and is then occupied again. With
+    /// `require_down`, a port that is free at the start doesn't count as down.
+    Restart { require_down: bool },
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -72,18 +386,69 @@ pub struct KillResult {
     pub process: ProcessInfo,
     pub success: bool,
     pub signal_sent: String,
+    /// Human-readable reason, for output
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// What went wrong, for callers that react to it. Set to `ProcessGone`
+    /// on success when the process had already exited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<KillErrorKind>,
+    /// What was signalled when it wasn't just the PID, e.g. `pgid 4321`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// The `watch --rule` that chose the signal, e.g. `80:kill`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+/// Stable classification of kill errors, serialized as `error_kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillErrorKind {
+    PermissionDenied,
+    ProcessGone,
+    ServiceManaged,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PortSpec {
     Single(u16),
-    Range(u16, u16),
+    /// `start-end`, or every `step`th port of it with `start-end:step`
+    Range {
+        start: u16,
+        end: u16,
+        step: u16,
+    },
 }
 
 impl PortSpec {
+    /// Every port from `start` to `end`.
+    pub fn range(start: u16, end: u16) -> Self {
+        PortSpec::Range {
+            start,
+            end,
+            step: 1,
+        }
+    }
+
     pub fn parse(s: &str) -> Result<Self> {
+        if let Some((range, step)) = s.split_once(':') {
+            let step: u16 = step
+                .parse()
+                .map_err(|_| KillportError::InvalidPortRange(s.to_string()))?;
+            if step == 0 {
+                return Err(KillportError::InvalidPortRange(format!(
+                    "step 0 is not valid: {s}"
+                )));
+            }
+            return match Self::parse(range)? {
+                PortSpec::Range { start, end, .. } => Ok(PortSpec::Range { start, end, step }),
+                PortSpec::Single(_) => Err(KillportError::InvalidPortRange(format!(
+                    "a step needs a range: {s}"
+                ))),
+            };
+        }
         if let Some((start_str, end_str)) = s.split_once('-') {
             let start: u16 = start_str
                 .parse()
@@ -101,7 +466,7 @@ impl PortSpec {
                     "port 0 is not valid".to_string(),
                 ));
             }
-            Ok(PortSpec::Range(start, end))
+            Ok(PortSpec::range(start, end))
         } else {
             let n: u32 = s.parse().map_err(|_| KillportError::InvalidPort(0))?;
             if n == 0 || n > 65535 {
@@ -111,10 +476,52 @@ impl PortSpec {
         }
     }
 
-    pub fn expand(&self) -> Vec<u16> {
-        match self {
-            PortSpec::Single(p) => vec![*p],
-            PortSpec::Range(start, end) => (*start..=*end).collect(),
+    pub fn contains(&self, port: u16) -> bool {
+        match *self {
+            PortSpec::Single(p) => p == port,
+            PortSpec::Range { start, end, step } => {
+                (start..=end).contains(&port) && (port - start) % step == 0
+            }
+        }
+    }
+
+    /// Every port in the spec, without materializing wide ranges.
+    pub fn expand(&self) -> StepBy<RangeInclusive<u16>> {
+        match *self {
+            PortSpec::Single(p) => (p..=p).step_by(1),
+            PortSpec::Range { start, end, step } => (start..=end).step_by(usize::from(step)),
+        }
+    }
+
+    pub fn port_count(&self) -> usize {
+        match *self {
+            PortSpec::Single(_) => 1,
+            PortSpec::Range { start, end, step } => usize::from((end - start) / step) + 1,
+        }
+    }
+}
+
+/// Every port `specs` cover, once each, in the order given: `3000 3000-3001`
+/// is 3000 then 3001.
+pub fn unique_ports(specs: &[PortSpec]) -> Vec<u16> {
+    let mut seen = HashSet::new();
+    specs
+        .iter()
+        .flat_map(PortSpec::expand)
+        .filter(|port| seen.insert(*port))
+        .collect()
+}
+
+impl fmt::Display for PortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PortSpec::Single(p) => write!(f, "{p}"),
+            PortSpec::Range {
+                start,
+                end,
+                step: 1,
+            } => write!(f, "{start}-{end}"),
+            PortSpec::Range { start, end, step } => write!(f, "{start}-{end}:{step}"),
         }
     }
 }
@@ -122,19 +529,188 @@ impl PortSpec {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::scanner::mock::process;
+
+    #[test]
+    fn script_names_for_interpreters() {
+        let cases = [
+            ("node", "node server.js", Some("server.js")),
+            (
+                "node",
+                "/usr/bin/node --inspect=9229 -r ts-node/register src/app.ts",
+                Some("app.ts"),
+            ),
+            (
+                "node",
+                "node /app/node_modules/.bin/vite --port 5173",
+                Some("vite"),
+            ),
+            (
+                "node",
+                "node /usr/lib/node_modules/npm/bin/npm-cli.js exec serve",
+                Some("npm exec serve"),
+            ),
+            (
+                "node",
+                "node /home/me/.local/share/pnpm/pnpm dev",
+                Some("pnpm dev"),
+            ),
+            (
+                "node",
+                "node -e require('http').createServer().listen(3000)",
+                None,
+            ),
+            ("node", "node", None),
+            (
+                "node.exe",
+                r"C:\node\node.exe C:\app\server.js",
+                Some("server.js"),
+            ),
+            ("deno", "deno run --allow-net server.ts", Some("server.ts")),
+            ("bun", "bun run dev", Some("dev")),
+            (
+                "python3",
+                "python3 manage.py runserver 0.0.0.0:8000",
+                Some("manage.py"),
+            ),
+            (
+                "python3.11",
+                "/usr/bin/python3.11 -u -m http.server 8000",
+                Some("http.server"),
+            ),
+            (
+                "python3",
+                "python3 -m uvicorn main:app --reload",
+                Some("uvicorn main:app"),
+            ),
+            (
+                "python3",
+                "/venv/bin/python3 /venv/bin/gunicorn --bind 0.0.0.0:8000 -w 4 site.wsgi:app",
+                Some("gunicorn site.wsgi:app"),
+            ),
+            ("python", "python -c import http.server", None),
+            ("ruby", "ruby -I lib bin/rails server", Some("rails")),
+            (
+                "java",
+                "java -Xmx1g -cp lib/all.jar -jar build/app.jar",
+                Some("app.jar"),
+            ),
+            ("php", "php -S localhost:8000 -t public", None),
+            ("nginx", "nginx: master process /usr/sbin/nginx", None),
+            ("nodemon", "node /usr/bin/nodemon app.js", None),
+        ];
+        for (name, command, expected) in cases {
+            assert_eq!(
+                script_name(name, command).as_deref(),
+                expected,
+                "{name}: {command}"
+            );
+        }
+    }
+
+    #[test]
+    fn display_name_keeps_the_name_first() {
+        let mut p = listener(1, 3000, Protocol::Tcp);
+        p.name = "node".into();
+        assert_eq!(p.display_name(), "node");
+        p.command = Some("node server.js".into());
+        assert_eq!(p.display_name(), "node (server.js)");
+        assert_eq!(p.name, "node");
+    }
+
+    fn listener(pid: u32, port: u16, protocol: Protocol) -> ProcessInfo {
+        ProcessInfo {
+            protocol,
+            ..process(pid, &format!("proc{pid}"), port)
+        }
+    }
+
+    #[test]
+    fn groups_ports_by_pid_in_first_seen_order() {
+        let groups = ProcessGroup::group(&[
+            listener(7, 5000, Protocol::Tcp),
+            listener(3, 80, Protocol::Tcp),
+            listener(7, 5001, Protocol::Udp),
+            listener(7, 5000, Protocol::Tcp),
+        ]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!((groups[0].pid, groups[0].count), (7, 2));
+        assert_eq!(groups[0].protocols(), "TCP/UDP");
+        assert_eq!((groups[1].pid, groups[1].count), (3, 1));
+        assert_eq!(groups[1].protocols(), "TCP");
+    }
 
     #[test]
     fn parse_single_port() {
         let spec = PortSpec::parse("3000").unwrap();
         assert!(matches!(spec, PortSpec::Single(3000)));
-        assert_eq!(spec.expand(), vec![3000]);
+        assert_eq!(spec.expand().collect::<Vec<_>>(), vec![3000]);
     }
 
     #[test]
     fn parse_port_range() {
         let spec = PortSpec::parse("3000-3005").unwrap();
-        assert!(matches!(spec, PortSpec::Range(3000, 3005)));
-        assert_eq!(spec.expand(), vec![3000, 3001, 3002, 3003, 3004, 3005]);
+        assert_eq!(spec, PortSpec::range(3000, 3005));
+        assert_eq!(
+            spec.expand().collect::<Vec<_>>(),
+            vec![3000, 3001, 3002, 3003, 3004, 3005]
+        );
+        assert_eq!(spec.port_count(), 6);
+        assert_eq!(PortSpec::range(1, 65535).port_count(), 65535);
+    }
+
+    #[test]
+    fn parse_stepped_range() {
+        let spec = PortSpec::parse("3000-3010:2").unwrap();
+        assert_eq!(
+            spec,
+            PortSpec::Range {
+                start: 3000,
+                end: 3010,
+                step: 2
+            }
+        );
+        assert_eq!(
+            spec.expand().collect::<Vec<_>>(),
+            vec![3000, 3002, 3004, 3006, 3008, 3010]
+        );
+        assert_eq!(spec.port_count(), 6);
+        assert!(spec.contains(3004));
+        assert!(!spec.contains(3005));
+        assert!(!spec.contains(3012));
+        assert_eq!(spec.to_string(), "3000-3010:2");
+
+        // The end is only included when the step lands on it.
+        let spec = PortSpec::parse("3000-3010:3").unwrap();
+        assert_eq!(
+            spec.expand().collect::<Vec<_>>(),
+            vec![3000, 3003, 3006, 3009]
+        );
+        assert_eq!(spec.port_count(), 4);
+        assert!(!spec.contains(3010));
+        assert_eq!(PortSpec::parse("1-65535:1").unwrap().to_string(), "1-65535");
+    }
+
+    #[test]
+    fn step_larger_than_range_yields_start() {
+        let spec = PortSpec::parse("3000-3005:100").unwrap();
+        assert_eq!(spec.expand().collect::<Vec<_>>(), vec![3000]);
+        assert_eq!(spec.port_count(), 1);
+    }
+
+    #[test]
+    fn parse_invalid_steps() {
+        assert!(PortSpec::parse("3000-3010:0")
+            .unwrap_err()
+            .to_string()
+            .contains("step 0"));
+        assert!(PortSpec::parse("3000:2")
+            .unwrap_err()
+            .to_string()
+            .contains("needs a range"));
+        assert!(PortSpec::parse("3000-3010:x").is_err());
+        assert!(PortSpec::parse("3000-3010:2:2").is_err());
+        assert!(PortSpec::parse("3000-3010:70000").is_err());
     }
 
     #[test]
@@ -0,0 +1,106 @@
+//! A progress bar on stderr for searches that probe ports one at a time,
+//! like `free` over a wide range. It only appears on a terminal, and is
+//! erased before anything else is printed so it never mixes with results.
+
+use signal_hook::SigId;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Searches shorter than this finish before a bar would be worth drawing.
+pub const MIN_PORTS: usize = 200;
+
+const WIDTH: usize = 30;
+const REDRAW_EVERY: Duration = Duration::from_millis(50);
+
+pub struct Progress {
+    total: usize,
+    done: usize,
+    last_draw: Option<Instant>,
+    interrupted: Arc<AtomicBool>,
+    handler: Option<SigId>,
+}
+
+impl Progress {
+    /// A bar for probing `total` ports, or `None` when it wouldn't help:
+    /// stderr isn't a terminal, `quiet` is set, or there are few ports.
+    pub fn start(total: usize, quiet: bool) -> Option<Self> {
+        if quiet || total < MIN_PORTS || !std::io::stderr().is_terminal() {
+            return None;
+        }
+        // Ctrl+C is handled between probes so the bar can be erased first
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let handler =
+            signal_hook::flag::register(signal_hook::consts::SIGINT, interrupted.clone()).ok();
+        Some(Self {
+            total,
+            done: 0,
+            last_draw: None,
+            interrupted,
+            handler,
+        })
+    }
+
+    /// Note that `port` is probed next. Exits with status 130 after erasing
+    /// the bar if Ctrl+C was pressed.
+    pub fn tick(&mut self, port: u16) {
+        if self.interrupted.load(Ordering::Relaxed) {
+            self.clear();
+            std::process::exit(130);
+        }
+        if !self.last_draw.is_some_and(|at| at.elapsed() < REDRAW_EVERY) {
+            self.last_draw = Some(Instant::now());
+            eprint!(
+                "\r\x1b[2K{} {}/{} ports, trying {port}",
+                bar(self.done, self.total),
+                self.done,
+                self.total
+            );
+            let _ = std::io::stderr().flush();
+        }
+        self.done += 1;
+    }
+
+    fn clear(&self) {
+        if self.last_draw.is_some() {
+            eprint!("\r\x1b[2K");
+            let _ = std::io::stderr().flush();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.clear();
+        if let Some(handler) = self.handler.take() {
+            signal_hook::low_level::unregister(handler);
+        }
+    }
+}
+
+/// `[=====>      ]` for `done` out of `total`.
+fn bar(done: usize, total: usize) -> String {
+    let filled = (done * WIDTH / total.max(1)).min(WIDTH);
+    let head = if filled < WIDTH { ">" } else { "" };
+    format!(
+        "[{}{head}{}]",
+        "=".repeat(filled),
+        " ".repeat(WIDTH - filled - head.len())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bar_fills_in_proportion() {
+        assert_eq!(bar(0, 100), format!("[>{}]", " ".repeat(WIDTH - 1)));
+        assert_eq!(
+            bar(50, 100),
+            format!("[{}>{}]", "=".repeat(15), " ".repeat(14))
+        );
+        assert_eq!(bar(100, 100), format!("[{}]", "=".repeat(WIDTH)));
+    }
+}
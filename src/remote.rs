@@ -0,0 +1,314 @@
+//! `--host`: scan another machine over SSH. The remote side runs
+//! `portzap list --format json` when portzap is installed there and
+//! `ss -tulpnH` otherwise; either way the rows come back as ordinary
+//! `ProcessInfo`s tagged with the host, so output works unchanged.
+
+use crate::errors::{KillportError, Result};
+use crate::process::{PortSpec, ProcessInfo, Protocol};
+use crate::scanner::{PortScanner, ScanResult, ScanWarning};
+use chrono::DateTime;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+/// An SSH destination and extra `ssh -o` options, e.g. `ProxyJump=bastion`.
+#[derive(Debug, Clone)]
+pub struct RemoteHost {
+    pub target: String,
+    pub ssh_options: Vec<String>,
+}
+
+/// Exit status of `REMOTE_SCRIPT` when the host has neither tool.
+const NO_TOOL: i32 = 69;
+
+/// Picks a tool on the remote side and names it on the first line of output.
+const REMOTE_SCRIPT: &str =
+    "if command -v portzap >/dev/null 2>&1; then echo portzap; exec portzap list --format json; \
+     elif command -v ss >/dev/null 2>&1; then echo ss; exec ss -tulpnH; \
+     else exit 69; fi";
+
+pub struct RemoteScanner {
+    host: RemoteHost,
+}
+
+impl RemoteScanner {
+    pub fn new(host: RemoteHost) -> Self {
+        Self { host }
+    }
+
+    fn run(&self) -> Result<String> {
+        let target = &self.host.target;
+        let _span = tracing::debug_span!("ssh", host = %target).entered();
+        let output = Command::new("ssh")
+            .args(
+                self.host
+                    .ssh_options
+                    .iter()
+                    .flat_map(|o| ["-o", o.as_str()]),
+            )
+            .arg("--")
+            .arg(target)
+            .arg(REMOTE_SCRIPT)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    KillportError::Remote("--host needs ssh, which was not found on PATH".into())
+                }
+                _ => KillportError::Remote(format!("could not run ssh: {e}")),
+            })?;
+        tracing::debug!(status = ?output.status.code(), bytes = output.stdout.len(), "ssh finished");
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        match output.status.code() {
+            Some(0) => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+            // ssh itself failed: unknown host, refused, authentication.
+            Some(255) => Err(KillportError::Remote(format!(
+                "could not connect to {target}: {}",
+                stderr
+                    .lines()
+                    .last()
+                    .unwrap_or("ssh exited with status 255")
+            ))),
+            Some(NO_TOOL) => Err(KillportError::Remote(format!(
+                "cannot scan {target}: neither portzap nor ss is installed there"
+            ))),
+            _ if stderr.is_empty() => Err(KillportError::Remote(format!(
+                "scanning {target} failed ({})",
+                output.status
+            ))),
+            _ => Err(KillportError::Remote(format!(
+                "scanning {target} failed: {stderr}"
+            ))),
+        }
+    }
+}
+
+impl PortScanner for RemoteScanner {
+    fn find_processes_by_port(&self, port: u16) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_port(port)?.processes)
+    }
+
+    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_all()?.processes)
+    }
+
+    /// The remote tools can't look at one port, so this is a whole scan
+    /// filtered down, the same as for several ports at once.
+    fn scan_port(&self, port: u16) -> Result<ScanResult> {
+        self.scan_ports(&[PortSpec::Single(port)])
+    }
+
+    fn scan_all(&self) -> Result<ScanResult> {
+        let stdout = self.run()?;
+        parse_output(&stdout, &self.host.target).map_err(KillportError::Remote)
+    }
+}
+
+/// Split the tool marker from its output and parse the rest.
+fn parse_output(stdout: &str, target: &str) -> std::result::Result<ScanResult, String> {
+    let (tool, body) = stdout.split_once('\n').unwrap_or((stdout, ""));
+    let mut scan = match tool.trim() {
+        "portzap" => parse_portzap_json(body)
+            .map_err(|e| format!("could not parse portzap output from {target}: {e}"))?,
+        "ss" => parse_ss(body),
+        other => return Err(format!("unexpected output from {target}: {other}")),
+    };
+    for p in &mut scan.processes {
+        p.host = Some(target.to_string());
+    }
+    Ok(scan)
+}
+
+/// The fields of a remote `list --format json` row that make sense here.
+/// Containers and service managers refer to the other machine, so they're
+/// left out.
+#[derive(Deserialize)]
+struct RemoteRow {
+    pid: u32,
+    name: String,
+    port: u16,
+    protocol: Protocol,
+    command: Option<String>,
+    user: Option<String>,
+    uid: Option<u32>,
+    ppid: Option<u32>,
+    parent_name: Option<String>,
+    started_at: Option<String>,
+    cwd: Option<PathBuf>,
+}
+
+/// `list --format json` prints nothing at all when nothing is listening.
+fn parse_portzap_json(body: &str) -> std::result::Result<ScanResult, String> {
+    if body.trim().is_empty() {
+        return Ok(ScanResult::default());
+    }
+    let rows: Vec<RemoteRow> = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let processes: Vec<ProcessInfo> = rows
+        .into_iter()
+        .map(|row| ProcessInfo {
+            pid: row.pid,
+            name: row.name,
+            port: row.port,
+            protocol: row.protocol,
+            command: row.command,
+            user: row.user,
+            uid: row.uid,
+            container: None,
+            managed_by: None,
+            ppid: row.ppid,
+            parent_name: row.parent_name,
+            started_at: row
+                .started_at
+                .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                .map(SystemTime::from),
+            cwd: row.cwd,
+            host: None,
+            origin: None,
+        })
+        .collect();
+    Ok(ScanResult::from(processes))
+}
+
+/// Parse `ss -tulpnH` rows:
+///
+/// ```text
+/// tcp LISTEN 0 4096 127.0.0.1:5432 0.0.0.0:* users:(("postgres",pid=812,fd=5))
+/// ```
+///
+/// Without root, ss leaves out the `users:` column for other users' sockets;
+/// those are counted as hidden.
+fn parse_ss(body: &str) -> ScanResult {
+    static USERS: OnceLock<Regex> = OnceLock::new();
+    let users = USERS.get_or_init(|| Regex::new(r#"\("([^"]*)",pid=(\d+)"#).unwrap());
+
+    let mut scan = ScanResult::default();
+    let mut seen = HashSet::new();
+    let mut hidden = 0;
+    for line in body.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let protocol = match fields.first() {
+            Some(&"tcp") => Protocol::Tcp,
+            Some(&"udp") => Protocol::Udp,
+            _ => continue,
+        };
+        let Some(port) = fields
+            .get(4)
+            .and_then(|local| local.rsplit_once(':'))
+            .and_then(|(_, port)| port.parse::<u16>().ok())
+        else {
+            continue;
+        };
+        let owners = fields
+            .get(6..)
+            .map(|rest| rest.join(" "))
+            .unwrap_or_default();
+        let mut found = false;
+        for caps in users.captures_iter(&owners) {
+            let Ok(pid) = caps[2].parse::<u32>() else {
+                continue;
+            };
+            found = true;
+            if seen.insert((pid, port, protocol)) {
+                scan.processes.push(ProcessInfo {
+                    pid,
+                    name: caps[1].to_string(),
+                    port,
+                    protocol,
+                    command: None,
+                    user: None,
+                    uid: None,
+                    container: None,
+                    managed_by: None,
+                    ppid: None,
+                    parent_name: None,
+                    started_at: None,
+                    cwd: None,
+                    host: None,
+                    origin: None,
+                });
+            }
+        }
+        if !found {
+            hidden += 1;
+        }
+    }
+    scan.processes.sort_by_key(|p| (p.port, p.pid));
+    if hidden > 0 {
+        scan.warnings
+            .push(ScanWarning::HiddenSockets { count: hidden });
+    }
+    scan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ss_rows_and_counts_hidden_sockets() {
+        let output = "ss\n\
+            tcp   LISTEN 0      4096       127.0.0.1:5432      0.0.0.0:*    users:((\"postgres\",pid=812,fd=5))\n\
+            tcp   LISTEN 0      4096            [::]:5432         [::]:*    users:((\"postgres\",pid=812,fd=6))\n\
+            udp   UNCONN 0      0      127.0.0.53%lo:53        0.0.0.0:*    users:((\"systemd-resolve\",pid=650,fd=13),(\"helper\",pid=651,fd=3))\n\
+            tcp   LISTEN 0      128          0.0.0.0:22        0.0.0.0:*\n";
+        let scan = parse_output(output, "dev1").unwrap();
+        let rows: Vec<_> = scan
+            .processes
+            .iter()
+            .map(|p| (p.pid, p.name.as_str(), p.port, p.protocol))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (650, "systemd-resolve", 53, Protocol::Udp),
+                (651, "helper", 53, Protocol::Udp),
+                (812, "postgres", 5432, Protocol::Tcp),
+            ]
+        );
+        assert!(scan
+            .processes
+            .iter()
+            .all(|p| p.host.as_deref() == Some("dev1")));
+        assert_eq!(scan.warnings, [ScanWarning::HiddenSockets { count: 1 }]);
+    }
+
+    #[test]
+    fn parses_remote_portzap_json() {
+        let output = r#"portzap
+[
+  {
+    "pid": 4242,
+    "name": "node",
+    "port": 3000,
+    "protocol": "tcp",
+    "command": "node server.js",
+    "user": "alice",
+    "started_at": "2026-10-16T09:00:00Z",
+    "container": {"id": "abc", "name": "web", "image": "node:20"}
+  }
+]
+"#;
+        let scan = parse_output(output, "alice@dev1").unwrap();
+        let p = &scan.processes[0];
+        assert_eq!((p.pid, p.port, p.protocol), (4242, 3000, Protocol::Tcp));
+        assert_eq!(p.command.as_deref(), Some("node server.js"));
+        assert!(p.started_at.is_some());
+        assert!(p.container.is_none());
+        assert_eq!(p.host.as_deref(), Some("alice@dev1"));
+
+        assert!(parse_output("portzap\n", "dev1")
+            .unwrap()
+            .processes
+            .is_empty());
+        assert!(parse_output("portzap\n{", "dev1")
+            .unwrap_err()
+            .contains("could not parse portzap output from dev1"));
+        assert!(parse_output("", "dev1").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Best-effort resource usage for a single process. Fields are `None` when the
+/// process could not be inspected (permissions, exited, unsupported platform).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub rss_bytes: Option<u64>,
+    pub cpu_percent: Option<f32>,
+}
+
+/// Samples memory and CPU usage across refreshes. CPU% is derived from the
+/// difference in consumed CPU time between two samples, so the first sample
+/// of a process reports no CPU figure.
+pub struct ResourceSampler {
+    previous: HashMap<u32, Duration>,
+    last_sample: Option<Instant>,
+}
+
+impl ResourceSampler {
+    pub fn new() -> Self {
+        Self {
+            previous: HashMap::new(),
+            last_sample: None,
+        }
+    }
+
+    pub fn sample(&mut self, pids: impl IntoIterator<Item = u32>) -> HashMap<u32, ResourceUsage> {
+        let now = Instant::now();
+        let wall = self.last_sample.map(|t| now.duration_since(t));
+        let mut current = HashMap::new();
+        let mut usage = HashMap::new();
+
+        for pid in pids {
+            if usage.contains_key(&pid) {
+                continue;
+            }
+            let Some(raw) = read_raw_usage(pid) else {
+                usage.insert(pid, ResourceUsage::default());
+                continue;
+            };
+            let cpu_percent = match (wall, self.previous.get(&pid)) {
+                (Some(wall), Some(&prev)) if !wall.is_zero() => {
+                    let used = raw.cpu_time.saturating_sub(prev);
+                    Some((used.as_secs_f64() / wall.as_secs_f64() * 100.0) as f32)
+                }
+                _ => None,
+            };
+            current.insert(pid, raw.cpu_time);
+            usage.insert(
+                pid,
+                ResourceUsage {
+                    rss_bytes: Some(raw.rss_bytes),
+                    cpu_percent,
+                },
+            );
+        }
+
+        self.previous = current;
+        self.last_sample = Some(now);
+        usage
+    }
+}
+
+struct RawUsage {
+    rss_bytes: u64,
+    cpu_time: Duration,
+}
+
+#[cfg(target_os = "linux")]
+fn read_raw_usage(pid: u32) -> Option<RawUsage> {
+    let stat = procfs::process::Process::new(pid as i32)
+        .ok()?
+        .stat()
+        .ok()?;
+    let ticks = procfs::ticks_per_second();
+    Some(RawUsage {
+        rss_bytes: stat.rss * procfs::page_size(),
+        cpu_time: Duration::from_secs_f64((stat.utime + stat.stime) as f64 / ticks as f64),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn read_raw_usage(pid: u32) -> Option<RawUsage> {
+    use libproc::proc_pid::pidinfo;
+    use libproc::task_info::TaskInfo;
+
+    let info = pidinfo::<TaskInfo>(pid as i32, 0).ok()?;
+    // pti_total_* are in Mach absolute time units, which are only nanoseconds on Intel.
+    let mut timebase = libc::mach_timebase_info { numer: 0, denom: 0 };
+    let ticks = info.pti_total_user + info.pti_total_system;
+    let ok = unsafe { libc::mach_timebase_info(&mut timebase) } == 0;
+    let nanos = if ok && timebase.denom != 0 {
+        ticks * timebase.numer as u64 / timebase.denom as u64
+    } else {
+        ticks
+    };
+    Some(RawUsage {
+        rss_bytes: info.pti_resident_size,
+        cpu_time: Duration::from_nanos(nanos),
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_raw_usage(_pid: u32) -> Option<RawUsage> {
+    None
+}
+
+/// Format a byte count for display, e.g. "14.2 MB" or "1.3 GB".
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_bytes_small_values() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(512), "512 B");
+    }
+
+    #[test]
+    fn format_bytes_scales_units() {
+        assert_eq!(format_bytes(1024), "1.0 KB");
+        assert_eq!(format_bytes(14_890_000), "14.2 MB");
+        assert_eq!(format_bytes(1_395_864_371), "1.3 GB");
+    }
+
+    #[test]
+    fn sampler_reports_own_memory() {
+        let mut sampler = ResourceSampler::new();
+        let pid = std::process::id();
+        let first = sampler.sample([pid]);
+        if cfg!(any(target_os = "linux", target_os = "macos")) {
+            assert!(first[&pid].rss_bytes.unwrap_or(0) > 0);
+            assert!(first[&pid].cpu_percent.is_none());
+            let second = sampler.sample([pid]);
+            assert!(second[&pid].cpu_percent.is_some());
+        }
+    }
+}
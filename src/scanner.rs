@@ -1,5 +1,18 @@
+use crate::docker;
 use crate::errors::Result;
-use crate::process::ProcessInfo;
+use crate::process::{PortSpec, ProcessInfo};
+use crate::remote::{RemoteHost, RemoteScanner};
+use crate::service_manager;
+use crate::wsl::WindowsScanner;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How old a scan may get before it's checked again ahead of a kill, e.g.
+/// after the user spent a while at the `-i` prompt.
+pub const STALE_AFTER: Duration = Duration::from_secs(1);
 
 pub trait PortScanner {
     /// Find all processes bound to the given port.
@@ -7,9 +20,404 @@ pub trait PortScanner {
 
     /// Find all processes currently listening on any port.
     fn find_all_listening(&self) -> Result<Vec<ProcessInfo>>;
+
+    /// Like `find_processes_by_port`, but also reports what the scan couldn't see.
+    fn scan_port(&self, port: u16) -> Result<ScanResult> {
+        Ok(ScanResult::from(self.find_processes_by_port(port)?))
+    }
+
+    /// Like `find_all_listening`, but also reports what the scan couldn't see.
+    fn scan_all(&self) -> Result<ScanResult> {
+        Ok(ScanResult::from(self.find_all_listening()?))
+    }
+
+    /// Processes bound to any port in `specs`, in a single pass. Hidden
+    /// sockets elsewhere on the system aren't reported.
+    fn scan_ports(&self, specs: &[PortSpec]) -> Result<ScanResult> {
+        let mut scan = self.scan_all()?;
+        scan.processes
+            .retain(|p| specs.iter().any(|spec| spec.contains(p.port)));
+        scan.warnings.retain(|w| match w {
+            ScanWarning::OwnerUnknown { port } => specs.iter().any(|spec| spec.contains(*port)),
+            ScanWarning::HiddenSockets { .. } => false,
+            _ => true,
+        });
+        Ok(scan)
+    }
+}
+
+/// Every requested port as seen by one scan, so a multi-port run looks at a
+/// single moment: a process holding two of the ports shows up on both.
+#[derive(Debug)]
+pub struct ScanSnapshot {
+    by_port: HashMap<u16, ScanResult>,
+    /// Warnings about the whole scan, handed out with the first port
+    warnings: Vec<ScanWarning>,
+    taken_at: Instant,
+}
+
+impl ScanSnapshot {
+    /// Scan the ports in `specs` in one pass.
+    pub fn take(scanner: &dyn PortScanner, specs: &[PortSpec]) -> Result<Self> {
+        let taken_at = Instant::now();
+        let scan = scanner.scan_ports(specs)?;
+        let mut by_port: HashMap<u16, ScanResult> = HashMap::new();
+        for process in scan.processes {
+            by_port
+                .entry(process.port)
+                .or_default()
+                .processes
+                .push(process);
+        }
+        let mut warnings = Vec::new();
+        for warning in scan.warnings {
+            match warning {
+                ScanWarning::OwnerUnknown { port } => {
+                    by_port.entry(port).or_default().warnings.push(warning);
+                }
+                _ => warnings.push(warning),
+            }
+        }
+        Ok(Self {
+            by_port,
+            warnings,
+            taken_at,
+        })
+    }
+
+    /// The processes listening on `port` when the snapshot was taken.
+    pub fn processes_on(&self, port: u16) -> &[ProcessInfo] {
+        self.by_port
+            .get(&port)
+            .map_or(&[], |scan| scan.processes.as_slice())
+    }
+
+    /// When the scan started.
+    pub fn taken_at(&self) -> Instant {
+        self.taken_at
+    }
+
+    /// Hand out `port`'s part of the scan, with the scan-wide warnings if
+    /// they haven't gone out with an earlier port.
+    fn take_port(&mut self, port: u16) -> ScanResult {
+        let mut scan = self.by_port.remove(&port).unwrap_or_default();
+        scan.warnings.append(&mut self.warnings);
+        scan
+    }
+}
+
+/// Per-port scans for commands that take port lists. A single port is looked
+/// up on its own; several are answered from one [`ScanSnapshot`].
+pub enum PortScans<'a> {
+    Live(&'a dyn PortScanner),
+    Snapshot(ScanSnapshot),
+}
+
+impl<'a> PortScans<'a> {
+    pub fn new(scanner: &'a dyn PortScanner, specs: &[PortSpec]) -> Result<Self> {
+        let requested: usize = specs.iter().map(PortSpec::port_count).sum();
+        if requested <= 1 {
+            return Ok(Self::Live(scanner));
+        }
+        Ok(Self::Snapshot(ScanSnapshot::take(scanner, specs)?))
+    }
+
+    /// What's on `port`. Each port is handed out once from a snapshot.
+    pub fn scan(&mut self, port: u16) -> Result<ScanResult> {
+        match self {
+            Self::Live(scanner) => scanner.scan_port(port),
+            Self::Snapshot(snapshot) => Ok(snapshot.take_port(port)),
+        }
+    }
+
+    /// When the snapshot was taken; `None` for live scans.
+    pub fn taken_at(&self) -> Option<Instant> {
+        match self {
+            Self::Live(_) => None,
+            Self::Snapshot(snapshot) => Some(snapshot.taken_at()),
+        }
+    }
+}
+
+/// Processes found by a scan, plus anything that limited what it could see.
+#[derive(Debug, Default, Clone)]
+pub struct ScanResult {
+    pub processes: Vec<ProcessInfo>,
+    pub warnings: Vec<ScanWarning>,
+}
+
+impl From<Vec<ProcessInfo>> for ScanResult {
+    fn from(processes: Vec<ProcessInfo>) -> Self {
+        Self {
+            processes,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl ScanResult {
+    /// True when a socket is bound to `port` but no visible process owns it.
+    pub fn owner_unknown(&self, port: u16) -> bool {
+        self.warnings.contains(&ScanWarning::OwnerUnknown { port })
+    }
+
+    /// Add this scan's warnings that aren't about one port to `seen`, once
+    /// each. Per-port `OwnerUnknown` is reported with its port instead.
+    pub fn collect_warnings(&self, seen: &mut Vec<ScanWarning>) {
+        for warning in &self.warnings {
+            if !matches!(warning, ScanWarning::OwnerUnknown { .. }) && !seen.contains(warning) {
+                seen.push(warning.clone());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScanWarning {
+    /// Sockets whose owning process we couldn't inspect (other users, `hidepid`)
+    HiddenSockets { count: usize },
+    /// A socket is bound to the port, but its owner is one we couldn't inspect
+    OwnerUnknown { port: u16 },
+    /// A socket table that exists but couldn't be read, e.g. `/proc/net/tcp6`
+    SourceUnavailable { source: String },
+    /// Some of this process's fds couldn't be read, so it may hold sockets we missed
+    Truncated { pid: u32 },
+}
+
+impl fmt::Display for ScanWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanWarning::HiddenSockets { count: 1 } => write!(
+                f,
+                "1 socket belongs to a process that could not be inspected (run with sudo to see it)"
+            ),
+            ScanWarning::HiddenSockets { count } => write!(
+                f,
+                "{count} sockets belong to processes that could not be inspected (run with sudo to see them)"
+            ),
+            ScanWarning::OwnerUnknown { port } => write!(
+                f,
+                "a process is listening on {port} but its owner could not be determined (run with sudo)"
+            ),
+            ScanWarning::SourceUnavailable { source } => write!(
+                f,
+                "could not read {source}; sockets listed there are missing from the results"
+            ),
+            ScanWarning::Truncated { pid } => write!(
+                f,
+                "some open files of PID {pid} could not be read; its sockets may be missing"
+            ),
+        }
+    }
+}
+
+/// The scanner for `--host`, or this machine's without one, with the
+/// Windows side added for `--include-windows`.
+pub fn create_scanner_for(
+    host: Option<&RemoteHost>,
+    include_windows: bool,
+) -> Result<Box<dyn PortScanner>> {
+    match host {
+        Some(host) => Ok(Box::new(RemoteScanner::new(host.clone()))),
+        None if include_windows => Ok(Box::new(WindowsScanner::new(create_scanner())?)),
+        None => Ok(create_scanner()),
+    }
+}
+
+/// `inner` without portzap's own sockets, which are never what anyone is
+/// looking for and shouldn't be killable by accident.
+pub fn without_self(inner: Box<dyn PortScanner>) -> Box<dyn PortScanner> {
+    Box::new(SelfExcluded { inner })
+}
+
+struct SelfExcluded {
+    inner: Box<dyn PortScanner>,
+}
+
+impl SelfExcluded {
+    fn strip(mut result: ScanResult) -> ScanResult {
+        result.processes.retain(|p| !p.is_self());
+        result
+    }
+}
+
+impl PortScanner for SelfExcluded {
+    fn find_processes_by_port(&self, port: u16) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_port(port)?.processes)
+    }
+
+    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_all()?.processes)
+    }
+
+    fn scan_port(&self, port: u16) -> Result<ScanResult> {
+        Ok(Self::strip(self.inner.scan_port(port)?))
+    }
+
+    fn scan_all(&self) -> Result<ScanResult> {
+        Ok(Self::strip(self.inner.scan_all()?))
+    }
+
+    fn scan_ports(&self, specs: &[PortSpec]) -> Result<ScanResult> {
+        Ok(Self::strip(self.inner.scan_ports(specs)?))
+    }
+}
+
+/// Answers a repeated scan with the first result, so one look at the system
+/// can be printed twice, e.g. `list --output` writing the file and then
+/// showing a table.
+pub struct Recorded<'a> {
+    inner: &'a dyn PortScanner,
+    scans: RefCell<Vec<(Scan, ScanResult)>>,
+}
+
+#[derive(PartialEq)]
+enum Scan {
+    All,
+    Port(u16),
+    Ports(Vec<PortSpec>),
+}
+
+impl<'a> Recorded<'a> {
+    pub fn new(inner: &'a dyn PortScanner) -> Self {
+        Self {
+            inner,
+            scans: RefCell::default(),
+        }
+    }
+
+    fn recall(&self, scan: Scan, run: impl FnOnce() -> Result<ScanResult>) -> Result<ScanResult> {
+        if let Some((_, result)) = self.scans.borrow().iter().find(|(s, _)| *s == scan) {
+            return Ok(result.clone());
+        }
+        let result = run()?;
+        self.scans.borrow_mut().push((scan, result.clone()));
+        Ok(result)
+    }
+}
+
+impl PortScanner for Recorded<'_> {
+    fn find_processes_by_port(&self, port: u16) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_port(port)?.processes)
+    }
+
+    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_all()?.processes)
+    }
+
+    fn scan_port(&self, port: u16) -> Result<ScanResult> {
+        self.recall(Scan::Port(port), || self.inner.scan_port(port))
+    }
+
+    fn scan_all(&self) -> Result<ScanResult> {
+        self.recall(Scan::All, || self.inner.scan_all())
+    }
+
+    fn scan_ports(&self, specs: &[PortSpec]) -> Result<ScanResult> {
+        self.recall(Scan::Ports(specs.to_vec()), || self.inner.scan_ports(specs))
+    }
 }
 
 pub fn create_scanner() -> Box<dyn PortScanner> {
+    Box::new(EnrichedScanner {
+        inner: platform_scanner(),
+    })
+}
+
+/// Wraps the platform scanner and fills in details that need other sources:
+/// Docker containers behind proxies and service-manager-owned sockets.
+struct EnrichedScanner {
+    inner: Box<dyn PortScanner>,
+}
+
+impl EnrichedScanner {
+    fn enrich(&self, mut result: ScanResult) -> ScanResult {
+        docker::enrich(&mut result.processes);
+        service_manager::enrich(&mut result.processes);
+        tracing::debug!(
+            processes = result.processes.len(),
+            warnings = result.warnings.len(),
+            "scan finished"
+        );
+        result
+    }
+}
+
+impl PortScanner for EnrichedScanner {
+    fn find_processes_by_port(&self, port: u16) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_port(port)?.processes)
+    }
+
+    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_all()?.processes)
+    }
+
+    fn scan_port(&self, port: u16) -> Result<ScanResult> {
+        let _span = tracing::debug_span!("scan", port).entered();
+        tracing::debug!("scan started");
+        Ok(self.enrich(self.inner.scan_port(port)?))
+    }
+
+    fn scan_all(&self) -> Result<ScanResult> {
+        let _span = tracing::debug_span!("scan", port = %"all").entered();
+        tracing::debug!("scan started");
+        Ok(self.enrich(self.inner.scan_all()?))
+    }
+
+    fn scan_ports(&self, specs: &[PortSpec]) -> Result<ScanResult> {
+        let ports = specs.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let _span = tracing::debug_span!("scan", port = %ports.join(",")).entered();
+        tracing::debug!("scan started");
+        Ok(self.enrich(self.inner.scan_ports(specs)?))
+    }
+}
+
+/// Environment variable capping the scan thread pool; `1` scans serially.
+#[cfg(unix)]
+pub const SCAN_THREADS_ENV: &str = "PORTZAP_SCAN_THREADS";
+
+/// Map `f` over `items` on a bounded thread pool, keeping input order.
+/// Platform scanners use this for per-process work, which is dominated by
+/// syscalls rather than CPU, so a handful of threads is plenty.
+#[cfg(unix)]
+pub(crate) fn scan_parallel<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync + Send,
+{
+    use rayon::prelude::*;
+    use std::sync::OnceLock;
+
+    static POOL: OnceLock<Option<rayon::ThreadPool>> = OnceLock::new();
+
+    let threads = std::env::var(SCAN_THREADS_ENV)
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map_or(1, |n| n.get())
+                .min(8)
+        });
+    if threads == 1 {
+        return items.into_iter().map(f).collect();
+    }
+    let pool = POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("portzap-scan-{i}"))
+            .build()
+            .ok()
+    });
+    match pool {
+        Some(pool) => pool.install(|| items.into_par_iter().map(f).collect()),
+        None => items.into_iter().map(f).collect(),
+    }
+}
+
+fn platform_scanner() -> Box<dyn PortScanner> {
     #[cfg(target_os = "macos")]
     {
         Box::new(crate::platform::macos::MacosScanner::new())
@@ -23,3 +431,183 @@ pub fn create_scanner() -> Box<dyn PortScanner> {
         Box::new(crate::platform::windows::WindowsScanner::new())
     }
 }
+
+/// An in-memory scanner for unit-testing commands without real sockets.
+#[cfg(test)]
+pub mod mock {
+    use super::{PortScanner, ScanResult, ScanWarning};
+    use crate::errors::Result;
+    use crate::process::{ProcessInfo, Protocol};
+    use std::cell::{Cell, RefCell};
+
+    /// Answers scans from a fixed process list. `remove` lets a test change
+    /// what later scans see, as a kill or a shutdown would.
+    #[derive(Debug, Default)]
+    pub struct MockScanner {
+        processes: RefCell<Vec<ProcessInfo>>,
+        /// Reported by every scan
+        warnings: Vec<ScanWarning>,
+        scans: Cell<usize>,
+    }
+
+    impl MockScanner {
+        pub fn new(processes: Vec<ProcessInfo>) -> Self {
+            Self {
+                processes: RefCell::new(processes),
+                warnings: Vec::new(),
+                scans: Cell::new(0),
+            }
+        }
+
+        pub fn with_warnings(self, warnings: Vec<ScanWarning>) -> Self {
+            Self { warnings, ..self }
+        }
+
+        pub fn remove(&self, pid: u32) {
+            self.processes.borrow_mut().retain(|p| p.pid != pid);
+        }
+
+        /// How many scans, of one port or of everything, have been run.
+        pub fn scan_count(&self) -> usize {
+            self.scans.get()
+        }
+    }
+
+    impl PortScanner for MockScanner {
+        fn find_processes_by_port(&self, port: u16) -> Result<Vec<ProcessInfo>> {
+            self.scans.set(self.scans.get() + 1);
+            let processes = self.processes.borrow();
+            Ok(processes
+                .iter()
+                .filter(|p| p.port == port)
+                .cloned()
+                .collect())
+        }
+
+        fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
+            self.scans.set(self.scans.get() + 1);
+            Ok(self.processes.borrow().clone())
+        }
+
+        fn scan_port(&self, port: u16) -> Result<ScanResult> {
+            Ok(ScanResult {
+                processes: self.find_processes_by_port(port)?,
+                warnings: self.warnings.clone(),
+            })
+        }
+
+        fn scan_all(&self) -> Result<ScanResult> {
+            Ok(ScanResult {
+                processes: self.find_all_listening()?,
+                warnings: self.warnings.clone(),
+            })
+        }
+    }
+
+    /// A TCP listener with only a pid, name and port filled in.
+    pub fn process(pid: u32, name: &str, port: u16) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.into(),
+            port,
+            protocol: Protocol::Tcp,
+            command: None,
+            user: None,
+            uid: None,
+            container: None,
+            managed_by: None,
+            ppid: None,
+            parent_name: None,
+            started_at: None,
+            cwd: None,
+            host: None,
+            origin: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{process, MockScanner};
+    use super::*;
+
+    fn warnings() -> Vec<ScanWarning> {
+        vec![
+            ScanWarning::SourceUnavailable {
+                source: "/proc/net/tcp6".into(),
+            },
+            ScanWarning::OwnerUnknown { port: 3005 },
+            ScanWarning::HiddenSockets { count: 2 },
+        ]
+    }
+
+    #[test]
+    fn snapshot_hands_out_scan_warnings_once() {
+        let scanner = MockScanner::new(vec![process(1, "node", 3001)]).with_warnings(warnings());
+        let mut scans = PortScans::new(&scanner, &[PortSpec::range(3000, 3100)]).unwrap();
+        assert!(matches!(scans, PortScans::Snapshot(_)));
+
+        let first = scans.scan(3000).unwrap();
+        assert_eq!(first.warnings, warnings()[..1]);
+        assert!(scans.scan(3001).unwrap().warnings.is_empty());
+        assert!(scans.scan(3005).unwrap().owner_unknown(3005));
+    }
+
+    #[test]
+    fn several_ports_share_one_scan() {
+        let scanner = MockScanner::new(vec![
+            process(1, "node", 3000),
+            process(1, "node", 3001),
+            process(2, "pg", 5432),
+        ]);
+        let specs = [PortSpec::Single(3000), PortSpec::Single(5432)];
+        let snapshot = ScanSnapshot::take(&scanner, &specs).unwrap();
+        assert_eq!(scanner.scan_count(), 1);
+        assert_eq!(snapshot.processes_on(3000)[0].pid, 1);
+        assert_eq!(snapshot.processes_on(5432)[0].pid, 2);
+        assert!(snapshot.processes_on(3001).is_empty());
+        assert!(snapshot.processes_on(8080).is_empty());
+
+        let mut scans = PortScans::new(&scanner, &specs[..1]).unwrap();
+        assert!(scans.taken_at().is_none());
+        assert_eq!(scans.scan(3000).unwrap().processes.len(), 1);
+        assert_eq!(scanner.scan_count(), 2);
+    }
+
+    #[test]
+    fn collected_warnings_skip_per_port_ones_and_repeats() {
+        let scan = ScanResult {
+            processes: Vec::new(),
+            warnings: warnings(),
+        };
+        let mut seen = Vec::new();
+        scan.collect_warnings(&mut seen);
+        scan.collect_warnings(&mut seen);
+        assert_eq!(seen, [warnings()[0].clone(), warnings()[2].clone()]);
+        assert_eq!(
+            serde_json::to_string(&seen[0]).unwrap(),
+            r#"{"kind":"source_unavailable","source":"/proc/net/tcp6"}"#
+        );
+    }
+
+    #[test]
+    fn without_self_drops_only_our_own_rows() {
+        let me = std::process::id();
+        let mut remote = process(me, "sshd", 22);
+        remote.host = Some("dev1".into());
+        let scanner = without_self(Box::new(MockScanner::new(vec![
+            process(me, "portzap", 3000),
+            process(1, "node", 3000),
+            remote,
+        ])));
+        let pids: Vec<_> = scanner
+            .scan_all()
+            .unwrap()
+            .processes
+            .iter()
+            .map(|p| (p.pid, p.port))
+            .collect();
+        assert_eq!(pids, [(1, 3000), (me, 22)]);
+        assert_eq!(scanner.find_processes_by_port(3000).unwrap().len(), 1);
+    }
+}
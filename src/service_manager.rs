@@ -0,0 +1,367 @@
+//! Detect sockets owned by a service manager (systemd socket activation on
+//! Linux, launchd jobs on macOS), where killing the listener is futile
+//! because the manager simply listens again or restarts the daemon.
+
+use crate::process::ProcessInfo;
+
+/// Annotate `managed_by` on processes whose socket belongs to a service manager.
+#[cfg(target_os = "linux")]
+pub fn enrich(processes: &mut [ProcessInfo]) {
+    systemd::enrich(processes);
+}
+
+#[cfg(target_os = "macos")]
+pub fn enrich(processes: &mut [ProcessInfo]) {
+    launchd::enrich(processes);
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn enrich(_processes: &mut [ProcessInfo]) {}
+
+#[cfg(target_os = "linux")]
+mod systemd {
+    use crate::process::{ManagedBy, ProcessInfo, ServiceManager};
+    use serde::Deserialize;
+    use std::process::Command;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// How long `systemctl list-sockets` output is reused; the TUI rescans every few seconds.
+    const CACHE_TTL: Duration = Duration::from_secs(30);
+
+    static SOCKET_UNITS: Mutex<Option<(Instant, Vec<SocketUnit>)>> = Mutex::new(None);
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    pub(super) struct SocketUnit {
+        pub listen: String,
+        pub unit: String,
+        #[serde(default)]
+        pub activates: String,
+    }
+
+    impl SocketUnit {
+        fn port(&self) -> Option<u16> {
+            let (_, port) = self.listen.rsplit_once(':')?;
+            port.parse().ok()
+        }
+    }
+
+    pub fn enrich(processes: &mut [ProcessInfo]) {
+        let services: Vec<Option<String>> = processes.iter().map(|p| service_unit(p.pid)).collect();
+        // Only ask systemd when something could be socket-activated
+        if !processes.iter().any(|p| p.pid == 1) && services.iter().all(Option::is_none) {
+            return;
+        }
+        let units = socket_units();
+        if units.is_empty() {
+            return;
+        }
+        for (p, service) in processes.iter_mut().zip(services) {
+            p.managed_by =
+                match_socket(&units, p.port, p.pid, service.as_deref()).map(|unit| ManagedBy {
+                    manager: ServiceManager::Systemd,
+                    unit: unit.unit.clone(),
+                    domain: None,
+                });
+        }
+    }
+
+    /// The socket unit listening on `port` that is either held by systemd
+    /// itself (pid 1) or activates the service `pid` runs in.
+    pub(super) fn match_socket<'a>(
+        units: &'a [SocketUnit],
+        port: u16,
+        pid: u32,
+        service: Option<&str>,
+    ) -> Option<&'a SocketUnit> {
+        units.iter().find(|u| {
+            u.port() == Some(port)
+                && (pid == 1 || service.is_some_and(|s| u.activates.split(", ").any(|a| a == s)))
+        })
+    }
+
+    /// The system `.service` unit a process runs in, from its cgroup path.
+    fn service_unit(pid: u32) -> Option<String> {
+        if pid == 1 {
+            return None;
+        }
+        let cgroup = std::fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+        parse_cgroup_service(&cgroup)
+    }
+
+    pub(super) fn parse_cgroup_service(cgroup: &str) -> Option<String> {
+        // Prefer the unified hierarchy ("0::/..."), else the named systemd one
+        let path = cgroup
+            .lines()
+            .find_map(|l| l.strip_prefix("0::"))
+            .filter(|p| *p != "/")
+            .or_else(|| {
+                cgroup
+                    .lines()
+                    .find_map(|l| l.split_once(":name=systemd:").map(|(_, p)| p))
+            })?;
+        if !path.starts_with("/system.slice/") {
+            return None;
+        }
+        path.rsplit('/')
+            .find(|component| component.ends_with(".service"))
+            .map(str::to_string)
+    }
+
+    fn socket_units() -> Vec<SocketUnit> {
+        let mut cache = SOCKET_UNITS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((at, units)) = cache.as_ref() {
+            if at.elapsed() < CACHE_TTL {
+                return units.clone();
+            }
+        }
+        let units = Command::new("systemctl")
+            .args(["list-sockets", "--all", "--no-pager", "--output=json"])
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| parse_list_sockets(&String::from_utf8_lossy(&out.stdout)))
+            .unwrap_or_default();
+        *cache = Some((Instant::now(), units.clone()));
+        units
+    }
+
+    /// Parse `systemctl list-sockets` output: JSON on newer systemd, the
+    /// LISTEN/UNIT/ACTIVATES table on versions that ignore `--output=json`.
+    pub(super) fn parse_list_sockets(output: &str) -> Vec<SocketUnit> {
+        if let Ok(units) = serde_json::from_str(output) {
+            return units;
+        }
+        output
+            .lines()
+            .skip_while(|l| !l.starts_with("LISTEN"))
+            .skip(1)
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                Some(SocketUnit {
+                    listen: fields.next()?.to_string(),
+                    unit: fields
+                        .next()
+                        .filter(|u| u.ends_with(".socket"))?
+                        .to_string(),
+                    activates: fields.collect::<Vec<_>>().join(" "),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod launchd {
+    use crate::process::{ManagedBy, ProcessInfo, ServiceManager};
+    use std::collections::HashMap;
+    use std::process::Command;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// How long `launchctl list` output is reused; the TUI rescans every few seconds.
+    const CACHE_TTL: Duration = Duration::from_secs(10);
+
+    static JOBS: Mutex<Option<(Instant, HashMap<u32, String>)>> = Mutex::new(None);
+
+    /// Executables under these prefixes are macOS system daemons launchd keeps alive.
+    const SYSTEM_PREFIXES: &[&str] = &["/System/", "/usr/libexec/", "/usr/sbin/"];
+
+    pub fn enrich(processes: &mut [ProcessInfo]) {
+        let jobs = running_jobs();
+        let euid = nix::unistd::geteuid();
+        let domain = if euid.is_root() {
+            "system".to_string()
+        } else {
+            format!("gui/{euid}")
+        };
+        for p in processes.iter_mut() {
+            p.managed_by = classify(p, &jobs, &domain);
+        }
+    }
+
+    pub(super) fn classify(
+        p: &ProcessInfo,
+        jobs: &HashMap<u32, String>,
+        domain: &str,
+    ) -> Option<ManagedBy> {
+        let managed = |unit: &str, domain: Option<&str>| ManagedBy {
+            manager: ServiceManager::Launchd,
+            unit: unit.to_string(),
+            domain: domain.map(str::to_string),
+        };
+        if p.pid == 1 {
+            // launchd holds the socket itself for an on-demand job
+            return Some(managed("launchd", None));
+        }
+        if let Some(label) = jobs.get(&p.pid) {
+            return Some(managed(label, Some(domain)));
+        }
+        let path = p.command.as_deref()?.split_whitespace().next()?;
+        SYSTEM_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+            .then(|| managed(&p.name, None))
+    }
+
+    /// PID → label for running jobs in the caller's launchd domain (the
+    /// system domain when running as root).
+    fn running_jobs() -> HashMap<u32, String> {
+        let mut cache = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((at, jobs)) = cache.as_ref() {
+            if at.elapsed() < CACHE_TTL {
+                return jobs.clone();
+            }
+        }
+        let jobs = Command::new("launchctl")
+            .arg("list")
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| parse_launchctl_list(&String::from_utf8_lossy(&out.stdout)))
+            .unwrap_or_default();
+        *cache = Some((Instant::now(), jobs.clone()));
+        jobs
+    }
+
+    /// Parse `launchctl list`: a `PID Status Label` header, then one job per
+    /// line with `-` as the PID of jobs that aren't running. Apps opened from
+    /// the Dock or Finder show up as `application.*` jobs, but launchd won't
+    /// restart them, so they are left out.
+    pub(super) fn parse_launchctl_list(output: &str) -> HashMap<u32, String> {
+        output
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pid = fields.next()?.parse().ok()?;
+                let label = fields.nth(1)?;
+                (!label.starts_with("application.")).then(|| (pid, label.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::launchd::*;
+    use crate::process::ProcessInfo;
+    use crate::scanner::mock;
+
+    const LIST: &str = "\
+PID\tStatus\tLabel
+-\t0\tcom.apple.SafariHistoryServiceAgent
+412\t0\tcom.apple.AirPlayUIAgent
+8871\t-9\tcom.example.devserver
+5120\t0\tapplication.com.google.Chrome.1234.5678
+";
+
+    fn process(pid: u32, name: &str, command: &str) -> ProcessInfo {
+        ProcessInfo {
+            command: Some(command.into()),
+            ..mock::process(pid, name, 7000)
+        }
+    }
+
+    #[test]
+    fn parses_running_jobs_only() {
+        let jobs = parse_launchctl_list(LIST);
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[&412], "com.apple.AirPlayUIAgent");
+    }
+
+    #[test]
+    fn classifies_launchd_jobs_and_system_daemons() {
+        let jobs = parse_launchctl_list(LIST);
+        let job = classify(&process(8871, "node", "node server.js"), &jobs, "gui/501").unwrap();
+        assert_eq!(job.to_string(), "launchd: com.example.devserver");
+        assert_eq!(
+            job.stop_command().as_deref(),
+            Some("launchctl bootout gui/501/com.example.devserver")
+        );
+
+        let daemon = process(
+            300,
+            "ControlCenter",
+            "/System/Library/CoreServices/ControlCenter.app/Contents/MacOS/ControlCenter",
+        );
+        assert_eq!(
+            classify(&daemon, &jobs, "gui/501").unwrap().unit,
+            "ControlCenter"
+        );
+        assert!(classify(&process(1, "launchd", "/sbin/launchd"), &jobs, "system").is_some());
+        assert!(classify(
+            &process(999, "node", "/usr/local/bin/node"),
+            &jobs,
+            "system"
+        )
+        .is_none());
+        let chrome = process(
+            5120,
+            "Google Chrome",
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+        );
+        assert!(classify(&chrome, &jobs, "gui/501").is_none());
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::systemd::*;
+
+    const JSON: &str = r#"[
+        {"listen":"/run/dbus/system_bus_socket","type":"Stream","unit":"dbus.socket","activates":"dbus.service"},
+        {"listen":"[::]:631","type":"Stream","unit":"cups.socket","activates":"cups.service"},
+        {"listen":"0.0.0.0:22","type":"Stream","unit":"ssh.socket","activates":"ssh.service"}
+    ]"#;
+
+    const TABLE: &str = "\
+LISTEN                      UNIT          ACTIVATES
+/run/dbus/system_bus_socket dbus.socket   dbus.service
+[::]:631                    cups.socket   cups.service
+
+2 sockets listed.
+";
+
+    #[test]
+    fn parses_json_and_table_output() {
+        let json = parse_list_sockets(JSON);
+        assert_eq!(json.len(), 3);
+        let table = parse_list_sockets(TABLE);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[1], json[1]);
+    }
+
+    #[test]
+    fn matches_pid1_or_activated_service() {
+        let units = parse_list_sockets(JSON);
+        assert_eq!(
+            match_socket(&units, 631, 1, None).unwrap().unit,
+            "cups.socket"
+        );
+        assert_eq!(
+            match_socket(&units, 22, 812, Some("ssh.service"))
+                .unwrap()
+                .unit,
+            "ssh.socket"
+        );
+        assert!(match_socket(&units, 22, 812, Some("nginx.service")).is_none());
+        assert!(match_socket(&units, 8080, 1, None).is_none());
+    }
+
+    #[test]
+    fn cgroup_service_names() {
+        assert_eq!(
+            parse_cgroup_service("0::/system.slice/cups.service\n").as_deref(),
+            Some("cups.service")
+        );
+        assert_eq!(
+            parse_cgroup_service("1:name=systemd:/system.slice/ssh.service\n0::/\n").as_deref(),
+            Some("ssh.service")
+        );
+        assert_eq!(
+            parse_cgroup_service("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! `watch --emit-service`: a systemd user unit or launchd agent that runs
+//! `portzap watch` in the foreground with the same flags, so the init system
+//! supervises it instead of `--daemon`. Rendering is plain templating over
+//! the program path and arguments; only `--install` touches the filesystem.
+
+use crate::errors::{KillportError, Result};
+use std::path::{Path, PathBuf};
+
+/// Which init system to write a definition for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceKind {
+    Systemd,
+    Launchd,
+}
+
+/// A rendered service definition and where it belongs.
+#[derive(Debug, Clone)]
+pub struct ServiceFile {
+    pub kind: ServiceKind,
+    /// Unit file name for systemd, job label for launchd
+    pub name: String,
+    pub contents: String,
+}
+
+impl ServiceFile {
+    /// Definition running `program args` that watches `ports`, given as
+    /// port specs for the name and description.
+    pub fn render(kind: ServiceKind, ports: &[String], program: &Path, args: &[String]) -> Self {
+        let description = format!(
+            "portzap watch: keep port{} {} free",
+            if ports.len() == 1 && !ports[0].contains('-') {
+                ""
+            } else {
+                "s"
+            },
+            ports.join(", ")
+        );
+        let id = ports.join("_");
+        let program = program.to_string_lossy();
+        match kind {
+            ServiceKind::Systemd => Self {
+                kind,
+                name: format!("portzap-watch-{id}.service"),
+                contents: systemd_unit(&description, &program, args),
+            },
+            ServiceKind::Launchd => {
+                let label = format!("com.portzap.watch.{id}");
+                Self {
+                    kind,
+                    contents: launchd_plist(&label, &program, args),
+                    name: label,
+                }
+            }
+        }
+    }
+
+    /// The user-level location for this definition.
+    pub fn install_path(&self) -> Result<PathBuf> {
+        let missing =
+            || KillportError::PlatformError("could not find the home or config directory".into());
+        Ok(match self.kind {
+            ServiceKind::Systemd => dirs::config_dir()
+                .ok_or_else(missing)?
+                .join("systemd/user")
+                .join(&self.name),
+            ServiceKind::Launchd => dirs::home_dir()
+                .ok_or_else(missing)?
+                .join("Library/LaunchAgents")
+                .join(format!("{}.plist", self.name)),
+        })
+    }
+
+    /// The shell command that starts an installed definition now and at login.
+    pub fn enable_command(&self, path: &Path) -> String {
+        match self.kind {
+            ServiceKind::Systemd => format!(
+                "systemctl --user daemon-reload && systemctl --user enable --now {}",
+                self.name
+            ),
+            ServiceKind::Launchd => {
+                format!("launchctl bootstrap gui/$(id -u) {}", path.display())
+            }
+        }
+    }
+}
+
+/// Restarted when it fails, e.g. on a scan error, but not when stopped.
+fn systemd_unit(description: &str, program: &str, args: &[String]) -> String {
+    let exec: Vec<String> = std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(systemd_quote)
+        .collect();
+    format!(
+        "[Unit]\n\
+         Description={description}\n\
+         After=network.target\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exec.join(" ")
+    )
+}
+
+/// One `ExecStart` word. systemd expands `%` specifiers and `$` variables
+/// even inside quotes, so those are doubled everywhere.
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    let plain = !escaped.is_empty()
+        && escaped
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%$".contains(c));
+    if plain {
+        escaped
+    } else {
+        format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Kept alive unless it exits cleanly, which it only does when stopped.
+fn launchd_plist(label: &str, program: &str, args: &[String]) -> String {
+    let arguments: String = std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(|arg| format!("\t\t<string>{}</string>\n", xml_escape(arg)))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \t<key>Label</key>\n\
+         \t<string>{}</string>\n\
+         \t<key>ProgramArguments</key>\n\
+         \t<array>\n\
+         {arguments}\
+         \t</array>\n\
+         \t<key>RunAtLoad</key>\n\
+         \t<true/>\n\
+         \t<key>KeepAlive</key>\n\
+         \t<dict>\n\
+         \t\t<key>SuccessfulExit</key>\n\
+         \t\t<false/>\n\
+         \t</dict>\n\
+         </dict>\n\
+         </plist>\n",
+        xml_escape(label)
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands, Signal};
+    use crate::commands::watch::{command_args, WatchOptions, WatchRule};
+    use crate::output::OutputFormat;
+    use crate::process::{KillSignal, PortSpec};
+    use clap::Parser;
+    use std::time::Duration;
+
+    fn options() -> WatchOptions {
+        WatchOptions {
+            ports: vec![
+                PortSpec::parse("3000").unwrap(),
+                PortSpec::parse("8000-8010").unwrap(),
+            ],
+            signal: KillSignal::Kill,
+            graceful: false,
+            graceful_timeout: Duration::from_secs(3),
+            poll_interval_ms: 250,
+            allow_names: vec!["my db".into(), "100%".into()],
+            format: OutputFormat::Plain,
+            history: None,
+            rules: vec![WatchRule::parse("80:graceful=1500ms").unwrap()],
+            dry_run: false,
+            log: None,
+            pidfiles: Vec::new(),
+        }
+    }
+
+    /// Split an `ExecStart` line back into words the way systemd does.
+    fn unquote(line: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut chars = line.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c == ' ' {
+                chars.next();
+                continue;
+            }
+            let mut word = String::new();
+            if c == '"' {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => word.extend(chars.next()),
+                        '"' => break,
+                        c => word.push(c),
+                    }
+                }
+            } else {
+                while let Some(&c) = chars.peek() {
+                    if c == ' ' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+            }
+            words.push(word.replace("%%", "%").replace("$$", "$"));
+        }
+        words
+    }
+
+    #[test]
+    fn systemd_unit_runs_the_same_watch() {
+        let opts = options();
+        let ports: Vec<String> = opts.ports.iter().map(|p| p.to_string()).collect();
+        let unit = ServiceFile::render(
+            ServiceKind::Systemd,
+            &ports,
+            Path::new("/opt/port zap/portzap"),
+            &command_args(&opts),
+        );
+        assert_eq!(unit.name, "portzap-watch-3000_8000-8010.service");
+        assert!(unit
+            .contents
+            .contains("Description=portzap watch: keep ports 3000, 8000-8010 free\n"));
+        assert!(unit.contents.contains("Restart=on-failure\n"));
+
+        let exec = unit
+            .contents
+            .lines()
+            .find_map(|line| line.strip_prefix("ExecStart="))
+            .unwrap();
+        let words = unquote(exec);
+        assert_eq!(words[0], "/opt/port zap/portzap");
+        let cli = Cli::try_parse_from(&words).unwrap();
+        let Some(Commands::Watch(args)) = cli.command else {
+            panic!("not a watch command: {exec}");
+        };
+        assert_eq!(args.ports, ["3000", "8000-8010"]);
+        assert!(matches!(args.signal, Some(Signal::Kill)));
+        assert!(args.no_graceful && !args.graceful);
+        assert_eq!(
+            (args.timeout, args.poll),
+            (Some(Duration::from_secs(3)), Some(250))
+        );
+        assert_eq!(args.allow_names, ["my db", "100%"]);
+        assert_eq!(args.rules, ["80:graceful=1500ms"]);
+        assert!(!args.daemon);
+    }
+
+    #[test]
+    fn launchd_plist_lists_every_argument() {
+        let plist = ServiceFile::render(
+            ServiceKind::Launchd,
+            &["3000".into()],
+            Path::new("/usr/local/bin/portzap"),
+            &[
+                "watch".into(),
+                "3000".into(),
+                "--allow-name".into(),
+                "a&b".into(),
+            ],
+        );
+        assert_eq!(plist.name, "com.portzap.watch.3000");
+        assert!(plist.contents.contains(
+            "\t\t<string>/usr/local/bin/portzap</string>\n\
+             \t\t<string>watch</string>\n\
+             \t\t<string>3000</string>\n\
+             \t\t<string>--allow-name</string>\n\
+             \t\t<string>a&amp;b</string>\n"
+        ));
+        assert!(plist
+            .enable_command(Path::new("/Users/me/Library/LaunchAgents/x.plist"))
+            .starts_with("launchctl bootstrap gui/"));
+    }
+}
@@ -1,25 +1,95 @@
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// How long to wait for the terminal to report its background colour.
+/// Terminals that answer do so within a few milliseconds.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// How much longer a terminal that missed [`QUERY_TIMEOUT`] gets to finish
+/// answering before its input is flushed.
+#[cfg(unix)]
+const LATE_REPLY_GRACE: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub enum ThemeVariant {
+    /// Light or dark, whichever suits the terminal background.
+    #[serde(rename = "auto")]
+    #[default]
+    Auto,
     #[serde(rename = "dark")]
     Dark,
     #[serde(rename = "light")]
     Light,
-}
-
-impl Default for ThemeVariant {
-    fn default() -> Self {
-        ThemeVariant::Dark
-    }
+    #[serde(rename = "solarized")]
+    Solarized,
+    #[serde(rename = "dracula")]
+    Dracula,
+    #[serde(rename = "gruvbox")]
+    Gruvbox,
+    #[serde(rename = "nord")]
+    Nord,
 }
 
 impl ThemeVariant {
+    /// Every concrete theme, in the order `t` cycles through them.
+    pub const ALL: [ThemeVariant; 6] = [
+        ThemeVariant::Dark,
+        ThemeVariant::Light,
+        ThemeVariant::Solarized,
+        ThemeVariant::Dracula,
+        ThemeVariant::Gruvbox,
+        ThemeVariant::Nord,
+    ];
+
+    /// Their names in config.toml, in the same order after "auto".
+    pub const NAMES: [&'static str; 7] = [
+        "auto",
+        "dark",
+        "light",
+        "solarized",
+        "dracula",
+        "gruvbox",
+        "nord",
+    ];
+
+    /// The next theme, wrapping around.
     pub fn toggle(&self) -> Self {
+        let i = Self::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Name for the status bar, e.g. "Solarized".
+    pub fn label(self) -> &'static str {
         match self {
-            ThemeVariant::Dark => ThemeVariant::Light,
-            ThemeVariant::Light => ThemeVariant::Dark,
+            ThemeVariant::Auto => "Auto",
+            ThemeVariant::Dark => "Dark",
+            ThemeVariant::Light => "Light",
+            ThemeVariant::Solarized => "Solarized",
+            ThemeVariant::Dracula => "Dracula",
+            ThemeVariant::Gruvbox => "Gruvbox",
+            ThemeVariant::Nord => "Nord",
+        }
+    }
+
+    /// Pick light or dark for `Auto` by asking the terminal; other
+    /// variants are returned as they are.
+    pub fn resolve(self) -> Self {
+        match self {
+            ThemeVariant::Auto => detect(),
+            variant => variant,
+        }
+    }
+
+    /// The palette. `Auto` that hasn't been resolved uses the dark one.
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeVariant::Auto | ThemeVariant::Dark => Theme::dark(),
+            ThemeVariant::Light => Theme::light(),
+            ThemeVariant::Solarized => Theme::solarized(),
+            ThemeVariant::Dracula => Theme::dracula(),
+            ThemeVariant::Gruvbox => Theme::gruvbox(),
+            ThemeVariant::Nord => Theme::nord(),
         }
     }
 }
@@ -118,4 +188,335 @@ impl Theme {
             command_color: Color::Rgb(100, 100, 130),
         }
     }
+
+    /// Solarized dark, by Ethan Schoonover
+    pub fn solarized() -> Self {
+        Self {
+            background: Color::Rgb(0, 43, 54),
+            background_secondary: Color::Rgb(3, 48, 60),
+            background_tertiary: Color::Rgb(7, 54, 66),
+
+            text_default: Color::Rgb(147, 161, 161),
+            text_secondary: Color::Rgb(131, 148, 150),
+            text_tertiary: Color::Rgb(88, 110, 117),
+
+            accent_secondary: Color::Rgb(181, 137, 0),
+            accent_tertiary: Color::Rgb(88, 110, 117),
+
+            success: Color::Rgb(133, 153, 0),
+            error: Color::Rgb(220, 50, 47),
+            info: Color::Rgb(38, 139, 210),
+
+            border: Color::Rgb(88, 110, 117),
+            header_title: Color::Rgb(203, 75, 22),
+            highlight_bg: Color::Rgb(7, 54, 66),
+            highlight_fg: Color::Rgb(203, 75, 22),
+            selected_fg: Color::Rgb(181, 137, 0),
+            port_fg: Color::Rgb(203, 75, 22),
+            port_selected_fg: Color::Rgb(181, 137, 0),
+            tcp_color: Color::Rgb(42, 161, 152),
+            udp_color: Color::Rgb(108, 113, 196),
+            command_color: Color::Rgb(101, 123, 131),
+        }
+    }
+
+    pub fn dracula() -> Self {
+        Self {
+            background: Color::Rgb(40, 42, 54),
+            background_secondary: Color::Rgb(33, 34, 44),
+            background_tertiary: Color::Rgb(52, 55, 70),
+
+            text_default: Color::Rgb(248, 248, 242),
+            text_secondary: Color::Rgb(191, 194, 214),
+            text_tertiary: Color::Rgb(98, 114, 164),
+
+            accent_secondary: Color::Rgb(241, 250, 140),
+            accent_tertiary: Color::Rgb(98, 114, 164),
+
+            success: Color::Rgb(80, 250, 123),
+            error: Color::Rgb(255, 85, 85),
+            info: Color::Rgb(139, 233, 253),
+
+            border: Color::Rgb(68, 71, 90),
+            header_title: Color::Rgb(255, 121, 198),
+            highlight_bg: Color::Rgb(68, 71, 90),
+            highlight_fg: Color::Rgb(255, 184, 108),
+            selected_fg: Color::Rgb(241, 250, 140),
+            port_fg: Color::Rgb(255, 184, 108),
+            port_selected_fg: Color::Rgb(241, 250, 140),
+            tcp_color: Color::Rgb(139, 233, 253),
+            udp_color: Color::Rgb(189, 147, 249),
+            command_color: Color::Rgb(98, 114, 164),
+        }
+    }
+
+    /// Gruvbox dark, medium contrast
+    pub fn gruvbox() -> Self {
+        Self {
+            background: Color::Rgb(40, 40, 40),
+            background_secondary: Color::Rgb(29, 32, 33),
+            background_tertiary: Color::Rgb(60, 56, 54),
+
+            text_default: Color::Rgb(235, 219, 178),
+            text_secondary: Color::Rgb(189, 174, 147),
+            text_tertiary: Color::Rgb(146, 131, 116),
+
+            accent_secondary: Color::Rgb(250, 189, 47),
+            accent_tertiary: Color::Rgb(124, 111, 100),
+
+            success: Color::Rgb(184, 187, 38),
+            error: Color::Rgb(251, 73, 52),
+            info: Color::Rgb(131, 165, 152),
+
+            border: Color::Rgb(80, 73, 69),
+            header_title: Color::Rgb(254, 128, 25),
+            highlight_bg: Color::Rgb(60, 56, 54),
+            highlight_fg: Color::Rgb(254, 128, 25),
+            selected_fg: Color::Rgb(250, 189, 47),
+            port_fg: Color::Rgb(254, 128, 25),
+            port_selected_fg: Color::Rgb(250, 189, 47),
+            tcp_color: Color::Rgb(142, 192, 124),
+            udp_color: Color::Rgb(211, 134, 155),
+            command_color: Color::Rgb(146, 131, 116),
+        }
+    }
+
+    pub fn nord() -> Self {
+        Self {
+            background: Color::Rgb(46, 52, 64),
+            background_secondary: Color::Rgb(59, 66, 82),
+            background_tertiary: Color::Rgb(67, 76, 94),
+
+            text_default: Color::Rgb(236, 239, 244),
+            text_secondary: Color::Rgb(216, 222, 233),
+            text_tertiary: Color::Rgb(97, 110, 136),
+
+            accent_secondary: Color::Rgb(235, 203, 139),
+            accent_tertiary: Color::Rgb(76, 86, 106),
+
+            success: Color::Rgb(163, 190, 140),
+            error: Color::Rgb(191, 97, 106),
+            info: Color::Rgb(136, 192, 208),
+
+            border: Color::Rgb(76, 86, 106),
+            header_title: Color::Rgb(136, 192, 208),
+            highlight_bg: Color::Rgb(67, 76, 94),
+            highlight_fg: Color::Rgb(208, 135, 112),
+            selected_fg: Color::Rgb(235, 203, 139),
+            port_fg: Color::Rgb(208, 135, 112),
+            port_selected_fg: Color::Rgb(235, 203, 139),
+            tcp_color: Color::Rgb(129, 161, 193),
+            udp_color: Color::Rgb(180, 142, 173),
+            command_color: Color::Rgb(97, 110, 136),
+        }
+    }
+}
+
+/// Light or dark for the terminal's background: its answer to an OSC 11
+/// query, else `COLORFGBG`, else dark. Expects raw mode, so the reply isn't
+/// echoed, and gives up after [`QUERY_TIMEOUT`].
+pub fn detect() -> ThemeVariant {
+    let (variant, source) = if let Some(rgb) = query_background() {
+        (variant_for(rgb), "OSC 11")
+    } else if let Some(light) = std::env::var("COLORFGBG")
+        .ok()
+        .and_then(|v| colorfgbg_is_light(&v))
+    {
+        let variant = if light {
+            ThemeVariant::Light
+        } else {
+            ThemeVariant::Dark
+        };
+        (variant, "COLORFGBG")
+    } else {
+        (ThemeVariant::Dark, "default")
+    };
+    tracing::debug!(
+        theme = variant.label(),
+        source,
+        "detected terminal background"
+    );
+    variant
+}
+
+fn variant_for((r, g, b): (f32, f32, f32)) -> ThemeVariant {
+    if 0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5 {
+        ThemeVariant::Light
+    } else {
+        ThemeVariant::Dark
+    }
+}
+
+/// Ask the terminal for its background with OSC 11, followed by a device
+/// attributes request that nearly every terminal answers. Once that answer
+/// arrives there's no point waiting for an OSC 11 reply that isn't coming.
+/// A reply still on its way at the timeout is read and thrown away, or
+/// flushed, so the TUI doesn't take it for key presses.
+#[cfg(unix)]
+fn query_background() -> Option<(f32, f32, f32)> {
+    use nix::sys::termios::{tcflush, FlushArg};
+    use std::io::{IsTerminal, Write};
+    use std::time::Instant;
+
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    tty.write_all(b"\x1b]11;?\x07\x1b[c").ok()?;
+    tty.flush().ok()?;
+
+    let mut reply = Vec::new();
+    read_reply(&mut tty, &mut reply, Instant::now() + QUERY_TIMEOUT);
+    let background = parse_osc11(&String::from_utf8_lossy(&reply));
+    if !answered_device_attributes(&reply) {
+        tracing::debug!("terminal didn't answer the background query");
+        read_reply(&mut tty, &mut reply, Instant::now() + LATE_REPLY_GRACE);
+        let _ = tcflush(&tty, FlushArg::TCIFLUSH);
+    }
+    background
+}
+
+/// Add what the terminal sends to `reply` until it has answered the device
+/// attributes request, `deadline` passes or the tty can't be read.
+#[cfg(unix)]
+fn read_reply(tty: &mut std::fs::File, reply: &mut Vec<u8>, deadline: std::time::Instant) {
+    use nix::poll::{poll, PollFd, PollFlags};
+    use std::io::Read;
+    use std::os::fd::AsFd;
+    use std::time::Instant;
+
+    let mut buf = [0u8; 64];
+    while !answered_device_attributes(reply) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let millis = u16::try_from(left.as_millis()).unwrap_or(u16::MAX);
+        if millis == 0 {
+            return;
+        }
+        let mut fds = [PollFd::new(tty.as_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, millis) {
+            Ok(0) => continue,
+            Ok(_) => {}
+            Err(_) => return,
+        }
+        match tty.read(&mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => reply.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<(f32, f32, f32)> {
+    None
+}
+
+/// Whether `reply` holds the answer to `ESC [ c`: `ESC [ ? ... c`.
+#[cfg(unix)]
+fn answered_device_attributes(reply: &[u8]) -> bool {
+    reply
+        .windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|start| reply[start..].contains(&b'c'))
+}
+
+/// The colour in an OSC 11 reply, `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` ended by
+/// BEL or ST, as fractions of full brightness. Channels may have 1–4 hex
+/// digits.
+fn parse_osc11(reply: &str) -> Option<(f32, f32, f32)> {
+    let start = reply.find("\x1b]11;rgb:")? + "\x1b]11;rgb:".len();
+    let rest = &reply[start..];
+    let end = rest.find(['\x07', '\x1b']).unwrap_or(rest.len());
+    let mut channels = rest[..end].split('/').map(|hex| {
+        if hex.is_empty() || hex.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        Some(value as f32 / ((1u32 << (4 * hex.len())) - 1) as f32)
+    });
+    let rgb = (channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(rgb)
+}
+
+/// Whether `COLORFGBG` ("15;0", "0;default;15") names a light background.
+/// The last field is the background as an ANSI colour index; 7 and 9–15
+/// are the light ones.
+fn colorfgbg_is_light(value: &str) -> Option<bool> {
+    let bg: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+    Some(matches!(bg, 7 | 9..=15))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_visits_every_theme_once() {
+        let mut variant = ThemeVariant::Dark;
+        let mut seen = Vec::new();
+        for _ in ThemeVariant::ALL {
+            seen.push(variant);
+            variant = variant.toggle();
+        }
+        assert_eq!(seen, ThemeVariant::ALL);
+        assert_eq!(variant, ThemeVariant::Dark);
+    }
+
+    #[test]
+    fn names_match_config_values() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            theme: ThemeVariant,
+        }
+        let variants = std::iter::once(ThemeVariant::Auto).chain(ThemeVariant::ALL);
+        for (variant, name) in variants.zip(ThemeVariant::NAMES) {
+            let parsed: Wrapper = toml::from_str(&format!("theme = \"{name}\"")).unwrap();
+            assert_eq!(parsed.theme, variant);
+        }
+    }
+
+    #[test]
+    fn reads_background_from_osc11_replies() {
+        let white = parse_osc11("\x1b]11;rgb:ffff/ffff/ffff\x07").unwrap();
+        assert_eq!(white, (1.0, 1.0, 1.0));
+        assert_eq!(variant_for(white), ThemeVariant::Light);
+
+        // Two-digit channels, ST terminator, device attributes after it.
+        let reply = "\x1b]11;rgb:1e/1e/2e\x1b\\\x1b[?62;22c";
+        let dark = parse_osc11(reply).unwrap();
+        assert_eq!(variant_for(dark), ThemeVariant::Dark);
+
+        assert_eq!(parse_osc11("\x1b[?1;2c"), None);
+        assert_eq!(parse_osc11("\x1b]11;rgb:ffff/ffff\x07"), None);
+        assert_eq!(parse_osc11("\x1b]11;rgb:fffff/0/0\x07"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn device_attributes_end_the_query() {
+        assert!(answered_device_attributes(
+            b"\x1b]11;rgb:0/0/0\x07\x1b[?62;22c"
+        ));
+        assert!(!answered_device_attributes(b"\x1b]11;rgb:0/0/0\x07"));
+        assert!(!answered_device_attributes(b"\x1b[?62;22"));
+    }
+
+    #[test]
+    fn reads_background_from_colorfgbg() {
+        assert_eq!(colorfgbg_is_light("15;0"), Some(false));
+        assert_eq!(colorfgbg_is_light("0;15"), Some(true));
+        assert_eq!(colorfgbg_is_light("0;default;7"), Some(true));
+        assert_eq!(colorfgbg_is_light("7;8"), Some(false));
+        assert_eq!(colorfgbg_is_light("default"), None);
+        assert_eq!(colorfgbg_is_light(""), None);
+    }
+
+    #[test]
+    fn auto_toggles_into_the_cycle() {
+        assert_eq!(ThemeVariant::Dark.resolve(), ThemeVariant::Dark);
+        assert!(ThemeVariant::ALL.contains(&ThemeVariant::Auto.toggle()));
+    }
 }
@@ -1,8 +1,18 @@
 use crate::config::Config;
+use crate::diff::ProcessDiff;
+use crate::errors::KillportError;
+use crate::filter::ProcessFilter;
+use crate::format;
+use crate::history::{History, Source};
 use crate::killer::{self, KillConfig};
-use crate::process::{ProcessInfo, Protocol};
-use crate::scanner::create_scanner;
-use crate::theme::Theme;
+use crate::output;
+use crate::portinfo::{self, PortCategory};
+use crate::process::{KillErrorKind, KillResult, PortSpec, ProcessInfo, Protocol};
+use crate::resources::{self, ResourceSampler, ResourceUsage};
+use crate::scanner::{create_scanner, without_self, ScanResult, ScanWarning};
+use crate::theme::{Theme, ThemeVariant};
+
+use chrono::{DateTime, Local};
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use crossterm::execute;
@@ -11,36 +21,322 @@ use crossterm::terminal::{
 };
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Alignment, Constraint, Layout, Rect};
-use ratatui::style::{Modifier, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{
-    Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table, TableState,
+    Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap,
 };
 use ratatui::Terminal;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
 use std::io::{self, stdout};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc};
+use std::thread;
 use std::time::{Duration, Instant};
 
-const REFRESH_RATE: Duration = Duration::from_secs(2);
 const TICK_RATE: Duration = Duration::from_millis(100);
+const ACTIVITY_LOG_CAPACITY: usize = 500;
+const CONFIRM_MAX_LISTED: usize = 8;
+const FILTER_HISTORY_CAPACITY: usize = 20;
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+/// Below this the dashboard only says the terminal is too small.
+const MIN_SIZE: (u16, u16) = (20, 6);
+/// Below this height the header loses its border.
+const COMPACT_HEIGHT: u16 = 16;
+/// The table drops the Command column, then Proto, below these widths.
+const COMMAND_MIN_WIDTH: u16 = 90;
+const PROTO_MIN_WIDTH: u16 = 70;
+/// How long a row that appeared in a refresh stays highlighted.
+const NEW_ROW_HIGHLIGHT: Duration = Duration::from_secs(3);
+
+/// What identifies a row across refreshes; see `ProcessInfo::key`.
+type RowKey = (u32, u16, Protocol);
+
+/// Rows PageUp/PageDown move the help overlay by.
+const HELP_PAGE: isize = 10;
+
+/// What a key does in the process list; see `App::main_action`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    ClearFilterText,
+    Quit,
+    Move(i32),
+    Top,
+    Bottom,
+    ToggleSelection,
+    SelectAll,
+    Visual,
+    InvertSelection,
+    Zap,
+    ZapFiltered,
+    SudoRetry,
+    Refresh,
+    Sort { backwards: bool },
+    Pause,
+    Filter,
+    CycleProtocol,
+    CommandLine,
+    Export(ExportFormat),
+    ClearFilters,
+    Theme,
+    ActivityLog,
+    Details,
+    Help,
+}
+
+/// One row of the key reference. The hints bar shows the rows with a
+/// `hint`, the help overlay lists them all, and `codes` are the keys
+/// `App::main_action` answers to.
+struct KeyBinding {
+    keys: &'static str,
+    codes: &'static [KeyCode],
+    help: &'static str,
+    hint: Option<&'static str>,
+    /// Shown in the error colour: the key kills something
+    danger: bool,
+    /// Only listed while this holds, like `Z` after a denied zap
+    when: Option<fn(&App) -> bool>,
+}
+
+impl KeyBinding {
+    const fn new(keys: &'static str, codes: &'static [KeyCode], help: &'static str) -> Self {
+        Self {
+            keys,
+            codes,
+            help,
+            hint: None,
+            danger: false,
+            when: None,
+        }
+    }
+
+    const fn hint(mut self, hint: &'static str) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    const fn danger(mut self) -> Self {
+        self.danger = true;
+        self
+    }
+
+    const fn when(mut self, when: fn(&App) -> bool) -> Self {
+        self.when = Some(when);
+        self
+    }
+}
+
+/// The process list's keys, in the order the hints bar and help show them.
+const KEY_BINDINGS: &[KeyBinding] = &[
+    KeyBinding::new(
+        "↑↓/jk",
+        &[
+            KeyCode::Up,
+            KeyCode::Down,
+            KeyCode::Char('k'),
+            KeyCode::Char('j'),
+        ],
+        "Move selection up/down",
+    )
+    .hint("navigate"),
+    KeyBinding::new(
+        "g/G",
+        &[
+            KeyCode::Home,
+            KeyCode::End,
+            KeyCode::Char('g'),
+            KeyCode::Char('G'),
+        ],
+        "Jump to top/bottom (also Home/End)",
+    ),
+    KeyBinding::new("Space", &[KeyCode::Char(' ')], "Toggle select process").hint("select"),
+    KeyBinding::new("a", &[KeyCode::Char('a')], "Select/deselect all").hint("all"),
+    KeyBinding::new(
+        "V",
+        &[KeyCode::Char('V')],
+        "Select a range (move, then Space)",
+    )
+    .hint("range"),
+    KeyBinding::new("I", &[KeyCode::Char('I')], "Invert selection in the view"),
+    KeyBinding::new(
+        "x/Enter",
+        &[KeyCode::Char('x'), KeyCode::Enter],
+        "Zap selected processes",
+    )
+    .hint("zap")
+    .danger(),
+    KeyBinding::new(
+        "X",
+        &[KeyCode::Char('X')],
+        "Zap everything matching the filter",
+    )
+    .hint("zap filtered")
+    .danger(),
+    KeyBinding::new(
+        "Z",
+        &[KeyCode::Char('Z')],
+        "Retry permission-denied zaps with sudo",
+    )
+    .hint("sudo retry")
+    .danger()
+    .when(App::can_sudo_retry),
+    KeyBinding::new("/", &[KeyCode::Char('/')], "Search/filter processes").hint("filter"),
+    KeyBinding::new(
+        "u",
+        &[KeyCode::Char('u')],
+        "Cycle protocol filter (all/TCP/UDP)",
+    )
+    .hint("proto"),
+    KeyBinding::new("c", &[KeyCode::Char('c')], "Clear all filters"),
+    KeyBinding::new(
+        ":",
+        &[KeyCode::Char(':')],
+        "Command: :3000 jump, :kill 3000, :q",
+    ),
+    KeyBinding::new(
+        "e/E",
+        &[KeyCode::Char('e'), KeyCode::Char('E')],
+        "Export view as JSON / CSV",
+    ),
+    KeyBinding::new(
+        "s/S",
+        &[KeyCode::Char('s'), KeyCode::Char('S')],
+        "Cycle sort column forward/back",
+    )
+    .hint("sort"),
+    KeyBinding::new("r", &[KeyCode::Char('r')], "Refresh process list").hint("refresh"),
+    KeyBinding::new("p", &[KeyCode::Char('p')], "Pause/resume auto-refresh").hint("pause"),
+    KeyBinding::new("t", &[KeyCode::Char('t')], "Toggle theme").hint("theme"),
+    KeyBinding::new(
+        "i",
+        &[KeyCode::Char('i')],
+        "Details of the selected process",
+    )
+    .hint("info"),
+    KeyBinding::new("L", &[KeyCode::Char('L')], "Show activity log").hint("log"),
+    KeyBinding::new("?", &[KeyCode::Char('?')], "Toggle this help").hint("help"),
+    KeyBinding::new(
+        "q/Esc",
+        &[KeyCode::Char('q'), KeyCode::Esc],
+        "Quit (Esc clears the filter first)",
+    )
+    .hint("quit"),
+];
 
 struct App {
     processes: Vec<ProcessInfo>,
+    resources: HashMap<u32, ResourceUsage>,
+    sampler: ResourceSampler,
     table_state: TableState,
-    selected: HashSet<usize>,
+    /// Rows picked for a zap, by `ProcessInfo::key` so picks survive
+    /// re-sorting and refreshes
+    selected: HashSet<RowKey>,
+    /// Where `V` started a range; the range runs to the cursor
+    visual_anchor: Option<RowKey>,
     should_quit: bool,
+    /// When the last scan finished, successfully or not
     last_refresh: Instant,
+    /// When the last scan succeeded; `None` before the first one
+    last_success: Option<Instant>,
+    /// Why the last scan failed; the rows shown are from an older one
+    scan_error: Option<String>,
+    /// The scan running on a background thread, and when it started
+    scan: Option<(mpsc::Receiver<crate::errors::Result<ScanResult>>, Instant)>,
+    refresh_interval: Duration,
+    paused: bool,
     status_message: Option<(String, Instant, StatusKind)>,
+    /// What the last scan couldn't see, shown in the status bar
+    scan_warnings: Vec<ScanWarning>,
     show_help: bool,
+    help_scroll: usize,
     sort_column: SortColumn,
     sort_ascending: bool,
     filter_text: String,
-    filter_mode: bool,
+    filter: ProcessFilter,
+    filter_error: Option<String>,
+    /// The filter being edited after `/`
+    filter_edit: Option<FilterEdit>,
+    /// Filters applied with Enter, newest first
+    filter_history: VecDeque<String>,
+    protocol_filter: ProtocolFilter,
+    export_prompt: Option<ExportPrompt>,
+    command_line: Option<CommandLine>,
     config: Config,
+    /// Where kills are recorded, if history is enabled
+    history: Option<History>,
     theme: Theme,
+    /// The theme on screen, which `gui --theme` sets apart from the config's
+    theme_variant: ThemeVariant,
+    /// The OS ephemeral range, for dimming auto-assigned ports
+    ephemeral: (u16, u16),
     show_confirm_dialog: bool,
-    confirm_target_count: usize,
+    confirm_targets: Vec<ProcessInfo>,
+    confirm_filter: Option<String>,
     zapping: Vec<(usize, Instant)>,
+    /// Zapped rows, so their disappearance isn't reported as "gone"
+    zapped: HashSet<RowKey>,
+    /// Rows that appeared in a refresh, and when
+    appeared: Vec<(RowKey, Instant)>,
+    activity_log: VecDeque<ActivityEntry>,
+    show_activity_log: bool,
+    activity_scroll: usize,
+    show_details: bool,
+    /// Processes the last zap wasn't allowed to signal, for `Z`
+    denied: Vec<ProcessInfo>,
+    /// `Z` was pressed; the event loop runs sudo, which may need the terminal
+    sudo_requested: bool,
+}
+
+/// A single event recorded in the session activity log.
+struct ActivityEntry {
+    at: DateTime<Local>,
+    event: ActivityEvent,
+}
+
+enum ActivityEvent {
+    Kill(KillResult),
+    Skipped(ProcessInfo, &'static str),
+    RefreshError(String),
+}
+
+impl ActivityEntry {
+    fn describe(&self) -> String {
+        match &self.event {
+            ActivityEvent::Kill(r) => {
+                let outcome = if r.error_kind == Some(KillErrorKind::ProcessGone) {
+                    "already exited".to_string()
+                } else if r.success {
+                    "zapped".to_string()
+                } else {
+                    format!("failed: {}", r.error.as_deref().unwrap_or("unknown error"))
+                };
+                format!(
+                    "{} (PID {}) on port {}/{} [{}] {}",
+                    r.process.name,
+                    r.process.pid,
+                    r.process.port,
+                    r.process.protocol,
+                    r.signal_sent,
+                    outcome
+                )
+            }
+            ActivityEvent::Skipped(p, reason) => format!(
+                "{} (PID {}) on port {}/{} skipped ({reason})",
+                p.name, p.pid, p.port, p.protocol
+            ),
+            ActivityEvent::RefreshError(e) => format!("refresh failed: {e}"),
+        }
+    }
+
+    fn is_error(&self) -> bool {
+        match &self.event {
+            ActivityEvent::Kill(r) => !r.success,
+            ActivityEvent::Skipped(..) => false,
+            ActivityEvent::RefreshError(_) => true,
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -50,62 +346,280 @@ enum StatusKind {
     Info,
 }
 
+/// Vim-style `:` command being typed, plus the cursor row to restore on Esc.
+struct CommandLine {
+    input: String,
+    saved_row: Option<usize>,
+}
+
+/// State of the filter bar while editing. The text itself is `filter_text`,
+/// so the table filters as you type.
+struct FilterEdit {
+    /// The filter before editing, restored by Esc
+    saved: String,
+    /// Cursor position in characters
+    cursor: usize,
+    /// Which history entry is shown, and the text typed before browsing
+    browsing: Option<(usize, String)>,
+}
+
+/// Pending export of the current view; `path` is edited in the input line.
+struct ExportPrompt {
+    format: ExportFormat,
+    path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ProtocolFilter {
+    Both,
+    Tcp,
+    Udp,
+}
+
+impl ProtocolFilter {
+    fn cycle(self) -> Self {
+        match self {
+            ProtocolFilter::Both => ProtocolFilter::Tcp,
+            ProtocolFilter::Tcp => ProtocolFilter::Udp,
+            ProtocolFilter::Udp => ProtocolFilter::Both,
+        }
+    }
+
+    fn matches(self, protocol: Protocol) -> bool {
+        match self {
+            ProtocolFilter::Both => true,
+            ProtocolFilter::Tcp => protocol == Protocol::Tcp,
+            ProtocolFilter::Udp => protocol == Protocol::Udp,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ProtocolFilter::Both => "all",
+            ProtocolFilter::Tcp => "TCP",
+            ProtocolFilter::Udp => "UDP",
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 enum SortColumn {
     Port,
     Pid,
     Name,
     Protocol,
+    Age,
+    User,
+    Command,
+    Memory,
+    Cpu,
+}
+
+impl SortColumn {
+    /// The order `s` steps through; the resource columns are only shown
+    /// when usage is collected.
+    fn cycle(show_usage: bool) -> &'static [SortColumn] {
+        const ALL: [SortColumn; 9] = [
+            SortColumn::Port,
+            SortColumn::Pid,
+            SortColumn::Name,
+            SortColumn::Protocol,
+            SortColumn::Age,
+            SortColumn::User,
+            SortColumn::Command,
+            SortColumn::Memory,
+            SortColumn::Cpu,
+        ];
+        if show_usage {
+            &ALL
+        } else {
+            &ALL[..7]
+        }
+    }
 }
 
 impl App {
-    fn new() -> Self {
-        let config = Config::load();
-        let theme = match config.theme {
-            crate::theme::ThemeVariant::Dark => Theme::dark(),
-            crate::theme::ThemeVariant::Light => Theme::light(),
-        };
+    fn new(config: Config) -> Self {
+        let theme_variant = config.theme;
+
+        let refresh_interval = Duration::from_secs(config.refresh_secs.max(1));
 
         let mut app = Self {
             processes: Vec::new(),
+            resources: HashMap::new(),
+            sampler: ResourceSampler::new(),
             table_state: TableState::default(),
             selected: HashSet::new(),
+            visual_anchor: None,
             should_quit: false,
-            last_refresh: Instant::now() - REFRESH_RATE,
+            last_refresh: Instant::now() - refresh_interval,
+            last_success: None,
+            scan_error: None,
+            scan: None,
+            refresh_interval,
+            paused: false,
             status_message: None,
+            scan_warnings: Vec::new(),
             show_help: false,
+            help_scroll: 0,
             sort_column: SortColumn::Port,
             sort_ascending: true,
             filter_text: String::new(),
-            filter_mode: false,
+            filter: ProcessFilter::default(),
+            filter_error: None,
+            filter_edit: None,
+            filter_history: VecDeque::new(),
+            protocol_filter: ProtocolFilter::Both,
+            export_prompt: None,
+            command_line: None,
+            ephemeral: portinfo::ephemeral_or_default().0,
+            history: History::from_config(&config),
             config,
-            theme,
+            theme: theme_variant.theme(),
+            theme_variant,
             show_confirm_dialog: false,
-            confirm_target_count: 0,
+            confirm_targets: Vec::new(),
+            confirm_filter: None,
             zapping: Vec::new(),
+            zapped: HashSet::new(),
+            appeared: Vec::new(),
+            activity_log: VecDeque::new(),
+            show_activity_log: false,
+            activity_scroll: 0,
+            show_details: false,
+            denied: Vec::new(),
+            sudo_requested: false,
         };
         app.refresh_processes();
-        if !app.processes.is_empty() {
-            app.table_state.select(Some(0));
-        }
         app
     }
 
+    /// Start a scan on a background thread, unless one is already running.
+    /// `poll_scan` applies the result once it arrives.
     fn refresh_processes(&mut self) {
-        let scanner = create_scanner();
+        if self.scan.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            // The receiver is gone if the dashboard quit meanwhile.
+            let _ = tx.send(without_self(create_scanner()).scan_all());
+        });
+        self.scan = Some((rx, Instant::now()));
+    }
+
+    /// Apply the background scan if it has finished.
+    fn poll_scan(&mut self) {
+        let Some((rx, _)) = &self.scan else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err(KillportError::PlatformError(
+                "the scan thread stopped unexpectedly".into(),
+            )),
+        };
+        self.scan = None;
+        self.apply_scan(result);
+    }
+
+    /// Show a finished scan. A failed one keeps the previous rows, marked
+    /// stale, and the next cycle tries again.
+    fn apply_scan(&mut self, result: crate::errors::Result<ScanResult>) {
         let old_selection = self.current_process_key();
 
-        if let Ok(mut procs) = scanner.find_all_listening() {
-            self.sort_processes(&mut procs);
-            self.processes = procs;
+        match result {
+            Ok(scan) => {
+                let mut procs = scan.processes;
+                self.scan_warnings = scan.warnings;
+                if self.config.show_resource_usage {
+                    self.resources = self.sampler.sample(procs.iter().map(|p| p.pid));
+                }
+                self.sort_processes(&mut procs);
+                // The first scan has nothing to compare against
+                if self.last_success.is_some() {
+                    let diff = ProcessDiff::between(&self.processes, &procs);
+                    self.note_changes(&diff);
+                }
+                self.zapped.clear();
+                self.processes = procs;
+                self.last_success = Some(Instant::now());
+                self.scan_error = None;
+            }
+            Err(e) => {
+                let message = e.to_string();
+                // Report a failure once, not on every retry.
+                if self.scan_error.as_ref() != Some(&message) {
+                    self.set_status(&format!("Scan failed: {message}"), StatusKind::Error);
+                    self.log_activity(ActivityEvent::RefreshError(message.clone()));
+                }
+                self.scan_error = Some(message);
+            }
         }
 
         self.last_refresh = Instant::now();
-        self.selected.clear();
+        let live: HashSet<RowKey> = self.processes.iter().map(ProcessInfo::key).collect();
+        self.selected.retain(|key| live.contains(key));
+        self.restore_cursor(old_selection);
+    }
+
+    /// Highlight rows that appeared, and say what changed unless it was
+    /// only the processes we zapped going away.
+    fn note_changes(&mut self, diff: &ProcessDiff) {
+        let now = Instant::now();
+        self.appeared
+            .extend(diff.added.iter().map(|p| (p.key(), now)));
+        let gone: Vec<RowKey> = diff
+            .removed
+            .iter()
+            .map(ProcessInfo::key)
+            .filter(|key| !self.zapped.contains(key))
+            .collect();
+        if diff.added.is_empty() && gone.is_empty() {
+            return;
+        }
+
+        let mut parts = Vec::new();
+        if !diff.added.is_empty() {
+            parts.push(format!("+{} new", diff.added.len()));
+        }
+        if !gone.is_empty() {
+            parts.push(format!("-{} gone", gone.len()));
+        }
+        let mut msg = parts.join(", ");
+        let unselected = gone
+            .iter()
+            .filter(|key| self.selected.contains(key))
+            .count();
+        if unselected > 0 {
+            msg.push_str(&format!(
+                " ({unselected} selected process{} exited and left the selection)",
+                if unselected == 1 { "" } else { "es" }
+            ));
+        }
+        self.set_status(&msg, StatusKind::Info);
+    }
 
-        // Try to re-select the same process after refresh
-        if let Some((pid, port)) = old_selection {
-            let filtered = self.filtered_indices();
+    /// Put the cursor back on the process it was on, or keep it in range.
+    fn restore_cursor(&mut self, key: Option<(u32, u16)>) {
+        let filtered = self.filtered_indices();
+        if let Some((pid, port)) = key {
             for (table_row, &orig_idx) in filtered.iter().enumerate() {
                 let p = &self.processes[orig_idx];
                 if p.pid == pid && p.port == port {
@@ -115,26 +629,22 @@ impl App {
             }
         }
 
-        let filtered = self.filtered_indices();
-        if !filtered.is_empty() {
-            let sel = self.table_state.selected().unwrap_or(0);
-            if sel >= filtered.len() {
-                self.table_state.select(Some(filtered.len().saturating_sub(1)));
-            }
-        } else {
+        if filtered.is_empty() {
             self.table_state.select(None);
+        } else {
+            let sel = self.table_state.selected().unwrap_or(0);
+            self.table_state
+                .select(Some(sel.min(filtered.len().saturating_sub(1))));
         }
     }
 
+    /// When the background scan started, while one is running.
+    fn scan_started(&self) -> Option<Instant> {
+        self.scan.as_ref().map(|(_, started)| *started)
+    }
+
     fn current_process_key(&self) -> Option<(u32, u16)> {
-        let filtered = self.filtered_indices();
-        self.table_state
-            .selected()
-            .and_then(|i| filtered.get(i))
-            .map(|&idx| {
-                let p = &self.processes[idx];
-                (p.pid, p.port)
-            })
+        self.current_process().map(|p| (p.pid, p.port))
     }
 
     fn sort_processes(&self, procs: &mut [ProcessInfo]) {
@@ -148,36 +658,188 @@ impl App {
                     let pb = matches!(b.protocol, Protocol::Tcp);
                     pa.cmp(&pb)
                 }
+                // Youngest first; unknown start times sort as oldest
+                SortColumn::Age => b.started_at.cmp(&a.started_at),
+                SortColumn::User => a.user.cmp(&b.user),
+                SortColumn::Command => {
+                    let ca = a.command.as_deref().unwrap_or("").to_lowercase();
+                    let cb = b.command.as_deref().unwrap_or("").to_lowercase();
+                    ca.cmp(&cb)
+                }
+                SortColumn::Memory => self
+                    .usage(a.pid)
+                    .rss_bytes
+                    .cmp(&self.usage(b.pid).rss_bytes),
+                SortColumn::Cpu => self
+                    .usage(a.pid)
+                    .cpu_percent
+                    .unwrap_or(-1.0)
+                    .total_cmp(&self.usage(b.pid).cpu_percent.unwrap_or(-1.0)),
             };
-            if self.sort_ascending {
+            let ord = if self.sort_ascending {
                 ord
             } else {
                 ord.reverse()
-            }
+            };
+            // Ties keep a fixed order so equal rows don't swap between refreshes
+            ord.then(a.port.cmp(&b.port))
+                .then(a.pid.cmp(&b.pid))
+                .then(matches!(a.protocol, Protocol::Udp).cmp(&matches!(b.protocol, Protocol::Udp)))
         });
     }
 
-    fn filtered_indices(&self) -> Vec<usize> {
-        if self.filter_text.is_empty() {
-            return (0..self.processes.len()).collect();
+    /// The process under the cursor.
+    fn current_process(&self) -> Option<&ProcessInfo> {
+        let filtered = self.filtered_indices();
+        self.table_state
+            .selected()
+            .and_then(|i| filtered.get(i))
+            .map(|&idx| &self.processes[idx])
+    }
+
+    /// The port column's colour, by the same categories as `list` tables.
+    fn port_color(&self, port: u16, selected: bool) -> Color {
+        if selected {
+            return self.theme.port_selected_fg;
         }
-        let query = self.filter_text.to_lowercase();
+        match PortCategory::of(port, &self.config.highlight_ports, self.ephemeral) {
+            PortCategory::Privileged => self.theme.error,
+            PortCategory::Highlighted => self.theme.accent_secondary,
+            PortCategory::Ephemeral => self.theme.text_tertiary,
+            PortCategory::Other => self.theme.port_fg,
+        }
+    }
+
+    fn usage(&self, pid: u32) -> ResourceUsage {
+        self.resources.get(&pid).copied().unwrap_or_default()
+    }
+
+    fn filtered_indices(&self) -> Vec<usize> {
         self.processes
             .iter()
             .enumerate()
-            .filter(|(_, p)| {
-                p.name.to_lowercase().contains(&query)
-                    || p.port.to_string().contains(&query)
-                    || p.pid.to_string().contains(&query)
-                    || p.command
-                        .as_deref()
-                        .map(|c| c.to_lowercase().contains(&query))
-                        .unwrap_or(false)
-            })
+            .filter(|(_, p)| self.protocol_filter.matches(p.protocol))
+            .filter(|(_, p)| self.filter.matches(p))
+            .filter(|(_, p)| !(self.config.hide_unkillable && killer::needs_elevation(p)))
             .map(|(i, _)| i)
             .collect()
     }
 
+    /// Re-parse the filter text. On error the previous valid filter stays active
+    /// and the error is shown in the filter bar.
+    fn update_filter(&mut self) {
+        match ProcessFilter::parse(&self.filter_text) {
+            Ok(filter) => {
+                self.filter = filter;
+                self.filter_error = None;
+            }
+            Err(e) => self.filter_error = Some(e),
+        }
+        self.reset_cursor();
+    }
+
+    fn reset_cursor(&mut self) {
+        self.table_state
+            .select(if self.filtered_indices().is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+    }
+
+    fn cycle_protocol_filter(&mut self) {
+        self.protocol_filter = self.protocol_filter.cycle();
+        self.selected.clear();
+        self.reset_cursor();
+    }
+
+    fn start_filter_edit(&mut self) {
+        self.filter_edit = Some(FilterEdit {
+            saved: self.filter_text.clone(),
+            cursor: self.filter_text.chars().count(),
+            browsing: None,
+        });
+    }
+
+    /// Keys while the filter bar is being edited.
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        let Some(edit) = &mut self.filter_edit else {
+            return;
+        };
+        let text = &mut self.filter_text;
+        let len = text.chars().count();
+        let byte = |text: &str, at: usize| text.char_indices().nth(at).map_or(text.len(), |(i, _)| i);
+        match key.code {
+            KeyCode::Enter => {
+                let applied = text.trim().to_string();
+                self.filter_edit = None;
+                if !applied.is_empty() {
+                    self.filter_history.retain(|f| *f != applied);
+                    self.filter_history.push_front(applied);
+                    self.filter_history.truncate(FILTER_HISTORY_CAPACITY);
+                }
+                return;
+            }
+            KeyCode::Esc => {
+                *text = std::mem::take(&mut edit.saved);
+                self.filter_edit = None;
+            }
+            KeyCode::Left => edit.cursor = edit.cursor.saturating_sub(1),
+            KeyCode::Right => edit.cursor = (edit.cursor + 1).min(len),
+            KeyCode::Home => edit.cursor = 0,
+            KeyCode::End => edit.cursor = len,
+            KeyCode::Backspace if edit.cursor > 0 => {
+                edit.cursor -= 1;
+                text.remove(byte(text, edit.cursor));
+            }
+            KeyCode::Delete if edit.cursor < len => {
+                text.remove(byte(text, edit.cursor));
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let next = match (key.code, &edit.browsing) {
+                    (KeyCode::Up, None) => 0,
+                    (KeyCode::Up, Some((i, _))) => i + 1,
+                    (_, None) => return,
+                    (_, Some((0, _))) => {
+                        // Back past the newest entry to what was typed
+                        let (_, draft) = edit.browsing.take().unwrap();
+                        *text = draft;
+                        edit.cursor = text.chars().count();
+                        self.update_filter();
+                        return;
+                    }
+                    (_, Some((i, _))) => i - 1,
+                };
+                let Some(entry) = self.filter_history.get(next) else {
+                    return;
+                };
+                let draft = match edit.browsing.take() {
+                    Some((_, draft)) => draft,
+                    None => text.clone(),
+                };
+                edit.browsing = Some((next, draft));
+                *text = entry.clone();
+                edit.cursor = text.chars().count();
+            }
+            KeyCode::Char(c) => {
+                text.insert(byte(text, edit.cursor), c);
+                edit.cursor += 1;
+            }
+            _ => return,
+        }
+        self.update_filter();
+    }
+
+    fn clear_filters(&mut self) {
+        self.filter_text.clear();
+        self.filter = ProcessFilter::default();
+        self.filter_error = None;
+        self.protocol_filter = ProtocolFilter::Both;
+        self.selected.clear();
+        self.reset_cursor();
+        self.set_status("Filters cleared", StatusKind::Info);
+    }
+
     fn move_selection(&mut self, delta: i32) {
         let filtered = self.filtered_indices();
         if filtered.is_empty() {
@@ -190,40 +852,84 @@ impl App {
     }
 
     fn toggle_selection(&mut self) {
-        let filtered = self.filtered_indices();
-        if let Some(table_row) = self.table_state.selected() {
-            if let Some(&orig_idx) = filtered.get(table_row) {
-                if self.selected.contains(&orig_idx) {
-                    self.selected.remove(&orig_idx);
-                } else {
-                    self.selected.insert(orig_idx);
-                }
+        if let Some(key) = self.current_process().map(ProcessInfo::key) {
+            if !self.selected.remove(&key) {
+                self.selected.insert(key);
             }
         }
     }
 
+    /// Keys of the rows in the filtered view, top to bottom.
+    fn visible_keys(&self) -> Vec<RowKey> {
+        self.filtered_indices()
+            .into_iter()
+            .map(|idx| self.processes[idx].key())
+            .collect()
+    }
+
     fn select_all(&mut self) {
-        let filtered = self.filtered_indices();
-        if self.selected.len() == filtered.len() {
+        let visible = self.visible_keys();
+        if visible.iter().all(|key| self.selected.contains(key)) {
             self.selected.clear();
         } else {
-            self.selected = filtered.into_iter().collect();
+            self.selected.extend(visible);
         }
     }
 
-    fn kill_selected(&mut self) {
-        let filtered = self.filtered_indices();
-        let targets: Vec<usize> = if self.selected.is_empty() {
-            // Kill the currently highlighted one
-            self.table_state
-                .selected()
-                .and_then(|row| filtered.get(row).copied())
-                .into_iter()
-                .collect()
-        } else {
-            self.selected.iter().copied().collect()
+    /// Flip every row in the filtered view; rows hidden by the filter keep
+    /// their state.
+    fn invert_selection(&mut self) {
+        for key in self.visible_keys() {
+            if !self.selected.remove(&key) {
+                self.selected.insert(key);
+            }
+        }
+    }
+
+    fn start_visual(&mut self) {
+        self.visual_anchor = self.current_process().map(ProcessInfo::key);
+    }
+
+    /// Table rows between the `V` anchor and the cursor, while both are
+    /// visible.
+    fn visual_range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let anchor = self.visual_anchor?;
+        let anchor_row = self.visible_keys().iter().position(|&key| key == anchor)?;
+        let cursor = self.table_state.selected()?;
+        Some(anchor_row.min(cursor)..=anchor_row.max(cursor))
+    }
+
+    /// Add the visual range to the selection and leave visual mode.
+    fn commit_visual(&mut self) {
+        let Some(range) = self.visual_range() else {
+            self.visual_anchor = None;
+            self.set_status("Range start is no longer visible", StatusKind::Info);
+            return;
         };
+        let visible = self.visible_keys();
+        let count = range.clone().count();
+        self.selected
+            .extend(range.filter_map(|row| visible.get(row).copied()));
+        self.visual_anchor = None;
+        self.set_status(
+            &format!("Selected {count} row{}", if count == 1 { "" } else { "s" }),
+            StatusKind::Info,
+        );
+    }
+
+    /// Resolve the processes a kill would act on: the selection, or the highlighted row.
+    fn resolve_targets(&self) -> Vec<ProcessInfo> {
+        if self.selected.is_empty() {
+            return self.current_process().cloned().into_iter().collect();
+        }
+        self.processes
+            .iter()
+            .filter(|p| self.selected.contains(&p.key()))
+            .cloned()
+            .collect()
+    }
 
+    fn kill_targets(&mut self, targets: &[ProcessInfo]) {
         if targets.is_empty() {
             self.set_status("Nothing selected", StatusKind::Info);
             return;
@@ -232,30 +938,95 @@ impl App {
         let config = KillConfig::default();
         let mut killed = 0;
         let mut failed = 0;
+        let mut denied = 0;
+        let mut skipped = 0;
+        let mut results = Vec::new();
+
+        for target in targets {
+            if self.config.is_protected(target) {
+                skipped += 1;
+                self.log_activity(ActivityEvent::Skipped(target.clone(), "protected"));
+                continue;
+            }
 
-        for &idx in &targets {
-            if idx < self.processes.len() {
-                let result = killer::kill_process(&self.processes[idx], &config);
-                if result.success {
-                    killed += 1;
-                    // Add to zapping animation
-                    self.zapping.push((idx, Instant::now()));
-                } else {
-                    failed += 1;
+            let result = killer::kill_process(target, &config);
+            if result.success {
+                killed += 1;
+                self.start_zap_animation(target);
+            } else {
+                failed += 1;
+                if result.error_kind == Some(KillErrorKind::PermissionDenied) {
+                    denied += 1;
                 }
             }
+            results.push(result.clone());
+            self.log_activity(ActivityEvent::Kill(result));
         }
 
         self.selected.clear();
+        self.denied = results
+            .iter()
+            .filter(|r| r.error_kind == Some(KillErrorKind::PermissionDenied))
+            .map(|r| r.process.clone())
+            .collect();
+        let history_error = self.record_history(&results);
 
-        let msg = if failed == 0 {
-            format!("Zapped {} process{}", killed, if killed != 1 { "es" } else { "" })
-        } else {
+        let mut msg = if failed == 0 {
             format!(
-                "Zapped {}, {} failed (try sudo)",
-                killed, failed
+                "Zapped {} process{}",
+                killed,
+                if killed != 1 { "es" } else { "" }
             )
+        } else if denied > 0 && self.config.sudo_retry {
+            format!("Zapped {killed}, {failed} failed ({denied} permission denied, Z to retry with sudo)")
+        } else if denied > 0 {
+            format!("Zapped {killed}, {failed} failed ({denied} permission denied, try sudo)")
+        } else {
+            format!("Zapped {killed}, {failed} failed")
+        };
+        if skipped > 0 {
+            msg.push_str(&format!(", {skipped} skipped (protected)"));
+        }
+        if let Some(e) = history_error {
+            msg.push_str(&format!(" (history not saved: {e})"));
+        }
+        let kind = if failed == 0 {
+            StatusKind::Success
+        } else {
+            StatusKind::Error
+        };
+        self.set_status(&msg, kind);
+    }
+
+    /// Retry the kills that failed with permission denied through sudo.
+    /// `authorized` is false when the password prompt was cancelled.
+    fn retry_with_sudo(&mut self, authorized: bool) {
+        if !authorized {
+            self.set_status("sudo cancelled, nothing retried", StatusKind::Info);
+            return;
+        }
+        let targets = std::mem::take(&mut self.denied);
+        let config = KillConfig::default();
+        let mut results = Vec::new();
+        for target in &targets {
+            let result = killer::kill_with_sudo(target, &config);
+            if result.success {
+                self.start_zap_animation(target);
+            }
+            results.push(result.clone());
+            self.log_activity(ActivityEvent::Kill(result));
+        }
+
+        let killed = results.iter().filter(|r| r.success).count();
+        let failed = results.len() - killed;
+        let mut msg = if failed == 0 {
+            format!("Zapped {killed} with sudo")
+        } else {
+            format!("Zapped {killed} with sudo, {failed} failed (see the log, L)")
         };
+        if let Some(e) = self.record_history(&results) {
+            msg.push_str(&format!(" (history not saved: {e})"));
+        }
         let kind = if failed == 0 {
             StatusKind::Success
         } else {
@@ -264,12 +1035,36 @@ impl App {
         self.set_status(&msg, kind);
     }
 
-    fn cycle_sort(&mut self) {
-        let next = match self.sort_column {
-            SortColumn::Port => SortColumn::Pid,
-            SortColumn::Pid => SortColumn::Name,
-            SortColumn::Name => SortColumn::Protocol,
-            SortColumn::Protocol => SortColumn::Port,
+    fn start_zap_animation(&mut self, target: &ProcessInfo) {
+        self.zapped.insert(target.key());
+        if let Some(idx) = self
+            .processes
+            .iter()
+            .position(|p| p.pid == target.pid && p.port == target.port)
+        {
+            self.zapping.push((idx, Instant::now()));
+        }
+    }
+
+    /// Append kills to the history file if it's enabled. The terminal is in
+    /// raw mode, so errors are returned for the status bar.
+    fn record_history(&self, results: &[KillResult]) -> Option<io::Error> {
+        self.history
+            .as_ref()
+            .and_then(|history| history.record(results, Source::Tui).err())
+    }
+
+    /// Step to the next sort column, or with `backwards` the previous one.
+    fn cycle_sort(&mut self, backwards: bool) {
+        let cycle = SortColumn::cycle(self.config.show_resource_usage);
+        let current = cycle
+            .iter()
+            .position(|&c| c == self.sort_column)
+            .unwrap_or(0);
+        let next = if backwards {
+            cycle[(current + cycle.len() - 1) % cycle.len()]
+        } else {
+            cycle[(current + 1) % cycle.len()]
         };
         if next == self.sort_column {
             self.sort_ascending = !self.sort_ascending;
@@ -277,69 +1072,261 @@ impl App {
             self.sort_column = next;
             self.sort_ascending = true;
         }
-        self.refresh_processes();
+        let old_selection = self.current_process_key();
+        let mut procs = std::mem::take(&mut self.processes);
+        self.sort_processes(&mut procs);
+        self.processes = procs;
+        self.restore_cursor(old_selection);
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if self.paused {
+            self.set_status("Auto-refresh paused (r still refreshes)", StatusKind::Info);
+        } else {
+            self.set_status("Auto-refresh resumed", StatusKind::Info);
+        }
     }
 
     fn set_status(&mut self, msg: &str, kind: StatusKind) {
         self.status_message = Some((msg.to_string(), Instant::now(), kind));
     }
 
-    fn toggle_theme(&mut self) {
-        self.config.theme = self.config.theme.toggle();
-        self.theme = match self.config.theme {
-            crate::theme::ThemeVariant::Dark => Theme::dark(),
-            crate::theme::ThemeVariant::Light => Theme::light(),
-        };
-        if let Err(_) = self.config.save() {
-            self.set_status("Failed to save theme preference", StatusKind::Error);
-        } else {
-            let theme_name = match self.config.theme {
-                crate::theme::ThemeVariant::Dark => "Dark",
-                crate::theme::ThemeVariant::Light => "Light",
-            };
-            self.set_status(&format!("Switched to {} theme", theme_name), StatusKind::Info);
+    fn start_command_line(&mut self) {
+        self.command_line = Some(CommandLine {
+            input: String::new(),
+            saved_row: self.table_state.selected(),
+        });
+    }
+
+    fn run_command(&mut self, input: &str) {
+        let mut words = input.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (None, _, _) => {}
+            (Some("q") | Some("quit"), None, _) => self.should_quit = true,
+            (Some("kill"), Some(spec), None) => match PortSpec::parse(spec) {
+                Ok(spec) => self.request_port_kill(&spec),
+                Err(e) => self.set_status(&format!("Invalid port: {e}"), StatusKind::Error),
+            },
+            (Some(word), None, _) if word.parse::<u16>().is_ok() => {
+                self.jump_to_port(word.parse().unwrap_or(0));
+            }
+            _ => self.set_status(&format!("Unknown command: {input}"), StatusKind::Error),
         }
     }
 
-    fn request_kill_confirmation(&mut self) {
+    /// Move the cursor to the next row (wrapping) listening on `port`.
+    fn jump_to_port(&mut self, port: u16) {
         let filtered = self.filtered_indices();
-        let targets: Vec<usize> = if self.selected.is_empty() {
-            self.table_state
-                .selected()
-                .and_then(|row| filtered.get(row).copied())
-                .into_iter()
-                .collect()
-        } else {
-            self.selected.iter().copied().collect()
-        };
-
-        if targets.is_empty() {
-            self.set_status("Nothing selected", StatusKind::Info);
-            return;
+        let start = self.table_state.selected().map(|r| r + 1).unwrap_or(0);
+        let found = (0..filtered.len())
+            .map(|offset| (start + offset) % filtered.len())
+            .find(|&row| self.processes[filtered[row]].port == port);
+        match found {
+            Some(row) => self.table_state.select(Some(row)),
+            None => self.set_status(
+                &format!("Nothing listening on port {port}"),
+                StatusKind::Info,
+            ),
         }
+    }
 
-        if self.config.skip_confirm_dialog {
-            self.kill_selected();
+    fn request_port_kill(&mut self, spec: &PortSpec) {
+        let targets: Vec<ProcessInfo> = self
+            .processes
+            .iter()
+            .filter(|p| spec.contains(p.port))
+            .cloned()
+            .collect();
+        if targets.is_empty() {
+            self.set_status("No processes on that port", StatusKind::Info);
+        } else if self.may_skip_confirm(&targets) {
+            self.kill_targets(&targets);
         } else {
-            self.show_confirm_dialog = true;
-            self.confirm_target_count = targets.len();
+            self.confirm_kill(targets, None);
         }
     }
 
-    fn handle_key(&mut self, key: KeyEvent) {
-        if key.kind != KeyEventKind::Press {
+    fn start_export(&mut self, format: ExportFormat) {
+        let path = format!(
+            "portzap-{}.{}",
+            Local::now().format("%Y%m%d-%H%M%S"),
+            format.extension()
+        );
+        self.export_prompt = Some(ExportPrompt { format, path });
+    }
+
+    /// Write the currently filtered and sorted rows to the prompted path.
+    fn finish_export(&mut self) {
+        let Some(prompt) = self.export_prompt.take() else {
             return;
+        };
+        let rows: Vec<ProcessInfo> = self
+            .filtered_indices()
+            .into_iter()
+            .map(|idx| self.processes[idx].clone())
+            .collect();
+        let contents = match prompt.format {
+            ExportFormat::Json => output::processes_to_json(&rows),
+            ExportFormat::Csv => output::processes_to_csv(&rows),
+        };
+        match fs::write(&prompt.path, contents) {
+            Ok(()) => self.set_status(
+                &format!("Exported {} rows to {}", rows.len(), prompt.path),
+                StatusKind::Success,
+            ),
+            Err(e) => self.set_status(
+                &format!("Export to {} failed: {e}", prompt.path),
+                StatusKind::Error,
+            ),
         }
+    }
 
-        // Confirmation dialog captures input
-        if self.show_confirm_dialog {
-            match key.code {
-                KeyCode::Char('y') | KeyCode::Char('Y') => {
-                    self.show_confirm_dialog = false;
-                    self.kill_selected();
-                }
+    fn log_activity(&mut self, event: ActivityEvent) {
+        if self.activity_log.len() == ACTIVITY_LOG_CAPACITY {
+            self.activity_log.pop_front();
+        }
+        self.activity_log.push_back(ActivityEntry {
+            at: Local::now(),
+            event,
+        });
+    }
+
+    fn toggle_activity_log(&mut self) {
+        self.show_activity_log = !self.show_activity_log;
+        self.activity_scroll = 0;
+    }
+
+    /// Write the session's activity log (oldest first) to the config directory.
+    fn write_activity_log(&mut self) {
+        match self.save_activity_log() {
+            Ok(path) => self.set_status(
+                &format!("Activity log written to {}", path.display()),
+                StatusKind::Success,
+            ),
+            Err(e) => self.set_status(
+                &format!("Failed to write activity log: {e}"),
+                StatusKind::Error,
+            ),
+        }
+    }
+
+    fn save_activity_log(&self) -> io::Result<PathBuf> {
+        let mut path = Config::config_dir().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "could not determine config directory",
+            )
+        })?;
+        fs::create_dir_all(&path)?;
+        path.push(format!(
+            "activity-{}.log",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+
+        let contents: String = self
+            .activity_log
+            .iter()
+            .map(|entry| format!("{} {}\n", entry.at.to_rfc3339(), entry.describe()))
+            .collect();
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    fn toggle_theme(&mut self) {
+        self.theme_variant = self.theme_variant.toggle();
+        self.theme = self.theme_variant.theme();
+        self.config.theme = self.theme_variant;
+        if self.config.save().is_err() {
+            self.set_status("Failed to save theme preference", StatusKind::Error);
+        } else {
+            let label = self.theme_variant.label();
+            self.set_status(&format!("Switched to {label} theme"), StatusKind::Info);
+        }
+    }
+
+    fn request_kill_confirmation(&mut self) {
+        let targets = self.resolve_targets();
+
+        if targets.is_empty() {
+            self.set_status("Nothing selected", StatusKind::Info);
+            return;
+        }
+
+        if self.may_skip_confirm(&targets) {
+            self.kill_targets(&targets);
+        } else {
+            self.confirm_kill(targets, None);
+        }
+    }
+
+    /// `skip_confirm_dialog` never covers privileged ports.
+    fn may_skip_confirm(&self, targets: &[ProcessInfo]) -> bool {
+        self.config.skip_confirm_dialog && !targets.iter().any(|p| portinfo::is_privileged(p.port))
+    }
+
+    /// Kill every row in the filtered view. Always confirms, regardless of
+    /// `skip_confirm_dialog`, because the blast radius is the whole view.
+    fn request_zap_all_filtered(&mut self) {
+        let targets: Vec<ProcessInfo> = self
+            .filtered_indices()
+            .into_iter()
+            .map(|idx| self.processes[idx].clone())
+            .collect();
+
+        if targets.is_empty() {
+            self.set_status("Nothing matches the current filter", StatusKind::Info);
+            return;
+        }
+
+        let filter = self.filter_description();
+        self.confirm_kill(targets, Some(filter));
+    }
+
+    /// Ask before killing `targets`, unless they are all protected and
+    /// there is nothing left to ask about.
+    fn confirm_kill(&mut self, targets: Vec<ProcessInfo>, filter: Option<String>) {
+        if targets.iter().all(|p| self.config.is_protected(p)) {
+            let noun = if targets.len() == 1 { "process is" } else { "processes are" };
+            self.set_status(
+                &format!("Nothing to kill: {} {noun} protected", targets.len()),
+                StatusKind::Info,
+            );
+            return;
+        }
+        self.show_confirm_dialog = true;
+        self.confirm_targets = targets;
+        self.confirm_filter = filter;
+    }
+
+    fn filter_description(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.filter_text.is_empty() {
+            parts.push(self.filter_text.clone());
+        }
+        if self.protocol_filter != ProtocolFilter::Both {
+            parts.push(format!("proto:{}", self.protocol_filter.label()));
+        }
+        parts.join(" ")
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+
+        // Confirmation dialog captures input
+        if self.show_confirm_dialog {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.show_confirm_dialog = false;
+                    let targets = std::mem::take(&mut self.confirm_targets);
+                    self.kill_targets(&targets);
+                }
                 KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                     self.show_confirm_dialog = false;
+                    self.confirm_targets.clear();
+                    self.confirm_filter = None;
                     self.set_status("Kill cancelled", StatusKind::Info);
                 }
                 _ => {}
@@ -347,85 +1334,240 @@ impl App {
             return;
         }
 
-        // Filter mode captures text input
-        if self.filter_mode {
+        // Activity log overlay captures navigation
+        if self.show_activity_log {
             match key.code {
-                KeyCode::Esc | KeyCode::Enter => {
-                    self.filter_mode = false;
+                KeyCode::Char('L') | KeyCode::Esc => self.toggle_activity_log(),
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.activity_scroll = self.activity_scroll.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.activity_scroll =
+                        (self.activity_scroll + 1).min(self.activity_log.len().saturating_sub(1));
+                }
+                KeyCode::Char('W') => self.write_activity_log(),
+                _ => {}
+            }
+            return;
+        }
+
+        // Detail view: any of its keys closes it
+        if self.show_details {
+            if matches!(key.code, KeyCode::Char('i') | KeyCode::Esc | KeyCode::Enter) {
+                self.show_details = false;
+            }
+            return;
+        }
+
+        // Command line captures input until Enter/Esc
+        if let Some(cmd) = &mut self.command_line {
+            match key.code {
+                KeyCode::Enter => {
+                    let input = cmd.input.clone();
+                    self.command_line = None;
+                    self.run_command(&input);
+                }
+                KeyCode::Esc => {
+                    let saved_row = cmd.saved_row;
+                    self.command_line = None;
+                    self.table_state.select(saved_row);
                 }
                 KeyCode::Backspace => {
-                    self.filter_text.pop();
-                    self.table_state.select(if self.filtered_indices().is_empty() {
-                        None
-                    } else {
-                        Some(0)
-                    });
+                    cmd.input.pop();
                 }
-                KeyCode::Char(c) => {
-                    self.filter_text.push(c);
-                    self.table_state.select(if self.filtered_indices().is_empty() {
-                        None
-                    } else {
-                        Some(0)
-                    });
+                KeyCode::Char(c) => cmd.input.push(c),
+                _ => {}
+            }
+            return;
+        }
+
+        // Export prompt captures the path being typed
+        if let Some(prompt) = &mut self.export_prompt {
+            match key.code {
+                KeyCode::Enter if !prompt.path.is_empty() => self.finish_export(),
+                KeyCode::Esc => {
+                    self.export_prompt = None;
+                    self.set_status("Export cancelled", StatusKind::Info);
+                }
+                KeyCode::Backspace => {
+                    prompt.path.pop();
                 }
+                KeyCode::Char(c) => prompt.path.push(c),
                 _ => {}
             }
             return;
         }
 
-        // Help overlay
+        // Filter mode captures text input
+        if self.filter_edit.is_some() {
+            self.handle_filter_key(key);
+            return;
+        }
+
+        // Help overlay scrolls; only ? and Esc close it
         if self.show_help {
-            self.show_help = false;
+            match key.code {
+                KeyCode::Char('?') | KeyCode::Esc => self.show_help = false,
+                KeyCode::Up | KeyCode::Char('k') => self.scroll_help(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.scroll_help(1),
+                KeyCode::PageUp => self.scroll_help(-HELP_PAGE),
+                KeyCode::PageDown => self.scroll_help(HELP_PAGE),
+                KeyCode::Home | KeyCode::Char('g') => self.help_scroll = 0,
+                KeyCode::End | KeyCode::Char('G') => self.scroll_help(isize::MAX),
+                _ => {}
+            }
             return;
         }
 
-        match key.code {
-            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
-            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.should_quit = true;
-            }
-            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
-            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
-            KeyCode::Home | KeyCode::Char('g') => {
-                if !self.filtered_indices().is_empty() {
-                    self.table_state.select(Some(0));
+        // Visual mode: movement extends the range, Space/Enter commits it
+        if self.visual_anchor.is_some() {
+            match key.code {
+                KeyCode::Char(' ') | KeyCode::Enter => {
+                    self.commit_visual();
+                    return;
                 }
+                KeyCode::Esc | KeyCode::Char('V') => {
+                    self.visual_anchor = None;
+                    return;
+                }
+                KeyCode::Up
+                | KeyCode::Down
+                | KeyCode::Home
+                | KeyCode::End
+                | KeyCode::Char('j' | 'k' | 'g' | 'G') => {}
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {}
+                _ => return,
+            }
+        }
+
+        if let Some(action) = self.main_action(key) {
+            self.perform(action);
+        }
+    }
+
+    /// What `key` does in the process list. Every key answered here needs a
+    /// row in `KEY_BINDINGS`, so the hints and help can't miss it.
+    fn main_action(&self, key: KeyEvent) -> Option<Action> {
+        let action = match key.code {
+            // Esc clears an active filter before it quits
+            KeyCode::Esc if !self.filter_text.is_empty() => Action::ClearFilterText,
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            KeyCode::Up | KeyCode::Char('k') => Action::Move(-1),
+            KeyCode::Down | KeyCode::Char('j') => Action::Move(1),
+            KeyCode::Home | KeyCode::Char('g') if !self.filtered_indices().is_empty() => {
+                Action::Top
             }
-            KeyCode::End | KeyCode::Char('G') => {
+            KeyCode::End | KeyCode::Char('G') => Action::Bottom,
+            KeyCode::Char(' ') => Action::ToggleSelection,
+            KeyCode::Char('a') => Action::SelectAll,
+            KeyCode::Char('V') => Action::Visual,
+            KeyCode::Char('I') => Action::InvertSelection,
+            KeyCode::Enter | KeyCode::Char('x') => Action::Zap,
+            KeyCode::Char('X') => Action::ZapFiltered,
+            KeyCode::Char('Z') if self.can_sudo_retry() => Action::SudoRetry,
+            KeyCode::Char('r') => Action::Refresh,
+            KeyCode::Char('s') => Action::Sort { backwards: false },
+            KeyCode::Char('S') => Action::Sort { backwards: true },
+            KeyCode::Char('p') => Action::Pause,
+            KeyCode::Char('/') => Action::Filter,
+            KeyCode::Char('u') => Action::CycleProtocol,
+            KeyCode::Char(':') => Action::CommandLine,
+            KeyCode::Char('e') => Action::Export(ExportFormat::Json),
+            KeyCode::Char('E') => Action::Export(ExportFormat::Csv),
+            KeyCode::Char('c') => Action::ClearFilters,
+            KeyCode::Char('t') => Action::Theme,
+            KeyCode::Char('L') => Action::ActivityLog,
+            KeyCode::Char('i') if self.current_process().is_some() => Action::Details,
+            KeyCode::Char('?') => Action::Help,
+            _ => return None,
+        };
+        debug_assert!(
+            KEY_BINDINGS.iter().any(|b| b.codes.contains(&key.code)),
+            "{:?} is missing from KEY_BINDINGS",
+            key.code
+        );
+        Some(action)
+    }
+
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::ClearFilterText => {
+                self.filter_text.clear();
+                self.update_filter();
+                self.set_status("Filter cleared", StatusKind::Info);
+            }
+            Action::Quit => self.should_quit = true,
+            Action::Move(delta) => self.move_selection(delta),
+            Action::Top => self.table_state.select(Some(0)),
+            Action::Bottom => {
                 let filtered = self.filtered_indices();
                 if !filtered.is_empty() {
                     self.table_state.select(Some(filtered.len() - 1));
                 }
             }
-            KeyCode::Char(' ') => self.toggle_selection(),
-            KeyCode::Char('a') => self.select_all(),
-            KeyCode::Enter | KeyCode::Char('x') => self.request_kill_confirmation(),
-            KeyCode::Char('r') => {
+            Action::ToggleSelection => self.toggle_selection(),
+            Action::SelectAll => self.select_all(),
+            Action::Visual => self.start_visual(),
+            Action::InvertSelection => self.invert_selection(),
+            Action::Zap => self.request_kill_confirmation(),
+            Action::ZapFiltered => self.request_zap_all_filtered(),
+            Action::SudoRetry => self.sudo_requested = true,
+            Action::Refresh => {
                 self.refresh_processes();
-                self.set_status("Refreshed", StatusKind::Info);
+                self.set_status("Refreshing…", StatusKind::Info);
             }
-            KeyCode::Char('s') => self.cycle_sort(),
-            KeyCode::Char('/') => {
-                self.filter_mode = true;
-                self.filter_text.clear();
+            Action::Sort { backwards } => self.cycle_sort(backwards),
+            Action::Pause => self.toggle_pause(),
+            Action::Filter => self.start_filter_edit(),
+            Action::CycleProtocol => self.cycle_protocol_filter(),
+            Action::CommandLine => self.start_command_line(),
+            Action::Export(format) => self.start_export(format),
+            Action::ClearFilters => self.clear_filters(),
+            Action::Theme => self.toggle_theme(),
+            Action::ActivityLog => self.toggle_activity_log(),
+            Action::Details => self.show_details = true,
+            Action::Help => {
+                self.show_help = true;
+                self.help_scroll = 0;
             }
-            KeyCode::Char('t') => self.toggle_theme(),
-            KeyCode::Char('?') => self.show_help = true,
-            _ => {}
         }
     }
+
+    fn can_sudo_retry(&self) -> bool {
+        self.config.sudo_retry && !self.denied.is_empty()
+    }
+
+    /// The rows of `KEY_BINDINGS` that apply right now.
+    fn key_bindings(&self) -> impl Iterator<Item = &'static KeyBinding> + '_ {
+        KEY_BINDINGS
+            .iter()
+            .filter(|binding| binding.when.map_or(true, |when| when(self)))
+    }
+
+    fn scroll_help(&mut self, delta: isize) {
+        let last = self.key_bindings().count().saturating_sub(1);
+        self.help_scroll = self.help_scroll.saturating_add_signed(delta).min(last);
+    }
 }
 
-pub fn run() -> anyhow::Result<()> {
+/// Run the dashboard. `notice` is shown in the status bar on startup, e.g.
+/// config problems. `theme` overrides the configured theme for this session
+/// only; `auto` is settled here by asking the terminal for its background.
+pub fn run(
+    config: Config,
+    notice: Option<String>,
+    theme: Option<ThemeVariant>,
+) -> anyhow::Result<()> {
     // Setup terminal
     enable_raw_mode()?;
+    let theme = theme.unwrap_or(config.theme).resolve();
     let mut stdout = stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_app(&mut terminal);
+    let result = run_app(&mut terminal, config, notice, theme);
 
     // Restore terminal
     disable_raw_mode()?;
@@ -435,23 +1577,42 @@ pub fn run() -> anyhow::Result<()> {
     result
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> anyhow::Result<()> {
-    let mut app = App::new();
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    config: Config,
+    notice: Option<String>,
+    theme: ThemeVariant,
+) -> anyhow::Result<()> {
+    let mut app = App::new(config);
+    app.theme_variant = theme;
+    app.theme = theme.theme();
+    if let Some(notice) = notice {
+        app.set_status(&notice, StatusKind::Error);
+    }
     let animation_duration = Duration::from_millis(app.config.animation_duration_ms);
 
     loop {
+        app.poll_scan();
+
         // Clean up completed animations
         let now = Instant::now();
         let had_zapping = !app.zapping.is_empty();
         app.zapping.retain(|(_, start_time)| now.duration_since(*start_time) < animation_duration);
 
+        app.appeared
+            .retain(|(_, since)| now.duration_since(*since) < NEW_ROW_HIGHLIGHT);
+
         // Refresh processes after animations complete
         if had_zapping && app.zapping.is_empty() {
             app.refresh_processes();
         }
 
-        // Auto-refresh
-        if app.last_refresh.elapsed() >= REFRESH_RATE && !app.filter_mode {
+        // Auto-refresh (held while the confirm dialog shows a resolved target list)
+        if app.last_refresh.elapsed() >= app.refresh_interval
+            && !app.paused
+            && app.filter_edit.is_none()
+            && !app.show_confirm_dialog
+        {
             app.refresh_processes();
         }
 
@@ -471,12 +1632,56 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> anyhow::Res
             }
         }
 
+        if app.sudo_requested {
+            app.sudo_requested = false;
+            let authorized = killer::sudo_ready() || authorize_sudo(terminal, app.denied.len())?;
+            app.retry_with_sudo(authorized);
+        }
+
         if app.should_quit {
             return Ok(());
         }
     }
 }
 
+/// Leave the dashboard for sudo's password prompt, then restore it whether
+/// or not the password was accepted. Ctrl+C cancels the prompt rather than
+/// quitting portzap.
+fn authorize_sudo(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    count: usize,
+) -> anyhow::Result<bool> {
+    disable_raw_mode()?;
+    let authorized = prompt_for_sudo(terminal, count);
+    // Back to the dashboard even if the prompt couldn't be shown
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    authorized
+}
+
+/// The cooked-mode part of [`authorize_sudo`].
+fn prompt_for_sudo(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    count: usize,
+) -> anyhow::Result<bool> {
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    println!(
+        "portzap needs sudo to retry {count} kill{}. Ctrl+C cancels.",
+        if count == 1 { "" } else { "s" }
+    );
+
+    // sudo gets the SIGINT too and gives up; portzap just notes it.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler = signal_hook::flag::register(signal_hook::consts::SIGINT, interrupted).ok();
+    let authorized = killer::sudo_validate();
+    if let Some(id) = handler {
+        signal_hook::low_level::unregister(id);
+    }
+    Ok(authorized)
+}
+
 fn draw(frame: &mut ratatui::Frame, app: &mut App) {
     let area = frame.area();
 
@@ -486,12 +1691,18 @@ fn draw(frame: &mut ratatui::Frame, app: &mut App) {
         area,
     );
 
+    if area.width < MIN_SIZE.0 || area.height < MIN_SIZE.1 {
+        draw_too_small(frame, area, app);
+        return;
+    }
+
+    let header_height = if area.height < COMPACT_HEIGHT { 1 } else { 3 };
     let chunks = Layout::vertical([
-        Constraint::Length(3), // Header
-        Constraint::Length(1), // Filter bar
-        Constraint::Min(5),   // Table
-        Constraint::Length(1), // Status bar
-        Constraint::Length(1), // Key hints
+        Constraint::Length(header_height), // Header
+        Constraint::Length(1),             // Filter bar
+        Constraint::Min(3),                // Table
+        Constraint::Length(1),             // Status bar
+        Constraint::Length(1),             // Key hints
     ])
     .split(area);
 
@@ -501,13 +1712,71 @@ fn draw(frame: &mut ratatui::Frame, app: &mut App) {
     draw_status_bar(frame, chunks[3], app);
     draw_key_hints(frame, chunks[4], app);
 
+    // Overlays that don't fit become a line in the status bar instead.
+    let mut prompt = None;
+
+    if app.show_activity_log {
+        if fits(area, 40, 6) {
+            draw_activity_log(frame, area, app);
+        } else {
+            prompt = Some(activity_log_prompt(app));
+        }
+    }
+
+    if app.show_details {
+        if fits(area, 40, 8) {
+            draw_details(frame, area, app);
+        } else {
+            prompt = details_prompt(app);
+        }
+    }
+
     if app.show_confirm_dialog {
-        draw_confirm_dialog(frame, area, app);
+        if fits(area, 40, 10) {
+            draw_confirm_dialog(frame, area, app);
+        } else {
+            prompt = Some(confirm_prompt(app));
+        }
     }
 
     if app.show_help {
-        draw_help_overlay(frame, area, app);
+        if fits(area, 40, 10) {
+            draw_help_overlay(frame, area, app);
+        } else {
+            prompt = Some(Line::from(Span::styled(
+                " Help needs a larger terminal; ? or Esc closes it",
+                Style::default().fg(app.theme.info),
+            )));
+        }
     }
+
+    if let Some(line) = prompt {
+        let bar = Paragraph::new(line).style(Style::default().bg(app.theme.background_tertiary));
+        frame.render_widget(bar, chunks[3]);
+    }
+}
+
+/// Whether a popup of this size fits with a two-cell margin.
+fn fits(area: Rect, width: u16, height: u16) -> bool {
+    area.width >= width + 4 && area.height >= height + 4
+}
+
+fn draw_too_small(frame: &mut ratatui::Frame, area: Rect, app: &App) {
+    let lines = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            Style::default()
+                .fg(app.theme.error)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            format!("need {}x{}", MIN_SIZE.0, MIN_SIZE.1),
+            Style::default().fg(app.theme.text_secondary),
+        )),
+    ];
+    let y = area.y + area.height.saturating_sub(lines.len() as u16) / 2;
+    let message = Rect::new(area.x, y, area.width, area.height.min(lines.len() as u16));
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), message);
 }
 
 fn draw_header(frame: &mut ratatui::Frame, area: Rect, app: &App) {
@@ -534,6 +1803,33 @@ fn draw_header(frame: &mut ratatui::Frame, area: Rect, app: &App) {
         } else {
             Span::raw("")
         },
+        if app.scan_error.is_some() {
+            let since = match app.last_success {
+                Some(at) => format!(
+                    "last successful refresh {}",
+                    format::humanize_instant_ago(at)
+                ),
+                None => "no successful refresh yet".into(),
+            };
+            Span::styled(
+                format!(" | stale — {since}"),
+                Style::default()
+                    .fg(app.theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::raw("")
+        },
+        if let Some(range) = app.visual_range() {
+            Span::styled(
+                format!(" | VISUAL {} rows", range.count()),
+                Style::default()
+                    .fg(app.theme.accent_secondary)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::raw("")
+        },
         if selected_count > 0 {
             Span::styled(
                 format!(" | {} selected", selected_count),
@@ -544,20 +1840,80 @@ fn draw_header(frame: &mut ratatui::Frame, area: Rect, app: &App) {
         } else {
             Span::raw("")
         },
+        if app.protocol_filter != ProtocolFilter::Both {
+            Span::styled(
+                format!(" | proto: {}", app.protocol_filter.label()),
+                Style::default().fg(app.theme.info),
+            )
+        } else {
+            Span::raw("")
+        },
     ];
 
-    let header = Paragraph::new(Line::from(title_spans)).block(
-        Block::default()
+    let block = Block::default().style(Style::default().bg(app.theme.background_secondary));
+    let block = if area.height >= 3 {
+        block
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
             .border_style(Style::default().fg(app.theme.border))
-            .style(Style::default().bg(app.theme.background_secondary)),
-    );
+    } else {
+        block
+    };
+    let header = Paragraph::new(Line::from(title_spans)).block(block);
     frame.render_widget(header, area);
 }
 
 fn draw_filter_bar(frame: &mut ratatui::Frame, area: Rect, app: &App) {
-    if app.filter_mode {
+    if let Some(cmd) = &app.command_line {
+        let bar = Paragraph::new(Line::from(vec![
+            Span::styled(
+                " :",
+                Style::default()
+                    .fg(app.theme.accent_secondary)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(&cmd.input, Style::default().fg(app.theme.text_default)),
+            Span::styled(
+                "_",
+                Style::default()
+                    .fg(app.theme.text_default)
+                    .add_modifier(Modifier::SLOW_BLINK),
+            ),
+            Span::styled(
+                "  (<port> jump, kill <port>, q quit)",
+                Style::default().fg(app.theme.accent_tertiary),
+            ),
+        ]))
+        .style(Style::default().bg(app.theme.background_tertiary));
+        frame.render_widget(bar, area);
+    } else if let Some(prompt) = &app.export_prompt {
+        let bar = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!(" Export {} to: ", prompt.format.extension().to_uppercase()),
+                Style::default()
+                    .fg(app.theme.accent_secondary)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(&prompt.path, Style::default().fg(app.theme.text_default)),
+            Span::styled(
+                "_",
+                Style::default()
+                    .fg(app.theme.text_default)
+                    .add_modifier(Modifier::SLOW_BLINK),
+            ),
+            Span::styled(
+                "  (Enter to write, Esc to cancel)",
+                Style::default().fg(app.theme.accent_tertiary),
+            ),
+        ]))
+        .style(Style::default().bg(app.theme.background_tertiary));
+        frame.render_widget(bar, area);
+    } else if let Some(edit) = &app.filter_edit {
+        let before: String = app.filter_text.chars().take(edit.cursor).collect();
+        let mut after = app.filter_text.chars().skip(edit.cursor);
+        // The cursor sits on the next character, or past the end
+        let under = after.next().map_or_else(|| " ".to_string(), String::from);
+        let after: String = after.collect();
         let bar = Paragraph::new(Line::from(vec![
             Span::styled(
                 " Filter: ",
@@ -565,11 +1921,21 @@ fn draw_filter_bar(frame: &mut ratatui::Frame, area: Rect, app: &App) {
                     .fg(app.theme.accent_secondary)
                     .add_modifier(Modifier::BOLD),
             ),
+            Span::styled(before, Style::default().fg(app.theme.text_default)),
             Span::styled(
-                &app.filter_text,
-                Style::default().fg(app.theme.text_default),
+                under,
+                Style::default()
+                    .fg(app.theme.text_default)
+                    .add_modifier(Modifier::REVERSED),
             ),
-            Span::styled("_", Style::default().fg(app.theme.text_default).add_modifier(Modifier::SLOW_BLINK)),
+            Span::styled(after, Style::default().fg(app.theme.text_default)),
+            match &app.filter_error {
+                Some(e) => Span::styled(format!("  {e}"), Style::default().fg(app.theme.error)),
+                None => Span::styled(
+                    "  (port: pid: name: user: /regex/)",
+                    Style::default().fg(app.theme.accent_tertiary),
+                ),
+            },
         ]))
         .style(Style::default().bg(app.theme.background_tertiary));
         frame.render_widget(bar, area);
@@ -615,13 +1981,41 @@ fn draw_table(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
         }
     };
 
-    let header_cells = [
+    let show_usage = app.config.show_resource_usage;
+    let show_command = area.width >= COMMAND_MIN_WIDTH;
+    let show_proto = area.width >= PROTO_MIN_WIDTH;
+
+    let mut header_cells = vec![
         Cell::from(format!("Port{}", sort_indicator(SortColumn::Port))),
         Cell::from(format!("PID{}", sort_indicator(SortColumn::Pid))),
         Cell::from(format!("Name{}", sort_indicator(SortColumn::Name))),
-        Cell::from(format!("Proto{}", sort_indicator(SortColumn::Protocol))),
-        Cell::from("Command"),
     ];
+    if show_proto {
+        header_cells.push(Cell::from(format!(
+            "Proto{}",
+            sort_indicator(SortColumn::Protocol)
+        )));
+    }
+    header_cells.extend([
+        Cell::from(format!("Age{}", sort_indicator(SortColumn::Age))),
+        Cell::from(format!("User{}", sort_indicator(SortColumn::User))),
+    ]);
+    if show_usage {
+        header_cells.push(Cell::from(format!(
+            "Mem{}",
+            sort_indicator(SortColumn::Memory)
+        )));
+        header_cells.push(Cell::from(format!(
+            "CPU%{}",
+            sort_indicator(SortColumn::Cpu)
+        )));
+    }
+    if show_command {
+        header_cells.push(Cell::from(format!(
+            "Command{}",
+            sort_indicator(SortColumn::Command)
+        )));
+    }
     let header = Row::new(header_cells)
         .style(
             Style::default()
@@ -641,14 +2035,38 @@ fn draw_table(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
         }
     }
 
+    let visual = app.visual_range();
     let rows: Vec<Row> = filtered
         .iter()
-        .map(|&orig_idx| {
+        .enumerate()
+        .map(|(table_row, &orig_idx)| {
             let p = &app.processes[orig_idx];
-            let is_selected = app.selected.contains(&orig_idx);
+            let in_range = visual
+                .as_ref()
+                .is_some_and(|range| range.contains(&table_row));
+            let is_selected = app.selected.contains(&p.key()) || in_range;
             let is_zapping = zapping_indices.contains(&orig_idx);
+            let locked = killer::needs_elevation(p);
 
-            let marker = if is_selected { "● " } else { "  " };
+            let marker = if in_range {
+                "▸ "
+            } else if is_selected {
+                "● "
+            } else {
+                "  "
+            };
+            let mut name = if locked {
+                format!("🔒 {}", p.display_name())
+            } else {
+                p.display_name()
+            };
+            if let Some(c) = &p.container {
+                name.push_str(&format!(" → {}", c.name));
+            }
+            if p.managed_by.is_some() {
+                // Killing it is refused without --ignore-manager; see ManagedBy::explain
+                name.insert_str(0, "⚙ ");
+            }
 
             let proto_color = match p.protocol {
                 Protocol::Tcp => app.theme.tcp_color,
@@ -669,46 +2087,100 @@ fn draw_table(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
                 .next()
                 .unwrap_or("-");
 
-            // Apply animation to zapping processes
-            if is_zapping {
+            let age = p
+                .age()
+                .map(format::humanize_duration)
+                .unwrap_or_else(|| "-".into());
+            let usage = app.usage(p.pid);
+            let mem = usage
+                .rss_bytes
+                .map(resources::format_bytes)
+                .unwrap_or_else(|| "-".into());
+            let cpu = usage
+                .cpu_percent
+                .map(|c| format!("{c:.1}"))
+                .unwrap_or_else(|| "-".into());
+
+            // Zapping rows keep their cells and flash in the accent colour
+            let (port, port_color, pid_color, proto_color) = if is_zapping {
                 let lightning_chars = ["⚡", "✧", "✦"];
                 let animation_frame = (now.elapsed().as_millis() / 100) % 3;
                 let lightning = lightning_chars[animation_frame as usize];
-
-                Row::new(vec![
-                    Cell::from(format!("{}{} {}", marker, p.port, lightning)).style(
-                        Style::default().fg(app.theme.accent_secondary)
-                    ),
-                    Cell::from(p.pid.to_string()).style(Style::default().fg(app.theme.accent_secondary)),
-                    Cell::from(p.name.clone()).style(Style::default().fg(app.theme.accent_secondary).add_modifier(Modifier::BOLD)),
-                    Cell::from(p.protocol.to_string()).style(Style::default().fg(app.theme.accent_secondary)),
-                    Cell::from(cmd.to_string()).style(Style::default().fg(app.theme.accent_secondary)),
-                ])
+                let zap = app.theme.accent_secondary;
+                (format!("{}{} {}", marker, p.port, lightning), zap, zap, zap)
             } else {
-                Row::new(vec![
-                    Cell::from(format!("{}{}", marker, p.port)).style(Style::default().fg(
-                        if is_selected {
-                            app.theme.port_selected_fg
-                        } else {
-                            app.theme.port_fg
-                        },
-                    )),
-                    Cell::from(p.pid.to_string()).style(Style::default().fg(row_fg)),
-                    Cell::from(p.name.clone()).style(Style::default().fg(row_fg).add_modifier(Modifier::BOLD)),
+                (
+                    format!("{}{}", marker, p.port),
+                    app.port_color(p.port, is_selected),
+                    row_fg,
+                    proto_color,
+                )
+            };
+            let mut cells = vec![
+                Cell::from(port).style(Style::default().fg(port_color)),
+                Cell::from(p.pid.to_string()).style(Style::default().fg(pid_color)),
+                Cell::from(name).style(Style::default().fg(pid_color).add_modifier(Modifier::BOLD)),
+            ];
+            if show_proto {
+                cells.push(
                     Cell::from(p.protocol.to_string()).style(Style::default().fg(proto_color)),
-                    Cell::from(cmd.to_string()).style(Style::default().fg(app.theme.command_color)),
-                ])
+                );
+            }
+
+            let (detail_color, cmd_color) = if is_zapping {
+                (app.theme.accent_secondary, app.theme.accent_secondary)
+            } else {
+                (app.theme.text_secondary, app.theme.command_color)
+            };
+            cells.push(Cell::from(age).style(Style::default().fg(detail_color)));
+            cells.push(
+                Cell::from(p.user.as_deref().unwrap_or("-").to_string())
+                    .style(Style::default().fg(detail_color)),
+            );
+            if show_usage {
+                cells.push(Cell::from(mem).style(Style::default().fg(detail_color)));
+                cells.push(Cell::from(cpu).style(Style::default().fg(detail_color)));
+            }
+            if show_command {
+                cells.push(Cell::from(cmd.to_string()).style(Style::default().fg(cmd_color)));
+            }
+            let row = Row::new(cells);
+            let appeared = app
+                .appeared
+                .iter()
+                .find(|(key, _)| *key == p.key())
+                .map(|(_, since)| now.duration_since(*since));
+            if let Some(age) = appeared {
+                let t = age.as_secs_f32() / NEW_ROW_HIGHLIGHT.as_secs_f32();
+                row.style(Style::default().bg(fade(
+                    app.theme.success,
+                    app.theme.background_secondary,
+                    t,
+                )))
+            } else if locked && !is_selected {
+                row.style(Style::default().add_modifier(Modifier::DIM))
+            } else {
+                row
             }
         })
         .collect();
 
-    let widths = [
+    let mut widths = vec![
         Constraint::Length(10),
         Constraint::Length(8),
         Constraint::Length(20),
-        Constraint::Length(7),
-        Constraint::Min(20),
     ];
+    if show_proto {
+        widths.push(Constraint::Length(7));
+    }
+    widths.extend([Constraint::Length(8), Constraint::Length(10)]);
+    if show_usage {
+        widths.push(Constraint::Length(10));
+        widths.push(Constraint::Length(7));
+    }
+    if show_command {
+        widths.push(Constraint::Min(20));
+    }
 
     let table = Table::new(rows, widths)
         .header(header)
@@ -729,48 +2201,113 @@ fn draw_table(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
     frame.render_stateful_widget(table, area, &mut app.table_state);
 }
 
+/// Blend from one color to another, `t` running from 0 to 1. Named
+/// terminal colors can't be blended, so they switch over halfway.
+fn fade(from: Color, to: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    match (from, to) {
+        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+            Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+        }
+        _ if t < 0.5 => from,
+        _ => to,
+    }
+}
+
 fn draw_status_bar(frame: &mut ratatui::Frame, area: Rect, app: &App) {
-    let content = if let Some((msg, _, kind)) = &app.status_message {
+    let mut spans = Vec::new();
+    if app.paused {
+        spans.push(Span::styled(
+            " ⏸ PAUSED ",
+            Style::default()
+                .fg(app.theme.background)
+                .bg(app.theme.accent_secondary)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if let Some(started) = app.scan_started() {
+        let frame = (started.elapsed().as_millis() / 100) as usize % SPINNER_FRAMES.len();
+        spans.push(Span::styled(
+            format!(" {}", SPINNER_FRAMES[frame]),
+            Style::default().fg(app.theme.info),
+        ));
+    }
+
+    if let Some((msg, _, kind)) = &app.status_message {
         let (icon, color) = match kind {
             StatusKind::Success => ("✓", app.theme.success),
             StatusKind::Error => ("✗", app.theme.error),
             StatusKind::Info => ("●", app.theme.info),
         };
-        Line::from(vec![
-            Span::styled(format!(" {} ", icon), Style::default().fg(color)),
-            Span::styled(msg, Style::default().fg(color)),
-        ])
+        spans.push(Span::styled(
+            format!(" {} ", icon),
+            Style::default().fg(color),
+        ));
+        spans.push(Span::styled(msg, Style::default().fg(color)));
     } else {
-        let secs_ago = app.last_refresh.elapsed().as_secs();
-        Line::from(vec![Span::styled(
-            format!(" Last refreshed {}s ago", secs_ago),
+        let elapsed = app.last_refresh.elapsed();
+        let text = if app.scan.is_some() {
+            " Refreshing…".to_string()
+        } else if app.paused {
+            format!(
+                " Last refreshed {}",
+                format::humanize_instant_ago(app.last_refresh)
+            )
+        } else {
+            let remaining = app.refresh_interval.saturating_sub(elapsed);
+            // Round up so the countdown never shows "0s" before the refresh fires
+            let secs = (remaining.as_millis() as u64).div_ceil(1000);
+            format!(" Refresh in {}s", secs)
+        };
+        spans.push(Span::styled(
+            text,
             Style::default().fg(app.theme.accent_tertiary),
-        )])
-    };
+        ));
+        if let Some(e) = &app.scan_error {
+            spans.push(Span::styled(
+                format!("  ✗ scan failed: {e}"),
+                Style::default()
+                    .fg(app.theme.error)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        for warning in &app.scan_warnings {
+            spans.push(Span::styled(
+                format!("  ⚠ {warning}"),
+                Style::default().fg(app.theme.accent_secondary),
+            ));
+        }
+    }
+    let content = Line::from(spans);
 
     let bar = Paragraph::new(content).style(Style::default().bg(app.theme.background_secondary));
     frame.render_widget(bar, area);
 }
 
 fn draw_key_hints(frame: &mut ratatui::Frame, area: Rect, app: &App) {
-    let hints = if app.filter_mode {
+    let hints = if app.command_line.is_some() {
+        vec![("Enter", "run"), ("Esc", "cancel")]
+    } else if app.export_prompt.is_some() {
+        vec![("Enter", "write"), ("Esc", "cancel")]
+    } else if app.filter_edit.is_some() {
         vec![
-            ("Enter/Esc", "confirm"),
-            ("Backspace", "delete"),
+            ("Enter", "apply"),
+            ("Esc", "cancel"),
+            ("←→", "move"),
+            ("↑↓", "history"),
         ]
-    } else {
+    } else if app.visual_anchor.is_some() {
         vec![
-            ("↑↓/jk", "navigate"),
-            ("Space", "select"),
-            ("x/Enter", "zap"),
-            ("a", "all"),
-            ("/", "filter"),
-            ("s", "sort"),
-            ("r", "refresh"),
-            ("t", "theme"),
-            ("?", "help"),
-            ("q", "quit"),
+            ("↑↓/jk", "extend"),
+            ("Space/Enter", "select range"),
+            ("Esc", "cancel"),
         ]
+    } else {
+        app.key_bindings()
+            .filter_map(|binding| Some((binding.keys, binding.hint?)))
+            .collect()
     };
 
     let spans: Vec<Span> = hints
@@ -802,28 +2339,105 @@ fn draw_key_hints(frame: &mut ratatui::Frame, area: Rect, app: &App) {
 }
 
 fn draw_confirm_dialog(frame: &mut ratatui::Frame, area: Rect, app: &App) {
-    let width = 40u16.min(area.width.saturating_sub(4));
-    let height = 9u16.min(area.height.saturating_sub(4));
+    let targets = &app.confirm_targets;
+    let protected = targets
+        .iter()
+        .filter(|p| app.config.is_protected(p))
+        .count();
+    let locked = targets
+        .iter()
+        .filter(|p| !app.config.is_protected(p) && killer::needs_elevation(p))
+        .count();
+
+    let proxies: Vec<&str> = targets
+        .iter()
+        .filter(|p| !app.config.is_protected(p))
+        .filter_map(|p| p.container.as_ref().map(|c| c.name.as_str()))
+        .collect();
+
+    // Fixed lines: borders (2), blank + title + blank, blank + buttons + blank,
+    // plus the elevation and Docker warnings when they apply.
+    let fixed_height = 8u16 + u16::from(locked > 0) + u16::from(!proxies.is_empty());
+    let max_height = area.height.saturating_sub(4);
+    let room = max_height.saturating_sub(fixed_height) as usize;
+    let mut listed = targets.len().min(CONFIRM_MAX_LISTED).min(room);
+    let mut truncated = targets.len() - listed;
+    if truncated > 0 && listed > 0 && listed == room {
+        // Make space for the "…and N more" line
+        listed -= 1;
+        truncated += 1;
+    }
+    let list_height = listed as u16 + u16::from(truncated > 0);
+
+    let count = targets.len() - protected;
+    let elevation_warning = (locked > 0).then(|| {
+        format!("{locked} of {count} targets are owned by other users and will likely fail without sudo")
+    });
+    let container_warning = (!proxies.is_empty()).then(|| {
+        format!(
+            "Docker proxies only: containers keep running (docker stop {})",
+            proxies.join(" ")
+        )
+    });
+    let wanted_width = elevation_warning
+        .iter()
+        .chain(&container_warning)
+        .map(|w| w.chars().count() as u16 + 4)
+        .fold(60, u16::max);
+    let width = wanted_width.min(area.width.saturating_sub(4));
+    let height = (fixed_height + list_height).min(max_height);
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
     let popup_area = Rect::new(x, y, width, height);
 
     frame.render_widget(Clear, popup_area);
 
-    let count_text = if app.confirm_target_count == 1 {
-        "Kill 1 process?".to_string()
-    } else {
-        format!("Kill {} processes?", app.confirm_target_count)
-    };
+    let count_text = confirm_question(app);
 
-    let dialog_text = vec![
+    let mut dialog_text = vec![
         Line::from(""),
         Line::from(Span::styled(
-            &count_text,
+            count_text,
             Style::default()
                 .fg(app.theme.error)
                 .add_modifier(Modifier::BOLD),
         )),
+    ];
+    for warning in elevation_warning.iter().chain(&container_warning) {
+        dialog_text.push(Line::from(Span::styled(
+            warning.clone(),
+            Style::default().fg(app.theme.info),
+        )));
+    }
+    dialog_text.push(Line::from(""));
+
+    for p in targets.iter().take(listed) {
+        // Root's and other users' processes stand out even when we are root
+        let color = if killer::is_foreign(p) || app.config.is_protected(p) {
+            app.theme.error
+        } else {
+            app.theme.text_default
+        };
+        dialog_text.push(Line::from(vec![
+            Span::styled(
+                format!("  {:>5}/{} ", p.port, p.protocol),
+                Style::default().fg(app.theme.port_fg),
+            ),
+            Span::styled(format!("PID {:<7} ", p.pid), Style::default().fg(color)),
+            Span::styled(
+                p.display_name(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+    }
+    if truncated > 0 {
+        dialog_text.push(Line::from(Span::styled(
+            format!("  …and {truncated} more"),
+            Style::default().fg(app.theme.text_tertiary),
+        )));
+    }
+
+    dialog_text.extend([
         Line::from(""),
         Line::from(vec![
             Span::styled(
@@ -844,7 +2458,7 @@ fn draw_confirm_dialog(frame: &mut ratatui::Frame, area: Rect, app: &App) {
             Span::raw(" Cancel"),
         ]),
         Line::from(""),
-    ];
+    ]);
 
     let dialog = Paragraph::new(dialog_text).block(
         Block::default()
@@ -857,83 +2471,676 @@ fn draw_confirm_dialog(frame: &mut ratatui::Frame, area: Rect, app: &App) {
     frame.render_widget(dialog, popup_area);
 }
 
+/// "Kill 3 processes?", for the dialog and its one-line form.
+fn confirm_question(app: &App) -> String {
+    let targets = &app.confirm_targets;
+    let protected = targets
+        .iter()
+        .filter(|p| app.config.is_protected(p))
+        .count();
+    let count = targets.len() - protected;
+    let noun = if count == 1 { "process" } else { "processes" };
+    let mut question = match app.confirm_filter.as_deref() {
+        Some("") => format!("Kill all {count} {noun}?"),
+        Some(filter) => format!("Kill all {count} {noun} matching '{filter}'?"),
+        None => format!("Kill {count} {noun}?"),
+    };
+    if protected > 0 {
+        question.push_str(&format!(" ({protected} protected, skipped)"));
+    }
+    question
+}
+
+fn confirm_prompt(app: &App) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!(" {} ", confirm_question(app)),
+            Style::default()
+                .fg(app.theme.error)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            " Y ",
+            Style::default()
+                .fg(app.theme.background)
+                .bg(app.theme.success)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" "),
+        Span::styled(
+            " N ",
+            Style::default()
+                .fg(app.theme.background)
+                .bg(app.theme.error)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ])
+}
+
 fn draw_help_overlay(frame: &mut ratatui::Frame, area: Rect, app: &App) {
-    let width = 50u16.min(area.width.saturating_sub(4));
-    let height = 21u16.min(area.height.saturating_sub(4));
+    let width = 56u16.min(area.width.saturating_sub(4));
+    let rows = app.key_bindings().count() as u16;
+    let height = (rows + 2).min(area.height.saturating_sub(4));
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
     let popup_area = Rect::new(x, y, width, height);
 
     frame.render_widget(Clear, popup_area);
 
-    let help_text = vec![
-        Line::from(Span::styled(
-            "PortZap Keyboard Shortcuts",
+    let lines: Vec<Line> = app
+        .key_bindings()
+        .skip(app.help_scroll)
+        .map(|binding| {
+            let color = if binding.danger {
+                app.theme.error
+            } else {
+                app.theme.info
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("  {:<10} ", binding.keys),
+                    Style::default().fg(color),
+                ),
+                Span::raw(binding.help),
+            ])
+        })
+        .collect();
+
+    let help = Paragraph::new(lines).block(
+        Block::default()
+            .title(" PortZap Keyboard Shortcuts ")
+            .title_alignment(Alignment::Center)
+            .title_bottom(Line::from(" ↑↓ PgUp/PgDn scroll │ ?/Esc close ").centered())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(app.theme.highlight_fg))
+            .style(Style::default().bg(app.theme.background_tertiary)),
+    );
+
+    frame.render_widget(help, popup_area);
+}
+
+fn details_prompt(app: &App) -> Option<Line<'static>> {
+    let p = app.current_process()?;
+    Some(Line::from(vec![
+        Span::styled(
+            format!(" {} ", p.name),
             Style::default()
-                .fg(app.theme.highlight_fg)
+                .fg(app.theme.text_default)
                 .add_modifier(Modifier::BOLD),
-        )),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("  ↑/↓ or j/k  ", Style::default().fg(app.theme.info)),
-            Span::raw("Move selection up/down"),
-        ]),
-        Line::from(vec![
-            Span::styled("  g / G        ", Style::default().fg(app.theme.info)),
-            Span::raw("Jump to top/bottom"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Space        ", Style::default().fg(app.theme.info)),
-            Span::raw("Toggle select process"),
-        ]),
-        Line::from(vec![
-            Span::styled("  a            ", Style::default().fg(app.theme.info)),
-            Span::raw("Select/deselect all"),
-        ]),
-        Line::from(vec![
-            Span::styled("  x / Enter    ", Style::default().fg(app.theme.error)),
-            Span::raw("Zap selected processes"),
-        ]),
-        Line::from(vec![
-            Span::styled("  /            ", Style::default().fg(app.theme.info)),
-            Span::raw("Search/filter processes"),
-        ]),
-        Line::from(vec![
-            Span::styled("  s            ", Style::default().fg(app.theme.info)),
-            Span::raw("Cycle sort column"),
-        ]),
-        Line::from(vec![
-            Span::styled("  r            ", Style::default().fg(app.theme.info)),
-            Span::raw("Refresh process list"),
-        ]),
-        Line::from(vec![
-            Span::styled("  t            ", Style::default().fg(app.theme.info)),
-            Span::raw("Toggle theme"),
-        ]),
-        Line::from(vec![
-            Span::styled("  ?            ", Style::default().fg(app.theme.info)),
-            Span::raw("Toggle this help"),
-        ]),
-        Line::from(vec![
-            Span::styled("  q / Esc      ", Style::default().fg(app.theme.info)),
-            Span::raw("Quit"),
-        ]),
-        Line::from(""),
-        Line::from(Span::styled(
-            "  Press any key to close",
-            Style::default().fg(app.theme.accent_tertiary),
-        )),
+        ),
+        Span::styled(
+            format!("PID {} {}/{} ", p.pid, p.port, p.protocol),
+            Style::default().fg(app.theme.port_fg),
+        ),
+        Span::styled(
+            p.command.clone().unwrap_or_default(),
+            Style::default().fg(app.theme.command_color),
+        ),
+    ]))
+}
+
+fn draw_details(frame: &mut ratatui::Frame, area: Rect, app: &App) {
+    let Some(p) = app.current_process() else {
+        return;
+    };
+    let width = 80u16.min(area.width.saturating_sub(4));
+    let height = 16u16.min(area.height.saturating_sub(4));
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+    let popup_area = Rect::new(x, y, width, height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let usage = app.usage(p.pid);
+    let mut fields = vec![
+        ("PID", p.pid.to_string()),
+        ("Port", format!("{}/{}", p.port, p.protocol)),
+        ("User", p.user.clone().unwrap_or_else(|| "-".into())),
+        (
+            "Parent",
+            output::parent_label(p).unwrap_or_else(|| "-".into()),
+        ),
+        ("Command", p.command.clone().unwrap_or_else(|| "-".into())),
+        (
+            "Directory",
+            p.cwd
+                .as_ref()
+                .map(|cwd| cwd.display().to_string())
+                .unwrap_or_else(|| "-".into()),
+        ),
     ];
+    if let Some(age) = p.age() {
+        fields.push(("Running", format::humanize_duration(age)));
+    }
+    if let Some(c) = &p.container {
+        fields.push(("Container", format!("{} ({})", c.name, c.image)));
+    }
+    if let Some(managed) = &p.managed_by {
+        fields.push(("Managed by", managed.to_string()));
+    }
+    if let Some(rss) = usage.rss_bytes {
+        fields.push(("Memory", resources::format_bytes(rss)));
+    }
+    if let Some(cpu) = usage.cpu_percent {
+        fields.push(("CPU", format!("{cpu:.1}%")));
+    }
+
+    let lines: Vec<Line> = fields
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!(" {label:<11}"), Style::default().fg(app.theme.info)),
+                Span::styled(value, Style::default().fg(app.theme.text_default)),
+            ])
+        })
+        .collect();
 
-    let help = Paragraph::new(help_text).block(
+    let details = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
         Block::default()
-            .title(" Help ")
+            .title(format!(" {} ", p.name))
             .title_alignment(Alignment::Center)
+            .title_bottom(Line::from(" i/Esc close ").centered())
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
             .border_style(Style::default().fg(app.theme.highlight_fg))
             .style(Style::default().bg(app.theme.background_tertiary)),
     );
 
-    frame.render_widget(help, popup_area);
+    frame.render_widget(details, popup_area);
+}
+
+fn activity_log_prompt(app: &App) -> Line<'static> {
+    let Some(entry) = app.activity_log.iter().rev().nth(app.activity_scroll) else {
+        return Line::from(Span::styled(
+            " Nothing zapped yet this session",
+            Style::default().fg(app.theme.text_tertiary),
+        ));
+    };
+    let (icon, color) = if entry.is_error() {
+        ("✗", app.theme.error)
+    } else {
+        ("✓", app.theme.success)
+    };
+    Line::from(vec![
+        Span::styled(
+            format!(" {} ", entry.at.format("%H:%M:%S")),
+            Style::default().fg(app.theme.text_tertiary),
+        ),
+        Span::styled(format!("{icon} "), Style::default().fg(color)),
+        Span::styled(
+            entry.describe(),
+            Style::default().fg(app.theme.text_default),
+        ),
+    ])
+}
+
+fn draw_activity_log(frame: &mut ratatui::Frame, area: Rect, app: &App) {
+    let width = 90u16.min(area.width.saturating_sub(4));
+    let height = 20u16.min(area.height.saturating_sub(4));
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+    let popup_area = Rect::new(x, y, width, height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let lines: Vec<Line> = if app.activity_log.is_empty() {
+        vec![Line::from(Span::styled(
+            "  Nothing zapped yet this session",
+            Style::default().fg(app.theme.text_tertiary),
+        ))]
+    } else {
+        app.activity_log
+            .iter()
+            .rev()
+            .skip(app.activity_scroll)
+            .map(|entry| {
+                let (icon, color) = if entry.is_error() {
+                    ("✗", app.theme.error)
+                } else {
+                    ("✓", app.theme.success)
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!(" {} ", entry.at.format("%H:%M:%S")),
+                        Style::default().fg(app.theme.text_tertiary),
+                    ),
+                    Span::styled(format!("{icon} "), Style::default().fg(color)),
+                    Span::styled(
+                        entry.describe(),
+                        Style::default().fg(app.theme.text_default),
+                    ),
+                ])
+            })
+            .collect()
+    };
+
+    let log = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" Activity ({}) ", app.activity_log.len()))
+            .title_alignment(Alignment::Center)
+            .title_bottom(Line::from(" ↑↓ scroll │ W write to file │ L/Esc close ").centered())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(app.theme.highlight_fg))
+            .style(Style::default().bg(app.theme.background_tertiary)),
+    );
+
+    frame.render_widget(log, popup_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::mock::process;
+    use ratatui::backend::TestBackend;
+
+    fn app_with(processes: Vec<ProcessInfo>) -> App {
+        let mut app = App::new(Config::default());
+        app.processes = processes;
+        app.restore_cursor(None);
+        app
+    }
+
+    /// The screen as text, one string per row.
+    fn render(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn renders_at_any_size_with_every_overlay() {
+        let mut app = app_with(vec![process(4242, "node", 3000)]);
+        app.confirm_targets = app.processes.clone();
+        for (width, height) in [
+            (1, 1),
+            (19, 5),
+            (20, 6),
+            (40, 8),
+            (60, 12),
+            (80, 24),
+            (200, 60),
+        ] {
+            for overlay in 0..5 {
+                app.show_activity_log = overlay == 1;
+                app.show_details = overlay == 2;
+                app.show_confirm_dialog = overlay == 3;
+                app.show_help = overlay == 4;
+                render(&mut app, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn zapping_rows_keep_every_column() {
+        let mut app = app_with(vec![process(4242, "node", 3000)]);
+        app.config.animation_duration_ms = 60_000;
+        app.zapping.push((0, Instant::now()));
+        let screen = render(&mut app, 120, 30);
+        let row = screen.iter().find(|l| l.contains("3000")).unwrap();
+        assert!(row.contains('⚡') || row.contains('✧') || row.contains('✦'));
+        let pid = row.find("4242").unwrap();
+        assert!(pid < row.find("node").unwrap() && row.contains("TCP"));
+    }
+
+    #[test]
+    fn tiny_terminal_says_so() {
+        let mut app = app_with(vec![process(4242, "node", 3000)]);
+        let screen = render(&mut app, 19, 5).join("\n");
+        assert!(screen.contains("too small"));
+        assert!(!render(&mut app, 20, 6).join("\n").contains("too small"));
+    }
+
+    #[test]
+    fn narrow_table_drops_command_then_proto() {
+        let mut app = app_with(vec![process(4242, "node", 3000)]);
+        let header = |screen: Vec<String>| screen.into_iter().find(|l| l.contains("PID")).unwrap();
+
+        let wide = header(render(&mut app, 120, 30));
+        assert!(wide.contains("Command") && wide.contains("Proto"));
+        let medium = header(render(&mut app, 80, 24));
+        assert!(!medium.contains("Command") && medium.contains("Proto"));
+        let narrow = header(render(&mut app, 60, 12));
+        assert!(!narrow.contains("Command") && !narrow.contains("Proto"));
+    }
+
+    #[test]
+    fn refresh_notes_new_and_gone_rows() {
+        let mut app = app_with(vec![process(1, "node", 3000), process(2, "vite", 5173)]);
+        app.last_success = Some(Instant::now());
+        app.selected.insert(process(2, "vite", 5173).key());
+
+        let next = vec![process(1, "node", 3000), process(3, "redis", 6379)];
+        app.apply_scan(Ok(ScanResult::from(next)));
+
+        let (msg, _, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(
+            msg,
+            "+1 new, -1 gone (1 selected process exited and left the selection)"
+        );
+        assert!(app.selected.is_empty());
+        let appeared: Vec<_> = app.appeared.iter().map(|(key, _)| key.0).collect();
+        assert_eq!(appeared, [3]);
+    }
+
+    #[test]
+    fn zapped_rows_are_not_reported_gone() {
+        let mut app = app_with(vec![process(1, "node", 3000)]);
+        app.last_success = Some(Instant::now());
+        app.zapped.insert(process(1, "node", 3000).key());
+
+        app.apply_scan(Ok(ScanResult::default()));
+        assert!(app.status_message.is_none());
+        assert!(app.zapped.is_empty());
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn esc_cancels_a_filter_edit_then_clears_then_quits() {
+        let mut app = app_with(vec![process(1, "node", 3000)]);
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "node");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.filter_text, "node");
+
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "x");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.filter_text, "node");
+        assert!(app.filter_edit.is_none());
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.filter_text, "");
+        assert!(!app.should_quit);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn filter_edits_at_the_cursor_and_recalls_history() {
+        let mut app = app_with(vec![process(1, "node", 3000)]);
+        for filter in ["port:3000", "name:vite"] {
+            press(&mut app, KeyCode::Char('c'));
+            press(&mut app, KeyCode::Char('/'));
+            type_text(&mut app, filter);
+            press(&mut app, KeyCode::Enter);
+        }
+        assert_eq!(app.filter_history, ["name:vite", "port:3000"]);
+
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Home);
+        press(&mut app, KeyCode::Right);
+        type_text(&mut app, "é");
+        assert_eq!(app.filter_text, "néame:vite");
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Left);
+        press(&mut app, KeyCode::Delete);
+        assert_eq!(app.filter_text, "ame:vite");
+
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.filter_text, "name:vite");
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.filter_text, "port:3000");
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.filter_text, "port:3000");
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.filter_text, "ame:vite");
+    }
+
+    #[test]
+    fn confirm_falls_back_to_the_status_bar() {
+        let mut app = app_with(vec![process(4242, "node", 3000)]);
+        app.confirm_targets = app.processes.clone();
+        app.show_confirm_dialog = true;
+
+        let small = render(&mut app, 60, 12);
+        assert!(small[small.len() - 2].contains("Kill 1 process?"));
+        let large = render(&mut app, 120, 30);
+        assert!(!large[large.len() - 2].contains("Kill 1 process?"));
+        assert!(large.iter().any(|l| l.contains("Kill 1 process?")));
+    }
+
+    #[test]
+    fn zapping_only_protected_rows_does_not_ask() {
+        let mut app = app_with(vec![process(4242, "node", 3000), process(4243, "vite", 5173)]);
+        app.config.protected_ports = vec![3000, 5173];
+
+        press(&mut app, KeyCode::Char('X'));
+        assert!(!app.show_confirm_dialog);
+        let (msg, _, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "Nothing to kill: 2 processes are protected");
+
+        app.config.protected_ports = vec![3000];
+        press(&mut app, KeyCode::Char('X'));
+        assert!(app.show_confirm_dialog);
+        assert_eq!(confirm_question(&app), "Kill all 1 process? (1 protected, skipped)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn confirm_dialog_marks_roots_processes_whoever_runs_it() {
+        let mut app = app_with(vec![ProcessInfo {
+            uid: Some(0),
+            ..process(4242, "sshd", 3000)
+        }]);
+        app.confirm_targets = app.processes.clone();
+        app.show_confirm_dialog = true;
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let area = buffer.area;
+        let pid_color = (0..area.height).find_map(|y| {
+            let row: Vec<&str> = (0..area.width).map(|x| buffer[(x, y)].symbol()).collect();
+            let x = (0..row.len()).find(|&x| row[x..].concat().starts_with("PID 4242"))?;
+            row.concat()
+                .contains("3000/TCP")
+                .then(|| buffer[(x as u16, y)].fg)
+        });
+        assert_eq!(pid_color, Some(app.theme.error));
+    }
+
+    #[test]
+    fn failed_scans_keep_the_rows_and_log_each_error_once() {
+        let mut app = App::new(Config::default());
+        app.apply_scan(Ok(vec![process(4242, "node", 3000)].into()));
+        assert!(app.last_success.is_some());
+
+        let failure = || Err(KillportError::PlatformError("lsof went away".into()));
+        app.apply_scan(failure());
+        app.apply_scan(failure());
+        assert_eq!(app.processes.len(), 1);
+        assert_eq!(app.scan_error.as_deref(), Some("platform error: lsof went away"));
+        let errors = |app: &App| {
+            app.activity_log
+                .iter()
+                .filter(|entry| matches!(entry.event, ActivityEvent::RefreshError(_)))
+                .count()
+        };
+        assert_eq!(errors(&app), 1);
+        let (status, _, kind) = app.status_message.as_ref().unwrap();
+        assert!(status.starts_with("Scan failed:"));
+        assert!(matches!(kind, StatusKind::Error));
+        let screen = render(&mut app, 120, 30);
+        assert!(screen.iter().any(|row| row.contains("stale")));
+        assert!(screen.iter().any(|row| row.contains("node")));
+
+        // A scan thread that dies is reported like any other failure
+        let (tx, rx) = mpsc::channel();
+        drop(tx);
+        app.scan = Some((rx, Instant::now()));
+        app.poll_scan();
+        assert!(app.scan.is_none());
+        assert!(app.scan_error.as_ref().unwrap().contains("stopped unexpectedly"));
+        assert_eq!(errors(&app), 2);
+
+        app.apply_scan(Ok(vec![process(4242, "node", 3000)].into()));
+        assert!(app.scan_error.is_none());
+        let screen = render(&mut app, 120, 30);
+        assert!(!screen.iter().any(|row| row.contains("stale")));
+    }
+
+    #[test]
+    fn every_handled_key_is_documented() {
+        let mut app = app_with(vec![process(4242, "node", 3000)]);
+        app.config.sudo_retry = true;
+        app.denied = app.processes.clone();
+
+        let mut codes: Vec<KeyCode> = (' '..='~').map(KeyCode::Char).collect();
+        codes.extend([
+            KeyCode::Up,
+            KeyCode::Down,
+            KeyCode::Left,
+            KeyCode::Right,
+            KeyCode::Home,
+            KeyCode::End,
+            KeyCode::PageUp,
+            KeyCode::PageDown,
+            KeyCode::Tab,
+            KeyCode::Backspace,
+            KeyCode::Delete,
+            KeyCode::Enter,
+            KeyCode::Esc,
+        ]);
+        codes.extend((1..=12).map(KeyCode::F));
+        for code in codes {
+            let key = KeyEvent::new(code, KeyModifiers::NONE);
+            if app.main_action(key).is_some() {
+                assert!(
+                    KEY_BINDINGS.iter().any(|b| b.codes.contains(&code)),
+                    "{code:?} is handled but has no key binding"
+                );
+            }
+        }
+        let mut bound = HashSet::new();
+        for code in KEY_BINDINGS.iter().flat_map(|b| b.codes) {
+            assert!(bound.insert(*code), "{code:?} is in two key bindings");
+        }
+        assert!(app.key_bindings().any(|b| b.hint == Some("sudo retry")));
+        app.denied.clear();
+        assert!(!app.key_bindings().any(|b| b.hint == Some("sudo retry")));
+    }
+
+    #[test]
+    fn sort_back_does_not_trigger_a_sudo_retry() {
+        let mut app = app_with(vec![process(4242, "node", 3000)]);
+        app.config.sudo_retry = true;
+        app.denied = app.processes.clone();
+        let sort_column = app.sort_column;
+        press(&mut app, KeyCode::Char('S'));
+        assert!(!app.sudo_requested);
+        assert!(app.sort_column != sort_column);
+        press(&mut app, KeyCode::Char('Z'));
+        assert!(app.sudo_requested);
+    }
+
+    fn selected_pids(app: &App) -> Vec<u32> {
+        let mut pids: Vec<u32> = app.selected.iter().map(|key| key.0).collect();
+        pids.sort_unstable();
+        pids
+    }
+
+    #[test]
+    fn visual_range_selects_from_the_anchor_to_the_cursor() {
+        let rows = (1..=5).map(|pid| process(pid, "node", 3000 + pid as u16));
+        let mut app = app_with(rows.collect());
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('V'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        // Other keys do nothing until the range is committed or cancelled
+        press(&mut app, KeyCode::Char('x'));
+        assert!(!app.show_confirm_dialog);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(selected_pids(&app), [2, 3, 4]);
+        assert!(app.visual_anchor.is_none());
+
+        press(&mut app, KeyCode::Char('V'));
+        press(&mut app, KeyCode::Char('G'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(selected_pids(&app), [2, 3, 4]);
+        assert!(app.visual_anchor.is_none() && !app.should_quit);
+    }
+
+    #[test]
+    fn visual_range_only_selects_visible_rows() {
+        let mut app = app_with(vec![
+            process(1, "node", 3000),
+            process(2, "redis", 3001),
+            process(3, "node", 3002),
+        ]);
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "name:node");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('V'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(selected_pids(&app), [1, 3]);
+
+        // A range whose start has gone from the view selects nothing
+        app.selected.clear();
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('V'));
+        app.apply_scan(Ok(vec![process(3, "node", 3002)].into()));
+        press(&mut app, KeyCode::Char(' '));
+        assert!(app.selected.is_empty());
+        let (status, _, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(status, "Range start is no longer visible");
+    }
+
+    #[test]
+    fn invert_flips_the_visible_rows_and_keeps_hidden_ones() {
+        let mut app = app_with(vec![
+            process(1, "node", 3000),
+            process(2, "redis", 3001),
+            process(3, "node", 3002),
+        ]);
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(selected_pids(&app), [1, 2]);
+
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "name:node");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('I'));
+        assert_eq!(selected_pids(&app), [2, 3]);
+        press(&mut app, KeyCode::Char('I'));
+        assert_eq!(selected_pids(&app), [1, 2]);
+    }
+
+    #[test]
+    fn help_scrolls_and_only_question_mark_or_esc_close_it() {
+        let mut app = app_with(vec![process(4242, "node", 3000)]);
+        press(&mut app, KeyCode::Char('?'));
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.help_scroll, HELP_PAGE as usize);
+        press(&mut app, KeyCode::Char('x'));
+        assert!(app.show_help && !app.show_confirm_dialog);
+        press(&mut app, KeyCode::End);
+        assert_eq!(app.help_scroll, app.key_bindings().count() - 1);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.help_scroll, app.key_bindings().count() - 2);
+
+        press(&mut app, KeyCode::Esc);
+        assert!(!app.show_help && !app.should_quit);
+        press(&mut app, KeyCode::Char('?'));
+        assert_eq!(app.help_scroll, 0);
+        press(&mut app, KeyCode::Char('?'));
+        assert!(!app.show_help);
+    }
 }
@@ -0,0 +1,327 @@
+//! `--include-windows`: under WSL2, localhost forwarding means a port can be
+//! taken by a Windows process that the Linux socket tables never show.
+//! Windows' own `netstat.exe` and `tasklist.exe` are reachable through the
+//! interop layer, so their output is merged into the Linux scan with
+//! `origin: windows`, and those processes are ended with `taskkill.exe`.
+
+use crate::errors::{KillportError, Result};
+use crate::process::{Origin, PortSpec, ProcessInfo, Protocol};
+use crate::scanner::{PortScanner, ScanResult};
+use std::collections::{HashMap, HashSet};
+use std::process::{Command, Stdio};
+
+/// Whether we're running inside a WSL distribution.
+pub fn is_wsl() -> bool {
+    std::env::var_os("WSL_DISTRO_NAME").is_some()
+        || std::fs::read_to_string("/proc/version")
+            .is_ok_and(|v| v.to_ascii_lowercase().contains("microsoft"))
+}
+
+/// The Linux scanner plus the listeners on the Windows side.
+pub struct WindowsScanner {
+    inner: Box<dyn PortScanner>,
+}
+
+impl WindowsScanner {
+    pub fn new(inner: Box<dyn PortScanner>) -> Result<Self> {
+        if !is_wsl() {
+            return Err(KillportError::Usage(
+                "--include-windows only works inside WSL".into(),
+            ));
+        }
+        Ok(Self { inner })
+    }
+
+    fn with_windows(&self, mut scan: ScanResult, keep: impl Fn(u16) -> bool) -> Result<ScanResult> {
+        let windows = windows_listeners()?;
+        scan.processes
+            .extend(windows.into_iter().filter(|p| keep(p.port)));
+        Ok(scan)
+    }
+}
+
+impl PortScanner for WindowsScanner {
+    fn find_processes_by_port(&self, port: u16) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_port(port)?.processes)
+    }
+
+    fn find_all_listening(&self) -> Result<Vec<ProcessInfo>> {
+        Ok(self.scan_all()?.processes)
+    }
+
+    fn scan_port(&self, port: u16) -> Result<ScanResult> {
+        self.with_windows(self.inner.scan_port(port)?, |p| p == port)
+    }
+
+    fn scan_all(&self) -> Result<ScanResult> {
+        self.with_windows(self.inner.scan_all()?, |_| true)
+    }
+
+    fn scan_ports(&self, specs: &[PortSpec]) -> Result<ScanResult> {
+        self.with_windows(self.inner.scan_ports(specs)?, |p| {
+            specs.iter().any(|spec| spec.contains(p))
+        })
+    }
+}
+
+/// Every listening socket on the Windows side, named from `tasklist.exe`.
+fn windows_listeners() -> Result<Vec<ProcessInfo>> {
+    let netstat = run("netstat.exe", &["-ano"])?;
+    let sockets = parse_netstat(&netstat).ok_or_else(|| {
+        KillportError::PlatformError(
+            "could not read the output of `netstat.exe -ano` (no TCP or UDP rows found)".into(),
+        )
+    })?;
+    // Unnamed rows are still worth showing.
+    let names = run("tasklist.exe", &["/FO", "CSV", "/NH"])
+        .map(|out| parse_tasklist(&out))
+        .unwrap_or_default();
+    tracing::debug!(sockets = sockets.len(), "scanned the Windows side");
+    Ok(listeners(sockets, &names))
+}
+
+/// The WSL processes that forward Windows' localhost into the distro. They
+/// hold a Windows socket for every port a Linux process listens on, and
+/// ending them would cut the Linux side off rather than free the port.
+const WSL_RELAYS: &[&str] = &["wslrelay.exe", "wslhost.exe"];
+
+/// Named listeners from `netstat.exe` rows, leaving out the WSL relays.
+fn listeners(sockets: Vec<(Protocol, u16, u32)>, names: &HashMap<u32, String>) -> Vec<ProcessInfo> {
+    sockets
+        .into_iter()
+        .map(|(protocol, port, pid)| ProcessInfo {
+            pid,
+            name: names.get(&pid).cloned().unwrap_or_else(|| "unknown".into()),
+            port,
+            protocol,
+            command: None,
+            user: None,
+            uid: None,
+            container: None,
+            managed_by: None,
+            ppid: None,
+            parent_name: None,
+            started_at: None,
+            cwd: None,
+            host: None,
+            origin: Some(Origin::Windows),
+        })
+        .filter(|p| {
+            !WSL_RELAYS
+                .iter()
+                .any(|relay| p.name.eq_ignore_ascii_case(relay))
+        })
+        .collect()
+}
+
+/// Run a Windows program through interop and return its stdout.
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => KillportError::PlatformError(format!(
+                "{program} was not found; --include-windows needs WSL interop with Windows' System32 on PATH"
+            )),
+            _ => KillportError::PlatformError(format!("could not run {program}: {e}")),
+        })?;
+    if !output.status.success() {
+        return Err(KillportError::PlatformError(format!(
+            "{program} failed ({})",
+            output.status
+        )));
+    }
+    // Windows writes in the console code page; names may lose accents.
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Listening sockets in `netstat.exe -ano` output, as (protocol, port, pid):
+///
+/// ```text
+///   Proto  Local Address          Foreign Address        State           PID
+///   TCP    0.0.0.0:5432           0.0.0.0:0              LISTENING       812
+///   UDP    0.0.0.0:5353           *:*                                    2980
+/// ```
+///
+/// Headers and the state column are translated on localized Windows, so
+/// they're ignored: a TCP socket is listening when its foreign port is 0.
+/// `None` when the output has no TCP or UDP rows at all, which means it
+/// isn't what we expected.
+fn parse_netstat(output: &str) -> Option<Vec<(Protocol, u16, u32)>> {
+    let mut any_rows = false;
+    let mut seen = HashSet::new();
+    let mut sockets = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let protocol = match fields.first() {
+            Some(&"TCP") => Protocol::Tcp,
+            Some(&"UDP") => Protocol::Udp,
+            _ => continue,
+        };
+        any_rows = true;
+        let (Some(local), Some(foreign), Some(pid)) = (fields.get(1), fields.get(2), fields.last())
+        else {
+            continue;
+        };
+        if protocol == Protocol::Tcp && !foreign.ends_with(":0") {
+            continue;
+        }
+        let Some(port) = local
+            .rsplit_once(':')
+            .and_then(|(_, port)| port.parse::<u16>().ok())
+        else {
+            continue;
+        };
+        let Ok(pid) = pid.parse::<u32>() else {
+            continue;
+        };
+        if seen.insert((protocol, port, pid)) {
+            sockets.push((protocol, port, pid));
+        }
+    }
+    sockets.sort_by_key(|&(protocol, port, pid)| (port, pid, protocol == Protocol::Udp));
+    any_rows.then_some(sockets)
+}
+
+/// Image names by PID from `tasklist.exe /FO CSV /NH`:
+///
+/// ```text
+/// "postgres.exe","812","Services","0","12,345 K"
+/// ```
+fn parse_tasklist(output: &str) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    for line in output.lines() {
+        let Some((name, rest)) = line
+            .trim()
+            .strip_prefix('"')
+            .and_then(|line| line.split_once("\",\""))
+        else {
+            continue;
+        };
+        if let Some(pid) = rest.split('"').next().and_then(|pid| pid.parse().ok()) {
+            names.insert(pid, name.to_string());
+        }
+    }
+    names
+}
+
+/// Ask a Windows process to close, or with `force`, terminate it.
+pub fn taskkill(process: &ProcessInfo, force: bool) -> Result<()> {
+    let pid = process.pid.to_string();
+    let mut args = vec!["/PID", pid.as_str()];
+    if force {
+        args.push("/F");
+    }
+    tracing::debug!(pid = process.pid, force, "running taskkill.exe");
+    let output = Command::new("taskkill.exe")
+        .args(&args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| KillportError::KillFailed {
+            pid: process.pid,
+            name: process.name.clone(),
+            reason: format!("could not run taskkill.exe: {e}"),
+        })?;
+    match output.status.code() {
+        Some(0) => Ok(()),
+        // "The process ... not found"
+        Some(128) => Err(KillportError::ProcessGone {
+            pid: process.pid,
+            name: process.name.clone(),
+        }),
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(KillportError::KillFailed {
+                pid: process.pid,
+                name: process.name.clone(),
+                reason: match stderr.trim() {
+                    "" => format!("taskkill.exe failed ({})", output.status),
+                    message => message.to_string(),
+                },
+            })
+        }
+    }
+}
+
+/// Whether a Windows process with this PID is still running.
+pub fn is_running(pid: u32) -> bool {
+    let filter = format!("PID eq {pid}");
+    run("tasklist.exe", &["/FI", &filter, "/FO", "CSV", "/NH"])
+        .is_ok_and(|out| parse_tasklist(&out).contains_key(&pid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_out_the_wsl_relays() {
+        let names = HashMap::from([
+            (812, "postgres.exe".to_string()),
+            (900, "wslrelay.exe".to_string()),
+            (901, "WSLHost.exe".to_string()),
+        ]);
+        let sockets = vec![
+            (Protocol::Tcp, 3000, 900),
+            (Protocol::Tcp, 3000, 901),
+            (Protocol::Tcp, 5432, 812),
+            (Protocol::Tcp, 8080, 77),
+        ];
+        let found: Vec<(u16, String)> = listeners(sockets, &names)
+            .into_iter()
+            .map(|p| (p.port, p.name))
+            .collect();
+        assert_eq!(
+            found,
+            [(5432, "postgres.exe".into()), (8080, "unknown".into())]
+        );
+    }
+
+    #[test]
+    fn parses_listening_rows_from_netstat() {
+        let output = "\r\n\
+            Active Connections\r\n\
+            \r\n  Proto  Local Address          Foreign Address        State           PID\r\n\
+            \x20 TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1100\r\n\
+            \x20 TCP    0.0.0.0:5432           0.0.0.0:0              LISTENING       812\r\n\
+            \x20 TCP    [::]:5432              [::]:0                 LISTENING       812\r\n\
+            \x20 TCP    127.0.0.1:5432         127.0.0.1:50123        ESTABLISHED     812\r\n\
+            \x20 TCP    127.0.0.1:50123        127.0.0.1:5432         ESTABLISHED     4242\r\n\
+            \x20 UDP    0.0.0.0:5353           *:*                                    2980\r\n";
+        assert_eq!(
+            parse_netstat(output).unwrap(),
+            [
+                (Protocol::Tcp, 135, 1100),
+                (Protocol::Udp, 5353, 2980),
+                (Protocol::Tcp, 5432, 812),
+            ]
+        );
+    }
+
+    #[test]
+    fn localized_netstat_still_parses() {
+        let german = "\r\nAktive Verbindungen\r\n\r\n  Proto  Lokale Adresse         Remoteadresse          Status           PID\r\n\
+            \x20 TCP    0.0.0.0:3000           0.0.0.0:0              ABHÖREN          5120\r\n\
+            \x20 TCP    192.168.1.5:51000      140.82.112.4:443       HERGESTELLT      7000\r\n";
+        assert_eq!(
+            parse_netstat(german).unwrap(),
+            [(Protocol::Tcp, 3000, 5120)]
+        );
+        assert!(parse_netstat("Der Befehl ist falsch geschrieben.\r\n").is_none());
+    }
+
+    #[test]
+    fn parses_tasklist_csv() {
+        let output = "\"System Idle Process\",\"0\",\"Services\",\"0\",\"8 K\"\r\n\
+            \"postgres.exe\",\"812\",\"Services\",\"0\",\"12,345 K\"\r\n\
+            INFO: No tasks are running which match the specified criteria.\r\n";
+        let names = parse_tasklist(output);
+        assert_eq!(names.get(&812).map(String::as_str), Some("postgres.exe"));
+        assert_eq!(
+            names.get(&0).map(String::as_str),
+            Some("System Idle Process")
+        );
+        assert_eq!(names.len(), 2);
+    }
+}
@@ -1,7 +1,7 @@
 mod helpers;
 
 use assert_cmd::Command;
-use helpers::ListenerGuard;
+use helpers::{ConfigHome, ListenerGuard};
 use predicates::prelude::*;
 
 fn portzap() -> Command {
@@ -16,6 +16,39 @@ fn no_args_shows_help() {
         .stderr(predicate::str::contains("Usage"));
 }
 
+#[test]
+fn gui_default_command_falls_back_to_help_without_a_terminal() {
+    let home = ConfigHome::new("default_command = \"gui\"\n");
+    home.apply(&mut portzap())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Usage"));
+    home.apply(&mut portzap())
+        .arg("--no-gui")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Usage"));
+}
+
+#[test]
+fn list_default_command_lists_unless_no_gui() {
+    let home = ConfigHome::new("default_command = \"list\"\n");
+    let output = home
+        .apply(&mut portzap())
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let rows: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(rows.is_array());
+
+    home.apply(&mut portzap())
+        .arg("--no-gui")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::is_empty());
+}
+
 #[test]
 fn version_flag() {
     portzap()
@@ -25,6 +58,32 @@ fn version_flag() {
         .stdout(predicate::str::contains("portzap"));
 }
 
+#[test]
+fn version_subcommand_json_has_build_metadata() {
+    let output = portzap()
+        .args(["version", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["version"], env!("CARGO_PKG_VERSION"));
+    for key in ["commit", "build_date", "target"] {
+        assert!(parsed[key].as_str().is_some_and(|v| !v.is_empty()), "{key}");
+    }
+    let features = parsed["features"].as_array().unwrap();
+    assert!(!features.contains(&"default".into()), "{features:?}");
+}
+
+#[test]
+fn version_subcommand_plain() {
+    portzap()
+        .arg("version")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("commit:"))
+        .stdout(predicate::str::contains("target:"));
+}
+
 #[test]
 fn invalid_port_text() {
     portzap()
@@ -60,7 +119,31 @@ fn list_finds_listening_process() {
         .args(["list", "--format", "json", &port])
         .assert()
         .success()
-        .stdout(predicate::str::contains(&format!("\"port\": {}", guard.port())));
+        .stdout(predicate::str::contains(format!(
+            "\"port\": {}",
+            guard.port()
+        )));
+}
+
+#[test]
+fn list_fields_selects_plain_columns() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    // The listener lives in this test process, whose parent is still running.
+    portzap()
+        .args([
+            "list",
+            &port,
+            "--fields",
+            "port,ppid,parent",
+            "--format",
+            "plain",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!("{port}\t")))
+        .stdout(predicate::str::contains("(PID "));
 }
 
 #[test]
@@ -79,7 +162,10 @@ fn dry_run_does_not_kill() {
         .args(["list", "--format", "json", &port])
         .assert()
         .success()
-        .stdout(predicate::str::contains(&format!("\"port\": {}", guard.port())));
+        .stdout(predicate::str::contains(format!(
+            "\"port\": {}",
+            guard.port()
+        )));
 }
 
 #[test]
@@ -94,183 +180,2475 @@ fn json_output_is_valid() {
 
     let stdout = String::from_utf8(output.stdout).unwrap();
     let parsed: serde_json::Value = serde_json::from_str(&stdout).unwrap();
-    assert!(parsed.is_array());
+    assert!(parsed.is_object());
 
-    let arr = parsed.as_array().unwrap();
+    let arr = parsed[&port]["processes"].as_array().unwrap();
     assert!(!arr.is_empty());
     assert_eq!(arr[0]["port"], guard.port());
 }
 
 #[test]
-fn plain_output_format() {
+fn list_several_ports_is_one_json_document() {
     let guard = ListenerGuard::random();
     let port = guard.port().to_string();
 
+    let output = portzap()
+        .args(["list", "--format", "json", &port, "59999"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed[&port]["processes"][0]["port"], guard.port());
+    assert_eq!(parsed["59999"]["processes"], serde_json::json!([]));
+
+    // Table mode prints one combined table, then the empty ports
     portzap()
-        .args(["list", "--format", "plain", &port])
+        .args(["list", &port, "59999"])
         .assert()
         .success()
-        .stdout(predicate::str::contains(&format!("\t{}\t", guard.port())));
+        .stdout(predicate::str::contains(port.as_str()).count(1))
+        .stderr(predicate::str::contains("No processes found on port 59999"));
 }
 
 #[test]
-fn port_range_syntax() {
-    // Range where nothing is listening
+fn list_group_by_process_nests_ports() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    let output = portzap()
+        .args(["list", "--group-by", "process", "--format", "json", &port])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed[0]["pid"], std::process::id());
+    assert_eq!(parsed[0]["ports"][0]["port"], guard.port());
+    assert_eq!(parsed[0]["count"], 1);
+}
+
+#[test]
+fn list_watch_json_streams_changes() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    // The watch runs until killed; the first refresh reports the listener.
+    let output = portzap()
+        .args(["list", "--watch", "1", "--format", "json", &port])
+        .timeout(std::time::Duration::from_millis(1500))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let first: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().expect("no output")).unwrap();
+    assert_eq!(first["change"], "added");
+    assert_eq!(first["process"]["port"], guard.port());
+}
+
+#[test]
+fn list_count_and_assertion_flags() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
     portzap()
-        .args(["--dry-run", "59990-59995"])
+        .args(["list", "--count", &port, "59999"])
+        .assert()
+        .success()
+        .stdout("1\n");
+    portzap()
+        .args(["list", "--count", "--format", "json", "59999"])
+        .assert()
+        .success()
+        .stdout("{\"count\": 0}\n");
+    portzap()
+        .args(["list", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "1 process on 1 port (1 TCP, 0 UDP)",
+        ));
+
+    portzap()
+        .args(["list", "--fail-if-any", &port])
+        .assert()
+        .code(1);
+    portzap()
+        .args(["list", "--fail-if-any", "59999"])
         .assert()
         .success();
+    portzap()
+        .args(["list", "--fail-if-none", "59999"])
+        .assert()
+        .code(3);
+    portzap()
+        .args(["list", "--fail-if-none", "--count", &port])
+        .assert()
+        .success()
+        .stdout("1\n");
 }
 
 #[test]
-fn list_all_listening() {
-    // Just verify it doesn't crash — output depends on system state
+fn list_overlapping_specs_show_each_port_once() {
+    let guard = ListenerGuard::random();
+    let port = guard.port();
+    let range = format!("{port}-{}", port.saturating_add(1));
+
+    let output = portzap()
+        .args(["list", &port.to_string(), &range, "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let processes = json[port.to_string()]["processes"].as_array().unwrap();
+    assert_eq!(processes.len(), 1);
+}
+
+#[test]
+fn list_expect_free_and_used_assert_port_state() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
     portzap()
-        .arg("list")
+        .args(["list", "--expect-free", "--format", "plain", &port, "59999"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains(format!("\t{port}\t")))
+        .stderr(predicate::str::contains(format!(
+            "Expected free, but in use: {port}"
+        )));
+    portzap()
+        .args(["list", "--expect-free", "59999"])
         .assert()
         .success();
+    portzap()
+        .args(["list", "--expect-used", &port])
+        .assert()
+        .success();
+
+    let output = portzap()
+        .args(["list", "--expect-used", "--format", "json", &port, "59999"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["assertion"]["passed"], false);
+    let violations = json["assertion"]["violations"].as_array().unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0]["port"], 59999);
+
+    portzap()
+        .args(["list", "--expect-used"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--expect-used needs the ports"));
+
+    // An overlapping spec must not hide the listener from the assertion
+    let range = format!("{port}-{}", guard.port().saturating_add(1));
+    portzap()
+        .args(["list", "--expect-free", &port, &range])
+        .assert()
+        .code(2);
 }
 
 #[test]
-fn kill_subcommand_works() {
+fn list_name_filters_rows() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
     portzap()
-        .args(["kill", "--dry-run", "59999"])
+        .args(["list", "--name", "no-such-process", &port])
         .assert()
-        .success();
+        .success()
+        .stdout("")
+        .stderr(predicate::str::contains(format!(
+            "No processes matching --name no-such-process found on port {port}"
+        )));
+
+    // The listener lives in this test binary, whose command names it
+    let output = portzap()
+        .args(["list", "--format", "json", "--name", "no-such-process"])
+        .args(["--name", "/CLI_INTEGRATION/", &port])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed[&port]["processes"][0]["pid"], std::process::id());
 }
 
-// ─── completions ───────────────────────────────────────────
+#[test]
+fn list_wide_range_scans_once() {
+    let guard = ListenerGuard::random();
+
+    // Per-port lookups would walk /proc once for each of ~64k ports.
+    let started = std::time::Instant::now();
+    let output = portzap()
+        .args(["list", "--format", "json", "1024-65535"])
+        .output()
+        .unwrap();
+    assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let port = guard.port().to_string();
+    assert_eq!(parsed[&port]["processes"][0]["pid"], std::process::id());
+    assert_eq!(parsed.as_object().unwrap().len(), 65535 - 1024 + 1);
+}
 
 #[test]
-fn completions_bash() {
+fn verbose_logs_scan_steps_to_stderr() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let pid = format!("pid={}", std::process::id());
+
     portzap()
-        .args(["completions", "bash"])
+        .env_remove("RUST_LOG")
+        .args(["list", "--format", "json", &port])
         .assert()
         .success()
-        .stdout(predicate::str::contains("portzap"));
-}
+        .stderr(predicate::str::contains("scan started").not());
 
-#[test]
-fn completions_zsh() {
     portzap()
-        .args(["completions", "zsh"])
+        .env_remove("RUST_LOG")
+        .args(["-v", "list", "--format", "json", &port])
         .assert()
         .success()
-        .stdout(predicate::str::contains("portzap"));
-}
+        .stderr(predicate::str::contains("scan started"))
+        .stderr(predicate::str::contains("scan finished processes=1"))
+        .stderr(predicate::str::contains("process holds target socket").not());
 
-#[test]
-fn completions_fish() {
     portzap()
-        .args(["completions", "fish"])
+        .env_remove("RUST_LOG")
+        .args(["list", "-vv", "--format", "json", &port])
         .assert()
         .success()
-        .stdout(predicate::str::contains("portzap"));
+        .stdout(predicate::str::contains("\"pid\""))
+        .stderr(predicate::str::contains("process holds target socket"))
+        .stderr(predicate::str::contains(pid));
 }
 
+#[cfg(unix)]
 #[test]
-fn completions_invalid_shell() {
+fn closed_stdout_exits_quietly() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Stdio;
+
+    let _guard = ListenerGuard::random();
+    // Like `portzap list | head -1` once head has exited.
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("portzap"))
+        .args(["list", "--format", "plain"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("panicked"), "{stderr}");
+    assert!(stderr.is_empty(), "{stderr}");
+    assert!(
+        output.status.success()
+            || output.status.signal() == Some(nix::sys::signal::Signal::SIGPIPE as i32),
+        "{:?}",
+        output.status
+    );
+}
+
+#[test]
+fn pids_and_ports_formats_print_unique_sorted_values() {
+    let a = ListenerGuard::random();
+    let b = ListenerGuard::random();
+    let (low, high) = (a.port().min(b.port()), a.port().max(b.port()));
+    let ports = [a.port().to_string(), b.port().to_string()];
+
     portzap()
-        .args(["completions", "nushell"])
+        .args(["list", "--format", "pids", &ports[0], &ports[1]])
         .assert()
-        .failure();
-}
+        .success()
+        .stdout(format!("{}\n", std::process::id()));
+    portzap()
+        .args(["list", "--format", "ports", &ports[1], &ports[0]])
+        .assert()
+        .success()
+        .stdout(format!("{low}\n{high}\n"));
 
-// ─── free ──────────────────────────────────────────────────
+    // Nothing on the port: no output at all, so `kill $(...)` gets no arguments.
+    let free = ListenerGuard::random().port().to_string();
+    portzap()
+        .args(["list", "--format", "pids", &free])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    portzap()
+        .args(["free", "3000", "--format", "pids"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only works with list"));
+}
 
 #[test]
-fn free_finds_unused_port() {
-    // High port unlikely to be in use
+fn kill_pids_format_prints_killed_pids() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .unwrap();
+    let pid = child.id().to_string();
+
     portzap()
-        .args(["free", "59999"])
+        .args(["kill", "--pid", &pid, "--no-graceful", "--yes", "--format", "pids"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("59999"));
+        .stdout(format!("{pid}\n"));
+    child.wait().unwrap();
 }
 
 #[test]
-fn free_skips_occupied_port() {
+fn addresses_and_urls_name_local_ports() {
     let guard = ListenerGuard::random();
-    let port = guard.port().to_string();
+    let pid = format!("{}\n", std::process::id());
 
-    // The free port returned should NOT be the occupied port
-    let output = portzap()
-        .args(["free", &port])
-        .output()
-        .unwrap();
+    for arg in [
+        format!("localhost:{}", guard.port()),
+        format!("http://127.0.0.1:{}/", guard.port()),
+        format!("[::1]:{}", guard.port()),
+    ] {
+        portzap()
+            .args(["list", "--format", "pids", &arg])
+            .assert()
+            .success()
+            .stdout(pid.clone());
+    }
 
-    assert!(output.status.success());
-    let stdout = String::from_utf8(output.stdout).unwrap();
-    let found_port: u16 = stdout.trim().parse().expect("should print a port number");
-    assert!(found_port > guard.port(), "free should skip the occupied port");
+    portzap()
+        .args(["--dry-run", "example.com:3000"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("host example.com is not local"));
 }
 
 #[test]
-fn free_json_output() {
+fn invalid_steps_are_reported_as_steps() {
     portzap()
-        .args(["free", "59999", "--format", "json"])
+        .args(["list", "3000-3010:0"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains(r#""port": 59999"#));
+        .code(2)
+        .stderr(predicate::str::contains("step 0 is not valid"))
+        .stderr(predicate::str::contains("not local").not());
 }
 
 #[test]
-fn free_no_port_in_range() {
-    let guard = ListenerGuard::new(59998);
+fn plain_output_format() {
+    let guard = ListenerGuard::random();
     let port = guard.port().to_string();
 
-    // Search only the occupied port
     portzap()
-        .args(["free", &port, "--max", &port])
+        .args(["list", "--format", "plain", &port])
         .assert()
-        .failure();
+        .success()
+        .stdout(predicate::str::contains(format!("\t{}\t", guard.port())));
 }
 
-// ─── wait ──────────────────────────────────────────────────
-
 #[test]
-fn wait_port_already_free() {
-    // Port 59999 should be free — returns immediately
+fn port_range_syntax() {
+    // Range where nothing is listening
     portzap()
-        .args(["wait", "59999", "--timeout", "2"])
+        .args(["--dry-run", "59990-59995"])
         .assert()
         .success();
 }
 
 #[test]
-fn wait_port_already_free_json() {
+fn list_all_listening() {
+    // Just verify it doesn't crash — output depends on system state
     portzap()
-        .args(["wait", "59999", "--timeout", "2", "--format", "json"])
+        .arg("list")
         .assert()
-        .success()
-        .stdout(predicate::str::contains(r#""status": "free""#));
+        .success();
 }
 
 #[test]
-fn wait_until_up_times_out() {
-    // Port 59999 is free, waiting for it to become occupied should time out
+fn kill_subcommand_works() {
     portzap()
-        .args(["wait", "59999", "--until", "up", "--timeout", "1"])
+        .args(["kill", "--dry-run", "59999"])
         .assert()
-        .failure();
+        .success();
 }
 
 #[test]
-fn wait_until_up_times_out_json() {
+fn kill_pid_checks_the_requested_ports() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let pid = std::process::id().to_string();
+
     portzap()
-        .args(["wait", "59999", "--until", "up", "--timeout", "1", "--format", "json"])
+        .args(["kill", "--pid", &pid, "59999"])
         .assert()
-        .failure()
-        .stdout(predicate::str::contains(r#""status": "timeout""#));
+        .code(3)
+        .stderr(predicate::str::contains(format!(
+            "PID {pid} is not listening on 59999"
+        )));
+
+    let output = portzap()
+        .args(["kill", "--dry-run", "--format", "json", "--pid", &pid])
+        .args(["59999", &port])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["results"][0]["process"]["pid"], std::process::id());
+    assert_eq!(parsed["results"][0]["process"]["port"], guard.port());
 }
 
 #[test]
-fn wait_occupied_port_already_up() {
+fn kill_require_name_refuses_unexpected_processes() {
     let guard = ListenerGuard::random();
     let port = guard.port().to_string();
 
+    // Refusal happens before any signal, dry run or not; keep it a dry run
+    // anyway since the listener is this test process.
+    let output = portzap()
+        .args(["kill", "--dry-run", "--format", "json"])
+        .args(["--require-name", "no-such-process", &port])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["results"], serde_json::json!([]));
+    assert_eq!(parsed["refused"][0]["port"], guard.port());
+    assert_eq!(
+        parsed["refused"][0]["processes"][0]["pid"],
+        std::process::id()
+    );
+
     portzap()
-        .args(["wait", &port, "--until", "up", "--timeout", "2"])
+        .args([
+            "kill",
+            "--dry-run",
+            "--require-name",
+            "/cli_integration/",
+            &port,
+        ])
         .assert()
-        .success();
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "Everything on port {port} matches --require-name /cli_integration/"
+        )));
+}
+
+#[test]
+fn kill_skips_privileged_ports_without_opt_in() {
+    portzap()
+        .args(["--dry-run", "80"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "port 80 is a privileged port; pass --allow-privileged to target it",
+        ));
+    portzap()
+        .args(["kill", "--dry-run", "--allow-privileged", "80"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("privileged").not());
+    // Alongside other ports, the skip alone doesn't fail the run
+    portzap()
+        .args(["--dry-run", "80", "59999"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn require_confirmation_always_needs_yes_without_a_terminal() {
+    let home = ConfigHome::new("require_confirmation = \"always\"\n");
+    let spawn = || {
+        std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap()
+    };
+
+    // Children of a root test run are root's, which always counts as foreign
+    let as_root = nix::unistd::geteuid().is_root();
+    let mut child = spawn();
+    let pid = child.id().to_string();
+    let assert = home
+        .apply(&mut portzap())
+        .args(["kill", "--no-graceful", "--pid", &pid])
+        .assert();
+    if as_root {
+        assert
+            .code(1)
+            .stderr(predicate::str::contains("pass --yes to kill them"));
+        child.kill().unwrap();
+    } else {
+        assert.success();
+    }
+    child.wait().unwrap();
+
+    let mut child = spawn();
+    let pid = child.id().to_string();
+    home.apply(&mut portzap())
+        .args(["kill", "--no-graceful", "--yes", "--pid", &pid])
+        .assert()
+        .success();
+    assert!(!child.wait().unwrap().success());
+}
+
+#[test]
+fn kill_pid_without_sockets() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .unwrap();
+    let pid = child.id().to_string();
+
+    portzap()
+        .args(["kill", "--pid", &pid, "--no-graceful"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!("(PID {pid})")))
+        .stderr(predicate::str::contains("on port").not());
+    assert!(!child.wait().unwrap().success());
+}
+
+#[test]
+fn kill_all_dry_run_lists_only_the_chosen_owners() {
+    let guard = ListenerGuard::random();
+    let pid = u64::from(std::process::id());
+    let targets = |extra: &[&str]| -> Vec<serde_json::Value> {
+        let output = portzap()
+            .args(["kill", "--all", "--dry-run", "--allow-privileged"])
+            .args(["--format", "json"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        report["results"].as_array().unwrap().clone()
+    };
+    let ours = |results: &[serde_json::Value]| {
+        results
+            .iter()
+            .find(|r| r["process"]["pid"] == pid)
+            .map(|r| r["ports"].clone())
+    };
+
+    // The listener is this test's, so it's the current user's
+    let results = targets(&[]);
+    let dry_run = |r: &serde_json::Value| r["signal_sent"] == "SIGTERM (dry-run)";
+    assert!(results.iter().all(dry_run));
+    assert_eq!(ours(&results), Some(serde_json::json!([guard.port()])));
+    assert_eq!(ours(&targets(&["--protocol", "udp"])), None);
+    let spared = targets(&["--except-name", "/cli_integration/"]);
+    assert_eq!(ours(&spared), None);
+    assert!(targets(&["--user", "no-such-user"]).is_empty());
+}
+
+#[test]
+fn kill_all_without_a_terminal_needs_yes() {
+    // Nothing matches the user, so even a missed check couldn't kill anything
+    portzap()
+        .args(["kill", "--all", "--user", "no-such-user"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("pass --yes"));
+}
+
+#[cfg(unix)]
+#[test]
+fn overlapping_specs_signal_a_listener_once() {
+    use std::os::fd::OwnedFd;
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let dir = tempfile::tempdir().unwrap();
+    let signals = dir.path().join("signals");
+    // The shell holds the socket as stdin and notes every SIGTERM it gets
+    let mut holder = Command::new("sh")
+        .args([
+            "-c",
+            r#"trap 'echo term >> "$0"; kill $!; exit 0' TERM; echo ready > "$0"; sleep 30 & wait"#,
+        ])
+        .arg(&signals)
+        .stdin(Stdio::from(OwnedFd::from(listener)))
+        .spawn()
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !std::fs::read_to_string(&signals).is_ok_and(|s| s.contains("ready")) {
+        assert!(Instant::now() < deadline, "the holder never started");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let output = portzap()
+        .arg(port.to_string())
+        .arg(format!("{port}-{}", port.saturating_add(1)))
+        .arg(port.to_string())
+        .args(["--format", "json", "--no-graceful"])
+        .output()
+        .unwrap();
+    holder.wait().unwrap();
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results = report["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1, "{report}");
+    assert_eq!(results[0]["process"]["pid"], holder.id());
+    assert_eq!(results[0]["ports"], serde_json::json!([port]));
+    let signals = std::fs::read_to_string(&signals).unwrap();
+    assert_eq!(signals.matches("term").count(), 1, "{signals}");
+}
+
+// ─── completions ───────────────────────────────────────────
+
+#[test]
+fn completions_bash() {
+    portzap()
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("portzap"));
+}
+
+#[test]
+fn completions_zsh() {
+    portzap()
+        .args(["completions", "zsh"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("portzap"));
+}
+
+#[test]
+fn completions_fish() {
+    portzap()
+        .args(["completions", "fish"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("portzap"));
+}
+
+#[test]
+fn completions_invalid_shell() {
+    portzap()
+        .args(["completions", "tcsh"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn completions_nushell() {
+    portzap()
+        .args(["completions", "nushell"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("export extern portzap"));
+}
+
+#[test]
+fn completions_cover_subcommands_and_value_enums() {
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish", "nushell"] {
+        let output = portzap().args(["completions", shell]).output().unwrap();
+        assert!(output.status.success(), "{shell} failed");
+        let script = String::from_utf8(output.stdout).unwrap();
+        for needle in ["config", "free", "wait", "format"] {
+            assert!(script.contains(needle), "{shell} completions lack '{needle}'");
+        }
+        // The PowerShell and Elvish generators don't enumerate argument values
+        if !matches!(shell, "powershell" | "elvish") {
+            for needle in ["json", "hup"] {
+                assert!(script.contains(needle), "{shell} completions lack '{needle}'");
+            }
+        }
+    }
+}
+
+// ─── free ──────────────────────────────────────────────────
+
+#[test]
+fn free_finds_unused_port() {
+    // High port unlikely to be in use
+    portzap()
+        .args(["free", "59999"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("59999"));
+}
+
+#[test]
+fn free_skips_occupied_port() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    // The free port returned should NOT be the occupied port
+    let output = portzap()
+        .args(["free", &port])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let found_port: u16 = stdout.trim().parse().expect("should print a port number");
+    assert!(found_port > guard.port(), "free should skip the occupied port");
+}
+
+#[test]
+fn free_json_output() {
+    portzap()
+        .args(["free", "59999", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""port": 59999"#));
+}
+
+#[test]
+fn free_no_port_in_range() {
+    let guard = ListenerGuard::new(59998);
+    let port = guard.port().to_string();
+
+    // Search only the occupied port
+    portzap()
+        .args(["free", &port, "--max", &port])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn free_searches_ranges_and_the_ephemeral_keyword() {
+    portzap()
+        .args(["free", "--start", "59990-59999"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("^5999[0-9]\n$").unwrap());
+
+    // Linux's default range, if /proc can't be read the IANA one
+    let (start, end) = std::fs::read_to_string("/proc/sys/net/ipv4/ip_local_port_range")
+        .ok()
+        .and_then(|raw| {
+            let mut fields = raw.split_whitespace().map(|f| f.parse::<u16>().ok());
+            Some((fields.next()??, fields.next()??))
+        })
+        .unwrap_or((49152, 65535));
+    let output = portzap().args(["free", "ephemeral"]).output().unwrap();
+    assert!(output.status.success());
+    let port: u16 = String::from_utf8(output.stdout)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap();
+    assert!((start..=end).contains(&port), "{port} not in {start}-{end}");
+
+    portzap()
+        .args(["--dry-run", "--format", "json", "ephemeral"])
+        .assert()
+        .stderr(predicate::str::contains(format!(
+            "ephemeral = ports {start}-{end}"
+        )));
+}
+
+#[test]
+fn free_hold_keeps_the_port_bound_until_released() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let start = ListenerGuard::random().port().to_string();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_portzap"))
+        .args(["free", &start, "--hold", "30s", "--hold-until-stdin-close"])
+        .args(["--format", "plain"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let port: u16 = line.trim().parse().expect("should print a port number");
+
+    // Printed before the hold ends, and nothing else can have it meanwhile,
+    // not even on a specific address
+    assert!(std::net::TcpListener::bind(("127.0.0.1", port)).is_err());
+    if std::net::TcpListener::bind(("::1", 0)).is_ok() {
+        assert!(std::net::TcpListener::bind(("::1", port)).is_err());
+    }
+
+    let released = std::time::Instant::now();
+    drop(child.stdin.take());
+    assert!(child.wait().unwrap().success());
+    assert!(released.elapsed() < std::time::Duration::from_secs(5));
+    assert!(std::net::TcpListener::bind(("127.0.0.1", port)).is_ok());
+}
+
+// ─── wait ──────────────────────────────────────────────────
+
+#[test]
+fn wait_port_already_free() {
+    // Port 59999 should be free — returns immediately
+    portzap()
+        .args(["wait", "59999", "--timeout", "2"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn wait_port_already_free_json() {
+    portzap()
+        .args(["wait", "59999", "--timeout", "2", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""status": "free""#));
+}
+
+#[test]
+fn wait_until_up_times_out() {
+    // Port 59999 is free, waiting for it to become occupied should time out
+    portzap()
+        .args(["wait", "59999", "--until", "up", "--timeout", "1"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn wait_until_up_times_out_json() {
+    portzap()
+        .args(["wait", "59999", "--until", "up", "--timeout", "1", "--format", "json"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(r#""status": "timeout""#));
+}
+
+#[test]
+fn wait_timeout_takes_durations() {
+    portzap()
+        .args(["wait", "59999", "--until", "up", "--timeout", "300ms"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("(timeout: 300ms, poll: 250ms)"));
+    portzap()
+        .args(["wait", "59999", "--timeout", "soon"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid duration 'soon'"));
+}
+
+#[test]
+fn wait_occupied_port_already_up() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    portzap()
+        .args(["wait", &port, "--until", "up", "--timeout", "2"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn wait_until_restart_sees_the_port_go_down_and_up() {
+    let guard = ListenerGuard::random();
+    let port = guard.port();
+    let restarter = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        drop(guard);
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        ListenerGuard::new(port)
+    });
+
+    let output = portzap()
+        .args(["wait", &port.to_string(), "--until", "restart"])
+        .args(["--poll", "50", "--timeout", "10", "--format", "json"])
+        .output()
+        .unwrap();
+    let _guard = restarter.join().unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["status"], "restarted");
+    assert!(parsed["down_at"].as_str().unwrap() < parsed["up_at"].as_str().unwrap());
+}
+
+#[test]
+fn wait_until_restart_can_require_a_down_transition() {
+    // Free throughout, so it never comes back up either way. By default the
+    // free port counts as gone down; --require-down waits to see it taken
+    // first, so it never even goes down.
+    portzap()
+        .args(["wait", "59999", "--until", "restart"])
+        .args(["--timeout", "1", "--format", "json"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(r#""down_at": null"#).not())
+        .stdout(predicate::str::contains(r#""up_at": null"#));
+    portzap()
+        .args(["wait", "59999", "--until", "restart", "--require-down"])
+        .args(["--timeout", "1", "--format", "json"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(r#""down_at": null"#));
+    portzap()
+        .args(["wait", "59999", "--require-down"])
+        .assert()
+        .code(2);
+}
+
+/// Serve `statuses` in order, one per connection, then stop accepting.
+#[cfg(feature = "http")]
+fn http_server(statuses: &'static [u16]) -> (u16, std::thread::JoinHandle<()>) {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        for status in statuses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+        }
+    });
+    (port, server)
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn wait_http_waits_for_a_passing_status() {
+    let (port, server) = http_server(&[502, 503, 200]);
+    portzap()
+        .args(["wait", &port.to_string(), "--http", "/health"])
+        .args(["--poll", "50", "--timeout", "10", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""http_status": 200"#));
+    server.join().unwrap();
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn wait_http_reports_the_last_status_on_timeout() {
+    let (port, _server) = http_server(&[503; 100]);
+    portzap()
+        .args(["wait", &port.to_string(), "--http"])
+        .args(["--expect-status", "2xx", "--poll", "100", "--timeout", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("(last try: HTTP 503)"));
+}
+
+// ─── check ─────────────────────────────────────────────────
+
+#[test]
+fn check_exits_zero_only_when_every_port_is_in_use() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    portzap()
+        .args(["check", &port])
+        .assert()
+        .code(0)
+        .stdout("")
+        .stderr("");
+    portzap()
+        .args(["check", &port, "59999"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("");
+    portzap()
+        .args(["check", &port, "59999", "--any"])
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn check_free_inverts_the_test() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    portzap()
+        .args(["check", "59999", "--free"])
+        .assert()
+        .code(0);
+    portzap()
+        .args(["check", &port, "59999", "--free"])
+        .assert()
+        .code(1);
+    portzap()
+        .args(["check", &port, "59999", "--free", "--any"])
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn check_json_maps_each_port() {
+    let guard = ListenerGuard::random();
+    let port = guard.port();
+
+    let output = portzap()
+        .args(["check", &port.to_string(), "59999", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        parsed,
+        serde_json::json!({ port.to_string(): true, "59999": false })
+    );
+}
+
+#[test]
+fn check_rejects_bad_ports_with_status_2() {
+    portzap().args(["check", "70000"]).assert().code(2);
+    portzap().arg("check").assert().code(2);
+}
+
+// ─── --output ───
+
+#[test]
+fn output_writes_json_to_the_file_and_a_table_to_the_terminal() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ports.json");
+    std::fs::write(&path, "stale").unwrap();
+
+    let output = portzap()
+        .args(["list", &port, "--format", "json", "--output"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("PID") && stdout.contains(&port), "{stdout}");
+    assert!(!stdout.contains('{'), "{stdout}");
+
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written[&port]["processes"][0]["port"].to_string(), port);
+    // Nothing left behind but the file itself
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn output_append_adds_to_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("checks.json");
+    for _ in 0..2 {
+        portzap()
+            .args(["check", "59999", "--free", "--format", "json"])
+            .args(["--append", "--output"])
+            .arg(&path)
+            .assert()
+            .code(0)
+            .stdout("");
+    }
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(written.matches(r#""59999": true"#).count(), 2, "{written}");
+}
+
+#[test]
+fn output_failures_name_the_path_and_keep_the_old_file() {
+    portzap()
+        .args(["check", "59999", "--output", "/nonexistent/out.json"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "could not write /nonexistent/out.json",
+        ));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ports.json");
+    std::fs::write(&path, "previous").unwrap();
+    portzap()
+        .args(["list", "70000", "--format", "json", "--output"])
+        .arg(&path)
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("\"error\""));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn output_write_errors_name_the_path() {
+    // Every write to /dev/full fails with ENOSPC, like a full disk
+    portzap()
+        .args(["check", "59999", "--free", "--format", "json"])
+        .args(["--append", "--output", "/dev/full"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("could not write /dev/full"))
+        .stderr(predicate::str::contains("panicked").not());
+}
+
+// ─── remote hosts ──────────────────────────────────────────
+
+/// Write an executable shell script `name` into `dir`, a stand-in for a
+/// program that tests put first on PATH.
+fn write_script(dir: &tempfile::TempDir, name: &str, body: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let script = dir.path().join(name);
+    std::fs::write(&script, format!("#!/bin/sh\n{body}")).unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// A directory holding an `ssh` stand-in that prints `stdout` and `stderr`
+/// and exits with `status`.
+fn fake_ssh(stdout: &str, stderr: &str, status: i32) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_script(
+        &dir,
+        "ssh",
+        &format!("printf '%s' '{stdout}'\nprintf '%s\\n' '{stderr}' >&2\nexit {status}\n"),
+    );
+    dir
+}
+
+fn path_with(dir: &tempfile::TempDir) -> String {
+    format!(
+        "{}:{}",
+        dir.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    )
+}
+
+#[test]
+fn list_host_shows_remote_ss_rows() {
+    let ssh = fake_ssh(
+        "ss\ntcp LISTEN 0 4096 0.0.0.0:5432 0.0.0.0:* users:((\"postgres\",pid=812,fd=5))\n",
+        "",
+        0,
+    );
+    portzap()
+        .env("PATH", path_with(&ssh))
+        .args(["list", "--host", "dev1", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""host": "dev1""#))
+        .stdout(predicate::str::contains(r#""name": "postgres""#));
+}
+
+#[test]
+fn list_host_scans_several_ports_over_one_connection() {
+    let dir = tempfile::tempdir().unwrap();
+    let (rows, log) = (dir.path().join("rows"), dir.path().join("ssh.log"));
+    std::fs::write(
+        &rows,
+        "ss\ntcp LISTEN 0 4096 0.0.0.0:5432 0.0.0.0:* users:((\"postgres\",pid=812,fd=5))\n",
+    )
+    .unwrap();
+    // Note each connection, then answer like ss would
+    let body = format!("echo >> '{}'\ncat '{}'\n", log.display(), rows.display());
+    write_script(&dir, "ssh", &body);
+
+    let output = portzap()
+        .env("PATH", path_with(&dir))
+        .args(["list", "--host", "dev1", "5432", "6000-6001", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#""name": "postgres""#), "{stdout}");
+    let log = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(log.lines().count(), 1, "{log}");
+}
+
+#[test]
+fn list_host_reports_connection_failures() {
+    let ssh = fake_ssh("", "ssh: Could not resolve hostname nope", 255);
+    portzap()
+        .env("PATH", path_with(&ssh))
+        .args(["list", "--host", "nope"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains(
+            "could not connect to nope: ssh: Could not resolve hostname nope",
+        ));
+
+    let ssh = fake_ssh("", "", 69);
+    portzap()
+        .env("PATH", path_with(&ssh))
+        .args(["wait", "3000", "--host", "bare", "--timeout", "1"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains(
+            "neither portzap nor ss is installed there",
+        ));
+}
+
+// ─── WSL interop ───────────────────────────────────────────
+
+/// Stand-ins for the Windows tools, with a postgres.exe listening on 47123.
+/// taskkill.exe records its arguments in `taskkill.log`.
+fn fake_windows() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_script(
+        &dir,
+        "netstat.exe",
+        "printf '  Proto  Local Address   Foreign Address   State       PID\\r\\n'\n\
+         printf '  TCP    0.0.0.0:47123   0.0.0.0:0         LISTENING   812\\r\\n'\n",
+    );
+    write_script(
+        &dir,
+        "tasklist.exe",
+        "printf '\"postgres.exe\",\"812\",\"Services\",\"0\",\"12,345 K\"\\r\\n'\n",
+    );
+    let log = dir.path().join("taskkill.log");
+    write_script(
+        &dir,
+        "taskkill.exe",
+        &format!("echo \"$@\" >> '{}'\n", log.display()),
+    );
+    dir
+}
+
+#[test]
+fn include_windows_lists_windows_listeners() {
+    let windows = fake_windows();
+    portzap()
+        .env("PATH", path_with(&windows))
+        .env("WSL_DISTRO_NAME", "Ubuntu")
+        .args(["list", "47123", "--include-windows", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""name": "postgres.exe""#))
+        .stdout(predicate::str::contains(r#""origin": "windows""#));
+}
+
+#[test]
+fn include_windows_kills_with_taskkill() {
+    let windows = fake_windows();
+    portzap()
+        .env("PATH", path_with(&windows))
+        .env("WSL_DISTRO_NAME", "Ubuntu")
+        .args(["kill", "47123", "--include-windows", "--no-graceful", "-s", "kill"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("postgres.exe"));
+    let log = std::fs::read_to_string(windows.path().join("taskkill.log")).unwrap();
+    assert_eq!(log.trim(), "/PID 812 /F");
+}
+
+#[test]
+fn include_windows_needs_wsl() {
+    if std::fs::read_to_string("/proc/version").is_ok_and(|v| v.contains("icrosoft")) {
+        return;
+    }
+    portzap()
+        .env_remove("WSL_DISTRO_NAME")
+        .args(["list", "--include-windows"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("only works inside WSL"));
+}
+
+// ─── config defaults ───────────────────────────────────────
+
+#[test]
+fn force_dry_run_reports_sigkill() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    portzap()
+        .args(["--force", "--dry-run", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("SIGKILL (dry-run)"));
+    let output = portzap()
+        .args(["kill", "-f", "--dry-run", &port, "--format", "json"])
+        .env("PORTZAP_SIGNAL", "term")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["results"][0]["signal_sent"], "SIGKILL (dry-run)");
+
+    for conflicting in ["--signal=term", "--graceful"] {
+        portzap()
+            .args(["kill", "--force", conflicting, "--dry-run", &port])
+            .env("PORTZAP_SIGNAL", "term")
+            .assert()
+            .code(2);
+    }
+
+    // --ignore-manager only overrides service managers; the signal stays graceful
+    portzap()
+        .args(["--ignore-manager", "--dry-run", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("SIGTERM (dry-run)"));
+}
+
+#[test]
+fn config_default_signal_used_for_dry_run() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new("default_signal = \"kill\"\ndefault_no_graceful = true\n");
+
+    home.apply(&mut portzap())
+        .args(["--dry-run", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("SIGKILL (dry-run)"));
+}
+
+#[test]
+fn cli_signal_overrides_config_default() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new("default_signal = \"kill\"\n");
+
+    home.apply(&mut portzap())
+        .args(["kill", "--dry-run", "--signal", "int", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("SIGINT (dry-run)"));
+}
+
+#[test]
+fn config_default_format_applies_and_cli_wins() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new("default_format = \"json\"\n");
+
+    let output = home
+        .apply(&mut portzap())
+        .args(["--dry-run", &port])
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["results"][0]["process"]["port"], guard.port());
+
+    home.apply(&mut portzap())
+        .args(["list", "--format", "plain", &port])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("\t{}\t", guard.port())));
+}
+
+#[test]
+fn invalid_config_default_warns_and_falls_back() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new("default_signal = \"sigfoo\"\n");
+
+    home.apply(&mut portzap())
+        .args(["--dry-run", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("invalid default_signal 'sigfoo'"))
+        .stderr(predicate::str::contains("SIGTERM (dry-run)"));
+}
+
+// ─── environment overrides ─────────────────────────────────
+
+#[test]
+fn env_signal_beats_config_default() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new("default_signal = \"kill\"\n");
+
+    home.apply(&mut portzap())
+        .env("PORTZAP_SIGNAL", "hup")
+        .args(["--dry-run", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("SIGHUP (dry-run)"));
+}
+
+#[test]
+fn cli_flag_beats_env_var() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new("default_signal = \"kill\"\n");
+
+    home.apply(&mut portzap())
+        .env("PORTZAP_SIGNAL", "hup")
+        .args(["--dry-run", "--signal", "int", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("SIGINT (dry-run)"));
+}
+
+#[test]
+fn env_format_beats_config_default() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new("default_format = \"table\"\n");
+
+    home.apply(&mut portzap())
+        .env("PORTZAP_FORMAT", "plain")
+        .args(["list", &port])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("\t{}\t", guard.port())));
+}
+
+#[test]
+fn portzap_config_selects_alternate_file() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ci.toml");
+    std::fs::write(&path, "default_signal = \"int\"\n").unwrap();
+
+    ConfigHome::new("default_signal = \"kill\"\n")
+        .apply(&mut portzap())
+        .env("PORTZAP_CONFIG", &path)
+        .args(["--dry-run", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("SIGINT (dry-run)"));
+}
+
+#[test]
+fn portzap_config_missing_file_is_an_error() {
+    portzap()
+        .env("PORTZAP_CONFIG", "/nonexistent/portzap.toml")
+        .args(["list", "59999"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("PORTZAP_CONFIG"));
+}
+
+#[test]
+fn multi_port_kill_prints_summary() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    portzap()
+        .args(["--dry-run", &port, "59999"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Would zap 1 process across 1 port, 1 port had nothing listening",
+        ));
+
+    portzap()
+        .args(["--dry-run", "--quiet", &port, "59999"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Would zap").not());
+
+    let output = portzap()
+        .args(["--dry-run", &port, "59999", "--format", "json"])
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["results"][0]["process"]["port"], guard.port());
+    assert_eq!(parsed["empty_ports"], serde_json::json!([59999]));
+    assert_eq!(
+        parsed["summary"],
+        serde_json::json!({"killed": 1, "failed": 0, "empty_ports": 1})
+    );
+}
+
+#[test]
+fn single_port_kill_has_no_summary() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    let output = portzap()
+        .args(["--dry-run", &port, "--format", "json"])
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(parsed.get("summary").is_none());
+}
+
+// ─── exit codes ────────────────────────────────────────────
+
+#[test]
+fn exit_code_zero_when_nothing_to_kill() {
+    portzap().args(["59999", "--dry-run"]).assert().code(0);
+}
+
+#[test]
+fn exit_code_one_when_a_check_fails() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    portzap()
+        .args(["free", &port, "--max", &port])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn exit_code_two_for_bad_ports_with_json_error() {
+    let output = portzap()
+        .args(["kill", "not-a-port", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["error"]["kind"], "usage");
+    assert!(parsed["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("invalid port"));
+}
+
+#[test]
+fn exit_code_two_for_config_errors() {
+    let output = portzap()
+        .env("PORTZAP_CONFIG", "/nonexistent/portzap.toml")
+        .args(["list", "59999", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["error"]["kind"], "config");
+}
+
+#[test]
+fn fatal_errors_stay_human_in_table_format() {
+    portzap()
+        .args(["kill", "not-a-port"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("Error:"));
+}
+
+#[test]
+fn interactive_without_a_terminal_exits_two() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    let output = portzap()
+        .args(["kill", &port, "-i", "--format", "json"])
+        .write_stdin("")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(parsed["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("interactive mode requires a terminal"));
+}
+
+#[test]
+fn interactive_fallback_none_kills_nothing() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+
+    let output = portzap()
+        .args(["kill", &port, "-i", "--format", "json"])
+        .args(["--non-interactive-fallback", "none"])
+        .write_stdin("")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["results"], serde_json::json!([]));
+}
+
+#[test]
+fn interactive_fallback_needs_interactive() {
+    portzap()
+        .args(["kill", "3000", "--non-interactive-fallback", "all"])
+        .assert()
+        .code(2);
+}
+
+// ─── stdout contract ───
+
+#[test]
+fn json_stdout_parses_whether_or_not_the_command_succeeds() {
+    let home = ConfigHome::new("");
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let cases: &[(&[&str], i32)] = &[
+        (&["list", &port], 0),
+        (&["list", "59999"], 0),
+        (&["list", &port, "59999"], 0),
+        (&["list", "--count", "59999"], 0),
+        (&["list", "70000"], 2),
+        (&["kill", "59999", "59998"], 0),
+        (&["kill", "--dry-run", &port], 0),
+        (&["kill", "--pid", "999999"], 3),
+        (&["free", "59999"], 0),
+        (&["free", &port, "--max", &port], 1),
+        (&["wait", "59999", "--timeout", "1"], 0),
+        (&["wait", "59999", "--until", "up", "--timeout", "1"], 1),
+        (&["check", "59999"], 1),
+        (&["info", &port], 0),
+        (&["stats"], 0),
+        (&["history"], 0),
+        (&["watch", "--status"], 0),
+        (&["watch", "--stop"], 1),
+        (&["config", "get", "theme"], 0),
+    ];
+    for (args, code) in cases {
+        let output = home
+            .apply(&mut portzap())
+            .args(*args)
+            .args(["--format", "json"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(*code), "{args:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            serde_json::from_str::<serde_json::Value>(&stdout).is_ok(),
+            "{args:?} printed {stdout:?}"
+        );
+    }
+
+    // A single empty port is keyed like several, so it still shows up
+    let output = portzap()
+        .args(["list", "59999", "--format", "json"])
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["59999"]["processes"], serde_json::json!([]));
+}
+
+#[test]
+fn plain_stdout_carries_no_prose() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let cases: &[&[&str]] = &[
+        &["list", "59999"],
+        &["kill", "59999"],
+        &["free", &port, "--max", &port],
+        &["wait", "59999", "--until", "up", "--timeout", "1"],
+    ];
+    for args in cases {
+        let output = portzap()
+            .args(*args)
+            .args(["--format", "plain"])
+            .output()
+            .unwrap();
+        assert_eq!(output.stdout, b"", "{args:?}");
+        assert!(!output.stderr.is_empty(), "{args:?}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn watch_json_streams_one_line_per_kill() {
+    use std::io::BufRead;
+    use std::os::fd::OwnedFd;
+    use std::process::{Command, Stdio};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let mut holder = Command::new("sleep")
+        .arg("30")
+        .stdin(Stdio::from(OwnedFd::from(listener)))
+        .spawn()
+        .unwrap();
+
+    let mut watch = Command::new(env!("CARGO_BIN_EXE_portzap"))
+        .args(["watch", &port, "--no-graceful", "--format", "json"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    std::io::BufReader::new(watch.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    watch.kill().unwrap();
+    watch.wait().unwrap();
+    holder.wait().unwrap();
+
+    let result: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(result["process"]["pid"], holder.id());
+    assert_eq!(result["success"], true);
+}
+
+#[cfg(unix)]
+#[test]
+fn watch_rules_pick_the_signal_per_port() {
+    use std::io::BufRead;
+    use std::os::fd::OwnedFd;
+    use std::process::{Command, Stdio};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let mut holder = Command::new("sleep")
+        .arg("30")
+        .stdin(Stdio::from(OwnedFd::from(listener)))
+        .spawn()
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let rules = dir.path().join("rules.toml");
+    std::fs::write(&rules, format!("[rules]\n\"{port}\" = \"kill\"\n")).unwrap();
+    // Ports nothing listens on once their guards are gone
+    let unused = ListenerGuard::random().port().to_string();
+    let graceful = format!("{}:graceful", ListenerGuard::random().port());
+    let mut watch = Command::new(env!("CARGO_BIN_EXE_portzap"))
+        .args(["watch", &unused, "--rule", &graceful])
+        .args(["--format", "json", "--rules-file"])
+        .arg(&rules)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    std::io::BufReader::new(watch.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    watch.kill().unwrap();
+    let output = watch.wait_with_output().unwrap();
+    holder.wait().unwrap();
+
+    let result: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(result["process"]["pid"], holder.id());
+    assert_eq!(result["signal_sent"], "SIGKILL");
+    assert_eq!(result["rule"], format!("{port}:kill"));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains(&format!("Rule {port}:kill matched sleep")));
+
+    portzap()
+        .args(["watch", "--rule", "3000-3010:kill", "--rule", "3005:term"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "rules 3000-3010:kill and 3005:term conflict on port 3005",
+        ));
+}
+
+#[test]
+fn watch_log_file_records_events() {
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("watch.ndjson");
+
+    let mut watch = Command::new(env!("CARGO_BIN_EXE_portzap"))
+        .args(["watch", &port, "--dry-run", "--poll", "100"])
+        .arg("--log-file")
+        .arg(&log)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let contents = loop {
+        let contents = std::fs::read_to_string(&log).unwrap_or_default();
+        if contents.ends_with('\n') || Instant::now() > deadline {
+            break contents;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    watch.kill().unwrap();
+    watch.wait().unwrap();
+
+    let event: serde_json::Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+    assert_eq!(event["process"]["pid"], std::process::id());
+    assert_eq!(event["process"]["port"], guard.port());
+    assert_eq!(event["success"], true);
+}
+
+#[cfg(unix)]
+#[test]
+fn watch_spares_the_pid_in_an_ignored_pidfile() {
+    use std::process::{Command, Stdio};
+    use std::time::Duration;
+
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let dir = tempfile::tempdir().unwrap();
+    let pidfile = dir.path().join("app.pid");
+    std::fs::write(&pidfile, format!("{}\n", std::process::id())).unwrap();
+    let log = dir.path().join("watch.ndjson");
+
+    let watch = Command::new(env!("CARGO_BIN_EXE_portzap"))
+        .args(["watch", &port, "--dry-run", "--poll", "100"])
+        .arg("--ignore-pidfile")
+        .arg(&pidfile)
+        .arg("--ignore-pidfile")
+        .arg(dir.path().join("missing.pid"))
+        .arg("--log-file")
+        .arg(&log)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(watch.id() as i32),
+        nix::sys::signal::Signal::SIGTERM,
+    )
+    .unwrap();
+    let output = watch.wait_with_output().unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.matches("ignored (pidfile").count(),
+        1,
+        "reported once: {stderr}"
+    );
+    assert_eq!(stderr.matches("missing.pid").count(), 1, "{stderr}");
+    let log = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(log.lines().count(), 1, "logged once: {log}");
+    let event: serde_json::Value = serde_json::from_str(log.trim_end()).unwrap();
+    assert_eq!(event["process"]["pid"], std::process::id());
+    assert_eq!(event["ignored"], "pidfile");
+    assert_eq!(event["pidfile"], pidfile.to_str().unwrap());
+}
+
+// ─── SO_REUSEPORT ───
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn kill_ends_reuseport_listeners_within_one_timeout() {
+    use socket2::{Domain, Socket, Type};
+    use std::io::BufRead;
+    use std::net::SocketAddr;
+    use std::os::fd::OwnedFd;
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let listen = |port: u16| {
+        let socket = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        socket.set_reuse_port(true).unwrap();
+        socket
+            .bind(&SocketAddr::from(([127, 0, 0, 1], port)).into())
+            .unwrap();
+        socket.listen(8).unwrap();
+        socket
+    };
+    let first = listen(0);
+    let port = first.local_addr().unwrap().as_socket().unwrap().port();
+    let second = listen(port);
+
+    // Both ignore SIGTERM, so each holds out for the whole timeout.
+    let holders: Vec<_> = [first, second]
+        .into_iter()
+        .map(|socket| {
+            let mut child = Command::new("sh")
+                .args(["-c", "trap '' TERM; echo ready; exec sleep 30"])
+                .stdin(Stdio::from(OwnedFd::from(socket)))
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut line = String::new();
+            std::io::BufReader::new(child.stdout.take().unwrap())
+                .read_line(&mut line)
+                .unwrap();
+            child
+        })
+        .collect();
+
+    let start = Instant::now();
+    let output = portzap()
+        .args(["kill", &port.to_string(), "--timeout", "1"])
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    let elapsed = start.elapsed();
+    for mut holder in holders {
+        let _ = holder.kill();
+        holder.wait().unwrap();
+    }
+
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results = parsed["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    for result in results {
+        assert_eq!(result["signal_sent"], "SIGTERM -> SIGKILL");
+    }
+    // One after the other, the two would take at least two seconds.
+    assert!(elapsed < Duration::from_millis(1900), "took {elapsed:?}");
+}
+
+// ─── config subcommand ─────────────────────────────────────
+
+#[test]
+fn config_set_then_get_preserves_unknown_keys() {
+    let home = ConfigHome::new("future_option = \"keep me\"\n");
+
+    home.apply(&mut portzap())
+        .args(["config", "set", "default_timeout_secs", "10"])
+        .assert()
+        .success();
+    home.apply(&mut portzap())
+        .args(["config", "get", "default_timeout_secs"])
+        .assert()
+        .success()
+        .stdout("10\n");
+
+    let path = home
+        .apply(&mut portzap())
+        .args(["config", "path"])
+        .output()
+        .unwrap()
+        .stdout;
+    let contents = std::fs::read_to_string(String::from_utf8(path).unwrap().trim()).unwrap();
+    assert!(contents.contains("future_option = \"keep me\""));
+    assert!(contents.contains("default_timeout_secs = 10"));
+}
+
+#[test]
+fn config_set_invalid_value_lists_allowed_values() {
+    let home = ConfigHome::new("");
+
+    home.apply(&mut portzap())
+        .args(["config", "set", "theme", "blue"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "allowed: auto, dark, light, solarized, dracula, gruvbox, nord",
+        ));
+    home.apply(&mut portzap())
+        .args(["config", "set", "theme", "nord"])
+        .assert()
+        .success();
+    home.apply(&mut portzap())
+        .args(["config", "set", "colour", "blue"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("valid keys"));
+}
+
+#[test]
+fn config_list_includes_defaults() {
+    let home = ConfigHome::new("theme = \"light\"\n");
+
+    home.apply(&mut portzap())
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("theme = \"light\""))
+        .stdout(predicate::str::contains("refresh_secs = 2"));
+}
+
+// ─── aliases ───────────────────────────────────────────────
+
+#[test]
+fn alias_resolves_for_list_and_kill() {
+    let guard = ListenerGuard::random();
+    let home = ConfigHome::new(&format!("[aliases]\nmine = \"59990,{}\"\n", guard.port()));
+
+    home.apply(&mut portzap())
+        .args(["list", "--format", "plain", "mine"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("\t{}\t", guard.port())));
+    home.apply(&mut portzap())
+        .args(["--dry-run", "mine"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("dry-run"));
+}
+
+#[test]
+fn unknown_alias_suggests_close_name() {
+    let home = ConfigHome::new("[aliases]\ndev = \"3000-3001\"\n");
+
+    home.apply(&mut portzap())
+        .args(["list", "dve"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid port: 'dve'"))
+        .stderr(predicate::str::contains("did you mean 'dev'"));
+}
+
+#[test]
+fn wait_accepts_single_port_alias() {
+    let home = ConfigHome::new("[aliases]\nspare = \"59999\"\nmany = \"59998-59999\"\n");
+
+    home.apply(&mut portzap())
+        .args(["wait", "spare", "--timeout", "2"])
+        .assert()
+        .success();
+    home.apply(&mut portzap())
+        .args(["wait", "many", "--timeout", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected exactly one"));
+}
+
+#[test]
+fn completions_offer_alias_names() {
+    let home = ConfigHome::new("[aliases]\nbackend = \"8080\"\n");
+
+    home.apply(&mut portzap())
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("backend"));
+}
+
+#[test]
+fn config_typo_warns_but_keeps_valid_settings() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new("default_signl = \"hup\"\ndefault_signal = \"kill\"\n");
+
+    home.apply(&mut portzap())
+        .args(["--dry-run", &port])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("unknown key 'default_signl'"))
+        .stderr(predicate::str::contains("SIGKILL (dry-run)"));
+}
+
+#[test]
+fn corrupt_config_warns_and_uses_defaults() {
+    let home = ConfigHome::new("theme = \n");
+
+    home.apply(&mut portzap())
+        .args(["list", "59999"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("is not valid TOML (line 1"));
+}
+
+// ─── per-command config sections ───────────────────────────
+
+#[test]
+fn config_free_max_limits_search() {
+    let guard = ListenerGuard::random();
+    let port = guard.port().to_string();
+    let home = ConfigHome::new(&format!("[free]\nmax = {}\n", guard.port()));
+
+    home.apply(&mut portzap())
+        .args(["free", &port])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!("{port}..={port}")));
+}
+
+#[test]
+fn config_wait_timeout_applies_when_flag_omitted() {
+    let home = ConfigHome::new("[wait]\ntimeout_secs = 1\npoll_ms = 100\n");
+    let start = std::time::Instant::now();
+
+    home.apply(&mut portzap())
+        .args(["wait", "59999", "--until", "up", "--format", "json"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(r#""status": "timeout""#));
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+}
+
+#[test]
+fn config_watch_poll_applies_when_flag_omitted() {
+    let home = ConfigHome::new("[watch]\npoll_ms = 250\nallow_names = [\"postgres\"]\n");
+
+    home.apply(&mut portzap())
+        .args(["watch", "59997"])
+        .timeout(std::time::Duration::from_secs(1))
+        .assert()
+        .stderr(predicate::str::contains("poll every 250ms"));
+}
+
+#[test]
+fn config_section_typo_is_reported() {
+    let home = ConfigHome::new("[watch]\npol_ms = 250\n");
+
+    home.apply(&mut portzap())
+        .args(["list", "59999"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("unknown key 'watch.pol_ms'"))
+        .stderr(predicate::str::contains("did you mean 'poll_ms'"));
+}
+
+#[test]
+fn doctor_json_reports_checks() {
+    let output = portzap()
+        .args(["doctor", "--format", "json"])
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["version"]["version"], env!("CARGO_PKG_VERSION"));
+    let checks = parsed["checks"].as_array().unwrap();
+    assert!(checks
+        .iter()
+        .all(|c| { ["pass", "warn", "fail"].contains(&c["status"].as_str().unwrap_or_default()) }));
+    if cfg!(any(target_os = "linux", target_os = "macos")) {
+        let self_test = checks
+            .iter()
+            .find(|c| c["name"] == "self-test listener")
+            .unwrap();
+        assert_eq!(self_test["status"], "pass");
+        assert!(output.status.success());
+    }
+
+    // The plain report is the result too, so it goes to stdout
+    portzap()
+        .arg("doctor")
+        .assert()
+        .stdout(predicate::str::contains("self-test listener"))
+        .stderr("");
+}
+
+#[test]
+fn stats_json_counts_listener() {
+    let _guard = ListenerGuard::random();
+
+    let output = portzap()
+        .args(["stats", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for key in ["total", "tcp", "udp", "privileged"] {
+        assert!(parsed[key].is_u64(), "{key}");
+    }
+    assert!(parsed["by_user"].is_array());
+    if cfg!(any(target_os = "linux", target_os = "macos")) {
+        assert!(parsed["tcp"].as_u64().unwrap() >= 1);
+    }
+}
+
+#[test]
+fn top_json_groups_ports_by_process() {
+    let guard = ListenerGuard::random();
+    let second = ListenerGuard::random();
+
+    let output = portzap()
+        .args(["top", "--by", "mem", "--limit", "10000", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    if cfg!(any(target_os = "linux", target_os = "macos")) {
+        let ours = parsed
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["pid"] == std::process::id())
+            .expect("the test process is listed");
+        let ports = ours["ports"].as_array().unwrap();
+        assert!(ports.contains(&guard.port().into()));
+        assert!(ports.contains(&second.port().into()));
+        assert!(ours["rss_bytes"].as_u64().unwrap() > 0);
+    }
+
+    let output = portzap()
+        .args(["top", "--limit", "1", "--format", "json"])
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed.as_array().unwrap().len(), 1);
+
+    if cfg!(any(target_os = "linux", target_os = "macos")) {
+        let output = portzap()
+            .args(["top", "--limit", "10000", "--format", "ports"])
+            .output()
+            .unwrap();
+        let ports = String::from_utf8_lossy(&output.stdout);
+        assert!(ports.lines().any(|line| line == guard.port().to_string()), "{ports}");
+        assert!(ports.lines().all(|line| line.parse::<u16>().is_ok()), "{ports}");
+    }
+}
+
+#[test]
+fn info_plain_prints_the_range_by_name() {
+    portzap()
+        .args(["info", "8081", "--format", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("range\tregistered\t1024-49151\n"));
+}
+
+#[test]
+fn info_json_classifies_unknown_port() {
+    let guard = ListenerGuard::random();
+
+    let output = portzap()
+        .args(["info", &guard.port().to_string(), "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["port"], guard.port());
+    assert!(parsed["range"].is_string());
+    if cfg!(any(target_os = "linux", target_os = "macos")) {
+        assert_eq!(parsed["processes"][0]["pid"], std::process::id());
+    }
+}
+
+#[test]
+fn info_describes_well_known_port() {
+    portzap()
+        .args(["info", "22"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ssh"))
+        .stdout(predicate::str::contains("well-known"));
+}
+
+#[test]
+fn history_filters_and_clears() {
+    let home = ConfigHome::new("history = true\n");
+    let log = home.config_dir().join("history.jsonl");
+    std::fs::write(
+        &log,
+        concat!(
+            r#"{"timestamp":"2024-05-01T15:00:00+00:00","port":3000,"pid":10,"name":"node","signal":"SIGTERM","success":true,"source":"cli"}"#,
+            "\n",
+            r#"{"timestamp":"2024-05-02T15:00:00+00:00","port":8080,"pid":11,"name":"java","signal":"SIGKILL","success":false,"error":"denied","source":"watch"}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+
+    let output = home
+        .apply(&mut portzap())
+        .args(["history", "--port", "8080", "--format", "json"])
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed.as_array().unwrap().len(), 1);
+    assert_eq!(parsed[0]["source"], "watch");
+
+    let output = home
+        .apply(&mut portzap())
+        .args([
+            "history",
+            "--since",
+            "2024-05-02T00:00:00Z",
+            "--format",
+            "json",
+        ])
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed[0]["pid"], 11);
+
+    home.apply(&mut portzap())
+        .args(["history", "--clear"])
+        .assert()
+        .success();
+    assert!(!log.exists());
+}
+
+#[test]
+fn history_off_hints_how_to_enable() {
+    let home = ConfigHome::new("");
+
+    home.apply(&mut portzap())
+        .arg("history")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("config set history true"));
+}
+
+#[test]
+fn ports_from_stdin() {
+    let guard = ListenerGuard::random();
+
+    let output = portzap()
+        .args(["list", "-", "--format", "json"])
+        .write_stdin(format!("# from discovery\n{}\n", guard.port()))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    if cfg!(any(target_os = "linux", target_os = "macos")) {
+        let port = guard.port().to_string();
+        assert_eq!(parsed[&port]["processes"][0]["port"], guard.port());
+    }
+}
+
+#[test]
+fn ports_from_stdin_report_line_numbers() {
+    portzap()
+        .args(["kill", "-", "--dry-run"])
+        .write_stdin("59999\n\nnot-a-port\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("<stdin>:3: invalid port"));
+}
+
+#[test]
+fn empty_stdin_means_no_ports() {
+    portzap()
+        .args(["kill", "-", "--dry-run"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no ports given"));
+
+    // `list` with no ports lists everything
+    portzap()
+        .args(["list", "-", "--format", "json"])
+        .write_stdin("\n# nothing here\n")
+        .assert()
+        .success();
+}
+
+#[test]
+fn ports_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ports.txt");
+    std::fs::write(&path, "# dev\n59998\n\n59999 # spare\n").unwrap();
+
+    portzap()
+        .args(["kill", "--dry-run", "--from-file"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("59998"))
+        .stderr(predicate::str::contains("59999"));
+
+    std::fs::write(&path, "59998\n3000-\n").unwrap();
+    portzap()
+        .args(["list", "--from-file"])
+        .arg(&path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("ports.txt:2:"));
+}
+
+// ─── own process ───
+
+/// Run `portzap args` holding a listening socket of its own, passed in as
+/// stdin, and return its PID and stdout.
+#[cfg(unix)]
+fn portzap_holding_a_socket(args: &[&str]) -> (u32, u16, String) {
+    use std::os::fd::OwnedFd;
+    use std::process::{Command, Stdio};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let child = Command::new(env!("CARGO_BIN_EXE_portzap"))
+        .args(args)
+        .stdin(Stdio::from(OwnedFd::from(listener)))
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let pid = child.id();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (pid, port, String::from_utf8(output.stdout).unwrap())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn list_leaves_out_its_own_sockets() {
+    let (pid, port, stdout) = portzap_holding_a_socket(&["list", "--format", "json"]);
+    // Nothing at all is printed when nothing else is listening
+    let rows: Vec<serde_json::Value> = match stdout.trim() {
+        "" => Vec::new(),
+        json => serde_json::from_str(json).unwrap(),
+    };
+    assert!(rows.iter().all(|row| row["pid"] != pid));
+    assert!(rows.iter().all(|row| row["port"] != port));
+
+    let (pid, port, stdout) =
+        portzap_holding_a_socket(&["list", "--format", "json", "--include-self"]);
+    let parsed: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert!(parsed
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|row| row["pid"] == pid && row["port"] == port));
+}
+
+// ─── watch --daemon ───
+
+#[cfg(target_os = "linux")]
+fn background_watchers(home: &ConfigHome) -> Vec<serde_json::Value> {
+    let output = home
+        .apply(&mut portzap())
+        .args(["watch", "--status", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn watch_daemon_runs_in_the_background_until_stopped() {
+    let home = ConfigHome::new("");
+    home.apply(&mut portzap())
+        .args(["watch", "59993", "--daemon", "--poll", "100"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Watching 59993 in the background"));
+
+    let watchers = background_watchers(&home);
+    assert_eq!(watchers.len(), 1);
+    assert_eq!(watchers[0]["ports"], serde_json::json!(["59993"]));
+    let pid = watchers[0]["pid"].as_u64().unwrap();
+    assert!(home.config_dir().join("watch/59993.pid").exists());
+
+    home.apply(&mut portzap())
+        .args(["watch", "59993", "--daemon"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--stop 59993"));
+
+    home.apply(&mut portzap())
+        .args(["watch", "--stop", "59993"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "Stopped the watcher for 59993 (PID {pid})"
+        )));
+    assert!(background_watchers(&home).is_empty());
+    assert!(!home.config_dir().join("watch/59993.pid").exists());
+    let log = std::fs::read_to_string(home.config_dir().join("watch/59993.log")).unwrap();
+    assert!(log.contains("Watch mode stopped."));
+
+    home.apply(&mut portzap())
+        .args(["watch", "--stop"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("No watchers running"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn watch_status_cleans_up_stale_pidfiles() {
+    let home = ConfigHome::new("");
+    let dir = home.config_dir().join("watch");
+    std::fs::create_dir_all(&dir).unwrap();
+    // PIDs this high are never handed out
+    std::fs::write(dir.join("3000.pid"), "4194304\n").unwrap();
+
+    home.apply(&mut portzap())
+        .args(["watch", "--status"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Cleaned up after the watcher for 3000: PID 4194304 is no longer running",
+        ));
+    assert!(!dir.join("3000.pid").exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn watch_emit_service_installs_a_user_unit() {
+    let home = ConfigHome::new("");
+    home.apply(&mut portzap())
+        .args(["watch", "3000", "--poll", "250"])
+        .args(["--emit-service", "systemd"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[Service]"))
+        .stdout(predicate::str::contains(" watch 3000 --signal term "))
+        .stdout(predicate::str::contains(" --poll 250 "));
+
+    home.apply(&mut portzap())
+        .args(["watch", "3000", "--emit-service", "systemd", "--install"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "systemctl --user enable --now portzap-watch-3000.service",
+        ));
+    let unit = home
+        .config_dir()
+        .parent()
+        .unwrap()
+        .join("systemd/user/portzap-watch-3000.service");
+    assert!(std::fs::read_to_string(unit)
+        .unwrap()
+        .contains("Restart=on-failure"));
 }
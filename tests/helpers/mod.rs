@@ -31,3 +31,34 @@ impl ListenerGuard {
         self.port
     }
 }
+
+/// A temporary home directory holding a portzap config file.
+pub struct ConfigHome {
+    dir: tempfile::TempDir,
+}
+
+impl ConfigHome {
+    pub fn new(config: &str) -> Self {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let home = Self { dir };
+        let config_dir = home.config_dir();
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(config_dir.join("config.toml"), config).unwrap();
+        home
+    }
+
+    /// The portzap config directory inside this home.
+    pub fn config_dir(&self) -> std::path::PathBuf {
+        if cfg!(target_os = "macos") {
+            self.dir.path().join("Library/Application Support/portzap")
+        } else {
+            self.dir.path().join(".config/portzap")
+        }
+    }
+
+    /// Point the command's HOME and XDG_CONFIG_HOME at this directory.
+    pub fn apply<'a>(&self, cmd: &'a mut assert_cmd::Command) -> &'a mut assert_cmd::Command {
+        cmd.env("HOME", self.dir.path())
+            .env("XDG_CONFIG_HOME", self.dir.path().join(".config"))
+    }
+}